@@ -0,0 +1,205 @@
+// Rule-based achievements. The catalog lives here in code; the database only
+// records which keys have been unlocked and when, so an unlock is permanent
+// even if the entries that earned it are later deleted.
+
+use crate::db;
+use chrono::{Local, NaiveDate};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Rule {
+    EntriesLogged(i64),
+    LifetimeMl(i64),
+    StreakDays(i32),
+    DaysGoalMet(i64),
+    GoalMetBeforeHour(u32),
+}
+
+pub struct AchievementDef {
+    pub key: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub rule: Rule,
+}
+
+pub const CATALOG: &[AchievementDef] = &[
+    AchievementDef {
+        key: "first_entry",
+        title: "First Sip",
+        description: "Log your first drink",
+        rule: Rule::EntriesLogged(1),
+    },
+    AchievementDef {
+        key: "entries_100",
+        title: "Regular",
+        description: "Log 100 drinks",
+        rule: Rule::EntriesLogged(100),
+    },
+    AchievementDef {
+        key: "first_goal",
+        title: "First Aid Spray",
+        description: "Reach your daily goal for the first time",
+        rule: Rule::DaysGoalMet(1),
+    },
+    AchievementDef {
+        key: "streak_7",
+        title: "Green Herb",
+        description: "Meet your goal 7 days in a row",
+        rule: Rule::StreakDays(7),
+    },
+    AchievementDef {
+        key: "streak_30",
+        title: "Mixed Herb",
+        description: "Meet your goal 30 days in a row",
+        rule: Rule::StreakDays(30),
+    },
+    AchievementDef {
+        key: "lifetime_100l",
+        title: "Hundred Liters",
+        description: "Drink 100 L in total",
+        rule: Rule::LifetimeMl(100_000),
+    },
+    AchievementDef {
+        key: "early_bird",
+        title: "Early Bird",
+        description: "Reach your daily goal before noon",
+        rule: Rule::GoalMetBeforeHour(12),
+    },
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Achievement {
+    pub key: String,
+    pub title: String,
+    pub description: String,
+    pub unlocked: bool,
+    pub unlocked_at: Option<String>,
+}
+
+// Everything the rules need, gathered from the database in one place so rule
+// evaluation itself stays a pure function.
+#[derive(Debug, Default, Clone)]
+pub struct AchievementFacts {
+    pub entries: i64,
+    pub lifetime_ml: i64,
+    pub best_streak: i32,
+    pub days_goal_met: i64,
+    // Earliest hour of day (0-23) at which any day's running total crossed the goal
+    pub earliest_goal_hour: Option<u32>,
+}
+
+pub fn is_satisfied(rule: Rule, facts: &AchievementFacts) -> bool {
+    match rule {
+        Rule::EntriesLogged(n) => facts.entries >= n,
+        Rule::LifetimeMl(ml) => facts.lifetime_ml >= ml,
+        Rule::StreakDays(days) => facts.best_streak >= days,
+        Rule::DaysGoalMet(days) => facts.days_goal_met >= days,
+        Rule::GoalMetBeforeHour(hour) => facts.earliest_goal_hour.is_some_and(|h| h < hour),
+    }
+}
+
+pub fn init_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS achievements (
+            key TEXT PRIMARY KEY,
+            unlocked_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn gather_facts(
+    conn: &Connection,
+    goal_on: impl Fn(NaiveDate) -> i32,
+    today: NaiveDate,
+) -> SqliteResult<AchievementFacts> {
+    let (entries, lifetime_ml): (i64, i64) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(amount_ml), 0) FROM water_entries",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    // Every day that reached its own goal, with the time it got there
+    let goal_met_times = crate::stats::query_goal_met_times(conn, "", &goal_on)?;
+    let days_goal_met = goal_met_times.len() as i64;
+
+    // Only entries logged on the calendar day they count toward are considered,
+    // otherwise a 01:00 drink rolled back onto the previous day would look early.
+    let earliest_goal_hour: Option<u32> = goal_met_times
+        .iter()
+        .filter(|(date, at)| at.starts_with(date.as_str()))
+        .filter_map(|(_, at)| at.get(11..13)?.parse().ok())
+        .min();
+
+    let (_, best_streak) = crate::calculate_streaks(conn, &goal_on, today);
+
+    Ok(AchievementFacts {
+        entries,
+        lifetime_ml,
+        best_streak,
+        days_goal_met,
+        earliest_goal_hour,
+    })
+}
+
+// Unlock every achievement whose rule is now satisfied and return the ones
+// that were newly unlocked by this call.
+pub fn check_achievements(
+    conn: &Connection,
+    goal_on: impl Fn(NaiveDate) -> i32,
+    today: NaiveDate,
+) -> SqliteResult<Vec<Achievement>> {
+    let facts = gather_facts(conn, goal_on, today)?;
+    let unlocked_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut newly_unlocked = Vec::new();
+
+    // All or none, so an unlock is never stored without being returned
+    db::with_tx(conn, |tx| {
+        for def in CATALOG {
+            if !is_satisfied(def.rule, &facts) {
+                continue;
+            }
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO achievements (key, unlocked_at) VALUES (?1, ?2)",
+                params![def.key, unlocked_at],
+            )?;
+            if inserted > 0 {
+                newly_unlocked.push(Achievement {
+                    key: def.key.to_string(),
+                    title: def.title.to_string(),
+                    description: def.description.to_string(),
+                    unlocked: true,
+                    unlocked_at: Some(unlocked_at.clone()),
+                });
+            }
+        }
+        Ok(newly_unlocked)
+    })
+}
+
+// The full catalog with unlock status
+pub fn get_achievements(conn: &Connection) -> SqliteResult<Vec<Achievement>> {
+    let mut stmt = conn.prepare("SELECT unlocked_at FROM achievements WHERE key = ?1")?;
+
+    CATALOG
+        .iter()
+        .map(|def| {
+            let unlocked_at: Option<String> = stmt
+                .query_row([def.key], |row| row.get(0))
+                .map(Some)
+                .or_else(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                    e => Err(e),
+                })?;
+            Ok(Achievement {
+                key: def.key.to_string(),
+                title: def.title.to_string(),
+                description: def.description.to_string(),
+                unlocked: unlocked_at.is_some(),
+                unlocked_at,
+            })
+        })
+        .collect()
+}
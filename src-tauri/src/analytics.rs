@@ -0,0 +1,317 @@
+// Longer-range analysis built on top of the per-day totals.
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Changes smaller than this are reported as flat
+const FLAT_THRESHOLD_PERCENT: f32 = 2.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WeeklyAverage {
+    pub week_start: String,
+    pub week_end: String,
+    pub average_ml: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendDirection {
+    Up,
+    Down,
+    Flat,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Trend {
+    InsufficientData {
+        weeks_with_data: usize,
+        weekly_averages: Vec<WeeklyAverage>,
+    },
+    Trending {
+        direction: TrendDirection,
+        // Change across the whole window, relative to the mean weekly average
+        change_percentage: f32,
+        weekly_averages: Vec<WeeklyAverage>,
+    },
+}
+
+// Average daily intake for each of the last `weeks` weeks, oldest first. The
+// most recent week ends on `today`; days without entries count as zero.
+pub fn weekly_averages(conn: &Connection, weeks: u32, today: NaiveDate) -> SqliteResult<Vec<WeeklyAverage>> {
+    let first_day = today - Duration::days(7 * weeks as i64 - 1);
+
+    let mut stmt = conn.prepare(
+        "SELECT date, SUM(amount_ml) FROM water_entries
+         WHERE date BETWEEN ?1 AND ?2 GROUP BY date",
+    )?;
+    let totals: HashMap<String, i64> = stmt
+        .query_map(
+            params![
+                first_day.format("%Y-%m-%d").to_string(),
+                today.format("%Y-%m-%d").to_string()
+            ],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?
+        .collect::<SqliteResult<_>>()?;
+
+    Ok((0..weeks as i64)
+        .map(|week| {
+            let week_start = first_day + Duration::days(7 * week);
+            let week_end = week_start + Duration::days(6);
+            let total: i64 = (0..7)
+                .map(|offset| {
+                    let date = (week_start + Duration::days(offset)).format("%Y-%m-%d").to_string();
+                    totals.get(&date).copied().unwrap_or(0)
+                })
+                .sum();
+            WeeklyAverage {
+                week_start: week_start.format("%Y-%m-%d").to_string(),
+                week_end: week_end.format("%Y-%m-%d").to_string(),
+                average_ml: total as f32 / 7.0,
+            }
+        })
+        .collect())
+}
+
+// Least-squares slope over the weekly averages, expressed as the change from
+// the first to the last week relative to the overall mean.
+pub fn compute_trend(weekly_averages: Vec<WeeklyAverage>) -> Trend {
+    let weeks_with_data = weekly_averages.iter().filter(|w| w.average_ml > 0.0).count();
+    if weekly_averages.len() < 2 || weeks_with_data < 2 {
+        return Trend::InsufficientData {
+            weeks_with_data,
+            weekly_averages,
+        };
+    }
+
+    let n = weekly_averages.len() as f32;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = weekly_averages.iter().map(|w| w.average_ml).sum::<f32>() / n;
+
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (i, week) in weekly_averages.iter().enumerate() {
+        let dx = i as f32 - mean_x;
+        covariance += dx * (week.average_ml - mean_y);
+        variance += dx * dx;
+    }
+    let slope = covariance / variance;
+    let change_percentage = slope * (n - 1.0) / mean_y * 100.0;
+
+    let direction = if change_percentage.abs() < FLAT_THRESHOLD_PERCENT {
+        TrendDirection::Flat
+    } else if change_percentage > 0.0 {
+        TrendDirection::Up
+    } else {
+        TrendDirection::Down
+    };
+
+    Trend::Trending {
+        direction,
+        change_percentage,
+        weekly_averages,
+    }
+}
+
+// Number of logged days the rolling first-drink average covers
+pub const ROLLING_WINDOW_DAYS: usize = 7;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DayDrinkTimes {
+    pub date: String,
+    pub first_drink_at: String,
+    pub last_drink_at: String,
+    // Hours since the start of `date`; may exceed 24 with a late rollover hour
+    pub first_drink_hour: f32,
+    pub last_drink_hour: f32,
+    pub rolling_first_drink_hour: f32,
+}
+
+// Averages over a range of days, kept separate from the per-day rows so the
+// reminder scheduler can consume it on its own
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DrinkTimeSummary {
+    pub days_counted: usize,
+    pub average_first_drink_hour: Option<f32>,
+    pub average_last_drink_hour: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DrinkTimeTrends {
+    pub days: Vec<DayDrinkTimes>,
+    pub summary: DrinkTimeSummary,
+    pub rolling_window_days: usize,
+    pub malformed_timestamps: usize,
+}
+
+fn mean(values: impl Iterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+pub fn drink_time_trends(conn: &Connection, start: NaiveDate, end: NaiveDate) -> SqliteResult<DrinkTimeTrends> {
+    let mut stmt = conn.prepare(
+        "SELECT date, timestamp FROM water_entries
+         WHERE date BETWEEN ?1 AND ?2 ORDER BY date, timestamp",
+    )?;
+    let rows = stmt
+        .query_map(
+            params![
+                start.format("%Y-%m-%d").to_string(),
+                end.format("%Y-%m-%d").to_string()
+            ],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let mut malformed_timestamps = 0;
+    let mut days: Vec<DayDrinkTimes> = Vec::new();
+
+    for (date, timestamp) in rows {
+        let parsed = crate::dates::parse_date(&date).zip(crate::dates::parse_timestamp(&timestamp));
+        let Some((day, at)) = parsed else {
+            malformed_timestamps += 1;
+            continue;
+        };
+        let Some(midnight) = day.and_hms_opt(0, 0, 0) else {
+            malformed_timestamps += 1;
+            continue;
+        };
+        let hour = (at - midnight).num_minutes() as f32 / 60.0;
+        let time = at.format("%H:%M").to_string();
+
+        // Rows arrive ordered by date then time, so a day's first row is its
+        // first drink and every later row moves its last drink forward
+        match days.last_mut() {
+            Some(day) if day.date == date => {
+                day.last_drink_at = time;
+                day.last_drink_hour = hour;
+            }
+            _ => days.push(DayDrinkTimes {
+                date,
+                first_drink_at: time.clone(),
+                last_drink_at: time,
+                first_drink_hour: hour,
+                last_drink_hour: hour,
+                rolling_first_drink_hour: hour,
+            }),
+        }
+    }
+
+    let first_hours: Vec<f32> = days.iter().map(|d| d.first_drink_hour).collect();
+    for (i, day) in days.iter_mut().enumerate() {
+        let window_start = (i + 1).saturating_sub(ROLLING_WINDOW_DAYS);
+        if let Some(rolling) = mean(first_hours[window_start..=i].iter().copied()) {
+            day.rolling_first_drink_hour = rolling;
+        }
+    }
+
+    let summary = DrinkTimeSummary {
+        days_counted: days.len(),
+        average_first_drink_hour: mean(days.iter().map(|d| d.first_drink_hour)),
+        average_last_drink_hour: mean(days.iter().map(|d| d.last_drink_hour)),
+    };
+
+    Ok(DrinkTimeTrends {
+        days,
+        summary,
+        rolling_window_days: ROLLING_WINDOW_DAYS,
+        malformed_timestamps,
+    })
+}
+
+// History the drinking pattern is learned from, and the least of it worth
+// trusting over a plain interval
+pub const PATTERN_HISTORY_DAYS: i64 = 60;
+pub const PATTERN_MIN_DAYS: usize = 14;
+const PATTERN_BUCKET_MINUTES: i32 = 30;
+// A half hour with a drink on at least this share of days counts as typical
+const TYPICAL_BUCKET_SHARE: f32 = 0.5;
+const PATTERN_CACHE_KEY: &str = "typical_pattern";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PatternBucket {
+    // Start of the half hour, minutes after midnight
+    pub minute_of_day: i32,
+    // Share of the counted days with at least one entry in this half hour
+    pub share: f32,
+    pub typical: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TypicalPattern {
+    // Logical day the pattern was computed on; it's only recomputed once that changes
+    pub computed_on: String,
+    pub days_counted: usize,
+    pub enough_history: bool,
+    pub buckets: Vec<PatternBucket>,
+}
+
+impl TypicalPattern {
+    pub fn typical_minutes(&self) -> impl Iterator<Item = i32> + '_ {
+        self.buckets.iter().filter(|b| b.typical).map(|b| b.minute_of_day)
+    }
+}
+
+// Built from the days before `today`, so a half-finished day can't skew it
+fn compute_typical_pattern(conn: &Connection, today: NaiveDate) -> SqliteResult<TypicalPattern> {
+    let mut stmt = conn.prepare(
+        "SELECT date, timestamp FROM water_entries
+         WHERE date >= ?1 AND date < ?2",
+    )?;
+    let rows = stmt
+        .query_map(
+            params![
+                (today - Duration::days(PATTERN_HISTORY_DAYS)).format("%Y-%m-%d").to_string(),
+                today.format("%Y-%m-%d").to_string()
+            ],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let mut days = HashSet::new();
+    let mut bucket_days: HashMap<i32, HashSet<String>> = HashMap::new();
+    for (date, timestamp) in rows {
+        let Some(at) = crate::dates::parse_timestamp(&timestamp) else {
+            continue;
+        };
+        let bucket = crate::reminders::minute_of_day(at) / PATTERN_BUCKET_MINUTES * PATTERN_BUCKET_MINUTES;
+        bucket_days.entry(bucket).or_default().insert(date.clone());
+        days.insert(date);
+    }
+
+    let days_counted = days.len();
+    let buckets = (0..crate::reminders::MINUTES_PER_DAY / PATTERN_BUCKET_MINUTES)
+        .map(|i| {
+            let minute_of_day = i * PATTERN_BUCKET_MINUTES;
+            let hits = bucket_days.get(&minute_of_day).map_or(0, |d| d.len());
+            let share = if days_counted > 0 { hits as f32 / days_counted as f32 } else { 0.0 };
+            PatternBucket {
+                minute_of_day,
+                share,
+                typical: share >= TYPICAL_BUCKET_SHARE,
+            }
+        })
+        .collect();
+
+    Ok(TypicalPattern {
+        computed_on: today.format("%Y-%m-%d").to_string(),
+        days_counted,
+        enough_history: days_counted >= PATTERN_MIN_DAYS,
+        buckets,
+    })
+}
+
+// Cached alongside the settings and recomputed at most once per logical day
+pub fn typical_pattern(conn: &Connection, today: NaiveDate) -> SqliteResult<TypicalPattern> {
+    let cached: Option<TypicalPattern> = crate::settings::get(conn, PATTERN_CACHE_KEY)?;
+    let today_str = today.format("%Y-%m-%d").to_string();
+    if let Some(pattern) = cached.filter(|p| p.computed_on == today_str) {
+        return Ok(pattern);
+    }
+    let pattern = compute_typical_pattern(conn, today)?;
+    crate::settings::set(conn, PATTERN_CACHE_KEY, &pattern)?;
+    Ok(pattern)
+}
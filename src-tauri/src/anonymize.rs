@@ -0,0 +1,103 @@
+// Entry export for sharing with someone else, e.g. for research. What leaves
+// the machine is only how much was drunk, roughly when in the day, and the
+// goal it counted toward:
+//
+// - `id` is dropped; row ids reveal how many entries were deleted or merged.
+// - `date` is replaced by `day`, the number of days since the first logged
+//   day. The same date always gets the same index, so gaps between days and
+//   the day-to-day order survive, but no calendar date does.
+// - `timestamp` is cut down to `hour`, its wall-clock hour (0-23). Minutes,
+//   seconds and the calendar date are dropped.
+// - `amount_ml` is kept as is.
+// - `goal_ml` is the goal in effect on that day, so adherence can still be
+//   worked out.
+//
+// Entries carry no notes or tags, and no settings, names, paths or other
+// tables are included.
+
+use crate::{dates, AppError};
+use chrono::{NaiveDate, Timelike};
+use rusqlite::{Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum AnonymizedFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct AnonymizedEntry {
+    hour: u32,
+    amount_ml: i32,
+}
+
+#[derive(Debug, Serialize)]
+struct AnonymizedDay {
+    day: i64,
+    goal_ml: i32,
+    entries: Vec<AnonymizedEntry>,
+}
+
+// Days in order, each with its entries; rows whose date or timestamp can't be
+// read are left out rather than guessed at
+fn anonymized_days(conn: &Connection, goal_on: impl Fn(NaiveDate) -> i32) -> SqliteResult<Vec<AnonymizedDay>> {
+    let mut stmt = conn.prepare("SELECT date, timestamp, amount_ml FROM water_entries ORDER BY date, timestamp")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i32>(2)?))
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let mut first_date = None;
+    let mut days: Vec<(NaiveDate, AnonymizedDay)> = Vec::new();
+    for (date, timestamp, amount_ml) in rows {
+        let Some((date, at)) = dates::parse_date(&date).zip(dates::parse_timestamp(&timestamp)) else {
+            continue;
+        };
+        let first = *first_date.get_or_insert(date);
+        let entry = AnonymizedEntry {
+            hour: at.hour(),
+            amount_ml,
+        };
+        match days.last_mut() {
+            Some((last, day)) if *last == date => day.entries.push(entry),
+            _ => days.push((
+                date,
+                AnonymizedDay {
+                    day: (date - first).num_days(),
+                    goal_ml: goal_on(date),
+                    entries: vec![entry],
+                },
+            )),
+        }
+    }
+    Ok(days.into_iter().map(|(_, day)| day).collect())
+}
+
+// Returns the file contents and the number of entries in it
+pub fn export(
+    conn: &Connection,
+    format: AnonymizedFormat,
+    goal_on: impl Fn(NaiveDate) -> i32,
+) -> Result<(String, usize), AppError> {
+    let days = anonymized_days(conn, goal_on)?;
+    let count = days.iter().map(|d| d.entries.len()).sum();
+
+    let output = match format {
+        AnonymizedFormat::Json => {
+            serde_json::to_string_pretty(&serde_json::json!({ "days": days }))?
+        }
+        AnonymizedFormat::Csv => {
+            let mut csv = String::from("day,hour,amount_ml,goal_ml\n");
+            for day in &days {
+                for entry in &day.entries {
+                    csv.push_str(&format!("{},{},{},{}\n", day.day, entry.hour, entry.amount_ml, day.goal_ml));
+                }
+            }
+            csv
+        }
+    };
+    Ok((output, count))
+}
@@ -0,0 +1,30 @@
+// Keeps the `start_with_system` setting and the OS login item in step. The OS
+// is the source of truth: users can remove login items outside the app.
+
+use crate::{settings, AppError, Database};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_autostart::ManagerExt;
+
+// Register or remove the login item. Errors are returned as-is so the UI can
+// explain why the toggle didn't stick (e.g. no writable autostart dir on Linux).
+pub fn apply(app: &AppHandle, enabled: bool) -> Result<(), AppError> {
+    let autolaunch = app.autolaunch();
+    let result = if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    result.map_err(|e| AppError::Other(format!("Could not update start with system: {}", e)))
+}
+
+// On startup, overwrite the stored flag with what the OS actually has
+pub fn reconcile(app: &AppHandle) -> Result<(), AppError> {
+    let enabled = app.autolaunch().is_enabled().map_err(|e| AppError::Other(e.to_string()))?;
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let stored: Option<bool> = settings::get(&conn, "start_with_system")?;
+    if stored.unwrap_or_default() != enabled {
+        settings::set(&conn, "start_with_system", &enabled)?;
+    }
+    Ok(())
+}
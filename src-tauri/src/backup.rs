@@ -0,0 +1,601 @@
+// Whole-database backups as one JSON document. Tables are dumped row by row
+// with their column names, so a backup stays readable by later versions;
+// `schema_version` says how to translate it when the layout changes.
+//
+// Restoring either replaces everything or merges. A merge adds entries
+// unless one with the same amount exists within a minute of it, resolves
+// settings that differ by the chosen strategy, and adds other rows only where
+// their key is free. A replace first snapshots the database into the backups
+// directory. A dry run does all of it inside the transaction and rolls back.
+//
+// Automatic backups, when turned on, copy the database into the same
+// directory once per logical day, at startup or before the first entry, and
+// only the newest few are kept. Both kinds can be restored like a JSON backup.
+
+use crate::{datadir, dates, db, migrations, settings, AppError, Settings};
+use chrono::{DateTime, Local, NaiveDate};
+use rusqlite::{params_from_iter, types::Value, Connection, OpenFlags, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Everything the app stores. Settings are the raw key/value rows, so
+// reminder state such as an active snooze comes along too.
+pub const TABLES: [&str; 6] = [
+    "water_entries",
+    "app_settings",
+    "goal_presets",
+    "streak_freezes",
+    "achievements",
+    "milestones_reached",
+];
+
+pub type Row = Map<String, JsonValue>;
+
+const AUTO_BACKUP_PREFIX: &str = "auto-";
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+// Day a failed automatic backup was last reported, so it's reported once
+const AUTO_BACKUP_FAILED_KEY: &str = "auto_backup_failed_on";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Backup {
+    pub schema_version: u32,
+    pub app_version: String,
+    pub exported_at: String,
+    pub tables: BTreeMap<String, Vec<Row>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BackupSummary {
+    pub path: String,
+    pub size_bytes: u64,
+    pub row_counts: BTreeMap<String, usize>,
+}
+
+fn to_json(value: Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Integer(i) => JsonValue::from(i),
+        Value::Real(f) => JsonValue::from(f),
+        Value::Text(s) => JsonValue::String(s),
+        // No table stores blobs; kept as bytes rather than dropped
+        Value::Blob(b) => JsonValue::from(b),
+    }
+}
+
+fn dump_table(conn: &Connection, table: &str) -> SqliteResult<Vec<Row>> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {}", table))?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let rows = stmt
+        .query_map([], |row| {
+            let mut object = Map::new();
+            for (i, column) in columns.iter().enumerate() {
+                object.insert(column.clone(), to_json(row.get(i)?));
+            }
+            Ok(object)
+        })?
+        .collect();
+    rows
+}
+
+pub fn create(conn: &Connection, app_version: &str) -> SqliteResult<Backup> {
+    let tables = TABLES
+        .iter()
+        .map(|table| Ok((table.to_string(), dump_table(conn, table)?)))
+        .collect::<SqliteResult<_>>()?;
+    Ok(Backup {
+        schema_version: SCHEMA_VERSION,
+        app_version: app_version.to_string(),
+        exported_at: Local::now().format(dates::TIMESTAMP_FORMAT).to_string(),
+        tables,
+    })
+}
+
+// Written beside the target and renamed over it once complete, so a crash
+// midway leaves either the old file or none, never a truncated backup
+pub fn write(backup: &Backup, path: &Path) -> Result<BackupSummary, AppError> {
+    let json = serde_json::to_vec_pretty(backup)?;
+    let tmp = path.with_extension("tmp");
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(&json)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(AppError::Other(format!("Can't write {}: {}", path.display(), e)));
+    }
+
+    Ok(BackupSummary {
+        path: path.display().to_string(),
+        size_bytes: json.len() as u64,
+        row_counts: backup
+            .tables
+            .iter()
+            .map(|(table, rows)| (table.clone(), rows.len()))
+            .collect(),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreMode {
+    Replace,
+    Merge,
+}
+
+// Which side wins when a setting differs between the database and a merged backup
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    #[default]
+    KeepLocal,
+    KeepBackup,
+    // Whichever side saved its settings last
+    Newest,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeAction {
+    Inserted,
+    Skipped,
+    Overridden,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MergeItem {
+    pub table: String,
+    // The entry's timestamp and amount, or the row's key
+    pub key: String,
+    pub action: MergeAction,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RestoreSummary {
+    pub dry_run: bool,
+    pub inserted: BTreeMap<String, usize>,
+    pub skipped: BTreeMap<String, usize>,
+    pub overridden: BTreeMap<String, usize>,
+    // Every row of a merge and what happened to it; empty for a replace
+    pub items: Vec<MergeItem>,
+    // Copy of the database taken before a replace
+    pub snapshot_path: Option<String>,
+}
+
+// Entries this close in time with the same amount are the same drink logged
+// on two devices
+const MERGE_TOLERANCE_SECONDS: i64 = 60;
+
+// A JSON backup, or a database file such as an automatic backup or snapshot.
+// Errors about the file's contents name `path`.
+pub fn read(path: &Path) -> Result<Backup, AppError> {
+    let cant_read = |e: &dyn std::fmt::Display| AppError::Other(format!("Can't read {}: {}", path.display(), e));
+    let contents = fs::read(path).map_err(|e| cant_read(&e))?;
+    if contents.starts_with(SQLITE_HEADER) {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let found = migrations::schema_version(&conn)?;
+        if found > migrations::latest_version() {
+            return Err(newer_version(found));
+        }
+        if found == migrations::latest_version() {
+            return create(&conn, env!("CARGO_PKG_VERSION")).map_err(|e| cant_read(&e));
+        }
+
+        // An older file is upgraded in a temporary copy, never in place
+        let copy = std::env::temp_dir().join(format!("hydra-restore-{}.db", std::process::id()));
+        let _ = fs::remove_file(&copy);
+        let backup = db::vacuum_into(&conn, &copy)
+            .map_err(|e| cant_read(&e))
+            .and_then(|()| {
+                let conn = Connection::open(&copy)?;
+                migrations::run(&conn).map_err(|e| cant_read(&e))?;
+                create(&conn, env!("CARGO_PKG_VERSION")).map_err(|e| cant_read(&e))
+            });
+        let _ = fs::remove_file(&copy);
+        return backup;
+    }
+    let backup: Backup = serde_json::from_slice(&contents)
+        .map_err(|e| AppError::invalid("path", format!("not a backup file: {}", e)))?;
+    migrate(backup)
+}
+
+// Brings an older backup up to the current layout, one version at a time
+fn migrate(backup: Backup) -> Result<Backup, AppError> {
+    match backup.schema_version {
+        SCHEMA_VERSION => Ok(backup),
+        v if v > SCHEMA_VERSION => Err(newer_version(v)),
+        v => Err(AppError::invalid("path", format!("unknown backup schema version {}", v))),
+    }
+}
+
+fn newer_version(schema: u32) -> AppError {
+    AppError::invalid(
+        "path",
+        format!("this backup is from a newer version (schema {}); update the app to restore it", schema),
+    )
+}
+
+fn to_sql(value: &JsonValue) -> Value {
+    match value {
+        JsonValue::Null => Value::Null,
+        JsonValue::Bool(b) => Value::Integer(*b as i64),
+        JsonValue::Number(n) => n
+            .as_i64()
+            .map(Value::Integer)
+            .or_else(|| n.as_f64().map(Value::Real))
+            .unwrap_or(Value::Null),
+        JsonValue::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+fn table_columns(conn: &Connection, table: &str) -> SqliteResult<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get(1))?.collect();
+    columns
+}
+
+// Columns the backup has that the table doesn't are ignored; ones it lacks
+// take the column default. Returns whether a row was inserted.
+fn insert_row(conn: &Connection, table: &str, columns: &[String], row: &Row, or_ignore: bool) -> SqliteResult<bool> {
+    let present: Vec<&String> = columns.iter().filter(|c| row.contains_key(c.as_str())).collect();
+    if present.is_empty() {
+        return Ok(false);
+    }
+    let sql = format!(
+        "INSERT {}INTO {} ({}) VALUES ({})",
+        if or_ignore { "OR IGNORE " } else { "" },
+        table,
+        present.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", "),
+        vec!["?"; present.len()].join(", ")
+    );
+    let values = present.iter().map(|c| to_sql(&row[c.as_str()]));
+    Ok(conn.execute(&sql, params_from_iter(values))? > 0)
+}
+
+// The rollover hour in the backup's settings, which its entries were
+// written under
+fn backup_day_starts_at_hour(backup: &Backup) -> i32 {
+    backup
+        .tables
+        .get("app_settings")
+        .into_iter()
+        .flatten()
+        .find(|row| row.get("key").and_then(JsonValue::as_str) == Some("day_starts_at_hour"))
+        .and_then(|row| serde_json::from_str(row.get("value")?.as_str()?).ok())
+        .unwrap_or(0)
+}
+
+// Entries are dated from their timestamp, whatever date the backup has
+fn with_derived_date(row: &Row, day_starts_at_hour: i32) -> Row {
+    let mut row = row.clone();
+    if let Some(at) = row.get("timestamp").and_then(JsonValue::as_str).and_then(dates::parse_timestamp) {
+        row.insert("date".to_string(), JsonValue::String(db::entry_date(at, day_starts_at_hour)));
+    }
+    row
+}
+
+// A range on timestamp, so each lookup is a seek on idx_timestamp rather
+// than a scan of every entry
+pub const ENTRY_EXISTS_QUERY: &str = "SELECT EXISTS(SELECT 1 FROM water_entries
+     WHERE timestamp BETWEEN datetime(?1, printf('-%d seconds', ?3)) AND datetime(?1, printf('+%d seconds', ?3))
+       AND amount_ml = ?2)";
+
+fn entry_exists(conn: &Connection, row: &Row) -> SqliteResult<bool> {
+    conn.query_row(
+        ENTRY_EXISTS_QUERY,
+        [
+            to_sql(row.get("timestamp").unwrap_or(&JsonValue::Null)),
+            to_sql(row.get("amount_ml").unwrap_or(&JsonValue::Null)),
+            Value::Integer(MERGE_TOLERANCE_SECONDS),
+        ],
+        |row| row.get(0),
+    )
+}
+
+fn row_key(table: &str, row: &Row) -> String {
+    let text = |column: &str| match row.get(column) {
+        Some(JsonValue::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    match table {
+        "water_entries" => format!("{} {} ml", text("timestamp"), text("amount_ml")),
+        "app_settings" => text("key"),
+        "goal_presets" => text("name"),
+        "streak_freezes" => text("date"),
+        "achievements" => text("key"),
+        "milestones_reached" => text("threshold_ml"),
+        _ => String::new(),
+    }
+}
+
+// Whether the backup's settings should replace differing local ones
+fn backup_settings_win(conn: &Connection, backup: &Backup, strategy: ConflictStrategy) -> SqliteResult<bool> {
+    match strategy {
+        ConflictStrategy::KeepLocal => Ok(false),
+        ConflictStrategy::KeepBackup => Ok(true),
+        ConflictStrategy::Newest => {
+            let local: Option<String> = settings::get(conn, settings::UPDATED_AT_KEY)?;
+            // Older backups lack the key; their export time is the latest the
+            // settings in them can be from
+            let from_backup = backup
+                .tables
+                .get("app_settings")
+                .into_iter()
+                .flatten()
+                .find(|row| row.get("key").and_then(JsonValue::as_str) == Some(settings::UPDATED_AT_KEY))
+                .and_then(|row| row.get("value")?.as_str())
+                .and_then(|value| serde_json::from_str::<String>(value).ok())
+                .unwrap_or_else(|| backup.exported_at.clone());
+            Ok(local.is_none_or(|local| from_backup > local))
+        }
+    }
+}
+
+// A differing setting is overridden or left alone; an equal one is skipped
+fn merge_setting(conn: &Connection, row: &Row, backup_wins: bool) -> SqliteResult<MergeAction> {
+    let (Some(key), Some(value)) = (row.get("key"), row.get("value")) else {
+        return Ok(MergeAction::Skipped);
+    };
+    let local: Option<String> = conn
+        .query_row("SELECT value FROM app_settings WHERE key = ?1", [to_sql(key)], |r| r.get(0))
+        .optional()?;
+    match local {
+        None => {
+            conn.execute("INSERT INTO app_settings (key, value) VALUES (?1, ?2)", [to_sql(key), to_sql(value)])?;
+            Ok(MergeAction::Inserted)
+        }
+        Some(local) if backup_wins && Some(local.as_str()) != value.as_str() => {
+            conn.execute("UPDATE app_settings SET value = ?2 WHERE key = ?1", [to_sql(key), to_sql(value)])?;
+            Ok(MergeAction::Overridden)
+        }
+        Some(_) => Ok(MergeAction::Skipped),
+    }
+}
+
+// A compact, consistent copy of the live database at `path`. VACUUM INTO
+// reads through SQLite inside one read transaction, so the copy can't be
+// torn. The caller holds the connection's lock throughout, so other commands
+// simply wait for the copy to finish. An existing file is only replaced with
+// `overwrite`, and then only once the new copy is complete.
+pub fn snapshot_to(conn: &Connection, path: &Path, overwrite: bool) -> Result<u64, AppError> {
+    if path.exists() && !overwrite {
+        return Err(AppError::invalid("path", format!("{} already exists", path.display())));
+    }
+    let tmp = path.with_extension("tmp");
+    let _ = fs::remove_file(&tmp);
+    let result = db::vacuum_into(conn, &tmp)
+        .map_err(AppError::from)
+        .and_then(|_| Ok(fs::rename(&tmp, path)?));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(AppError::Other(format!("Can't write {}: {}", path.display(), e)));
+    }
+    Ok(fs::metadata(path)?.len())
+}
+
+fn snapshot(conn: &Connection) -> Result<PathBuf, AppError> {
+    let dir = datadir::backups_dir()?;
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("before-restore-{}.db", Local::now().format("%Y%m%d-%H%M%S")));
+    db::vacuum_into(conn, &path)
+        .map_err(|e| AppError::Other(format!("Can't snapshot the database before restoring: {}", e)))?;
+    Ok(path)
+}
+
+// Why the restore transaction was undone: a dry run undoes it on purpose,
+// carrying the summary of what it would have done
+enum Undone {
+    DryRun(Box<RestoreSummary>),
+    Failed(AppError),
+}
+
+impl From<rusqlite::Error> for Undone {
+    fn from(e: rusqlite::Error) -> Self {
+        Undone::Failed(e.into())
+    }
+}
+
+impl From<AppError> for Undone {
+    fn from(e: AppError) -> Self {
+        Undone::Failed(e)
+    }
+}
+
+pub fn restore(
+    conn: &Connection,
+    backup: &Backup,
+    mode: RestoreMode,
+    strategy: ConflictStrategy,
+    dry_run: bool,
+) -> Result<RestoreSummary, AppError> {
+    let snapshot_path = match mode {
+        RestoreMode::Replace if !dry_run => Some(snapshot(conn)?),
+        _ => None,
+    };
+
+    let result = db::with_tx(conn, |tx| {
+        let summary = restore_tables(tx, backup, mode, strategy, dry_run, snapshot_path)?;
+        if dry_run {
+            return Err(Undone::DryRun(Box::new(summary)));
+        }
+        Ok(summary)
+    });
+    match result {
+        Ok(summary) => Ok(summary),
+        Err(Undone::DryRun(summary)) => Ok(*summary),
+        Err(Undone::Failed(e)) => Err(e),
+    }
+}
+
+fn restore_tables(
+    tx: &Connection,
+    backup: &Backup,
+    mode: RestoreMode,
+    strategy: ConflictStrategy,
+    dry_run: bool,
+    snapshot_path: Option<PathBuf>,
+) -> Result<RestoreSummary, AppError> {
+    let backup_wins = backup_settings_win(tx, backup, strategy)?;
+    // Restored entries are dated under the rollover hour they were written
+    // with; entries already here keep the dates they have
+    let day_starts_at_hour = match mode {
+        RestoreMode::Replace => backup_day_starts_at_hour(backup),
+        RestoreMode::Merge => db::get_day_starts_at_hour(tx),
+    };
+    let mut summary = RestoreSummary {
+        dry_run,
+        inserted: BTreeMap::new(),
+        skipped: BTreeMap::new(),
+        overridden: BTreeMap::new(),
+        items: Vec::new(),
+        snapshot_path: snapshot_path.map(|p| p.display().to_string()),
+    };
+
+    for table in TABLES {
+        let mut columns = table_columns(tx, table)?;
+        if mode == RestoreMode::Replace {
+            tx.execute(&format!("DELETE FROM {}", table), [])?;
+        } else if table == "water_entries" {
+            // Ids are local to each database; merged entries get new ones
+            columns.retain(|c| c != "id");
+        }
+
+        let mut counts = BTreeMap::new();
+        for row in backup.tables.get(table).into_iter().flatten() {
+            let row = match table {
+                "water_entries" => Cow::Owned(with_derived_date(row, day_starts_at_hour)),
+                _ => Cow::Borrowed(row),
+            };
+            let row = row.as_ref();
+            let inserted = |is_new: bool| if is_new { MergeAction::Inserted } else { MergeAction::Skipped };
+            let action = match mode {
+                RestoreMode::Replace => insert_row(tx, table, &columns, row, false).map(inserted),
+                RestoreMode::Merge if table == "water_entries" => match entry_exists(tx, row) {
+                    Ok(true) => Ok(MergeAction::Skipped),
+                    Ok(false) => insert_row(tx, table, &columns, row, false).map(inserted),
+                    Err(e) => Err(e),
+                },
+                RestoreMode::Merge if table == "app_settings" => merge_setting(tx, row, backup_wins),
+                RestoreMode::Merge => insert_row(tx, table, &columns, row, true).map(inserted),
+            }
+            .map_err(|e| AppError::Other(format!("Restoring {} failed: {}", table, e)))?;
+
+            *counts.entry(action).or_insert(0) += 1;
+            if mode == RestoreMode::Merge {
+                summary.items.push(MergeItem {
+                    table: table.to_string(),
+                    key: row_key(table, row),
+                    action,
+                });
+            }
+        }
+        for (map, action) in [
+            (&mut summary.inserted, MergeAction::Inserted),
+            (&mut summary.skipped, MergeAction::Skipped),
+            (&mut summary.overridden, MergeAction::Overridden),
+        ] {
+            map.insert(table.to_string(), counts.get(&action).copied().unwrap_or(0));
+        }
+    }
+
+    Ok(summary)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BackupFile {
+    pub name: String,
+    pub path: String,
+    pub modified_at: String,
+    pub size_bytes: u64,
+    // Made by the daily backup rather than before a restore
+    pub automatic: bool,
+}
+
+// Newest first
+pub fn list() -> Result<Vec<BackupFile>, AppError> {
+    let dir = datadir::backups_dir()?;
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "db"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            Some(BackupFile {
+                automatic: name.starts_with(AUTO_BACKUP_PREFIX),
+                path: entry.path().display().to_string(),
+                modified_at: DateTime::<Local>::from(metadata.modified().ok()?)
+                    .format(dates::TIMESTAMP_FORMAT)
+                    .to_string(),
+                size_bytes: metadata.len(),
+                name,
+            })
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    Ok(files)
+}
+
+// Automatic backups are named by date, so name order is age order
+fn prune(dir: &Path, keep: usize) -> std::io::Result<()> {
+    let mut names: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(AUTO_BACKUP_PREFIX) && name.ends_with(".db"))
+        .collect();
+    names.sort();
+    let excess = names.len().saturating_sub(keep);
+    for name in &names[..excess] {
+        fs::remove_file(dir.join(name))?;
+    }
+    Ok(())
+}
+
+// Returns the new file, or None when today's backup already exists
+fn run_auto_backup(conn: &Connection, keep: usize, today: NaiveDate) -> Result<Option<PathBuf>, AppError> {
+    let dir = datadir::backups_dir()?;
+    let path = dir.join(format!("{}{}.db", AUTO_BACKUP_PREFIX, today.format(dates::DATE_FORMAT)));
+    if path.exists() {
+        return Ok(None);
+    }
+    fs::create_dir_all(&dir)?;
+    db::vacuum_into(conn, &path)?;
+    prune(&dir, keep).map_err(|e| AppError::Other(format!("pruning old backups: {}", e)))?;
+    Ok(Some(path))
+}
+
+// Failures are logged every time but reported to the user once a day, so a
+// backup that keeps failing isn't silent and isn't a notification per entry
+pub fn auto_backup(conn: &Connection, settings: &Settings, today: NaiveDate) -> Option<String> {
+    if !settings.auto_backup_enabled {
+        return None;
+    }
+    match run_auto_backup(conn, settings.auto_backup_keep as usize, today) {
+        Ok(_) => {
+            let _ = settings::remove(conn, AUTO_BACKUP_FAILED_KEY);
+            None
+        }
+        Err(e) => {
+            tracing::error!("automatic backup failed: {}", e);
+            let today = today.format(dates::DATE_FORMAT).to_string();
+            let reported: Option<String> = settings::get(conn, AUTO_BACKUP_FAILED_KEY).ok().flatten();
+            if reported.as_deref() == Some(today.as_str()) {
+                return None;
+            }
+            let _ = settings::set(conn, AUTO_BACKUP_FAILED_KEY, &today);
+            Some(e.to_string())
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// Progress checkpoints such as "40% by 13:00". A background loop wakes up
+// once a minute and looks at every checkpoint that passed since its previous
+// pass, so a machine that slept through the exact minute still gets checked.
+
+use crate::settings::ValidationError;
+use crate::{reminders, units, AppError, Database, DailyStats, Settings};
+use chrono::{Local, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::{thread, time::Duration};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Checkpoint {
+    // Minutes since midnight
+    pub time_of_day: i32,
+    pub expected_percentage: f32,
+}
+
+impl Checkpoint {
+    fn time(&self) -> Option<NaiveTime> {
+        NaiveTime::from_hms_opt(self.time_of_day as u32 / 60, self.time_of_day as u32 % 60, 0)
+    }
+}
+
+pub fn validate(checkpoints: &[Checkpoint]) -> Result<(), ValidationError> {
+    for checkpoint in checkpoints {
+        if !(0..reminders::MINUTES_PER_DAY).contains(&checkpoint.time_of_day) {
+            return Err(ValidationError::new("checkpoints", "times must be between 0 and 1439 minutes"));
+        }
+        if !(checkpoint.expected_percentage > 0.0 && checkpoint.expected_percentage <= 100.0) {
+            return Err(ValidationError::new(
+                "checkpoints",
+                "percentages must be above 0 and at most 100",
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Latest checkpoint whose time falls in (last_checked, now]. After a long
+// sleep several may have passed; only the most recent one still matters.
+pub fn passed_checkpoint(
+    checkpoints: &[Checkpoint],
+    last_checked: NaiveDateTime,
+    now: NaiveDateTime,
+) -> Option<Checkpoint> {
+    checkpoints
+        .iter()
+        .filter_map(|c| Some((now.date().and_time(c.time()?), *c)))
+        .filter(|(at, _)| *at > last_checked && *at <= now)
+        .max_by_key(|(at, _)| *at)
+        .map(|(_, c)| c)
+}
+
+// Millilitres short of the checkpoint, or None when it's already satisfied
+pub fn shortfall_ml(checkpoint: &Checkpoint, stats: &DailyStats) -> Option<i32> {
+    if stats.percentage >= checkpoint.expected_percentage {
+        return None;
+    }
+    let expected_ml = (stats.goal_ml as f32 * checkpoint.expected_percentage / 100.0).ceil() as i32;
+    Some(expected_ml - stats.total_ml).filter(|ml| *ml > 0)
+}
+
+fn check(app: &AppHandle, last_checked: NaiveDateTime, now: NaiveDateTime) -> Result<(), AppError> {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let settings: Settings = crate::settings::load(&conn)?;
+
+    let Some(checkpoint) = passed_checkpoint(&settings.checkpoints, last_checked, now) else {
+        return Ok(());
+    };
+    if !reminders::is_reminder_day(&settings, now)
+        || reminders::in_quiet_hours(&settings, reminders::minute_of_day(now))
+        || reminders::is_snoozed(&conn, now)?
+    {
+        return Ok(());
+    }
+    let stats = crate::today_stats(&conn)?;
+    drop(conn);
+
+    if let Some(behind_ml) = shortfall_ml(&checkpoint, &stats) {
+        let _ = app
+            .notification()
+            .builder()
+            .title("💧 Falling behind")
+            .body(format!(
+                "You're {} behind — aim for {:.0}% by {:02}:{:02}.",
+                units::format_amount(behind_ml, settings.unit),
+                checkpoint.expected_percentage,
+                checkpoint.time_of_day / 60,
+                checkpoint.time_of_day % 60
+            ))
+            .show();
+    }
+    Ok(())
+}
+
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last_checked = Local::now().naive_local();
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+            let now = Local::now().naive_local();
+            let _ = check(&app, last_checked, now);
+            last_checked = now;
+        }
+    });
+}
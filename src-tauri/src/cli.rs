@@ -0,0 +1,134 @@
+// Command-line subcommands:
+//
+//   hydra-tracker add 500
+//   hydra-tracker add 500 --at "2024-06-01 14:00"
+//   hydra-tracker stats
+//   hydra-tracker cleanup
+//
+// `stats` only reads the database, so the launching process answers it and
+// exits without starting the app, as does `cleanup`, which the Windows
+// uninstaller runs to remove what the app left outside its data folder.
+// `add` goes through the app: a second invocation is forwarded to the
+// running instance by the single-instance plugin, and a first one is handled
+// during startup. Either way the result is shown as a notification, since
+// the launching process has already exited.
+
+use crate::{dates, notifications, settings, tray, units, AppError, Database};
+use chrono::{Local, NaiveDateTime};
+use rusqlite::Connection;
+use tauri::{AppHandle, Manager};
+
+pub const ENTRY_SOURCE: &str = "cli";
+
+pub const USAGE: &str = "Usage:
+  hydra-tracker add <ml> [--at \"YYYY-MM-DD HH:MM\"]   log an entry, optionally back-dated
+  hydra-tracker stats                                 print today's progress
+  hydra-tracker cleanup                               remove the Windows jump list";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Add { amount_ml: i32, at: Option<NaiveDateTime> },
+    Stats,
+    Cleanup,
+}
+
+fn parse_at(value: &str) -> Result<NaiveDateTime, String> {
+    dates::parse_timestamp(value)
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").ok())
+        .ok_or_else(|| format!("'{}' is not a time like \"2024-06-01 14:00\"", value))
+}
+
+fn parse_add(args: &[String]) -> Result<Command, String> {
+    let (mut amount_ml, mut at) = (None, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--at" => {
+                let value = args.next().ok_or("--at needs a time")?;
+                at = Some(parse_at(value)?);
+            }
+            value if amount_ml.is_none() => {
+                let ml = value
+                    .trim_end_matches("ml")
+                    .parse::<i32>()
+                    .map_err(|_| format!("'{}' is not an amount in ml", value))?;
+                amount_ml = Some(ml);
+            }
+            value => return Err(format!("unexpected argument '{}'", value)),
+        }
+    }
+    let amount_ml = amount_ml.ok_or("add needs an amount in ml")?;
+    if amount_ml <= 0 {
+        return Err(format!("amount {} must be positive", amount_ml));
+    }
+    Ok(Command::Add { amount_ml, at })
+}
+
+// `args` as from std::env::args, program name first. Ok(None) for a plain
+// launch, which includes flags such as autostart's --hidden and the link a
+// hydra:// launch passes.
+pub fn parse(args: &[String]) -> Result<Option<Command>, String> {
+    let Some(subcommand) = args.get(1) else {
+        return Ok(None);
+    };
+    if subcommand.starts_with('-') || subcommand.contains(':') {
+        return Ok(None);
+    }
+    match subcommand.as_str() {
+        "add" => parse_add(&args[2..]).map(Some),
+        "stats" if args.len() == 2 => Ok(Some(Command::Stats)),
+        "stats" => Err("stats takes no arguments".to_string()),
+        "cleanup" if args.len() == 2 => Ok(Some(Command::Cleanup)),
+        "cleanup" => Err("cleanup takes no arguments".to_string()),
+        other => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+// "Today: 2,250 / 4,000 ml (56%)"
+pub fn stats_text(conn: &Connection) -> Result<String, AppError> {
+    let unit = settings::load(conn)?.unit;
+    let stats = crate::today_stats(conn)?;
+    Ok(format!("Today: {}", tray::progress_text(&stats, unit)))
+}
+
+// Refusals are worded for the command line rather than naming a field
+fn add(app: &AppHandle, amount_ml: i32, at: Option<NaiveDateTime>) -> Result<String, AppError> {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let settings = settings::load(&conn)?;
+    if amount_ml > settings.max_entry_ml {
+        return Err(AppError::Other(format!(
+            "{} ml is more than the {} ml allowed per entry",
+            amount_ml, settings.max_entry_ml
+        )));
+    }
+    let now = Local::now().naive_local();
+    if at.is_some_and(|at| at > now) {
+        return Err(AppError::Other("--at can't be in the future".to_string()));
+    }
+    crate::record_entry_at(app, &conn, amount_ml, ENTRY_SOURCE, at.unwrap_or(now))?;
+    drop(conn);
+
+    let amount = units::format_amount(amount_ml, settings.unit);
+    Ok(match at {
+        Some(at) => format!("Logged {} at {}.", amount, at.format("%Y-%m-%d %H:%M")),
+        None => format!("Logged {}.", amount),
+    })
+}
+
+// Runs a forwarded or startup `add`. Must be called without the database lock.
+pub fn run(app: &AppHandle, args: &[String]) {
+    let result = match parse(args) {
+        Ok(Some(Command::Add { amount_ml, at })) => add(app, amount_ml, at),
+        // Answered by the launching process
+        Ok(Some(Command::Stats | Command::Cleanup)) | Ok(None) => return,
+        Err(e) => Err(AppError::Other(e)),
+    };
+    match result {
+        Ok(message) => notifications::show_logged(app, &message),
+        Err(e) => {
+            tracing::error!("couldn't log water from the command line: {}", e);
+            notifications::show_plain(app, "Couldn't log water", &e.to_string());
+        }
+    }
+}
@@ -0,0 +1,1107 @@
+// The #[tauri::command] handlers. Each one takes the database lock, calls
+// into db, stats or the feature modules, and sends whatever events follow;
+// the queries themselves live with those modules.
+//
+// Synchronous commands run on the main thread, which also drives every
+// window, so only quick ones stay synchronous. Anything that reads a year of
+// history or writes a file is async and does its work through `with_db` on
+// the blocking pool; the connection stays behind the one Mutex, so those
+// still wait their turn for it, but the window doesn't wait with them.
+//
+// Commands that change stored data run through `logged`, so the log has
+// each one with what it was given and how it went.
+
+use crate::db::{get_base_goal_ml, get_day_starts_at_hour, logical_today};
+use crate::models::ChangeKind;
+use crate::stats::{calculate_streaks, monthly_stats_with_streaks, today_stats};
+use crate::{
+    achievements, analytics, anonymize, autostart, backup, csv_export, csv_import, datadir, dates, db, freezes,
+    goal_calculator, goals, health_export, hydrocoach, ical, logging, maintenance, notifications, presets, reminders, report, retention,
+    scheduler, settings, share_card, shortcuts, sound, stats, text_import, theme, units, waterminder, widget,
+    window_state,
+};
+use crate::{AddWaterOutcome, AllTimeStats, AppError, DailyStats, Database, MonthlyStats, Settings, WaterEntry, YearOverYear};
+use chrono::{Datelike, Local, NaiveDate};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fmt;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const ENTRY_SOURCE_APP: &str = "app";
+// Most lines get_recent_logs returns at once
+const MAX_RECENT_LOG_LINES: usize = 5000;
+
+// Runs `work` on the blocking thread pool with the app and the database.
+// Every command that takes the database lock goes through here: synchronous
+// commands run on the main thread, which would freeze every window while
+// an import or a backup holds the lock.
+async fn with_db<T: Send + 'static>(
+    app: AppHandle,
+    work: impl FnOnce(&AppHandle, &Database) -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tauri::async_runtime::spawn_blocking(move || work(&app, &app.state::<Database>()))
+        .await
+        .map_err(|e| AppError::Other(format!("Background task failed: {}", e)))?
+}
+
+// A "YYYY-MM-DD" argument, named by `field` when it doesn't parse
+fn parse_date_arg(field: &str, value: &str) -> Result<NaiveDate, AppError> {
+    dates::parse_date(value).ok_or_else(|| AppError::invalid(field, "must be a date like 2024-06-01"))
+}
+
+fn date_range(start_date: &str, end_date: &str) -> Result<(NaiveDate, NaiveDate), AppError> {
+    let start = parse_date_arg("start_date", start_date)?;
+    let end = parse_date_arg("end_date", end_date)?;
+    if start > end {
+        return Err(AppError::invalid("start_date", "must not be after end_date"));
+    }
+    Ok((start, end))
+}
+
+// Runs a command that changes stored data, logging its name, a summary of
+// its arguments and the outcome. Summaries stick to amounts, ids, dates and
+// counts; see `logging` for what never goes in.
+fn logged<T>(command: &str, args: fmt::Arguments, work: impl FnOnce() -> Result<T, AppError>) -> Result<T, AppError> {
+    let result = work();
+    match &result {
+        Ok(_) => tracing::info!("{}({}) ok", command, args),
+        Err(e) => tracing::error!("{}({}) failed: {}", command, args, e),
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn add_water(
+    app: AppHandle,
+    amount_ml: i32,
+    force: Option<bool>,
+) -> Result<AddWaterOutcome, AppError> {
+    with_db(app, move |app, db| {
+        logged("add_water", format_args!("amount_ml={} force={:?}", amount_ml, force), || {
+            if amount_ml <= 0 {
+                return Err(AppError::invalid("amount_ml", "must be positive"));
+            }
+            let conn = db.lock();
+
+            if let Some(outcome) = crate::check_entry_limit(&conn, amount_ml, force.unwrap_or(false))? {
+                return Ok(outcome);
+            }
+
+            let entry = crate::record_entry(app, &conn, amount_ml, ENTRY_SOURCE_APP)?;
+            Ok(AddWaterOutcome::Added { entry })
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn remove_entry(app: AppHandle, id: i64) -> Result<(), AppError> {
+    with_db(app, move |app, db| {
+        logged("remove_entry", format_args!("id={}", id), || {
+            let conn = db.lock();
+            let Some(date) = db::delete_entry(&conn, id)? else {
+                return Err(AppError::NotFound(format!("entry {}", id)));
+            };
+            notifications::entries_changed(app, ChangeKind::Removed, [date]);
+            Ok(())
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_today_stats(app: AppHandle) -> Result<DailyStats, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        today_stats(&conn).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_today_entries(app: AppHandle) -> Result<Vec<WaterEntry>, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        db::entries_on(&conn, logical_today(&conn)).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_monthly_stats(app: AppHandle, year: i32, month: u32) -> Result<MonthlyStats, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        monthly_stats_with_streaks(&conn, year, month).map_err(AppError::from)
+    })
+    .await
+}
+
+// Summary, per-week table and adherence for any range, with fonts embedded
+#[tauri::command]
+pub async fn generate_pdf_report(
+    app: AppHandle,
+    start_date: String,
+    end_date: String,
+    path: String,
+) -> Result<report::PdfReport, AppError> {
+    with_db(app, move |_, db| {
+        let (start, end) = date_range(&start_date, &end_date)?;
+
+        let conn = db.lock();
+        let settings = settings::load(&conn)?;
+        let name = settings.user_name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+        let (bytes, page_count) = report::range_pdf(
+            &conn,
+            start,
+            end,
+            logical_today(&conn),
+            db::goal_schedule(&conn),
+            settings.unit,
+            name,
+        )?;
+        drop(conn);
+        std::fs::write(&path, bytes)?;
+        Ok(report::PdfReport { path, page_count })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn render_share_card(
+    app: AppHandle,
+    kind: share_card::CardKind,
+    path: String,
+) -> Result<String, AppError> {
+    with_db(app, move |app, db| {
+        let conn = db.lock();
+        let mut settings = settings::load(&conn)?;
+        theme::fill_resolved(app, &mut settings);
+        let today = logical_today(&conn);
+        let amount = |ml: i32| units::format_amount(ml, settings.unit);
+
+        let content = match kind {
+            share_card::CardKind::Today => {
+                let stats = today_stats(&conn)?;
+                share_card::CardContent {
+                    title: "Today".to_string(),
+                    value: amount(stats.total_ml),
+                    detail: format!("{:.0}% of the {} goal", stats.percentage, amount(stats.goal_ml)),
+                    progress: Some(stats.percentage / 100.0),
+                }
+            }
+            share_card::CardKind::Streak => {
+                let (current, best) = calculate_streaks(&conn, db::goal_schedule(&conn), today);
+                share_card::CardContent {
+                    title: "Current streak".to_string(),
+                    value: format!("{} {}", current, if current == 1 { "day" } else { "days" }),
+                    detail: format!("Best: {} days", best),
+                    progress: None,
+                }
+            }
+            share_card::CardKind::Month => {
+                let stats = monthly_stats_with_streaks(&conn, today.year(), today.month())?;
+                share_card::CardContent {
+                    title: format!("{} {}", stats.month, stats.year),
+                    value: amount(stats.total_ml),
+                    detail: format!(
+                        "Goal met on {} days ({:.0}% adherence)",
+                        stats.days_goal_met, stats.adherence_percentage
+                    ),
+                    progress: Some(stats.adherence_percentage / 100.0),
+                }
+            }
+        };
+        drop(conn);
+
+        let png = share_card::render(&content, &settings, today)?;
+        std::fs::write(&path, png)?;
+        Ok(path)
+    })
+    .await
+}
+
+// Self-contained HTML summary of a month, built from get_monthly_stats' data
+#[tauri::command]
+pub async fn generate_report(app: AppHandle, year: i32, month: u32, path: String) -> Result<(), AppError> {
+    with_db(app, move |_, db| {
+        if !(1..=12).contains(&month) {
+            return Err(AppError::invalid("month", "must be between 1 and 12"));
+        }
+        let conn = db.lock();
+        let stats = monthly_stats_with_streaks(&conn, year, month)?;
+        let unit = settings::load(&conn)?.unit;
+        drop(conn);
+        std::fs::write(&path, report::monthly_html(&stats, unit)).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn compare_year_over_year(app: AppHandle, year: i32, month: u32) -> Result<YearOverYear, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        stats::year_over_year(&conn, year, month).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_settings(app: AppHandle) -> Result<Settings, AppError> {
+    with_db(app, move |app, db| {
+        let conn = db.lock();
+        let mut settings = settings::load(&conn)?;
+        theme::fill_resolved(app, &mut settings);
+        Ok(settings)
+    })
+    .await
+}
+
+// Returns the settings as stored and broadcasts them as `settings-changed`,
+// which the tray listens for as well as every window
+#[tauri::command]
+pub async fn save_settings(app: AppHandle, settings: Settings) -> Result<Settings, AppError> {
+    with_db(app, move |app, db| {
+        logged("save_settings", format_args!(""), || {
+            let conn = db.lock();
+            apply_settings(app, &conn, &settings)
+        })
+    })
+    .await
+}
+
+// Validate, sync the login item, store and broadcast. Everything that
+// changes settings wholesale goes through here so the side effects match.
+fn apply_settings(app: &AppHandle, conn: &Connection, settings: &Settings) -> Result<Settings, AppError> {
+    settings::validate(settings)?;
+
+    // Only touch the login item on an actual change, and before saving, so a
+    // failure leaves the stored flag matching the OS
+    let previous = settings::load(conn)?;
+    if previous.start_with_system != settings.start_with_system {
+        autostart::apply(app, settings.start_with_system)?;
+    }
+    settings::save(conn, settings)?;
+    let mut saved = settings::load(conn)?;
+    theme::fill_resolved(app, &mut saved);
+    // Names only; some values are paths
+    tracing::info!("settings changed: {}", settings::changed_fields(&previous, &saved).join(", "));
+
+    let _ = app.emit(settings::CHANGED_EVENT, &saved);
+    let _ = app.emit(theme::RESOLVED_EVENT, &saved.resolved_theme);
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn export_settings(app: AppHandle, path: String) -> Result<(), AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        let settings = settings::load(&conn)?;
+        drop(conn);
+        std::fs::write(&path, settings::export_json(&settings)?).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn export_backup(app: AppHandle, path: String) -> Result<backup::BackupSummary, AppError> {
+    with_db(app, move |app, db| {
+        let conn = db.lock();
+        let backup = backup::create(&conn, &app.package_info().version.to_string())?;
+        drop(conn);
+        backup::write(&backup, std::path::Path::new(&path))
+    })
+    .await
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreResult {
+    #[serde(flatten)]
+    summary: backup::RestoreSummary,
+    settings: Settings,
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    path: String,
+    mode: backup::RestoreMode,
+    strategy: Option<backup::ConflictStrategy>,
+    dry_run: Option<bool>,
+) -> Result<RestoreResult, AppError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let strategy = strategy.unwrap_or_default();
+    with_db(app, move |app, db| {
+        logged("restore_backup", format_args!("mode={:?} strategy={:?} dry_run={}", mode, strategy, dry_run), || {
+            let backup = backup::read(std::path::Path::new(&path))?;
+            let conn = db.lock();
+            let summary = backup::restore(&conn, &backup, mode, strategy, dry_run)?;
+            if dry_run {
+                let mut settings = settings::load(&conn)?;
+                theme::fill_resolved(app, &mut settings);
+                return Ok(RestoreResult { summary, settings });
+            }
+            // Restored entries can unlock what the backup's records didn't have
+            let unlocked = db::with_tx(&conn, |conn| {
+                crate::check_unlocked(conn, &today_stats(conn)?, Local::now().naive_local())
+            })?;
+            crate::announce_unlocked(app, &conn, unlocked)?;
+            drop(conn);
+
+            // The restored login-item flag may not match what the OS has
+            let _ = autostart::reconcile(app);
+            let conn = db.lock();
+            let mut settings = settings::load(&conn)?;
+            theme::fill_resolved(app, &mut settings);
+            let _ = app.emit(settings::CHANGED_EVENT, &settings);
+            notifications::entries_changed(app, ChangeKind::Restored, []);
+            Ok(RestoreResult { summary, settings })
+        })
+    })
+    .await
+}
+
+// Dietary-water records for Apple Health; returns how many were written.
+// `volume` defaults to the effective amount.
+#[tauri::command]
+pub async fn export_apple_health(
+    app: AppHandle,
+    path: String,
+    start_date: String,
+    end_date: String,
+    volume: Option<health_export::Volume>,
+) -> Result<usize, AppError> {
+    with_db(app, move |_, db| {
+        let (start, end) = date_range(&start_date, &end_date)?;
+
+        let conn = db.lock();
+        let (xml, records) = health_export::apple_health_xml(
+            &conn,
+            start,
+            end,
+            volume.unwrap_or_default(),
+            Local::now().fixed_offset(),
+        )?;
+        drop(conn);
+        std::fs::write(&path, xml)?;
+        Ok(records)
+    })
+    .await
+}
+
+// Hydration points for Google Fit / Health Connect, as JSON or CSV
+#[tauri::command]
+pub async fn export_google_fit(
+    app: AppHandle,
+    path: String,
+    start_date: String,
+    end_date: String,
+    format: health_export::GoogleFitFormat,
+    volume: Option<health_export::Volume>,
+) -> Result<usize, AppError> {
+    with_db(app, move |_, db| {
+        let (start, end) = date_range(&start_date, &end_date)?;
+
+        let conn = db.lock();
+        let (contents, records) = health_export::google_fit(&conn, start, end, format, volume.unwrap_or_default())?;
+        drop(conn);
+        std::fs::write(&path, contents)?;
+        Ok(records)
+    })
+    .await
+}
+
+// Every entry in the layout import_csv reads; returns the number written
+#[tauri::command]
+pub async fn export_csv(app: AppHandle, path: String) -> Result<usize, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        csv_export::export(&conn, std::path::Path::new(&path))
+    })
+    .await
+}
+
+// How the last scheduled export went; None before the first one
+#[tauri::command]
+pub async fn get_export_status(app: AppHandle) -> Result<Option<csv_export::ExportStatus>, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        csv_export::status(&conn).map_err(AppError::from)
+    })
+    .await
+}
+
+// Puts the main window back to its default size and position
+#[tauri::command]
+pub async fn reset_window_geometry(app: AppHandle) -> Result<(), AppError> {
+    with_db(app, move |app, db| {
+        logged("reset_window_geometry", format_args!(""), || {
+            let conn = db.lock();
+            window_state::reset(app, &conn)
+        })
+    })
+    .await
+}
+
+// Which global shortcuts are in place, and why any of them aren't
+#[tauri::command]
+pub fn get_shortcut_status(registrations: State<shortcuts::ShortcutRegistrations>) -> Result<Vec<shortcuts::ShortcutStatus>, AppError> {
+    registrations.0.lock().map(|statuses| statuses.clone()).map_err(AppError::from)
+}
+
+// An all-day calendar event for each day of `year` that met the goal
+#[tauri::command]
+pub async fn export_ical(app: AppHandle, year: i32, path: String) -> Result<usize, AppError> {
+    with_db(app, move |_, db| {
+        let invalid_year = || AppError::invalid("year", "out of range");
+        let start = NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(invalid_year)?;
+        let end = NaiveDate::from_ymd_opt(year, 12, 31).ok_or_else(invalid_year)?;
+
+        let conn = db.lock();
+        let settings = settings::load(&conn)?;
+        let (ics, events) = ical::goal_met_calendar(&conn, start, end, db::goal_schedule(&conn), settings.unit)?;
+        drop(conn);
+        std::fs::write(&path, ics)?;
+        Ok(events)
+    })
+    .await
+}
+
+// Every entry with dates reduced to day numbers and times to the hour; see
+// anonymize.rs for exactly what is kept
+#[tauri::command]
+pub async fn export_anonymized(
+    app: AppHandle,
+    path: String,
+    format: Option<anonymize::AnonymizedFormat>,
+) -> Result<usize, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        let (contents, entries) = anonymize::export(
+            &conn,
+            format.unwrap_or(anonymize::AnonymizedFormat::Csv),
+            db::goal_schedule(&conn),
+        )?;
+        drop(conn);
+        std::fs::write(&path, contents)?;
+        Ok(entries)
+    })
+    .await
+}
+
+// Returns the size of the written file in bytes
+#[tauri::command]
+pub async fn export_database_snapshot(
+    app: AppHandle,
+    path: String,
+    overwrite: Option<bool>,
+) -> Result<u64, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        backup::snapshot_to(&conn, std::path::Path::new(&path), overwrite.unwrap_or(false))
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn list_backups() -> Result<Vec<backup::BackupFile>, AppError> {
+    backup::list()
+}
+
+// Shared by the importers: runs one against the database and refreshes the
+// open window when anything was added
+async fn run_import(
+    app: AppHandle,
+    command: &'static str,
+    import: impl FnOnce(&Connection, i32) -> Result<csv_import::CsvImportSummary, AppError> + Send + 'static,
+) -> Result<csv_import::CsvImportSummary, AppError> {
+    with_db(app, move |app, db| {
+        logged(command, format_args!(""), || {
+            // Whatever the importer writes lands together, with what it unlocks
+            let (summary, unlocked) = db.with_tx(|conn| {
+                let day_starts_at_hour = get_day_starts_at_hour(conn);
+                let summary = import(conn, day_starts_at_hour)?;
+                let unlocked = crate::check_unlocked(conn, &today_stats(conn)?, Local::now().naive_local())?;
+                Ok::<_, AppError>((summary, unlocked))
+            })?;
+            tracing::info!(
+                "imported {} entries, {} duplicates skipped, {} rows rejected",
+                summary.inserted,
+                summary.skipped_duplicates,
+                summary.rejected_total
+            );
+            crate::announce_unlocked(app, &db.lock(), unlocked)?;
+            if summary.inserted > 0 {
+                notifications::entries_changed(app, ChangeKind::Imported, summary.dates.iter().cloned());
+            }
+            Ok(summary)
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn import_csv(
+    app: AppHandle,
+    path: String,
+    strict: Option<bool>,
+) -> Result<csv_import::CsvImportSummary, AppError> {
+    run_import(app, "import_csv", move |conn, day_starts_at_hour| {
+        csv_import::import(conn, std::path::Path::new(&path), day_starts_at_hour, strict.unwrap_or(false))
+    })
+    .await
+}
+
+// How many entries a purge before `date` would delete, with the token that
+// confirms it
+#[tauri::command]
+pub async fn preview_purge(app: AppHandle, date: String) -> Result<retention::PurgePreview, AppError> {
+    with_db(app, move |app, db| {
+        let pending = app.state::<retention::PendingPurge>();
+        let before = parse_date_arg("date", &date)?;
+        let conn = db.lock();
+        retention::preview(&conn, &pending, before)
+    })
+    .await
+}
+
+// Deletes every entry before `date`; needs the token from preview_purge
+#[tauri::command]
+pub async fn purge_entries_before(app: AppHandle, date: String, token: String) -> Result<usize, AppError> {
+    with_db(app, move |app, db| {
+        logged("purge_entries_before", format_args!("before={}", date), || {
+            let pending = app.state::<retention::PendingPurge>();
+            let before = parse_date_arg("date", &date)?;
+            let conn = db.lock();
+            let deleted = retention::purge(&conn, &pending, before, &token)?;
+            drop(conn);
+            tracing::info!("purged {} entries before {}", deleted, before);
+            if deleted > 0 {
+                notifications::entries_changed(app, ChangeKind::Purged, []);
+            }
+            Ok(deleted)
+        })
+    })
+    .await
+}
+
+// Entries parsed from pasted notes for the logical day `date`; nothing is
+// stored until they're passed to commit_text_import
+#[tauri::command]
+pub async fn import_from_text(
+    app: AppHandle,
+    text: String,
+    date: String,
+) -> Result<text_import::TextImportPreview, AppError> {
+    with_db(app, move |_, db| {
+        let date = parse_date_arg("date", &date)?;
+        let conn = db.lock();
+        let max_entry_ml = settings::load(&conn)?.max_entry_ml;
+        Ok(text_import::parse(&text, date, get_day_starts_at_hour(&conn), max_entry_ml))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn commit_text_import(
+    app: AppHandle,
+    entries: Vec<text_import::TextEntry>,
+) -> Result<csv_import::CsvImportSummary, AppError> {
+    run_import(app, "commit_text_import", move |conn, day_starts_at_hour| {
+        let rows = text_import::rows(&entries);
+        csv_import::insert_rows(conn, rows, text_import::ENTRY_SOURCE, day_starts_at_hour, false)
+    })
+    .await
+}
+
+// Takes the exported CSV or the ZIP it comes in
+#[tauri::command]
+pub async fn import_hydrocoach(
+    app: AppHandle,
+    path: String,
+    strict: Option<bool>,
+) -> Result<csv_import::CsvImportSummary, AppError> {
+    run_import(app, "import_hydrocoach", move |conn, day_starts_at_hour| {
+        hydrocoach::import(conn, std::path::Path::new(&path), day_starts_at_hour, strict.unwrap_or(false))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn import_waterminder(
+    app: AppHandle,
+    path: String,
+    strict: Option<bool>,
+) -> Result<csv_import::CsvImportSummary, AppError> {
+    run_import(app, "import_waterminder", move |conn, day_starts_at_hour| {
+        waterminder::import(conn, std::path::Path::new(&path), day_starts_at_hour, strict.unwrap_or(false))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn import_settings(app: AppHandle, path: String) -> Result<settings::SettingsImport, AppError> {
+    with_db(app, move |app, db| {
+        logged("import_settings", format_args!(""), || {
+            let contents = std::fs::read_to_string(&path)?;
+            let conn = db.lock();
+            let current = settings::load(&conn)?;
+            let (imported, warnings) = settings::merge_import(&current, &contents)?;
+            let saved = apply_settings(app, &conn, &imported)?;
+
+            Ok(settings::SettingsImport {
+                changed: settings::changed_fields(&current, &saved),
+                warnings,
+                settings: saved,
+            })
+        })
+    })
+    .await
+}
+
+// Only suggests; nothing is written until apply_suggested_goal is called
+#[tauri::command]
+pub fn calculate_goal(
+    weight_kg: f64,
+    activity_level: goal_calculator::ActivityLevel,
+    climate: goal_calculator::Climate,
+) -> Result<i32, AppError> {
+    goal_calculator::suggested_goal_ml(weight_kg, activity_level, climate)
+}
+
+#[tauri::command]
+pub async fn apply_suggested_goal(
+    app: AppHandle,
+    weight_kg: f64,
+    activity_level: goal_calculator::ActivityLevel,
+    climate: goal_calculator::Climate,
+) -> Result<i32, AppError> {
+    with_db(app, move |app, db| {
+        logged("apply_suggested_goal", format_args!(""), || {
+            let goal_ml = goal_calculator::suggested_goal_ml(weight_kg, activity_level, climate)?;
+            let conn = db.lock();
+            goals::ensure_no_program(&conn, "daily_goal_ml")?;
+            settings::set(&conn, "daily_goal_ml", &goal_ml)?;
+            tracing::info!("daily goal set to {} ml", goal_ml);
+            emit_settings_changed(app, &conn);
+            Ok(goal_ml)
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_goal_presets(app: AppHandle) -> Result<Vec<presets::GoalPreset>, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        presets::list(&conn).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn save_goal_preset(
+    app: AppHandle,
+    name: String,
+    goal_ml: i32,
+) -> Result<presets::GoalPreset, AppError> {
+    with_db(app, move |app, db| {
+        logged("save_goal_preset", format_args!("goal_ml={}", goal_ml), || {
+            let conn = db.lock();
+            let preset = presets::save(&conn, &name, goal_ml)?;
+            emit_settings_changed(app, &conn);
+            Ok(preset)
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_goal_preset(app: AppHandle, name: String) -> Result<(), AppError> {
+    with_db(app, move |app, db| {
+        logged("delete_goal_preset", format_args!(""), || {
+            let conn = db.lock();
+            presets::delete(&conn, &name)?;
+            emit_settings_changed(app, &conn);
+            Ok(())
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn activate_goal_preset(app: AppHandle, name: String) -> Result<presets::GoalPreset, AppError> {
+    with_db(app, move |app, db| {
+        logged("activate_goal_preset", format_args!(""), || {
+            let conn = db.lock();
+            let preset = presets::activate(&conn, &name)?;
+            emit_settings_changed(app, &conn);
+            Ok(preset)
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_active_goal_preset(app: AppHandle) -> Result<Option<presets::GoalPreset>, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        presets::active(&conn).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn get_data_directory() -> Result<String, AppError> {
+    Ok(datadir::data_dir()?.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn open_data_directory() -> Result<(), AppError> {
+    datadir::reveal()
+}
+
+// Async, since building a window from a synchronous command can deadlock
+// on Windows
+#[tauri::command]
+pub async fn show_widget(app: AppHandle) -> Result<(), AppError> {
+    widget::show(&app)
+}
+
+#[tauri::command]
+pub async fn hide_widget(app: AppHandle) -> Result<(), AppError> {
+    with_db(app, move |app, _| {
+        widget::hide(app)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_widget_stats(app: AppHandle) -> Result<widget::WidgetStats, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        widget::stats(&conn)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn widget_quick_add(app: AppHandle) -> Result<widget::WidgetStats, AppError> {
+    with_db(app, move |app, _| {
+        logged("widget_quick_add", format_args!(""), || {
+            widget::quick_add(app)
+        })
+    })
+    .await
+}
+
+// Holds the database lock throughout, so nothing is written to the old file
+// after the copy is taken. The old files stay where they were.
+#[tauri::command]
+pub async fn move_data_directory(app: AppHandle, new_path: String) -> Result<String, AppError> {
+    with_db(app, move |_, db| {
+        logged("move_data_directory", format_args!(""), || {
+            let mut conn = db.lock();
+            let current_dir = datadir::data_dir()?;
+            let new_dir = std::path::PathBuf::from(&new_path);
+            let moved = datadir::move_to(&conn, &current_dir, &new_dir)?;
+            // Dropping the old connection closes it
+            *conn = moved;
+            Ok(new_dir.to_string_lossy().to_string())
+        })
+    })
+    .await
+}
+
+// Lets the settings screen try a file before saving it; with no path, plays
+// the configured sound
+#[tauri::command]
+pub async fn preview_sound(app: AppHandle, path: Option<String>) -> Result<(), AppError> {
+    with_db(app, move |_, db| {
+        let path = match path {
+            Some(path) => {
+                sound::validate_sound_file("path", &path)?;
+                Some(path)
+            }
+            None => {
+                let conn = db.lock();
+                settings::load(&conn)?.reminder_sound_path
+            }
+        };
+        sound::play(path);
+        Ok(())
+    })
+    .await
+}
+
+// Minutes until the next reminder; None means none are due for the rest of today
+#[tauri::command]
+pub async fn get_reminder_interval(app: AppHandle) -> Result<Option<i32>, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        crate::reminder_interval(&conn).map_err(AppError::from)
+    })
+    .await
+}
+
+// For stretch goals: keep reminding after the goal is met, for today only
+#[tauri::command]
+pub async fn resume_reminders(app: AppHandle) -> Result<(), AppError> {
+    with_db(app, move |app, db| {
+        logged("resume_reminders", format_args!(""), || {
+            let conn = db.lock();
+            reminders::resume(&conn, logical_today(&conn))?;
+            let state = reminders::snooze_state(&conn, Local::now().naive_local())?;
+            let _ = app.emit(reminders::SNOOZE_CHANGED_EVENT, &state);
+            Ok(())
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn snooze_reminders(app: AppHandle, minutes: i64) -> Result<reminders::SnoozeState, AppError> {
+    with_db(app, move |app, _| {
+        logged("snooze_reminders", format_args!("minutes={}", minutes), || {
+            scheduler::snooze(app, minutes)
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_snooze_state(app: AppHandle) -> Result<reminders::SnoozeState, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        reminders::snooze_state(&conn, Local::now().naive_local()).map_err(AppError::from)
+    })
+    .await
+}
+
+// Called by the frontend on mount and when the open-quick-add event arrives
+#[tauri::command]
+pub fn take_pending_quick_add(
+    pending: State<notifications::PendingQuickAdd>,
+) -> Result<Option<notifications::QuickAddContext>, AppError> {
+    Ok(pending.0.lock()?.take())
+}
+
+// Whether reminders are currently held back because the user is away
+#[tauri::command]
+pub fn get_away_state(app: AppHandle) -> scheduler::AwayState {
+    scheduler::away_state(&app)
+}
+
+// Re-derive every entry's date from its timestamp using the current rollover
+// hour. Saving the setting never does this on its own; returns rows changed.
+#[tauri::command]
+pub async fn recompute_entry_dates(app: AppHandle) -> Result<usize, AppError> {
+    with_db(app, move |app, db| {
+        logged("recompute_entry_dates", format_args!(""), || {
+            let conn = db.lock();
+            let changed = db::recompute_entry_dates(&conn)?;
+            tracing::info!("moved {} entries to another date", changed);
+            if changed > 0 {
+                notifications::entries_changed(app, ChangeKind::Redated, []);
+            }
+            Ok(changed)
+        })
+    })
+    .await
+}
+
+// Checks and compacts the database. Refused while a run is still going; a
+// failed integrity check comes back in the report, with nothing changed
+#[tauri::command]
+pub async fn run_maintenance(app: AppHandle) -> Result<maintenance::MaintenanceReport, AppError> {
+    let guard = maintenance::try_begin().ok_or(AppError::AlreadyRunning("Database maintenance"))?;
+    with_db(app, move |_, db| {
+        let _guard = guard;
+        logged("run_maintenance", format_args!(""), || {
+            let conn = db.lock();
+            let report = maintenance::run(&conn)?;
+            tracing::info!(
+                "integrity {}, {} -> {} bytes in {} ms",
+                if report.integrity_ok { "ok" } else { "failed" },
+                report.size_before_bytes,
+                report.size_after_bytes,
+                report.duration_ms
+            );
+            Ok(report)
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_alltime_stats(app: AppHandle) -> Result<AllTimeStats, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        stats::alltime_stats(&conn).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_trend(app: AppHandle, weeks: u32) -> Result<analytics::Trend, AppError> {
+    with_db(app, move |_, db| {
+        if !(2..=52).contains(&weeks) {
+            return Err(AppError::invalid("weeks", "must be between 2 and 52"));
+        }
+
+        let conn = db.lock();
+        let weekly = analytics::weekly_averages(&conn, weeks, logical_today(&conn))?;
+
+        Ok(analytics::compute_trend(weekly))
+    })
+    .await
+}
+
+// Half-hour drinking distribution the adaptive reminders schedule from
+#[tauri::command]
+pub async fn get_typical_pattern(app: AppHandle) -> Result<analytics::TypicalPattern, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        analytics::typical_pattern(&conn, logical_today(&conn)).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_drink_time_trends(
+    app: AppHandle,
+    start_date: String,
+    end_date: String,
+) -> Result<analytics::DrinkTimeTrends, AppError> {
+    with_db(app, move |_, db| {
+        let (start, end) = date_range(&start_date, &end_date)?;
+
+        let conn = db.lock();
+        analytics::drink_time_trends(&conn, start, end).map_err(AppError::from)
+    })
+    .await
+}
+
+// The goal moves with the program, so both announce a settings change like
+// the preset commands; the tray and the scheduler follow that event
+#[tauri::command]
+pub async fn start_goal_program(
+    app: AppHandle,
+    program: goals::GoalProgram,
+) -> Result<goals::GoalProgramStatus, AppError> {
+    with_db(app, move |app, db| {
+        logged("start_goal_program", format_args!("{:?}", program), || {
+            program.validate()?;
+
+            let conn = db.lock();
+            goals::save_program(&conn, &program)?;
+            emit_settings_changed(app, &conn);
+
+            Ok(program.status_on(logical_today(&conn)))
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn stop_goal_program(app: AppHandle) -> Result<(), AppError> {
+    with_db(app, move |app, db| {
+        logged("stop_goal_program", format_args!(""), || {
+            let conn = db.lock();
+            goals::clear_program(&conn)?;
+            emit_settings_changed(app, &conn);
+            Ok(())
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_goal_program_status(app: AppHandle) -> Result<goals::GoalProgramStatus, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+
+        match goals::load_program(&conn)? {
+            Some(program) => Ok(program.status_on(logical_today(&conn))),
+            None => Ok(goals::GoalProgramStatus {
+                active: false,
+                program: None,
+                current_goal_ml: get_base_goal_ml(&conn),
+                phase: 0,
+                total_phases: 0,
+                next_increase_date: None,
+                target_reached: false,
+            }),
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn apply_streak_freeze(app: AppHandle, date: String) -> Result<freezes::StreakFreeze, AppError> {
+    with_db(app, move |_, db| {
+        logged("apply_streak_freeze", format_args!("date={}", date), || {
+            let date = parse_date_arg("date", &date)?;
+            let conn = db.lock();
+            freezes::apply_streak_freeze(&conn, date, db::goal_schedule(&conn), logical_today(&conn))
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_streak_freezes(app: AppHandle) -> Result<freezes::StreakFreezes, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        freezes::get_streak_freezes(&conn, logical_today(&conn)).map_err(AppError::from)
+    })
+    .await
+}
+
+// Converts between display units so the frontend never re-implements the
+// factors. Results are rounded to the target unit's display precision.
+#[tauri::command]
+pub fn convert_amount(value: f64, from: units::Unit, to: units::Unit) -> Result<f64, AppError> {
+    if !value.is_finite() {
+        return Err(AppError::invalid("value", "must be a finite number"));
+    }
+    Ok(units::convert(value, from, to))
+}
+
+#[tauri::command]
+pub async fn get_achievements(app: AppHandle) -> Result<Vec<achievements::Achievement>, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        achievements::get_achievements(&conn).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_yearly_overview(app: AppHandle, year: i32) -> Result<Vec<MonthlyStats>, AppError> {
+    with_db(app, move |_, db| {
+        let conn = db.lock();
+        stats::yearly_overview(&conn, year).map_err(AppError::from)
+    })
+    .await
+}
+
+// The last `lines` lines of the log, oldest first, for the help screen
+#[tauri::command]
+pub fn get_recent_logs(lines: Option<usize>) -> Result<Vec<String>, AppError> {
+    let lines = lines.unwrap_or(200).clamp(1, MAX_RECENT_LOG_LINES);
+    logging::recent_lines(&logging::log_path(), lines).map_err(AppError::from)
+}
+
+// The current log file, so it can be attached to a bug report
+#[tauri::command]
+pub fn get_log_path() -> String {
+    logging::log_path().to_string_lossy().to_string()
+}
+
+// For commands that change a single setting rather than saving them all
+fn emit_settings_changed(app: &AppHandle, conn: &Connection) {
+    if let Ok(mut settings) = settings::load(conn) {
+        theme::fill_resolved(app, &mut settings);
+        let _ = app.emit(settings::CHANGED_EVENT, &settings);
+    }
+}
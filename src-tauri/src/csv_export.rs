@@ -0,0 +1,129 @@
+// Full-history CSV export, in the same layout `csv_import` reads, so a file
+// exported here can be imported again. The scheduled export writes the same
+// file into a chosen folder once a day at `export_time`, replacing the
+// previous one only once the new copy is complete.
+
+use crate::{dates, reminders, settings, AppError, Settings};
+use chrono::NaiveDateTime;
+use rusqlite::{Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+pub const SCHEDULED_FILE_NAME: &str = "hydra-tracker-entries.csv";
+const HEADERS: [&str; 4] = ["timestamp", "amount_ml", "date", "source"];
+// Outcome of the most recent scheduled export
+const STATUS_KEY: &str = "scheduled_export_status";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportStatus {
+    pub attempted_at: String,
+    pub path: String,
+    pub succeeded: bool,
+    pub entries: usize,
+    pub error: Option<String>,
+}
+
+// Oldest first, read in idx_timestamp order rather than sorted
+pub const EXPORT_QUERY: &str = "SELECT timestamp, amount_ml, date, source FROM water_entries ORDER BY timestamp, id";
+
+fn write_csv(conn: &Connection, path: &Path) -> Result<usize, AppError> {
+    let mut stmt = conn.prepare(EXPORT_QUERY)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i32>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let file = File::create(path)?;
+    let mut writer = csv::Writer::from_writer(file);
+    writer.write_record(HEADERS)?;
+    for (timestamp, amount_ml, date, source) in &rows {
+        writer.write_record([
+            timestamp.as_str(),
+            &amount_ml.to_string(),
+            date.as_str(),
+            source.as_str(),
+        ])?;
+    }
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+    Ok(rows.len())
+}
+
+// Writes next to `path` and renames over it, so a reader never sees half a
+// file and a failed export leaves the previous one in place. Returns the
+// number of entries written.
+pub fn export(conn: &Connection, path: &Path) -> Result<usize, AppError> {
+    let tmp = path.with_extension("tmp");
+    let result = write_csv(conn, &tmp).and_then(|entries| {
+        fs::rename(&tmp, path)?;
+        Ok(entries)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result.map_err(|e| AppError::Other(format!("Can't write {}: {}", path.display(), e)))
+}
+
+pub fn scheduled_path(settings: &Settings) -> Option<PathBuf> {
+    let folder = settings.export_path.as_deref().map(str::trim).filter(|p| !p.is_empty())?;
+    Some(Path::new(folder).join(SCHEDULED_FILE_NAME))
+}
+
+pub fn status(conn: &Connection) -> SqliteResult<Option<ExportStatus>> {
+    settings::get(conn, STATUS_KEY)
+}
+
+// Once a day from `export_time` on, so a day the computer was off at that
+// time is caught up when the app next runs. A failed export waits for the
+// next day unless the folder was changed in the meantime.
+fn is_due(settings: &Settings, last: Option<&ExportStatus>, path: &Path, now: NaiveDateTime) -> bool {
+    let minute = reminders::minute_of_day(now);
+    if !settings.export_enabled || minute < settings.export_time {
+        return false;
+    }
+    let today = now.format(dates::DATE_FORMAT).to_string();
+    match last {
+        None => true,
+        Some(last) if !last.attempted_at.starts_with(&today) => true,
+        Some(last) => !last.succeeded && last.path != path.display().to_string(),
+    }
+}
+
+// Runs the scheduled export if it's due. Returns an error worth telling the
+// user about: only the first failure after a success, so a folder that stays
+// missing doesn't bring up a notification every day.
+pub fn run_scheduled(conn: &Connection, settings: &Settings, now: NaiveDateTime) -> Option<String> {
+    let path = scheduled_path(settings)?;
+    let last = status(conn).ok().flatten();
+    if !is_due(settings, last.as_ref(), &path, now) {
+        return None;
+    }
+
+    let result = export(conn, &path);
+    let status = ExportStatus {
+        attempted_at: now.format(dates::TIMESTAMP_FORMAT).to_string(),
+        path: path.display().to_string(),
+        succeeded: result.is_ok(),
+        entries: *result.as_ref().unwrap_or(&0),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    };
+    let _ = settings::set(conn, STATUS_KEY, &status);
+
+    match result {
+        Ok(entries) => {
+            tracing::info!("scheduled export wrote {} entries", entries);
+            None
+        }
+        Err(e) => {
+            tracing::error!("scheduled export failed: {}", e);
+            last.is_none_or(|last| last.succeeded).then(|| e.to_string())
+        }
+    }
+}
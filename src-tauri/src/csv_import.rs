@@ -0,0 +1,262 @@
+// Importing history kept elsewhere, e.g. a spreadsheet or another app.
+// Columns are found by header name, so their order doesn't matter. Rows
+// matching an existing entry on timestamp and amount are skipped, which makes
+// re-running an import safe. Other apps' formats parse their rows and hand
+// them to `insert_rows`.
+//
+// Every row that can't be imported is reported with its line, the offending
+// value and a reason code the frontend can act on. Duplicates are listed the
+// same way but apart, since they're expected on a re-import. Only the first
+// `MAX_REPORTED_REJECTIONS` of each are listed; `rejected_total` and
+// `skipped_duplicates` count them all.
+
+use crate::{dates, db, AppError};
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+pub const ENTRY_SOURCE: &str = "import";
+
+const TIMESTAMP_HEADERS: [&str; 4] = ["timestamp", "datetime", "time", "date"];
+const AMOUNT_HEADERS: [&str; 4] = ["amount_ml", "amount", "ml", "volume"];
+// Accepted besides the app's own "YYYY-MM-DD HH:MM:SS"
+const EXTRA_TIMESTAMP_FORMATS: [&str; 3] = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"];
+
+pub const MAX_REPORTED_REJECTIONS: usize = 100;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    // The line couldn't be read as CSV at all
+    MalformedRow,
+    BadTimestamp,
+    NonNumericAmount,
+    UnknownUnit,
+    OutOfRange,
+    // Already logged at the same time with the same amount
+    Duplicate,
+}
+
+// Why one row couldn't be imported
+#[derive(Debug, Clone)]
+pub struct RowError {
+    pub reason: RejectReason,
+    pub value: String,
+    pub message: String,
+}
+
+impl RowError {
+    pub fn new(reason: RejectReason, value: &str, message: impl Into<String>) -> Self {
+        Self {
+            reason,
+            value: value.trim().to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_timestamp(value: &str) -> Self {
+        Self::new(
+            RejectReason::BadTimestamp,
+            value,
+            format!("timestamp '{}' is not recognised", value.trim()),
+        )
+    }
+}
+
+impl From<csv::Error> for RowError {
+    fn from(e: csv::Error) -> Self {
+        Self::new(RejectReason::MalformedRow, "", e.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RejectedRow {
+    // 1-based line in the file, counting the header
+    pub line: usize,
+    pub reason: RejectReason,
+    // The cell at fault, as it appeared in the file
+    pub value: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct CsvImportSummary {
+    pub inserted: usize,
+    pub skipped_duplicates: usize,
+    // The first MAX_REPORTED_REJECTIONS duplicates, in file order
+    pub duplicates: Vec<RejectedRow>,
+    // The first MAX_REPORTED_REJECTIONS, in file order
+    pub rejected: Vec<RejectedRow>,
+    pub rejected_total: usize,
+    // Logical days that gained entries, for the entries-changed event
+    #[serde(skip)]
+    pub dates: BTreeSet<String>,
+}
+
+impl CsvImportSummary {
+    fn reject(&mut self, line: usize, error: RowError) {
+        self.rejected_total += 1;
+        if self.rejected.len() < MAX_REPORTED_REJECTIONS {
+            self.rejected.push(RejectedRow {
+                line,
+                reason: error.reason,
+                value: error.value,
+                message: error.message,
+            });
+        }
+    }
+
+    fn duplicate(&mut self, line: usize, timestamp: &str, amount_ml: i32) {
+        self.skipped_duplicates += 1;
+        if self.duplicates.len() < MAX_REPORTED_REJECTIONS {
+            self.duplicates.push(RejectedRow {
+                line,
+                reason: RejectReason::Duplicate,
+                value: timestamp.to_string(),
+                message: format!("{} ml at {} is already logged", amount_ml, timestamp),
+            });
+        }
+    }
+}
+
+pub fn find_column(headers: &csv::StringRecord, names: &[&str]) -> Option<usize> {
+    // Earlier names win, so "timestamp" beats a separate "date" column
+    names.iter().find_map(|name| {
+        headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name))
+    })
+}
+
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    dates::parse_timestamp(value).or_else(|| {
+        EXTRA_TIMESTAMP_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    })
+}
+
+// An amount already converted to ml; `value` is the cell it came from
+pub fn amount_in_range(ml: f64, value: &str) -> Result<i32, RowError> {
+    let ml = ml.round();
+    if !(1.0..=i32::MAX as f64).contains(&ml) {
+        return Err(RowError::new(
+            RejectReason::OutOfRange,
+            value,
+            format!("amount {} must be positive", ml),
+        ));
+    }
+    Ok(ml as i32)
+}
+
+pub fn parse_amount(value: &str) -> Result<i32, RowError> {
+    let amount: f64 = value.trim().parse().map_err(|_| {
+        RowError::new(
+            RejectReason::NonNumericAmount,
+            value,
+            format!("amount '{}' is not a number", value.trim()),
+        )
+    })?;
+    amount_in_range(amount, value)
+}
+
+// Rows may have missing trailing cells; those fail validation individually
+pub fn reader_builder() -> csv::ReaderBuilder {
+    let mut builder = csv::ReaderBuilder::new();
+    builder.flexible(true).trim(csv::Trim::All);
+    builder
+}
+
+pub fn open_reader(path: &Path) -> Result<csv::Reader<std::fs::File>, AppError> {
+    reader_builder()
+        .from_path(path)
+        .map_err(|e| AppError::Other(format!("Can't open {}: {}", path.display(), e)))
+}
+
+// Errors name `path`, the file the import command was given
+pub fn require_column(headers: &csv::StringRecord, names: &[&str], what: &str) -> Result<usize, AppError> {
+    find_column(headers, names).ok_or_else(|| {
+        AppError::invalid("path", format!("no {} column; expected one of {}", what, names.join(", ")))
+    })
+}
+
+// A parsed row, or why it was rejected, with its 1-based line in the file
+pub type ParsedRow = (usize, Result<(NaiveDateTime, i32), RowError>);
+
+// Shared by every importer. Everything goes in one transaction. Without
+// `strict` a bad row is reported and skipped; with it the first bad row
+// aborts the whole import.
+pub fn insert_rows(
+    conn: &Connection,
+    rows: impl IntoIterator<Item = ParsedRow>,
+    source: &str,
+    day_starts_at_hour: i32,
+    strict: bool,
+) -> Result<CsvImportSummary, AppError> {
+    let mut summary = CsvImportSummary::default();
+
+    db::with_tx(conn, |tx| {
+        for (line, row) in rows {
+            let (at, amount_ml) = match row {
+                Ok(row) => row,
+                Err(error) if strict => {
+                    return Err(AppError::invalid("path", format!("line {}: {}", line, error.message)))
+                }
+                Err(error) => {
+                    summary.reject(line, error);
+                    continue;
+                }
+            };
+
+            let timestamp = at.format(dates::TIMESTAMP_FORMAT).to_string();
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM water_entries WHERE timestamp = ?1 AND amount_ml = ?2)",
+                params![timestamp, amount_ml],
+                |row| row.get(0),
+            )?;
+            // Counted apart from the rejections, and never aborts a strict import
+            if exists {
+                summary.duplicate(line, &timestamp, amount_ml);
+                continue;
+            }
+
+            let entry = db::write_entry(tx, amount_ml, source, at, day_starts_at_hour)?;
+            summary.inserted += 1;
+            summary.dates.insert(entry.date);
+        }
+        Ok(())
+    })?;
+
+    Ok(summary)
+}
+
+// Our own layout: a timestamp column and an amount in ml
+pub fn import(
+    conn: &Connection,
+    path: &Path,
+    day_starts_at_hour: i32,
+    strict: bool,
+) -> Result<CsvImportSummary, AppError> {
+    let mut reader = open_reader(path)?;
+    let headers = reader.headers()?.clone();
+    let timestamp_column = require_column(&headers, &TIMESTAMP_HEADERS, "timestamp")?;
+    let amount_column = require_column(&headers, &AMOUNT_HEADERS, "amount")?;
+
+    let rows: Vec<ParsedRow> = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            let row = record.map_err(RowError::from).and_then(|record| {
+                let timestamp = record.get(timestamp_column).unwrap_or_default();
+                let at = parse_timestamp(timestamp).ok_or_else(|| RowError::bad_timestamp(timestamp))?;
+                let amount = parse_amount(record.get(amount_column).unwrap_or_default())?;
+                Ok((at, amount))
+            });
+            (index + 2, row)
+        })
+        .collect();
+    insert_rows(conn, rows, ENTRY_SOURCE, day_starts_at_hour, strict)
+}
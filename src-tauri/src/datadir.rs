@@ -0,0 +1,180 @@
+// Where hydra.db lives. The default is the platform data directory; a custom
+// location is remembered in a one-line pointer file inside the default
+// directory, since it can't be stored in the database it points to.
+//
+// While the app runs, hydra.db-wal and hydra.db-shm sit next to it (see
+// `db`). They're part of the database: the -wal file can hold writes that
+// aren't in hydra.db yet, so the three are only copied together, and only
+// through SQLite.
+
+use crate::AppError;
+use rusqlite::Connection;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DB_FILE: &str = "hydra.db";
+const POINTER_FILE: &str = "data_dir";
+const BACKUPS_DIR: &str = "backups";
+const LOGS_DIR: &str = "logs";
+
+pub fn default_data_dir() -> PathBuf {
+    match directories::ProjectDirs::from("com", "hydra", "tracker") {
+        Some(proj_dirs) => proj_dirs.data_dir().to_path_buf(),
+        None => PathBuf::from("."),
+    }
+}
+
+// The custom directory from the pointer file, or the default when there's no
+// pointer. A pointer to a directory without hydra.db, such as one on a drive
+// that isn't connected, is an error rather than a fresh empty history.
+pub fn data_dir() -> Result<PathBuf, AppError> {
+    let default = default_data_dir();
+    let pointer = default.join(POINTER_FILE);
+    let Ok(contents) = fs::read_to_string(&pointer) else {
+        return Ok(default);
+    };
+    let dir = PathBuf::from(contents.trim());
+    if !dir.join(DB_FILE).is_file() {
+        return Err(AppError::Other(format!(
+            "The data directory {} has no {}. If it's on a drive that isn't connected, connect it and start \
+             again; to start over in {}, delete {}.",
+            dir.display(),
+            DB_FILE,
+            default.display(),
+            pointer.display()
+        )));
+    }
+    Ok(dir)
+}
+
+// Backups and pre-restore snapshots, kept with the database
+pub fn backups_dir() -> Result<PathBuf, AppError> {
+    Ok(data_dir()?.join(BACKUPS_DIR))
+}
+
+// The rotated app logs (see `logging`). They stay behind when the data is
+// moved, and the next start writes into the new directory. A data directory
+// that can't be found logs into the default one, so the error is recorded.
+pub fn logs_dir() -> PathBuf {
+    data_dir().unwrap_or_else(|_| default_data_dir()).join(LOGS_DIR)
+}
+
+// Shows the data directory in the platform's file manager, creating it first
+// if nothing has been written yet
+pub fn reveal() -> Result<(), AppError> {
+    use std::process::Command;
+
+    let dir = data_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| AppError::Other(format!("Couldn't create {}: {}", dir.display(), e)))?;
+    let opener = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    let failed = |e: &dyn std::fmt::Display| AppError::Other(format!("Couldn't open {}: {}", dir.display(), e));
+    let mut command = Command::new(opener);
+    command.arg(&dir);
+    // Explorer exits with 1 even when it worked, so only the launch counts
+    if cfg!(windows) {
+        return command.spawn().map(|_| ()).map_err(|e| failed(&e));
+    }
+    // The others fail with a status, e.g. with no desktop session to open in
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(failed(&format!("{} exited with {}", opener, status))),
+        Err(e) => Err(failed(&e)),
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_pointer(dir: &Path) -> std::io::Result<()> {
+    let default = default_data_dir();
+    fs::create_dir_all(&default)?;
+    let pointer = default.join(POINTER_FILE);
+    if dir == default {
+        return match fs::remove_file(&pointer) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    // Write then rename so a crash can't leave a half-written pointer
+    let tmp = default.join(format!("{}.tmp", POINTER_FILE));
+    fs::write(&tmp, dir.to_string_lossy().as_bytes())?;
+    fs::rename(tmp, pointer)
+}
+
+// Copy the database (and backups) from `conn`'s directory into `new_dir`,
+// check the copy, and remember the new location. Returns the connection to
+// the copy; the caller swaps it in. Until that point nothing about the
+// original changes, and the original files are never deleted.
+// Errors about the destination name `new_path`, move_data_directory's argument.
+pub fn move_to(conn: &Connection, current_dir: &Path, new_dir: &Path) -> Result<Connection, AppError> {
+    let step =
+        |step: &str, e: &dyn std::fmt::Display| AppError::Other(format!("Moving data failed while {}: {}", step, e));
+
+    if new_dir == current_dir {
+        return Err(AppError::invalid("new_path", "the data is already in that directory"));
+    }
+    let new_db = new_dir.join(DB_FILE);
+    if new_db.exists() {
+        return Err(AppError::invalid(
+            "new_path",
+            format!("{} already contains a {}", new_dir.display(), DB_FILE),
+        ));
+    }
+    // What's already there is left alone if the move fails
+    let created_dir = !new_dir.exists();
+    let had_backups = new_dir.join(BACKUPS_DIR).exists();
+    fs::create_dir_all(new_dir).map_err(|e| step("creating the directory", &e))?;
+
+    let result = (|| {
+        // Written through SQLite itself, so nothing half-written can be
+        // picked up even mid-transaction, and nothing still in the WAL is lost
+        crate::db::vacuum_into(conn, &new_db).map_err(|e| step("copying the database", &e))?;
+
+        let backups = current_dir.join(BACKUPS_DIR);
+        if backups.is_dir() {
+            copy_dir(&backups, &new_dir.join(BACKUPS_DIR)).map_err(|e| step("copying backups", &e))?;
+        }
+
+        let copy = crate::db::open(&new_db).map_err(|e| step("opening the copy", &e))?;
+        let integrity: String = copy
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .map_err(|e| step("checking the copy", &e))?;
+        if integrity != "ok" {
+            return Err(step("checking the copy", &integrity));
+        }
+
+        write_pointer(new_dir).map_err(|e| step("saving the new location", &e))?;
+        Ok(copy)
+    })();
+
+    if result.is_err() {
+        if created_dir {
+            let _ = fs::remove_dir_all(new_dir);
+        } else {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = fs::remove_file(new_dir.join(format!("{}{}", DB_FILE, suffix)));
+            }
+            if !had_backups {
+                let _ = fs::remove_dir_all(new_dir.join(BACKUPS_DIR));
+            }
+        }
+    }
+    result
+}
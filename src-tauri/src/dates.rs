@@ -0,0 +1,38 @@
+// Calendar helpers. Day math works on the stored "YYYY-MM-DD" strings as
+// plain calendar dates, never on wall-clock instants, so DST transitions and
+// timezone changes can't shift a day boundary.
+
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT).ok()
+}
+
+pub fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()
+}
+
+// The calendar day before `date`. Every "yesterday" goes through here.
+pub fn previous_day(date: NaiveDate) -> Option<NaiveDate> {
+    date.pred_opt()
+}
+
+pub fn next_day(date: NaiveDate) -> Option<NaiveDate> {
+    date.succ_opt()
+}
+
+// Day an instant counts toward. Anything before the rollover hour still
+// belongs to the previous day, so 01:00 with a rollover of 3 is "yesterday".
+// Compares the wall-clock hour rather than subtracting a duration, so the
+// skipped or repeated hour of a DST change can't move an entry across days.
+pub fn logical_date(at: NaiveDateTime, day_starts_at_hour: i32) -> NaiveDate {
+    let date = at.date();
+    if (at.hour() as i32) < day_starts_at_hour {
+        previous_day(date).unwrap_or(date)
+    } else {
+        date
+    }
+}
@@ -0,0 +1,233 @@
+// Opening hydra.db, schema setup and the queries on water_entries that
+// don't need the app: everything past `open` takes a plain Connection, so it
+// runs the same against an in-memory database.
+//
+// The database runs in WAL mode, which keeps the log of recent writes in
+// hydra.db-wal next to it (and an index of it in hydra.db-shm) until they're
+// checkpointed into the main file. Readers such as the backups see a
+// consistent state while the app writes, and the busy timeout makes a
+// writer wait for another process holding the file instead of failing with
+// "database is locked". WAL needs shared memory, which some network
+// filesystems don't provide; there the database stays on the rollback
+// journal, with a warning.
+
+use crate::migrations::{self, MigrationError};
+use crate::{datadir, dates, goals, settings, AppError, WaterEntry};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use std::path::Path;
+
+const BUSY_TIMEOUT_MS: i32 = 5000;
+
+pub fn open(path: impl AsRef<Path>) -> SqliteResult<Connection> {
+    let conn = Connection::open(path)?;
+    configure(&conn)?;
+    Ok(conn)
+}
+
+// Applies the connection pragmas and checks each one took; any that didn't
+// is logged rather than refused, since the database still works without it
+pub fn configure(conn: &Connection) -> SqliteResult<()> {
+    conn.pragma_update(None, "busy_timeout", BUSY_TIMEOUT_MS)?;
+    conn.pragma_update(None, "foreign_keys", true)?;
+    let journal_mode: String = conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+
+    if !journal_mode.eq_ignore_ascii_case("wal") {
+        tracing::warn!("WAL isn't available here, staying on the {} journal", journal_mode);
+    }
+    let busy_timeout: i32 = conn.pragma_query_value(None, "busy_timeout", |row| row.get(0))?;
+    let foreign_keys: bool = conn.pragma_query_value(None, "foreign_keys", |row| row.get(0))?;
+    // 1 is NORMAL
+    let synchronous: i32 = conn.pragma_query_value(None, "synchronous", |row| row.get(0))?;
+    if busy_timeout != BUSY_TIMEOUT_MS || !foreign_keys || synchronous != 1 {
+        tracing::warn!(
+            "connection settings didn't all apply: busy_timeout={}, foreign_keys={}, synchronous={}",
+            busy_timeout, foreign_keys, synchronous
+        );
+    }
+    Ok(())
+}
+
+// A consistent, self-contained copy of the database at `path`. VACUUM INTO
+// reads through SQLite, so writes still in the WAL are included, and the
+// copy is switched to the rollback journal so it's a single file that opens
+// read-only anywhere.
+pub fn vacuum_into(conn: &Connection, path: &Path) -> SqliteResult<()> {
+    conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])?;
+    Connection::open(path)?.pragma_update_and_check(None, "journal_mode", "DELETE", |_| Ok(()))
+}
+
+// Runs `work` so its writes land together or not at all: kept when it
+// returns Ok, undone when it returns Err. It's a savepoint, so calls nest;
+// an Err from an inner one undoes only its own part, and nothing is
+// committed until the outermost one returns. In debug builds a panic leaves
+// it open for `Database::lock` to roll back; release builds abort, and
+// SQLite discards the open transaction the next time hydra.db is opened.
+pub fn with_tx<T, E: From<rusqlite::Error>>(
+    conn: &Connection,
+    work: impl FnOnce(&Connection) -> Result<T, E>,
+) -> Result<T, E> {
+    let outermost = conn.is_autocommit();
+    conn.execute_batch("SAVEPOINT with_tx")?;
+    let result = work(conn).and_then(|value| {
+        conn.execute_batch("RELEASE with_tx")?;
+        Ok(value)
+    });
+    if result.is_err() {
+        let undo = if outermost { "ROLLBACK" } else { "ROLLBACK TO with_tx; RELEASE with_tx" };
+        if let Err(e) = conn.execute_batch(undo) {
+            tracing::error!("couldn't roll back a failed transaction: {}", e);
+        }
+    }
+    result
+}
+
+// Brings the schema up to date; see `migrations`
+pub fn init_db(conn: &Connection) -> Result<(), MigrationError> {
+    migrations::run(conn).map(|_| ())
+}
+
+pub fn get_day_starts_at_hour(conn: &Connection) -> i32 {
+    settings::get(conn, "day_starts_at_hour")
+        .ok()
+        .flatten()
+        .unwrap_or(0)
+}
+
+pub fn get_base_goal_ml(conn: &Connection) -> i32 {
+    settings::get(conn, "daily_goal_ml")
+        .ok()
+        .flatten()
+        .unwrap_or(4000)
+}
+
+// The goal each day is judged by: the goal program's step from its start
+// date on, the plain daily_goal_ml setting before it and without one. A ramp
+// raising the goal leaves the days behind it at the goal they had.
+pub fn goal_schedule(conn: &Connection) -> impl Fn(NaiveDate) -> i32 {
+    let program = goals::load_program(conn).ok().flatten();
+    let base_goal = get_base_goal_ml(conn);
+    move |date| match &program {
+        Some(program) if date >= program.start_date => program.goal_on(date),
+        _ => base_goal,
+    }
+}
+
+// Goal in effect today
+pub fn get_goal_ml(conn: &Connection) -> i32 {
+    goal_schedule(conn)(logical_today(conn))
+}
+
+pub fn logical_today(conn: &Connection) -> NaiveDate {
+    dates::logical_date(Local::now().naive_local(), get_day_starts_at_hour(conn))
+}
+
+// Get database path
+pub fn get_db_path() -> Result<String, AppError> {
+    let data_dir = datadir::data_dir()?;
+    std::fs::create_dir_all(&data_dir).ok();
+    Ok(data_dir.join(datadir::DB_FILE).to_string_lossy().to_string())
+}
+
+// The date an entry made at `at` is stored under. Every write to
+// water_entries.date goes through here, and the table's CHECK holds each
+// row to its timestamp's calendar day or the one before.
+pub fn entry_date(at: NaiveDateTime, day_starts_at_hour: i32) -> String {
+    dates::logical_date(at, day_starts_at_hour)
+        .format(dates::DATE_FORMAT)
+        .to_string()
+}
+
+// Store one entry, dated by the rollover hour in effect now. Only the row:
+// see record_entry for what follows a drink.
+pub fn insert_entry(conn: &Connection, amount_ml: i32, source: &str, at: NaiveDateTime) -> SqliteResult<WaterEntry> {
+    write_entry(conn, amount_ml, source, at, get_day_starts_at_hour(conn))
+}
+
+// The one insert into water_entries, for callers that already know the
+// rollover hour, like an import writing many rows at once
+pub fn write_entry(
+    conn: &Connection,
+    amount_ml: i32,
+    source: &str,
+    at: NaiveDateTime,
+    day_starts_at_hour: i32,
+) -> SqliteResult<WaterEntry> {
+    let timestamp = at.format(dates::TIMESTAMP_FORMAT).to_string();
+    let date = entry_date(at, day_starts_at_hour);
+
+    conn.execute(
+        "INSERT INTO water_entries (amount_ml, timestamp, date, source) VALUES (?1, ?2, ?3, ?4)",
+        params![amount_ml, timestamp, date, source],
+    )?;
+
+    Ok(WaterEntry {
+        id: conn.last_insert_rowid(),
+        amount_ml,
+        timestamp,
+        date,
+        source: source.to_string(),
+    })
+}
+
+// The removed entry's date, or None when there was no entry with that id
+pub fn delete_entry(conn: &Connection, id: i64) -> SqliteResult<Option<String>> {
+    conn.query_row("DELETE FROM water_entries WHERE id = ?1 RETURNING date", [id], |row| row.get(0))
+        .optional()
+}
+
+pub const ENTRIES_ON_QUERY: &str =
+    "SELECT id, amount_ml, timestamp, date, source FROM water_entries WHERE date = ?1 ORDER BY timestamp DESC";
+
+// The logical day `date`'s entries, newest first
+pub fn entries_on(conn: &Connection, date: NaiveDate) -> SqliteResult<Vec<WaterEntry>> {
+    let mut stmt = conn.prepare_cached(ENTRIES_ON_QUERY)?;
+
+    let entries = stmt
+        .query_map([date.format("%Y-%m-%d").to_string()], |row| {
+            Ok(WaterEntry {
+                id: row.get(0)?,
+                amount_ml: row.get(1)?,
+                timestamp: row.get(2)?,
+                date: row.get(3)?,
+                source: row.get(4)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(entries)
+}
+
+// Re-derive every entry's date from its timestamp using the current rollover
+// hour; returns how many rows changed
+pub fn recompute_entry_dates(conn: &Connection) -> SqliteResult<usize> {
+    let day_starts_at_hour = get_day_starts_at_hour(conn);
+    with_tx(conn, |tx| {
+        let rows: Vec<(i64, String, String)> = {
+            let mut stmt = tx.prepare("SELECT id, timestamp, date FROM water_entries")?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .filter_map(|r| r.ok())
+                .collect();
+            rows
+        };
+
+        let mut changed = 0;
+        for (id, timestamp, date) in rows {
+            let Some(at) = dates::parse_timestamp(&timestamp) else {
+                continue;
+            };
+            let new_date = entry_date(at, day_starts_at_hour);
+            if new_date != date {
+                tx.execute(
+                    "UPDATE water_entries SET date = ?1 WHERE id = ?2",
+                    params![new_date, id],
+                )?;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    })
+}
@@ -0,0 +1,119 @@
+// hydra:// links, so launchers and scripts can log water without the window:
+//
+//   hydra://add?amount=350
+//   hydra://add?amount=12&unit=oz&drink=coffee
+//
+// `amount` is in `unit` (millilitres by default) and may not exceed
+// max_entry_ml, since there's nobody to confirm an outsized one. `drink` is
+// only echoed in the confirmation; every entry is stored as water. Anything
+// that doesn't parse is logged and dropped.
+//
+// A link reaches the app either as the launch argument or, when it's
+// already running, forwarded by the single-instance plugin.
+
+use crate::units::{self, Unit};
+use crate::{notifications, settings, AppError, Database};
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+pub const SCHEME: &str = "hydra";
+pub const ENTRY_SOURCE: &str = "deeplink";
+const MAX_DRINK_LEN: usize = 32;
+
+// True for a command line that's only a link, which the single-instance
+// plugin forwards without the window needing to come up
+pub fn is_link_launch(args: &[String]) -> bool {
+    matches!(args, [_, url] if url.starts_with(&format!("{}:", SCHEME)))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AddLink {
+    pub amount_ml: i32,
+    pub drink: Option<String>,
+}
+
+pub fn parse(url: &Url, max_entry_ml: i32) -> Result<AddLink, AppError> {
+    let invalid = |reason: String| AppError::invalid("url", reason);
+    if url.scheme() != SCHEME {
+        return Err(invalid(format!("not a {}:// link", SCHEME)));
+    }
+    let action = url.host_str().unwrap_or_default();
+    if action != "add" || !matches!(url.path(), "" | "/") {
+        return Err(invalid(format!("unknown action '{}{}'", action, url.path())));
+    }
+
+    let (mut amount, mut unit, mut drink) = (None, Unit::Ml, None);
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "amount" => {
+                let value = value.trim();
+                amount = Some(value.parse::<f64>().map_err(|_| invalid(format!("'{}' is not a number", value)))?);
+            }
+            "unit" => {
+                unit = Unit::from_label(value.trim()).ok_or_else(|| invalid(format!("'{}' is not a unit", value)))?;
+            }
+            "drink" => {
+                let value = value.trim();
+                if value.is_empty() || value.chars().count() > MAX_DRINK_LEN || value.chars().any(char::is_control) {
+                    return Err(invalid(format!("'{}' is not a drink name", value)));
+                }
+                drink = Some(value.to_string());
+            }
+            // Ignored, so links can carry extra parameters for other tools
+            _ => {}
+        }
+    }
+
+    let amount = amount.ok_or_else(|| invalid("no amount given".to_string()))?;
+    let ml = units::convert(amount, unit, Unit::Ml).round();
+    if !amount.is_finite() || ml < 1.0 {
+        return Err(invalid(format!("amount {} must be positive", amount)));
+    }
+    if ml > max_entry_ml as f64 {
+        return Err(invalid(format!("{} ml is more than the {} ml allowed per entry", ml, max_entry_ml)));
+    }
+    Ok(AddLink { amount_ml: ml as i32, drink })
+}
+
+fn add(app: &AppHandle, url: &Url) -> Result<(), AppError> {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let settings = settings::load(&conn)?;
+    let link = parse(url, settings.max_entry_ml)?;
+    crate::record_entry(app, &conn, link.amount_ml, ENTRY_SOURCE)?;
+    drop(conn);
+
+    let amount = units::format_amount(link.amount_ml, settings.unit);
+    let body = match link.drink {
+        Some(drink) => format!("Logged {} of {}.", amount, drink),
+        None => format!("Logged {}.", amount),
+    };
+    notifications::show_logged(app, &body);
+    Ok(())
+}
+
+fn open_urls(app: &AppHandle, urls: Vec<Url>) {
+    crate::in_background(app, move |app| {
+        for url in urls {
+            if let Err(e) = add(app, &url) {
+                tracing::warn!("ignoring {}: {}", url, e);
+            }
+        }
+    });
+}
+
+pub fn setup(app: &AppHandle) {
+    // Installed builds register the scheme themselves; this covers AppImages
+    // and development runs. macOS only takes it from the bundle.
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::warn!("couldn't register {}://: {}", SCHEME, e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| open_urls(&handle, event.urls()));
+    // The link the app was launched with arrived before the listener above
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        open_urls(app, urls);
+    }
+}
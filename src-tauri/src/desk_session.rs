@@ -0,0 +1,96 @@
+// A nudge after a long stretch at the computer without a drink, separate
+// from the interval reminders. A stretch is input without a pause of
+// BREAK or more, without the session being locked, and without the machine
+// sleeping. Once one has gone on for desk_reminder_minutes since it began or
+// since the last entry, whichever is later, a single notification fires; the
+// next needs a new stretch.
+//
+// Quiet hours and snoozes hold it back like any reminder, and it fires later
+// in the same stretch once they're over. Where the OS can't report idle time
+// there are no stretches to measure, so it never fires.
+
+use crate::{dates, idle, notifications, reminders, settings, sound, AppError, Database};
+use chrono::{Duration as ChronoDuration, NaiveDateTime};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+// A pause in input this long ends the stretch
+const BREAK: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Default)]
+pub struct DeskSession {
+    // When input resumed after the last break
+    started_at: Option<NaiveDateTime>,
+    // When this was last checked, so a sleep in between ends the stretch
+    checked_at: Option<NaiveDateTime>,
+    reminded: bool,
+}
+
+// "2h", "1h 30m", "45m"
+fn stretch_label(minutes: i32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+impl DeskSession {
+    // Follows the stretch given how long input has been idle at `now`
+    fn track(&mut self, idle: Option<Duration>, now: NaiveDateTime) {
+        let pause = ChronoDuration::from_std(BREAK).unwrap_or_default();
+        let slept = self.checked_at.is_some_and(|at| now - at >= pause);
+        let active = idle.filter(|idle| *idle < BREAK);
+        if slept || active.is_none() {
+            *self = Self::default();
+        }
+        self.checked_at = Some(now);
+        if let Some(idle) = active {
+            self.started_at
+                .get_or_insert(now - ChronoDuration::from_std(idle).unwrap_or_default());
+        }
+    }
+
+    // Called from the scheduler thread on every pass
+    pub fn check(&mut self, app: &AppHandle, now: NaiveDateTime) -> Result<(), AppError> {
+        let db = app.state::<Database>();
+        let settings = settings::load(&db.lock())?;
+        if !settings.desk_reminder_enabled {
+            *self = Self::default();
+            return Ok(());
+        }
+
+        // Asks the OS, possibly via a subprocess, so not while holding the database
+        self.track(idle::idle_time(), now);
+        let Some(started_at) = self.started_at.filter(|_| !self.reminded) else {
+            return Ok(());
+        };
+        if reminders::in_quiet_hours(&settings, reminders::minute_of_day(now)) {
+            return Ok(());
+        }
+
+        let conn = db.lock();
+        if reminders::is_snoozed(&conn, now)? {
+            return Ok(());
+        }
+        let last_entry: Option<String> =
+            conn.query_row("SELECT MAX(timestamp) FROM water_entries", [], |row| row.get(0))?;
+        drop(conn);
+        let since = last_entry
+            .and_then(|t| dates::parse_timestamp(&t))
+            .map_or(started_at, |entry| entry.max(started_at));
+        if now - since < ChronoDuration::minutes(settings.desk_reminder_minutes as i64) {
+            return Ok(());
+        }
+
+        self.reminded = true;
+        tracing::info!("sending the desk nudge, {} at the computer since {}", stretch_label(settings.desk_reminder_minutes), since.format("%H:%M"));
+        notifications::show_reminder(
+            app,
+            &format!("🪑 {} at your desk without water", stretch_label(settings.desk_reminder_minutes)),
+            "Take a break and get yourself a drink.",
+        );
+        sound::play_reminder(&settings, now, false);
+        Ok(())
+    }
+}
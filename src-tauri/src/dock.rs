@@ -0,0 +1,29 @@
+// The macOS dock badge: how much is left to drink today ("1.2L"), cleared
+// once the goal is met. Updated by the tray's refresh thread along with
+// everything else that shows today's progress, so it follows new entries
+// and the rollover. Other platforms have no dock, so this does nothing
+// there.
+//
+// A dock menu with the quick adds would belong here too, but Tauri has no
+// way to give the dock its own menu; the menu bar icon has them on macOS.
+
+use crate::units::{self, Unit};
+use crate::DailyStats;
+use tauri::AppHandle;
+
+pub fn badge_text(stats: &DailyStats, unit: Unit) -> Option<String> {
+    (stats.total_ml < stats.goal_ml).then(|| units::format_compact(stats.goal_ml - stats.total_ml, unit))
+}
+
+#[cfg(target_os = "macos")]
+pub fn set_badge(app: &AppHandle, label: Option<String>) {
+    use tauri::Manager;
+
+    // The badge belongs to the app, but Tauri sets it through a window
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_badge_label(label);
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_badge(_app: &AppHandle, _label: Option<String>) {}
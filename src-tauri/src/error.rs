@@ -0,0 +1,121 @@
+// The error every command returns. It reaches the frontend as
+// { code, message, field }: `code` is stable across releases so the UI can
+// branch on it, `message` is for people and may change, and `field` names
+// the argument or setting at fault for invalid input and is null otherwise.
+
+use crate::settings::ValidationError;
+use rusqlite::ErrorCode;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::sync::PoisonError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("database error: {0}")]
+    Database(rusqlite::Error),
+    // SQLite's own locking: another connection has the file. Worth retrying.
+    #[error("the database is busy, try again in a moment")]
+    Busy,
+    // A thread panicked while holding shared state; it stays unusable until
+    // the app restarts
+    #[error("internal state is unavailable after an earlier failure, restart the app")]
+    Poisoned,
+    #[error("{field}: {reason}")]
+    InvalidInput { field: String, reason: String },
+    #[error("{0} not found")]
+    NotFound(String),
+    // A job that only runs one at a time was started again before it finished
+    #[error("{0} is already running")]
+    AlreadyRunning(&'static str),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AppError {
+    pub fn invalid(field: &str, reason: impl Into<String>) -> Self {
+        Self::InvalidInput {
+            field: field.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Database(_) => "database",
+            Self::Busy => "database_busy",
+            Self::Poisoned => "state_poisoned",
+            Self::InvalidInput { .. } => "invalid_input",
+            Self::NotFound(_) => "not_found",
+            Self::AlreadyRunning(_) => "already_running",
+            Self::Io(_) => "io",
+            Self::Other(_) => "other",
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(error: rusqlite::Error) -> Self {
+        match error.sqlite_error_code() {
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => Self::Busy,
+            _ if matches!(error, rusqlite::Error::QueryReturnedNoRows) => Self::NotFound("record".to_string()),
+            _ => Self::Database(error),
+        }
+    }
+}
+
+impl<T> From<PoisonError<T>> for AppError {
+    fn from(_: PoisonError<T>) -> Self {
+        Self::Poisoned
+    }
+}
+
+impl From<ValidationError> for AppError {
+    fn from(error: ValidationError) -> Self {
+        Self::invalid(error.field, error.message)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Other(error.to_string())
+    }
+}
+
+impl From<csv::Error> for AppError {
+    fn from(error: csv::Error) -> Self {
+        Self::Other(error.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(error: tauri::Error) -> Self {
+        Self::Other(error.to_string())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let field = match self {
+            Self::InvalidInput { field, .. } => Some(field.as_str()),
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("field", &field)?;
+        state.end()
+    }
+}
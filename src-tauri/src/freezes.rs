@@ -0,0 +1,118 @@
+// Streak freezes: a frozen day neither extends nor breaks a streak. Each
+// calendar month grants a fixed allowance, and a freeze can only be spent on
+// a past day that actually missed the goal.
+
+use crate::AppError;
+use chrono::{Datelike, Local, NaiveDate};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const FREEZES_PER_MONTH: i64 = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreakFreeze {
+    pub date: String,
+    pub applied_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreakFreezes {
+    pub freezes: Vec<StreakFreeze>,
+    // Freezes still available for the month containing today
+    pub remaining_this_month: i64,
+}
+
+pub fn init_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS streak_freezes (
+            date TEXT PRIMARY KEY,
+            applied_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+pub const FROZEN_DATES_QUERY: &str = "SELECT date FROM streak_freezes";
+
+// Read with every month and streak, so the statement is cached
+pub fn frozen_dates(conn: &Connection) -> SqliteResult<HashSet<NaiveDate>> {
+    let mut stmt = conn.prepare_cached(FROZEN_DATES_QUERY)?;
+    let dates = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .filter_map(|r| r.ok())
+        .filter_map(|d| crate::dates::parse_date(&d))
+        .collect();
+    Ok(dates)
+}
+
+fn used_in_month(conn: &Connection, date: NaiveDate) -> SqliteResult<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM streak_freezes WHERE date LIKE ?1 || '%'",
+        [format!("{:04}-{:02}", date.year(), date.month())],
+        |row| row.get(0),
+    )
+}
+
+pub fn get_streak_freezes(conn: &Connection, today: NaiveDate) -> SqliteResult<StreakFreezes> {
+    let mut stmt = conn.prepare("SELECT date, applied_at FROM streak_freezes ORDER BY date DESC")?;
+    let freezes = stmt
+        .query_map([], |row| {
+            Ok(StreakFreeze {
+                date: row.get(0)?,
+                applied_at: row.get(1)?,
+            })
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    Ok(StreakFreezes {
+        freezes,
+        remaining_this_month: (FREEZES_PER_MONTH - used_in_month(conn, today)?).max(0),
+    })
+}
+
+pub fn apply_streak_freeze(
+    conn: &Connection,
+    date: NaiveDate,
+    goal_on: impl Fn(NaiveDate) -> i32,
+    today: NaiveDate,
+) -> Result<StreakFreeze, AppError> {
+    if date >= today {
+        return Err(AppError::invalid("date", "only past days can be frozen"));
+    }
+
+    let date_str = date.format("%Y-%m-%d").to_string();
+    let total_ml: i64 = conn
+        .query_row(crate::stats::DAY_TOTAL_QUERY, [&date_str], |row| row.get(0))?;
+    if total_ml >= goal_on(date) as i64 {
+        return Err(AppError::invalid("date", format!("the goal was met on {}, nothing to freeze", date_str)));
+    }
+
+    let already_frozen: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM streak_freezes WHERE date = ?1)",
+        [&date_str],
+        |row| row.get(0),
+    )?;
+    if already_frozen {
+        return Err(AppError::invalid("date", format!("{} is already frozen", date_str)));
+    }
+
+    if used_in_month(conn, date)? >= FREEZES_PER_MONTH {
+        return Err(AppError::invalid(
+            "date",
+            format!("no streak freezes left for {:04}-{:02}", date.year(), date.month()),
+        ));
+    }
+
+    let applied_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT INTO streak_freezes (date, applied_at) VALUES (?1, ?2)",
+        params![date_str, applied_at],
+    )?;
+
+    Ok(StreakFreeze {
+        date: date_str,
+        applied_at,
+    })
+}
@@ -0,0 +1,65 @@
+// Suggested daily goal from body weight, activity and climate:
+//
+//   33 ml per kg of body weight
+//   + 0 / 350 / 700 / 1000 ml for sedentary / light / moderate / active
+//   + 0 / 500 / 1000 ml for temperate / warm / hot climates
+//
+// rounded to the nearest 50 ml. This is the common 30-35 ml/kg rule of thumb
+// with flat adders for sweat loss, not medical advice.
+
+use crate::AppError;
+use serde::{Deserialize, Serialize};
+
+const ML_PER_KG: f64 = 33.0;
+const ROUND_TO_ML: f64 = 50.0;
+pub const MIN_WEIGHT_KG: f64 = 30.0;
+pub const MAX_WEIGHT_KG: f64 = 250.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityLevel {
+    Sedentary,
+    Light,
+    Moderate,
+    Active,
+}
+
+impl ActivityLevel {
+    fn extra_ml(self) -> f64 {
+        match self {
+            ActivityLevel::Sedentary => 0.0,
+            ActivityLevel::Light => 350.0,
+            ActivityLevel::Moderate => 700.0,
+            ActivityLevel::Active => 1000.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Climate {
+    Temperate,
+    Warm,
+    Hot,
+}
+
+impl Climate {
+    fn extra_ml(self) -> f64 {
+        match self {
+            Climate::Temperate => 0.0,
+            Climate::Warm => 500.0,
+            Climate::Hot => 1000.0,
+        }
+    }
+}
+
+pub fn suggested_goal_ml(weight_kg: f64, activity: ActivityLevel, climate: Climate) -> Result<i32, AppError> {
+    if !(MIN_WEIGHT_KG..=MAX_WEIGHT_KG).contains(&weight_kg) {
+        return Err(AppError::invalid(
+            "weight_kg",
+            format!("must be between {} and {} kg", MIN_WEIGHT_KG, MAX_WEIGHT_KG),
+        ));
+    }
+    let ml = weight_kg * ML_PER_KG + activity.extra_ml() + climate.extra_ml();
+    Ok(((ml / ROUND_TO_ML).round() * ROUND_TO_ML) as i32)
+}
@@ -0,0 +1,38 @@
+// Detects the entry that first takes today's total to the goal. The date
+// last celebrated is stored, so removing an entry and adding it back, or
+// drinking more afterwards, never celebrates the same day twice.
+
+use crate::{dates, settings, DailyStats};
+use chrono::NaiveDateTime;
+use rusqlite::{Connection, Result as SqliteResult};
+use serde::Serialize;
+
+pub const EVENT: &str = "goal-reached";
+const CELEBRATED_ON_KEY: &str = "celebrated_on";
+
+#[derive(Debug, Serialize, Clone)]
+pub struct GoalReached {
+    pub date: String,
+    pub total_ml: i32,
+    pub goal_ml: i32,
+    pub reached_at: String,
+}
+
+// `stats` must be for the logical day the new entry was filed under
+pub fn check(conn: &Connection, stats: &DailyStats, now: NaiveDateTime) -> SqliteResult<Option<GoalReached>> {
+    if stats.total_ml < stats.goal_ml {
+        return Ok(None);
+    }
+    let celebrated_on: Option<String> = settings::get(conn, CELEBRATED_ON_KEY)?;
+    if celebrated_on.as_deref() == Some(stats.date.as_str()) {
+        return Ok(None);
+    }
+
+    settings::set(conn, CELEBRATED_ON_KEY, &stats.date)?;
+    Ok(Some(GoalReached {
+        date: stats.date.clone(),
+        total_ml: stats.total_ml,
+        goal_ml: stats.goal_ml,
+        reached_at: now.format(dates::TIMESTAMP_FORMAT).to_string(),
+    }))
+}
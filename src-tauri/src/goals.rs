@@ -0,0 +1,157 @@
+// Progressive goal program: the daily goal starts at `start_ml` and grows by
+// `increment_ml` every `interval_days` until it reaches `target_ml`. While a
+// program is active its goal replaces `daily_goal_ml` everywhere; stopping it
+// falls back to the plain setting.
+
+use crate::{db, settings, AppError};
+use chrono::{Duration, NaiveDate};
+use rusqlite::{Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+const PROGRAM_KEYS: [&str; 5] = [
+    "goal_program_start_date",
+    "goal_program_start_ml",
+    "goal_program_increment_ml",
+    "goal_program_interval_days",
+    "goal_program_target_ml",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoalProgram {
+    pub start_ml: i32,
+    pub increment_ml: i32,
+    pub interval_days: i32,
+    pub start_date: NaiveDate,
+    pub target_ml: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoalProgramStatus {
+    pub active: bool,
+    pub program: Option<GoalProgram>,
+    pub current_goal_ml: i32,
+    // 1-based step of the ramp the program is currently on
+    pub phase: i32,
+    pub total_phases: i32,
+    pub next_increase_date: Option<String>,
+    pub target_reached: bool,
+}
+
+impl GoalProgram {
+    pub fn validate(&self) -> Result<(), AppError> {
+        // Every step is a daily goal, so the ramp stays within the same bounds
+        let goal_range = || {
+            format!(
+                "must be between {} and {} ml",
+                settings::DAILY_GOAL_RANGE_ML.start(),
+                settings::DAILY_GOAL_RANGE_ML.end()
+            )
+        };
+        if !settings::DAILY_GOAL_RANGE_ML.contains(&self.start_ml) {
+            return Err(AppError::invalid("start_ml", goal_range()));
+        }
+        if self.increment_ml <= 0 {
+            return Err(AppError::invalid("increment_ml", "must be positive"));
+        }
+        if self.interval_days <= 0 {
+            return Err(AppError::invalid("interval_days", "must be positive"));
+        }
+        if self.target_ml < self.start_ml {
+            return Err(AppError::invalid("target_ml", "must not be below start_ml"));
+        }
+        if !settings::DAILY_GOAL_RANGE_ML.contains(&self.target_ml) {
+            return Err(AppError::invalid("target_ml", goal_range()));
+        }
+        Ok(())
+    }
+
+    fn phase_on(&self, date: NaiveDate) -> i32 {
+        let elapsed = (date - self.start_date).num_days().max(0);
+        (elapsed / self.interval_days as i64) as i32
+    }
+
+    fn total_phases(&self) -> i32 {
+        // Number of increments needed to reach the target, rounding up so the
+        // last step lands on (and is capped at) target_ml
+        (self.target_ml - self.start_ml + self.increment_ml - 1) / self.increment_ml + 1
+    }
+
+    pub fn goal_on(&self, date: NaiveDate) -> i32 {
+        let raised = self.start_ml as i64 + self.phase_on(date) as i64 * self.increment_ml as i64;
+        raised.min(self.target_ml as i64) as i32
+    }
+
+    pub fn next_increase_on(&self, date: NaiveDate) -> Option<NaiveDate> {
+        if self.goal_on(date) >= self.target_ml {
+            return None;
+        }
+        if date < self.start_date {
+            return Some(self.start_date);
+        }
+        let next_phase = self.phase_on(date) as i64 + 1;
+        Some(self.start_date + Duration::days(next_phase * self.interval_days as i64))
+    }
+
+    pub fn status_on(&self, date: NaiveDate) -> GoalProgramStatus {
+        let current_goal_ml = self.goal_on(date);
+        GoalProgramStatus {
+            active: true,
+            program: Some(self.clone()),
+            current_goal_ml,
+            phase: (self.phase_on(date) + 1).min(self.total_phases()),
+            total_phases: self.total_phases(),
+            next_increase_date: self
+                .next_increase_on(date)
+                .map(|d| d.format("%Y-%m-%d").to_string()),
+            target_reached: current_goal_ml >= self.target_ml,
+        }
+    }
+}
+
+// Stored as separate settings keys; a missing start date means no program is
+// running
+pub fn load_program(conn: &Connection) -> SqliteResult<Option<GoalProgram>> {
+    let start_date: Option<String> = settings::get(conn, "goal_program_start_date")?;
+    let Some(start_date) = start_date.and_then(|d| crate::dates::parse_date(&d)) else {
+        return Ok(None);
+    };
+    let program = match (
+        settings::get(conn, "goal_program_start_ml")?,
+        settings::get(conn, "goal_program_increment_ml")?,
+        settings::get(conn, "goal_program_interval_days")?,
+        settings::get(conn, "goal_program_target_ml")?,
+    ) {
+        (Some(start_ml), Some(increment_ml), Some(interval_days), Some(target_ml)) => Some(GoalProgram {
+            start_ml,
+            increment_ml,
+            interval_days,
+            start_date,
+            target_ml,
+        }),
+        _ => None,
+    };
+    Ok(program)
+}
+
+pub fn save_program(conn: &Connection, program: &GoalProgram) -> SqliteResult<()> {
+    db::with_tx(conn, |tx| {
+        settings::set(tx, "goal_program_start_date", &program.start_date.format("%Y-%m-%d").to_string())?;
+        settings::set(tx, "goal_program_start_ml", &program.start_ml)?;
+        settings::set(tx, "goal_program_increment_ml", &program.increment_ml)?;
+        settings::set(tx, "goal_program_interval_days", &program.interval_days)?;
+        settings::set(tx, "goal_program_target_ml", &program.target_ml)
+    })
+}
+
+// Writing the plain daily goal while a program runs would change nothing
+// the user can see, so it's refused until the program is stopped
+pub fn ensure_no_program(conn: &Connection, field: &str) -> Result<(), AppError> {
+    if load_program(conn)?.is_some() {
+        return Err(AppError::invalid(field, "a goal program is running"));
+    }
+    Ok(())
+}
+
+pub fn clear_program(conn: &Connection) -> SqliteResult<()> {
+    db::with_tx(conn, |tx| PROGRAM_KEYS.iter().try_for_each(|key| settings::remove(tx, key)))
+}
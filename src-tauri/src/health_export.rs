@@ -0,0 +1,170 @@
+// Exports for other health apps. Volumes are converted from whole ml with
+// integer arithmetic, so 333 ml is exactly "0.333" L with no float rounding.
+// Timestamps are stored as local wall-clock time and get the local offset
+// that applied at that moment.
+
+use crate::dates;
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, Offset, TimeZone};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::Deserialize;
+use serde_json::json;
+
+const SOURCE_NAME: &str = "Hydra Tracker";
+const APPLE_WATER_TYPE: &str = "HKQuantityTypeIdentifierDietaryWater";
+const APPLE_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+const GOOGLE_HYDRATION_TYPE: &str = "com.google.hydration";
+// Health Connect rejects hydration records whose end isn't after their start
+const GOOGLE_RECORD_LENGTH_SECONDS: i64 = 60;
+
+// Which amount an entry exports as: the water it counts for, or what was
+// poured. Every entry is logged as plain water, which counts in full, so
+// the two agree until drinks carry a hydration factor of their own.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Volume {
+    #[default]
+    Effective,
+    Raw,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum GoogleFitFormat {
+    Json,
+    Csv,
+}
+
+struct ExportEntry {
+    amount_ml: i32,
+    at: NaiveDateTime,
+}
+
+impl ExportEntry {
+    fn volume_ml(&self, volume: Volume) -> i32 {
+        match volume {
+            Volume::Effective | Volume::Raw => self.amount_ml,
+        }
+    }
+}
+
+fn entries_in_range(conn: &Connection, start: NaiveDate, end: NaiveDate) -> SqliteResult<Vec<ExportEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT amount_ml, timestamp FROM water_entries
+         WHERE date BETWEEN ?1 AND ?2 ORDER BY timestamp",
+    )?;
+    let rows = stmt
+        .query_map(
+            params![
+                start.format(dates::DATE_FORMAT).to_string(),
+                end.format(dates::DATE_FORMAT).to_string()
+            ],
+            |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)),
+        )?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(amount_ml, timestamp)| {
+            Some(ExportEntry {
+                amount_ml,
+                at: dates::parse_timestamp(&timestamp)?,
+            })
+        })
+        .collect())
+}
+
+// Exact decimal litres, e.g. 1250 -> "1.25", 5 -> "0.005"
+pub fn ml_to_liters(ml: i32) -> String {
+    let sign = if ml < 0 { "-" } else { "" };
+    let ml = ml.unsigned_abs();
+    let fraction = format!("{:03}", ml % 1000);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{}{}", sign, ml / 1000)
+    } else {
+        format!("{}{}.{}", sign, ml / 1000, fraction)
+    }
+}
+
+// The earlier reading of a time repeated by a DST change; a time skipped by
+// one is taken as if the clocks hadn't moved yet
+fn with_local_offset(at: NaiveDateTime) -> DateTime<FixedOffset> {
+    if let Some(local) = Local.from_local_datetime(&at).earliest() {
+        return local.fixed_offset();
+    }
+    let before = Local
+        .from_local_datetime(&(at - Duration::hours(1)))
+        .earliest()
+        .map_or_else(|| Local::now().offset().fix(), |d| d.offset().fix());
+    before.from_local_datetime(&at).unwrap()
+}
+
+// The record layout of Health's own export.xml, which importers expect.
+// Entries are instants, so start and end are the same.
+pub fn apple_health_xml(
+    conn: &Connection,
+    start: NaiveDate,
+    end: NaiveDate,
+    volume: Volume,
+    exported_at: DateTime<FixedOffset>,
+) -> SqliteResult<(String, usize)> {
+    let entries = entries_in_range(conn, start, end)?;
+    let exported_at = exported_at.format(APPLE_DATE_FORMAT);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<HealthData locale=\"en_US\">\n");
+    xml.push_str(&format!(" <ExportDate value=\"{}\"/>\n", exported_at));
+    for entry in &entries {
+        let at = with_local_offset(entry.at).format(APPLE_DATE_FORMAT);
+        xml.push_str(&format!(
+            " <Record type=\"{}\" sourceName=\"{}\" unit=\"L\" creationDate=\"{}\" startDate=\"{}\" endDate=\"{}\" value=\"{}\"/>\n",
+            APPLE_WATER_TYPE,
+            SOURCE_NAME,
+            at,
+            at,
+            at,
+            ml_to_liters(entry.volume_ml(volume))
+        ));
+    }
+    xml.push_str("</HealthData>\n");
+    Ok((xml, entries.len()))
+}
+
+// Google Fit's hydration data type as Health Connect imports it: volume in
+// litres and epoch-millisecond start and end times
+pub fn google_fit(
+    conn: &Connection,
+    start: NaiveDate,
+    end: NaiveDate,
+    format: GoogleFitFormat,
+    volume: Volume,
+) -> SqliteResult<(String, usize)> {
+    let entries = entries_in_range(conn, start, end)?;
+    let points = entries.iter().map(|entry| {
+        let start_millis = with_local_offset(entry.at).timestamp_millis();
+        let end_millis = start_millis + GOOGLE_RECORD_LENGTH_SECONDS * 1000;
+        (start_millis, end_millis, ml_to_liters(entry.volume_ml(volume)))
+    });
+
+    let output = match format {
+        GoogleFitFormat::Json => {
+            let points: Vec<_> = points
+                .map(|(start_millis, end_millis, liters)| {
+                    json!({
+                        "startTimeMillis": start_millis,
+                        "endTimeMillis": end_millis,
+                        "volumeLiters": liters.parse::<f64>().unwrap_or_default(),
+                    })
+                })
+                .collect();
+            let document = json!({ "dataTypeName": GOOGLE_HYDRATION_TYPE, "points": points });
+            serde_json::to_string_pretty(&document).unwrap_or_default()
+        }
+        GoogleFitFormat::Csv => {
+            let mut csv = String::from("start_time_millis,end_time_millis,volume_liters\n");
+            for (start_millis, end_millis, liters) in points {
+                csv.push_str(&format!("{},{},{}\n", start_millis, end_millis, liters));
+            }
+            csv
+        }
+    };
+    Ok((output, entries.len()))
+}
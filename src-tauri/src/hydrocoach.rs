@@ -0,0 +1,94 @@
+// Hydro Coach's export, either the bare CSV or the ZIP it comes in. Times
+// are local wall-clock time, like ours, and amounts are already in ml.
+
+use crate::csv_import::{self, CsvImportSummary, ParsedRow, RowError};
+use crate::AppError;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::Connection;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+pub const ENTRY_SOURCE: &str = "hydrocoach";
+
+const TIMESTAMP_HEADERS: [&str; 4] = ["date time", "datetime", "timestamp", "date"];
+const TIME_HEADERS: [&str; 1] = ["time"];
+const AMOUNT_HEADERS: [&str; 4] = ["amount (ml)", "amount_ml", "amount", "ml"];
+const TIMESTAMP_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%d.%m.%Y %H:%M"];
+const DATE_FORMATS: [&str; 2] = ["%Y-%m-%d", "%d.%m.%Y"];
+const TIME_FORMATS: [&str; 2] = ["%H:%M:%S", "%H:%M"];
+
+// The CSV itself, pulled out of the archive when given the ZIP
+fn read_csv(path: &Path) -> Result<Vec<u8>, AppError> {
+    let mut file = File::open(path).map_err(|e| AppError::Other(format!("Can't open {}: {}", path.display(), e)))?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)?;
+    if !contents.starts_with(b"PK\x03\x04") {
+        return Ok(contents);
+    }
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(contents))
+        .map_err(|e| AppError::invalid("path", format!("not a readable archive: {}", e)))?;
+    let name = archive
+        .file_names()
+        .find(|name| name.to_ascii_lowercase().ends_with(".csv"))
+        .map(String::from)
+        .ok_or_else(|| AppError::invalid("path", "the archive has no CSV file in it"))?;
+    let mut csv = Vec::new();
+    archive
+        .by_name(&name)
+        .and_then(|mut entry| entry.read_to_end(&mut csv).map_err(Into::into))
+        .map_err(|e| AppError::Other(format!("Can't read {} from the archive: {}", name, e)))?;
+    Ok(csv)
+}
+
+// A full timestamp in one column, or a date with the time beside it
+fn parse_time(date: &str, time: Option<&str>) -> Option<NaiveDateTime> {
+    let date = date.trim();
+    match time {
+        Some(time) => {
+            let day = DATE_FORMATS.iter().find_map(|f| NaiveDate::parse_from_str(date, f).ok())?;
+            let at = TIME_FORMATS.iter().find_map(|f| NaiveTime::parse_from_str(time.trim(), f).ok())?;
+            Some(NaiveDateTime::new(day, at))
+        }
+        None => TIMESTAMP_FORMATS
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(date, f).ok()),
+    }
+}
+
+pub fn import(
+    conn: &Connection,
+    path: &Path,
+    day_starts_at_hour: i32,
+    strict: bool,
+) -> Result<CsvImportSummary, AppError> {
+    let contents = read_csv(path)?;
+    let mut reader = csv_import::reader_builder().from_reader(contents.as_slice());
+    let headers = reader.headers()?.clone();
+    let timestamp_column = csv_import::require_column(&headers, &TIMESTAMP_HEADERS, "date")?;
+    let time_column = csv_import::find_column(&headers, &TIME_HEADERS);
+    let amount_column = csv_import::require_column(&headers, &AMOUNT_HEADERS, "amount")?;
+
+    let rows: Vec<ParsedRow> = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            let row = record.map_err(RowError::from).and_then(|record| {
+                let date = record.get(timestamp_column).unwrap_or_default();
+                let time = time_column.map(|column| record.get(column).unwrap_or_default());
+                let at = parse_time(date, time).ok_or_else(|| {
+                    let value = match time {
+                        Some(time) => format!("{} {}", date.trim(), time.trim()),
+                        None => date.to_string(),
+                    };
+                    RowError::bad_timestamp(&value)
+                })?;
+                let amount = csv_import::parse_amount(record.get(amount_column).unwrap_or_default())?;
+                Ok((at, amount))
+            });
+            (index + 2, row)
+        })
+        .collect();
+    csv_import::insert_rows(conn, rows, ENTRY_SOURCE, day_starts_at_hour, strict)
+}
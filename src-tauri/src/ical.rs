@@ -0,0 +1,97 @@
+// Calendar export: an all-day event on every day the goal was met. UIDs are
+// derived from the date alone, so importing a newer export into the same
+// calendar updates those events instead of adding copies.
+
+use crate::units::{self, Unit};
+use crate::{dates, report, AppError};
+use chrono::{Duration, NaiveDate, Utc};
+use rusqlite::Connection;
+
+const PRODUCT_ID: &str = "-//Hydra Tracker//Hydration goals//EN";
+const UID_DOMAIN: &str = "hydra-tracker";
+const ICAL_DATE_FORMAT: &str = "%Y%m%d";
+// Content lines longer than this many octets must be folded (RFC 5545 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+// TEXT values escape backslashes, semicolons, commas and newlines (RFC 5545 3.3.11)
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Appends a content line, folding it with CRLF and a space so no physical
+// line exceeds the limit, without splitting a UTF-8 sequence
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            // The leading space counts toward the continuation line
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn event_uid(date: NaiveDate) -> String {
+    format!("goal-met-{}@{}", date.format(ICAL_DATE_FORMAT), UID_DOMAIN)
+}
+
+// Returns the calendar and the number of events in it
+pub fn goal_met_calendar(
+    conn: &Connection,
+    start: NaiveDate,
+    end: NaiveDate,
+    goal_on: impl Fn(NaiveDate) -> i32,
+    unit: Unit,
+) -> Result<(String, usize), AppError> {
+    let totals = report::daily_totals(conn, start, end)?;
+    let mut met: Vec<(NaiveDate, i64)> = totals
+        .into_iter()
+        .filter(|(date, total)| *total >= goal_on(*date) as i64)
+        .collect();
+    met.sort();
+
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, &format!("PRODID:{}", PRODUCT_ID));
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "METHOD:PUBLISH");
+    for (date, total) in &met {
+        let amount = units::format_amount((*total).clamp(0, i32::MAX as i64) as i32, unit);
+        push_line(&mut ics, "BEGIN:VEVENT");
+        push_line(&mut ics, &format!("UID:{}", event_uid(*date)));
+        push_line(&mut ics, &format!("DTSTAMP:{}", stamp));
+        push_line(&mut ics, &format!("DTSTART;VALUE=DATE:{}", date.format(ICAL_DATE_FORMAT)));
+        // All-day events end on the following day, exclusive
+        push_line(
+            &mut ics,
+            &format!("DTEND;VALUE=DATE:{}", (*date + Duration::days(1)).format(ICAL_DATE_FORMAT)),
+        );
+        push_line(
+            &mut ics,
+            &format!("SUMMARY:{}", escape_text(&format!("Hydration goal met ({})", amount))),
+        );
+        let description = format!("Logged {} on {}", amount, date.format(dates::DATE_FORMAT));
+        push_line(&mut ics, &format!("DESCRIPTION:{}", escape_text(&description)));
+        // Shows as free time rather than blocking the day
+        push_line(&mut ics, "TRANSP:TRANSPARENT");
+        push_line(&mut ics, "END:VEVENT");
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    Ok((ics, met.len()))
+}
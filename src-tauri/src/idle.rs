@@ -0,0 +1,112 @@
+// How long the user has been away from the machine. Each platform asks the
+// OS directly; None means it couldn't tell, and callers then behave as if
+// the user were present.
+
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::Command;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    // logind tracks both idleness (as reported by the desktop) and the lock
+    // screen for the session
+    pub fn idle_time() -> Option<Duration> {
+        let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "self".to_string());
+        let output = Command::new("loginctl")
+            .args(["show-session", &session, "-p", "IdleHint", "-p", "IdleSinceHint", "-p", "LockedHint"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let text = String::from_utf8_lossy(&output.stdout);
+        let value = |key: &str| {
+            text.lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                .map(str::trim)
+        };
+        if value("LockedHint") == Some("yes") {
+            return Some(Duration::MAX);
+        }
+        if value("IdleHint") != Some("yes") {
+            return value("IdleHint").map(|_| Duration::ZERO);
+        }
+        let since_us: u64 = value("IdleSinceHint")?.parse().ok()?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+        Some(now.saturating_sub(Duration::from_micros(since_us)))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::Command;
+    use std::time::Duration;
+
+    // HIDIdleTime is nanoseconds since the last keyboard or mouse input
+    pub fn idle_time() -> Option<Duration> {
+        let output = Command::new("ioreg").args(["-c", "IOHIDSystem", "-d", "4"]).output().ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let nanos: u64 = text
+            .lines()
+            .find_map(|line| line.split("\"HIDIdleTime\" = ").nth(1))?
+            .trim()
+            .parse()
+            .ok()?;
+        Some(Duration::from_nanos(nanos))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::time::Duration;
+
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    // Input stops while the workstation is locked, so this covers locking too
+    pub fn idle_time() -> Option<Duration> {
+        let mut info = LastInputInfo {
+            cb_size: std::mem::size_of::<LastInputInfo>() as u32,
+            dw_time: 0,
+        };
+        // SAFETY: `info` is a properly sized LASTINPUTINFO that outlives the call
+        let (ok, now) = unsafe { (GetLastInputInfo(&mut info), GetTickCount()) };
+        (ok != 0).then(|| Duration::from_millis(now.wrapping_sub(info.dw_time) as u64))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::time::Duration;
+
+    pub fn idle_time() -> Option<Duration> {
+        None
+    }
+}
+
+pub fn idle_time() -> Option<Duration> {
+    platform::idle_time()
+}
+
+// Away once idle for at least the threshold; a threshold of 0 turns this off
+pub fn is_away(idle: Option<Duration>, threshold_minutes: i32) -> bool {
+    if threshold_minutes <= 0 {
+        return false;
+    }
+    idle.is_some_and(|idle| idle >= Duration::from_secs(threshold_minutes as u64 * 60))
+}
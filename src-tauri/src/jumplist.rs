@@ -0,0 +1,145 @@
+// Windows jump list tasks, one "Add 250 ml" per quick-add amount. Each task
+// starts the executable with `add <ml>`, which the single-instance plugin
+// hands to the running app (see `cli`), so they work like the tray's quick
+// adds. The list is rebuilt when the amounts or the unit in their labels
+// change. `hydra-tracker cleanup`, run by the uninstaller, removes it.
+// Other platforms have no jump list, so this does nothing there.
+
+use crate::{settings, tray, units, AppError, Database, Settings};
+use std::sync::Mutex;
+use tauri::{AppHandle, Listener, Manager};
+
+// (title, arguments) for each task
+type Tasks = Vec<(String, String)>;
+
+fn tasks(settings: &Settings) -> Tasks {
+    tray::quick_add_amounts(settings)
+        .into_iter()
+        .map(|ml| (format!("Add {}", units::format_amount(ml, settings.unit)), format!("add {}", ml)))
+        .collect()
+}
+
+pub fn setup(app: &AppHandle) {
+    let settings = settings::load(&app.state::<Database>().lock()).unwrap_or_default();
+    let current = tasks(&settings);
+    rebuild(current.clone());
+
+    let shown = Mutex::new(current);
+    app.listen(settings::CHANGED_EVENT, move |event| {
+        let Ok(settings) = serde_json::from_str::<Settings>(event.payload()) else {
+            return;
+        };
+        let tasks = tasks(&settings);
+        let Ok(mut shown) = shown.lock() else {
+            return;
+        };
+        if *shown != tasks {
+            *shown = tasks.clone();
+            rebuild(tasks);
+        }
+    });
+}
+
+#[cfg(windows)]
+mod shell {
+    use super::Tasks;
+    use std::path::Path;
+    use windows::core::{Interface, Result, HSTRING, PCWSTR};
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::StructuredStorage::{PropVariantClear, PROPVARIANT};
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+    };
+    use windows::Win32::System::Variant::VT_LPWSTR;
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, SHStrDupW, ShellLink,
+    };
+
+    // Jump list tasks show the link's title property, not its description
+    unsafe fn set_title(link: &IShellLinkW, title: &str) -> Result<()> {
+        let store: IPropertyStore = link.cast()?;
+        let mut value = PROPVARIANT::default();
+        (*value.Anonymous.Anonymous).vt = VT_LPWSTR;
+        (*value.Anonymous.Anonymous).Anonymous.pwszVal = SHStrDupW(&HSTRING::from(title))?;
+        let result = store.SetValue(&PKEY_Title, &value).and_then(|_| store.Commit());
+        let _ = PropVariantClear(&mut value);
+        result
+    }
+
+    unsafe fn task(exe: &HSTRING, title: &str, arguments: &str) -> Result<IShellLinkW> {
+        let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+        link.SetPath(exe)?;
+        link.SetArguments(&HSTRING::from(arguments))?;
+        link.SetIconLocation(exe, 0)?;
+        set_title(&link, title)?;
+        Ok(link)
+    }
+
+    unsafe fn write(exe: &Path, tasks: &Tasks) -> Result<()> {
+        let list: ICustomDestinationList = CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+        let mut slots = 0;
+        let _removed: IObjectArray = list.BeginList(&mut slots)?;
+        let collection: IObjectCollection = CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+        let exe = HSTRING::from(exe);
+        for (title, arguments) in tasks {
+            collection.AddObject(&task(&exe, title, arguments)?)?;
+        }
+        list.AddUserTasks(&collection.cast::<IObjectArray>()?)?;
+        list.CommitList()
+    }
+
+    unsafe fn delete() -> Result<()> {
+        let list: ICustomDestinationList = CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+        list.DeleteList(PCWSTR::null())
+    }
+
+    // Callers run on threads that haven't set up COM themselves
+    fn with_com(f: impl FnOnce() -> Result<()>) -> Result<()> {
+        unsafe {
+            let initialized = CoInitializeEx(None, COINIT_APARTMENTTHREADED).is_ok();
+            let result = f();
+            if initialized {
+                CoUninitialize();
+            }
+            result
+        }
+    }
+
+    pub fn replace(exe: &Path, tasks: &Tasks) -> Result<()> {
+        with_com(|| unsafe { write(exe, tasks) })
+    }
+
+    pub fn clear() -> Result<()> {
+        with_com(|| unsafe { delete() })
+    }
+}
+
+#[cfg(windows)]
+fn rebuild(tasks: Tasks) {
+    // Not worth holding up the caller for
+    std::thread::spawn(move || {
+        let result = std::env::current_exe()
+            .map_err(AppError::from)
+            .and_then(|exe| shell::replace(&exe, &tasks).map_err(|e| AppError::Other(e.to_string())));
+        if let Err(e) = result {
+            tracing::warn!("couldn't update the jump list: {}", e);
+        }
+    });
+}
+
+#[cfg(not(windows))]
+fn rebuild(_tasks: Tasks) {}
+
+// Removes the tasks, which otherwise outlive the app. Called before the app
+// starts, so COM isn't set up on this thread yet.
+#[cfg(windows)]
+pub fn clear() -> Result<(), AppError> {
+    shell::clear().map_err(|e| AppError::Other(e.to_string()))
+}
+
+#[cfg(not(windows))]
+pub fn clear() -> Result<(), AppError> {
+    Ok(())
+}
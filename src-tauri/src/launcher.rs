@@ -0,0 +1,114 @@
+// Today's progress over the launcher icon on Linux docks that follow the
+// Unity LauncherEntry D-Bus interface (Dash to Dock, Plank, KDE's task
+// manager): the bar fills with the percentage and the count is what's left
+// in decilitres, 15 for 1.5 L. Both are cleared once the goal is met. Other
+// platforms have their own (see `taskbar` and `dock`), so this does nothing
+// there.
+//
+// Updates are signals sent from a thread of their own, which connects to
+// the session bus for the first one. Docks drop the entry when the
+// connection goes away, so it's kept while the app runs, but nothing ever
+// waits on it: the thread only blocks on its own channel and is never
+// joined, so quitting doesn't wait for the bus. Without a session bus the
+// thread gives up quietly; with no dock listening the signals go nowhere.
+
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use crate::DailyStats;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const ML_PER_COUNT: i32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LauncherProgress {
+    percent: u8,
+    remaining: i64,
+    goal_met: bool,
+}
+
+impl LauncherProgress {
+    pub fn new(stats: &DailyStats) -> Self {
+        let remaining_ml = (stats.goal_ml - stats.total_ml).max(0);
+        Self {
+            percent: stats.percentage.clamp(0.0, 100.0).floor() as u8,
+            // Rounded up, so it only reaches 0 at the goal
+            remaining: ((remaining_ml + ML_PER_COUNT - 1) / ML_PER_COUNT) as i64,
+            goal_met: stats.total_ml >= stats.goal_ml,
+        }
+    }
+}
+
+pub struct Launcher(Mutex<Sender<LauncherProgress>>);
+
+#[cfg(target_os = "linux")]
+mod unity {
+    use super::LauncherProgress;
+    use std::collections::HashMap;
+    use std::sync::mpsc::Receiver;
+    use zbus::blocking::Connection;
+    use zbus::names::BusName;
+    use zbus::zvariant::Value;
+
+    const OBJECT_PATH: &str = "/com/canonical/unity/launcherentry/hydra_tracker";
+    const INTERFACE: &str = "com.canonical.Unity.LauncherEntry";
+    const FALLBACK_DESKTOP_ID: &str = "hydra-tracker";
+
+    // Docks match entries to launchers by desktop file, which the bundles
+    // name after the executable
+    fn app_uri() -> String {
+        let id = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+            .unwrap_or_else(|| FALLBACK_DESKTOP_ID.to_string());
+        format!("application://{}.desktop", id)
+    }
+
+    fn properties(progress: LauncherProgress) -> HashMap<&'static str, Value<'static>> {
+        let visible = !progress.goal_met;
+        HashMap::from([
+            ("progress", Value::from(progress.percent as f64 / 100.0)),
+            ("progress-visible", Value::from(visible)),
+            ("count", Value::from(progress.remaining)),
+            ("count-visible", Value::from(visible)),
+        ])
+    }
+
+    pub fn run(updates: Receiver<LauncherProgress>) {
+        let uri = app_uri();
+        let mut connection = None;
+        for progress in updates {
+            if connection.is_none() {
+                match Connection::session() {
+                    Ok(session) => connection = Some(session),
+                    Err(_) => return,
+                }
+            }
+            if let Some(session) = &connection {
+                let _ = session.emit_signal(
+                    None::<BusName>,
+                    OBJECT_PATH,
+                    INTERFACE,
+                    "Update",
+                    &(uri.as_str(), properties(progress)),
+                );
+            }
+        }
+    }
+}
+
+pub fn start(app: &AppHandle) {
+    let (sender, _receiver) = mpsc::channel();
+    #[cfg(target_os = "linux")]
+    std::thread::spawn(move || unity::run(_receiver));
+    app.manage(Launcher(Mutex::new(sender)));
+}
+
+pub fn show(app: &AppHandle, progress: LauncherProgress) {
+    if let Some(launcher) = app.try_state::<Launcher>() {
+        if let Ok(sender) = launcher.0.lock() {
+            let _ = sender.send(progress);
+        }
+    }
+}
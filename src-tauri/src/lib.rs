@@ -0,0 +1,461 @@
+// The app as a library, so the database and statistics code can be tested
+// without a window: main.rs only calls run(). Commands are thin wrappers in
+// `commands` over functions here and in `db` and `stats` that take a plain
+// Connection.
+
+pub mod achievements;
+pub mod analytics;
+pub mod anonymize;
+mod autostart;
+pub mod backup;
+mod checkpoints;
+mod cli;
+mod commands;
+pub mod csv_export;
+pub mod csv_import;
+pub mod datadir;
+pub mod dates;
+pub mod db;
+mod deeplink;
+mod desk_session;
+mod dock;
+mod error;
+pub mod freezes;
+pub mod goal_calculator;
+mod goal_reached;
+pub mod goals;
+pub mod health_export;
+pub mod hydrocoach;
+pub mod ical;
+mod idle;
+mod jumplist;
+mod launcher;
+pub mod locale;
+pub mod logging;
+pub mod maintenance;
+pub mod milestones;
+pub mod migrations;
+pub mod models;
+mod notifications;
+mod overlay;
+pub mod presets;
+pub mod reminders;
+pub mod report;
+mod resume;
+pub mod retention;
+pub mod rollover;
+pub mod scheduler;
+pub mod settings;
+pub mod share_card;
+mod shortcuts;
+mod sound;
+pub mod stats;
+mod taskbar;
+pub mod text_import;
+mod theme;
+mod tray;
+mod tray_icon;
+pub mod units;
+pub mod waterminder;
+mod widget;
+mod window_state;
+
+pub use error::AppError;
+pub use models::{AddWaterOutcome, AllTimeStats, DailyStats, Database, MonthlyStats, WaterEntry, YearOverYear};
+pub use settings::Settings;
+pub use stats::{calculate_streaks, today_stats};
+
+use crate::db::logical_today;
+use crate::models::ChangeKind;
+use chrono::{Local, NaiveDateTime};
+use rusqlite::{Connection, Result as SqliteResult};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+// Runs `work` on the blocking thread pool. Tray, shortcut, link and
+// second-instance handlers are called on the main thread, where waiting for
+// the database lock would freeze every window.
+pub(crate) fn in_background(app: &AppHandle, work: impl FnOnce(&AppHandle) + Send + 'static) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || work(&app));
+}
+
+// The "are you sure?" answer for an amount over max_entry_ml, unless `force`
+// says it was already confirmed. None means go ahead and record it.
+pub fn check_entry_limit(conn: &Connection, amount_ml: i32, force: bool) -> Result<Option<AddWaterOutcome>, AppError> {
+    let max_entry_ml = settings::load(conn)?.max_entry_ml;
+    if amount_ml <= max_entry_ml || force {
+        return Ok(None);
+    }
+    tracing::info!("{} ml is over the {} ml limit, asking to confirm", amount_ml, max_entry_ml);
+    Ok(Some(AddWaterOutcome::NeedsConfirmation {
+        amount_ml,
+        max_entry_ml,
+    }))
+}
+
+// Insert an entry and run everything that follows a drink. Forced and
+// unforced adds both end up here, so they store exactly the same row.
+pub fn record_entry(app: &AppHandle, conn: &Connection, amount_ml: i32, source: &str) -> Result<WaterEntry, AppError> {
+    record_entry_at(app, conn, amount_ml, source, Local::now().naive_local())
+}
+
+// As record_entry, for a drink at `at` rather than just now
+pub fn record_entry_at(
+    app: &AppHandle,
+    conn: &Connection,
+    amount_ml: i32,
+    source: &str,
+    at: NaiveDateTime,
+) -> Result<WaterEntry, AppError> {
+    let now = Local::now().naive_local();
+    auto_backup(app, conn);
+    // The entry and what it unlocks are stored together, and only announced
+    // once they are
+    // A back-dated entry can only complete the day it's filed under
+    let day = dates::logical_date(at, db::get_day_starts_at_hour(conn));
+    let (entry, unlocked) = db::with_tx(conn, |conn| {
+        let entry = db::insert_entry(conn, amount_ml, source, at)?;
+        let unlocked = check_unlocked(conn, &stats::day_stats(conn, day)?, now)?;
+        Ok::<_, AppError>((entry, unlocked))
+    })?;
+    tracing::info!(
+        "recorded entry {}: {} ml at {} for {} from {}",
+        entry.id,
+        entry.amount_ml,
+        entry.timestamp,
+        entry.date,
+        entry.source
+    );
+    announce_unlocked(app, conn, unlocked)?;
+
+    // The tray follows the event. The next reminder counts from this entry,
+    // and smart intervals depend on today's total.
+    notifications::entries_changed(app, ChangeKind::Added, [entry.date.clone()]);
+    scheduler::wake(app);
+
+    Ok(entry)
+}
+
+// What a change to the entries unlocked: achievements, milestones and the
+// day's goal. Checked in the same transaction as the change.
+pub(crate) struct Unlocked {
+    achievements: Vec<achievements::Achievement>,
+    milestones: Vec<milestones::Milestone>,
+    reached: Option<goal_reached::GoalReached>,
+}
+
+// `stats` is for the day the goal celebration is judged on
+pub(crate) fn check_unlocked(conn: &Connection, stats: &DailyStats, now: NaiveDateTime) -> Result<Unlocked, AppError> {
+    Ok(Unlocked {
+        achievements: achievements::check_achievements(conn, db::goal_schedule(conn), logical_today(conn))?,
+        milestones: milestones::check_milestones(conn)?,
+        reached: goal_reached::check(conn, stats, now)?,
+    })
+}
+
+// Notifications and events for what check_unlocked found, once it is stored
+pub(crate) fn announce_unlocked(app: &AppHandle, conn: &Connection, unlocked: Unlocked) -> Result<(), AppError> {
+    for achievement in unlocked.achievements {
+        let _ = app.emit("achievement-unlocked", achievement);
+    }
+
+    for milestone in unlocked.milestones {
+        let _ = app
+            .notification()
+            .builder()
+            .title("💧 Milestone reached!")
+            .body(format!(
+                "You've logged {} L of water in total.",
+                milestone.threshold_ml / 1000
+            ))
+            .show();
+        let _ = app.emit("milestone-reached", milestone);
+    }
+
+    if let Some(reached) = unlocked.reached {
+        let settings = settings::load(conn)?;
+        if settings.notify_on_goal_reached {
+            let _ = app
+                .notification()
+                .builder()
+                .title("🎉 Daily goal reached!")
+                .body(format!(
+                    "You've had {} {}.",
+                    units::format_amount(reached.total_ml, settings.unit),
+                    if reached.date == logical_today(conn).format(dates::DATE_FORMAT).to_string() {
+                        "today".to_string()
+                    } else {
+                        format!("on {}", reached.date)
+                    }
+                ))
+                .show();
+        }
+        let _ = app.emit(goal_reached::EVENT, reached);
+    }
+    Ok(())
+}
+
+pub fn reminder_interval(conn: &Connection) -> SqliteResult<Option<i32>> {
+    let settings = settings::load(conn)?;
+    let stats = today_stats(conn)?;
+    let resumed = reminders::resumed_today(conn, logical_today(conn))?;
+    Ok(reminders::reminder_interval_minutes(
+        &settings,
+        &stats,
+        Local::now().naive_local(),
+        resumed,
+    ))
+}
+
+fn apply_retention(conn: &Connection) {
+    let Ok(settings) = settings::load(conn) else {
+        return;
+    };
+    let today = logical_today(conn);
+    match retention::apply_retention(conn, settings.retention_years, today) {
+        Ok(0) => {}
+        Ok(deleted) => tracing::info!("deleted {} entries older than {} years", deleted, settings.retention_years),
+        Err(e) => tracing::error!("retention purge failed: {}", e),
+    }
+}
+
+// Takes today's automatic backup if it's due, and reports a failure once
+fn auto_backup(app: &AppHandle, conn: &Connection) {
+    let Ok(settings) = settings::load(conn) else {
+        return;
+    };
+    if let Some(error) = backup::auto_backup(conn, &settings, logical_today(conn)) {
+        let _ = app
+            .notification()
+            .builder()
+            .title("Automatic backup failed")
+            .body(error)
+            .show();
+    }
+}
+
+const HIDDEN_FLAG: &str = "--hidden";
+
+pub fn run() {
+    let args: Vec<String> = std::env::args().collect();
+    // Passed by autostart so logging in doesn't bring up the window
+    let hidden = args.iter().any(|arg| arg == HIDDEN_FLAG);
+    let command = cli::parse(&args).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, cli::USAGE);
+        std::process::exit(2);
+    });
+
+    // Run by the uninstaller, which may be removing the database too
+    if command == Some(cli::Command::Cleanup) {
+        if let Err(e) = jumplist::clear() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    logging::init();
+    let db_path = db::get_db_path().unwrap_or_else(|e| {
+        tracing::error!("{}", e);
+        std::process::exit(1);
+    });
+    let conn = db::open(&db_path).expect("Failed to open database");
+    // Before anything reads it; a database from a newer version is left alone
+    if let Err(e) = db::init_db(&conn) {
+        tracing::error!("{}", e);
+        std::process::exit(1);
+    }
+    if let Ok(settings) = settings::load(&conn) {
+        logging::set_level(&settings.log_level);
+    }
+
+    // Only reads, so it doesn't need the running instance
+    if command == Some(cli::Command::Stats) {
+        match cli::stats_text(&conn) {
+            Ok(text) => println!("{}", text),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            // hydra:// links are handled by the deep-link plugin, which this
+            // forwards them to
+            if deeplink::is_link_launch(&args) {
+                return;
+            }
+            if let Ok(Some(_)) = cli::parse(&args) {
+                in_background(app, move |app| cli::run(app, &args));
+                return;
+            }
+            // If another instance tries to start, focus the existing window,
+            // unless it's autostart starting while the app already runs
+            if args.iter().any(|arg| arg == HIDDEN_FLAG) {
+                return;
+            }
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            Some(vec![HIDDEN_FLAG]),
+        ))
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .plugin(shortcuts::plugin())
+        .manage(Database::new(conn))
+        .manage(notifications::PendingQuickAdd::default())
+        .manage(retention::PendingPurge::default())
+        .manage(shortcuts::ShortcutRegistrations::default())
+        .manage(taskbar::LastProgress::default())
+        .manage(overlay::OverlayShowings::default())
+        .invoke_handler(tauri::generate_handler![
+            commands::add_water,
+            commands::remove_entry,
+            commands::get_today_stats,
+            commands::get_today_entries,
+            commands::get_monthly_stats,
+            commands::generate_report,
+            commands::generate_pdf_report,
+            commands::render_share_card,
+            commands::get_settings,
+            commands::save_settings,
+            commands::export_settings,
+            commands::import_settings,
+            commands::import_csv,
+            commands::import_waterminder,
+            commands::import_hydrocoach,
+            commands::import_from_text,
+            commands::commit_text_import,
+            commands::preview_purge,
+            commands::purge_entries_before,
+            commands::export_backup,
+            commands::restore_backup,
+            commands::list_backups,
+            commands::export_database_snapshot,
+            commands::export_apple_health,
+            commands::export_google_fit,
+            commands::export_anonymized,
+            commands::export_csv,
+            commands::get_export_status,
+            commands::export_ical,
+            commands::get_shortcut_status,
+            commands::reset_window_geometry,
+            commands::calculate_goal,
+            commands::apply_suggested_goal,
+            commands::get_goal_presets,
+            commands::save_goal_preset,
+            commands::delete_goal_preset,
+            commands::activate_goal_preset,
+            commands::get_active_goal_preset,
+            commands::get_data_directory,
+            commands::open_data_directory,
+            commands::show_widget,
+            commands::hide_widget,
+            commands::get_widget_stats,
+            commands::widget_quick_add,
+            commands::move_data_directory,
+            commands::preview_sound,
+            commands::snooze_reminders,
+            commands::get_snooze_state,
+            commands::get_away_state,
+            commands::take_pending_quick_add,
+            commands::get_reminder_interval,
+            commands::resume_reminders,
+            commands::get_yearly_overview,
+            commands::recompute_entry_dates,
+            commands::run_maintenance,
+            commands::get_recent_logs,
+            commands::get_log_path,
+            commands::get_achievements,
+            commands::convert_amount,
+            commands::get_alltime_stats,
+            commands::get_trend,
+            commands::compare_year_over_year,
+            commands::get_drink_time_trends,
+            commands::get_typical_pattern,
+            commands::start_goal_program,
+            commands::stop_goal_program,
+            commands::get_goal_program_status,
+            commands::apply_streak_freeze,
+            commands::get_streak_freezes,
+        ])
+        .setup(move |app| {
+            logging::follow_setting(app.handle());
+            let _ = autostart::reconcile(app.handle());
+            launcher::start(app.handle());
+            tray::setup_tray(app.handle())?;
+            shortcuts::setup(app.handle());
+            deeplink::setup(app.handle());
+            jumplist::setup(app.handle());
+            widget::setup(app.handle());
+            checkpoints::start(app.handle().clone());
+            scheduler::start(app.handle().clone());
+            resume::start(app.handle());
+            {
+                let db = app.state::<Database>();
+                let conn = db.lock();
+                // Backed up first, so a retention purge can still be undone
+                auto_backup(app.handle(), &conn);
+                apply_retention(&conn);
+            }
+            cli::run(app.handle(), &args);
+
+            // Show window after setup, unless it should start in the tray
+            let start_minimized = settings::load(&app.state::<Database>().lock())
+                .is_ok_and(|settings| settings.start_minimized);
+            if let Some(window) = app.get_webview_window("main") {
+                window_state::restore(app.handle(), &window);
+                if !hidden && !start_minimized {
+                    let _ = window.show();
+                }
+            }
+            
+            Ok(())
+        })
+        .on_window_event(|window, event| match event {
+            // Minimize to tray instead of closing
+            tauri::WindowEvent::CloseRequested { api, .. } if window.label() == widget::LABEL => {
+                if let Err(e) = widget::hide(window.app_handle()) {
+                    tracing::warn!("couldn't hide the widget: {}", e);
+                }
+                api.prevent_close();
+            }
+            tauri::WindowEvent::CloseRequested { api, .. } => {
+                // Only the main window's geometry is kept
+                if window.label() == "main" {
+                    if let Some(main) = window.get_webview_window("main") {
+                        window_state::save(window.app_handle(), &main);
+                    }
+                }
+                let _ = window.hide();
+                api.prevent_close();
+            }
+            tauri::WindowEvent::ThemeChanged(os_theme) => {
+                theme::on_os_theme_changed(window.app_handle(), *os_theme);
+            }
+            _ => {}
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Quitting from the tray leaves the window where it is
+            tauri::RunEvent::ExitRequested { .. } => {
+                if let Some(window) = app.get_webview_window("main") {
+                    window_state::save(app, &window);
+                }
+                widget::save(app);
+            }
+            tauri::RunEvent::Exit => shortcuts::unregister_all(app),
+            _ => {}
+        });
+}
+
@@ -0,0 +1,87 @@
+// Human-readable text the backend produces itself, such as month names.
+// Machine-readable fields (dates, timestamps) stay ISO regardless of locale.
+
+use crate::settings;
+use rusqlite::Connection;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+}
+
+const MONTHS_EN: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+const MONTHS_DE: [&str; 12] = [
+    "Januar", "Februar", "März", "April", "Mai", "Juni",
+    "Juli", "August", "September", "Oktober", "November", "Dezember",
+];
+const MONTHS_FR: [&str; 12] = [
+    "janvier", "février", "mars", "avril", "mai", "juin",
+    "juillet", "août", "septembre", "octobre", "novembre", "décembre",
+];
+
+const MONTH_ABBREVIATIONS_EN: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun",
+    "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const MONTH_ABBREVIATIONS_DE: [&str; 12] = [
+    "Jan", "Feb", "Mär", "Apr", "Mai", "Jun",
+    "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+];
+const MONTH_ABBREVIATIONS_FR: [&str; 12] = [
+    "janv.", "févr.", "mars", "avr.", "mai", "juin",
+    "juil.", "août", "sept.", "oct.", "nov.", "déc.",
+];
+
+impl Locale {
+    // Matches on the language part, so "de-AT" and "de_CH" are German.
+    // Anything unrecognised is English.
+    pub fn parse(tag: &str) -> Locale {
+        let language = tag.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+        match language.as_str() {
+            "de" => Locale::De,
+            "fr" => Locale::Fr,
+            _ => Locale::En,
+        }
+    }
+
+    // `month` is 1-based; out-of-range months give an empty string
+    pub fn month_name(self, month: u32) -> &'static str {
+        let names = match self {
+            Locale::En => &MONTHS_EN,
+            Locale::De => &MONTHS_DE,
+            Locale::Fr => &MONTHS_FR,
+        };
+        names.get((month as usize).wrapping_sub(1)).copied().unwrap_or("")
+    }
+
+    pub fn month_abbreviation(self, month: u32) -> &'static str {
+        let names = match self {
+            Locale::En => &MONTH_ABBREVIATIONS_EN,
+            Locale::De => &MONTH_ABBREVIATIONS_DE,
+            Locale::Fr => &MONTH_ABBREVIATIONS_FR,
+        };
+        names.get((month as usize).wrapping_sub(1)).copied().unwrap_or("")
+    }
+
+    pub fn today(self) -> &'static str {
+        match self {
+            Locale::En => "Today",
+            Locale::De => "Heute",
+            Locale::Fr => "Aujourd'hui",
+        }
+    }
+}
+
+pub fn current(conn: &Connection) -> Locale {
+    settings::get::<String>(conn, "locale")
+        .ok()
+        .flatten()
+        .map(|tag| Locale::parse(&tag))
+        .unwrap_or_default()
+}
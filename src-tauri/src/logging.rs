@@ -0,0 +1,276 @@
+// The app's log. `tracing` events from anywhere in the crate are written to
+// logs/hydra.log in the data directory, and to stderr for a terminal. The
+// file is capped: once it would pass MAX_FILE_BYTES it becomes hydra.log.1,
+// the older ones shift up a number, and only KEEP_FILES are kept, so the
+// logs never take more than a few megabytes.
+//
+// At info that's every command that changes stored data (see
+// `commands::logged`) and the scheduler's decisions; errors say what was
+// being done when they happened. Only amounts, ids, dates, counts and
+// setting names go in, never file paths, preset names or anything else the
+// user typed.
+//
+// Verbosity is the `log_level` setting. Events from other crates are left
+// out whatever it says, which is all the subscriber below has to decide
+// besides formatting the line.
+
+use crate::{datadir, settings, Settings};
+use chrono::Local;
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock, RwLock};
+use tauri::{AppHandle, Listener};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Metadata, Subscriber};
+
+pub const LOG_FILE: &str = "hydra.log";
+pub const LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+pub const DEFAULT_LEVEL: &str = "info";
+pub const MAX_FILE_BYTES: u64 = 1024 * 1024;
+// hydra.log and hydra.log.1 through .4
+pub const KEEP_FILES: usize = 5;
+const CRATE_TARGET: &str = "hydra_tracker_lib";
+
+static LEVEL: RwLock<LevelFilter> = RwLock::new(LevelFilter::INFO);
+static LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+// `path` with a rotation number, hydra.log.2
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", n));
+    path.with_file_name(name)
+}
+
+fn ignore_missing(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+// A log file that rotates itself by size. Opened on the first write, so
+// nothing is created until there's something to log.
+pub struct RollingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RollingFile {
+    pub fn new(path: PathBuf, max_bytes: u64, keep: usize) -> Self {
+        Self {
+            path,
+            max_bytes,
+            keep: keep.max(1),
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn open(&mut self) -> io::Result<&mut File> {
+        if self.file.is_none() {
+            let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            self.size = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().expect("opened above"))
+    }
+
+    // The oldest file is dropped and the rest move up one
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        if self.keep == 1 {
+            return ignore_missing(fs::remove_file(&self.path));
+        }
+        ignore_missing(fs::remove_file(numbered(&self.path, self.keep - 1)))?;
+        for n in (1..self.keep - 1).rev() {
+            ignore_missing(fs::rename(numbered(&self.path, n), numbered(&self.path, n + 1)))?;
+        }
+        ignore_missing(fs::rename(&self.path, numbered(&self.path, 1)))
+    }
+}
+
+impl Write for RollingFile {
+    // The subscriber hands over one whole line at a time, so a line is never
+    // split across two files
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.open()?;
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.open()?.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+// The last `lines` lines, reading back into the previous file when the
+// current one is short
+pub fn recent_lines(path: &Path, lines: usize) -> io::Result<Vec<String>> {
+    if lines == 0 {
+        return Ok(Vec::new());
+    }
+    let mut recent = VecDeque::with_capacity(lines);
+    for file in [numbered(path, 1), path.to_path_buf()] {
+        let contents = match fs::read(&file) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for line in String::from_utf8_lossy(&contents).lines() {
+            if recent.len() == lines {
+                recent.pop_front();
+            }
+            recent.push_back(line.to_string());
+        }
+    }
+    Ok(recent.into())
+}
+
+// The message, then any other fields as key=value
+#[derive(Default)]
+struct Line {
+    message: String,
+    fields: String,
+}
+
+impl Visit for Line {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+struct LogSubscriber {
+    file: Mutex<RollingFile>,
+}
+
+impl Subscriber for LogSubscriber {
+    // Asked again for every event, since the level can change while running
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if metadata.target().starts_with(CRATE_TARGET) {
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with(CRATE_TARGET) && *metadata.level() <= level()
+    }
+
+    // Spans aren't used; every event stands on its own
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut line = Line::default();
+        event.record(&mut line);
+        let module = metadata
+            .module_path()
+            .map(|path| path.trim_start_matches(CRATE_TARGET).trim_start_matches("::"))
+            .filter(|module| !module.is_empty())
+            .unwrap_or("app");
+        let text = format!(
+            "{} {:>5} {}: {}{}\n",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            metadata.level(),
+            module,
+            line.message,
+            line.fields
+        );
+
+        eprint!("{}", text);
+        // Nowhere left to report a failed write
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(text.as_bytes());
+        }
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+fn level() -> LevelFilter {
+    LEVEL.read().map(|level| *level).unwrap_or(LevelFilter::INFO)
+}
+
+// Unknown names are ignored; settings validation keeps them out
+pub fn set_level(name: &str) {
+    if !LEVELS.contains(&name) {
+        return;
+    }
+    if let (Ok(filter), Ok(mut level)) = (LevelFilter::from_str(name), LEVEL.write()) {
+        *level = filter;
+    }
+}
+
+// Where the log is written, whether or not anything has been yet
+pub fn log_path() -> PathBuf {
+    LOG_PATH
+        .get()
+        .cloned()
+        .unwrap_or_else(|| datadir::logs_dir().join(LOG_FILE))
+}
+
+// Starts writing into the logs directory. Called once at startup, before
+// the database is opened, so a failed migration is logged too; the level is
+// the default until the settings can be read.
+pub fn init() {
+    let path = datadir::logs_dir().join(LOG_FILE);
+    if let Some(dir) = path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Couldn't create {}: {}", dir.display(), e);
+        }
+    }
+    let _ = LOG_PATH.set(path.clone());
+    let subscriber = LogSubscriber {
+        file: Mutex::new(RollingFile::new(path, MAX_FILE_BYTES, KEEP_FILES)),
+    };
+    if tracing::subscriber::set_global_default(subscriber).is_err() {
+        eprintln!("A logger was already installed, not writing {}", LOG_FILE);
+    }
+}
+
+// Follows `log_level` from every settings change, whichever command made it
+pub fn follow_setting(app: &AppHandle) {
+    app.listen(settings::CHANGED_EVENT, |event| {
+        if let Ok(settings) = serde_json::from_str::<Settings>(event.payload()) {
+            set_level(&settings.log_level);
+        }
+    });
+}
@@ -0,0 +1,97 @@
+// Housekeeping for hydra.db, run on request from settings: an integrity
+// check, then fresh query planner statistics and a VACUUM to give back the
+// space that deleted entries left behind.
+//
+// A database that fails the check is left exactly as it is. VACUUM rewrites
+// every page, which can turn damage SQLite could still read around into
+// data that's gone, so the report carries the problems found instead and
+// the way out is a backup.
+
+use rusqlite::{Connection, ErrorCode, Result as SqliteResult};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+// integrity_check stops after this many problems
+const MAX_PROBLEMS: u32 = 100;
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MaintenanceReport {
+    pub integrity_ok: bool,
+    // What integrity_check found, empty when it passed
+    pub problems: Vec<String>,
+    // False when the check failed and nothing else was run
+    pub vacuumed: bool,
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+    pub duration_ms: u64,
+}
+
+// Held for the length of a run; a second run can't start until it's dropped
+pub struct RunGuard(());
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::Release);
+    }
+}
+
+// None while another run is going
+pub fn try_begin() -> Option<RunGuard> {
+    RUNNING
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .ok()
+        .map(|_| RunGuard(()))
+}
+
+// Size of the database in pages, which is what hydra.db shrinks to once the
+// WAL is checkpointed
+fn database_size(conn: &Connection) -> SqliteResult<u64> {
+    let page_count: u64 = conn.pragma_query_value(None, "page_count", |row| row.get(0))?;
+    let page_size: u64 = conn.pragma_query_value(None, "page_size", |row| row.get(0))?;
+    Ok(page_count * page_size)
+}
+
+// Damage bad enough that the check itself can't finish counts as a problem
+// too, rather than an error that reads like any other
+fn integrity_problems(conn: &Connection) -> SqliteResult<Vec<String>> {
+    let rows = conn
+        .prepare(&format!("PRAGMA integrity_check({})", MAX_PROBLEMS))
+        .and_then(|mut stmt| stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<SqliteResult<Vec<_>>>());
+    match rows {
+        Ok(rows) => Ok(rows.into_iter().filter(|row| row != "ok").collect()),
+        Err(e) if matches!(e.sqlite_error_code(), Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)) => {
+            Ok(vec![e.to_string()])
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub fn run(conn: &Connection) -> SqliteResult<MaintenanceReport> {
+    let started = Instant::now();
+    let size_before_bytes = database_size(conn)?;
+    let problems = integrity_problems(conn)?;
+    let integrity_ok = problems.is_empty();
+
+    if integrity_ok {
+        // Unqualified, ANALYZE covers every index, including any a later
+        // migration adds
+        conn.execute_batch("ANALYZE; PRAGMA optimize; VACUUM;")?;
+        // Moves the vacuumed pages into hydra.db and empties the WAL, so the
+        // file on disk shrinks now rather than at the next checkpoint
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    } else {
+        tracing::warn!("integrity check found {} problems, not vacuuming", problems.len());
+    }
+
+    Ok(MaintenanceReport {
+        integrity_ok,
+        problems,
+        vacuumed: integrity_ok,
+        size_before_bytes,
+        size_after_bytes: database_size(conn)?,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
@@ -0,0 +1,220 @@
+// Schema changes, in order. `PRAGMA user_version` in hydra.db records how
+// many of them have been applied; at startup the rest run in order inside
+// one transaction that also bumps user_version, so an upgrade that fails or
+// is interrupted partway leaves the database as it was before.
+//
+// Steps are only ever appended. Changing or removing one that has shipped
+// leaves databases that already applied it out of step with the others.
+//
+// Databases from before this existed are at user_version 0 but may already
+// have any of the first steps' changes, which is why those check before
+// they change anything. A database at a version past the end of the list
+// was written by a newer build and is refused untouched.
+
+use crate::{achievements, db, freezes, milestones, presets, settings};
+use rusqlite::{Connection, Result as SqliteResult};
+
+struct Migration {
+    description: &'static str,
+    apply: fn(&Connection) -> SqliteResult<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "create the entry, settings and progress tables",
+        apply: create_tables,
+    },
+    Migration {
+        description: "add water_entries.source",
+        apply: add_entry_source,
+    },
+    Migration {
+        description: "store amount_ml as an integer",
+        apply: integer_amounts,
+    },
+    Migration {
+        description: "index entries by timestamp and by date with amount",
+        apply: entry_indexes,
+    },
+    Migration {
+        description: "keep water_entries.date in step with timestamp",
+        apply: entry_dates_from_timestamps,
+    },
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error(
+        "hydra.db is at schema version {found}, but this version of Hydra Tracker only knows up to {known}. \
+         It was last opened by a newer version; update Hydra Tracker to open it"
+    )]
+    TooNew { found: u32, known: u32 },
+    #[error("Couldn't upgrade hydra.db: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("Upgrading hydra.db to schema version {version} ({description}) failed: {source}")]
+    Failed {
+        version: u32,
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+}
+
+// The version a database has once everything in this build has been applied
+pub fn latest_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+pub fn schema_version(conn: &Connection) -> SqliteResult<u32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+// Applies every step past the database's user_version, returning how many ran.
+// A step that fails undoes the others with it.
+pub fn run(conn: &Connection) -> Result<usize, MigrationError> {
+    let found = schema_version(conn)?;
+    let known = latest_version();
+    if found > known {
+        return Err(MigrationError::TooNew { found, known });
+    }
+    if found == known {
+        return Ok(0);
+    }
+
+    db::with_tx(conn, |conn| {
+        for (version, migration) in (found + 1..).zip(&MIGRATIONS[found as usize..]) {
+            (migration.apply)(conn).map_err(|source| MigrationError::Failed {
+                version,
+                description: migration.description,
+                source,
+            })?;
+            if found > 0 {
+                tracing::info!("upgrading hydra.db to schema version {}: {}", version, migration.description);
+            }
+        }
+        conn.pragma_update(None, "user_version", known)?;
+        Ok((known - found) as usize)
+    })
+}
+
+fn create_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS water_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            amount_ml INTEGER NOT NULL,
+            timestamp TEXT NOT NULL,
+            date TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_date ON water_entries(date)", [])?;
+
+    settings::init_table(conn)?;
+    achievements::init_table(conn)?;
+    milestones::init_table(conn)?;
+    freezes::init_table(conn)?;
+    presets::init_table(conn)?;
+    Ok(())
+}
+
+fn add_entry_source(conn: &Connection) -> SqliteResult<()> {
+    ensure_column(conn, "water_entries", "source", "TEXT NOT NULL DEFAULT 'app'")
+}
+
+// Older versions bound amount_ml as a string, so some rows may hold text or
+// a real rather than an integer, and sums relied on SQLite coercing them.
+// Rewrites those rows and checks none are left, failing the step otherwise.
+fn integer_amounts(conn: &Connection) -> SqliteResult<()> {
+    const NOT_INTEGER: &str = "SELECT COUNT(*) FROM water_entries WHERE typeof(amount_ml) != 'integer'";
+
+    let pending = conn.execute(
+        "UPDATE water_entries SET amount_ml = CAST(amount_ml AS INTEGER) WHERE typeof(amount_ml) != 'integer'",
+        [],
+    )?;
+    let remaining: i64 = conn.query_row(NOT_INTEGER, [], |row| row.get(0))?;
+    if remaining > 0 {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISMATCH),
+            Some(format!("{} entries still have a non-integer amount_ml", remaining)),
+        ));
+    }
+    if pending > 0 {
+        tracing::info!("stored amount_ml as an integer in {} entries", pending);
+    }
+    Ok(())
+}
+
+// Timestamp order for the exports and history walks, and (date, amount_ml)
+// so the per-day sums are read from the index alone. The latter also serves
+// every lookup idx_date did, which only slowed down inserts from then on.
+fn entry_indexes(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_timestamp ON water_entries(timestamp);
+         CREATE INDEX IF NOT EXISTS idx_date_amount ON water_entries(date, amount_ml);
+         DROP INDEX IF EXISTS idx_date;",
+    )
+}
+
+// Dates come from db::entry_date, but older versions wrote them separately
+// from the timestamp. Re-derives every row's date under the current rollover
+// hour, then rebuilds the table with a CHECK holding each date to its
+// timestamp's calendar day or the one before. The rollover hour is a
+// setting, so that's as close as the schema itself can hold it.
+fn entry_dates_from_timestamps(conn: &Connection) -> SqliteResult<()> {
+    let mut corrected = db::recompute_entry_dates(conn)?;
+    // Timestamps SQLite reads but the app doesn't, which the CHECK would
+    // still refuse; their calendar day is the best guess
+    corrected += conn.execute(
+        "UPDATE water_entries SET date = date(timestamp)
+         WHERE date NOT IN (date(timestamp), date(timestamp, '-1 day'))",
+        [],
+    )?;
+    if corrected > 0 {
+        tracing::info!("corrected the date of {} entries to match their timestamp", corrected);
+    }
+
+    let last_id: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(seq), 0) FROM sqlite_sequence WHERE name = 'water_entries'",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute_batch(
+        "CREATE TABLE water_entries_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            amount_ml INTEGER NOT NULL,
+            timestamp TEXT NOT NULL,
+            date TEXT NOT NULL CHECK (date IN (date(timestamp), date(timestamp, '-1 day'))),
+            source TEXT NOT NULL DEFAULT 'app'
+         );
+         INSERT INTO water_entries_new (id, amount_ml, timestamp, date, source)
+             SELECT id, amount_ml, timestamp, date, source FROM water_entries;
+         DROP TABLE water_entries;
+         ALTER TABLE water_entries_new RENAME TO water_entries;
+         CREATE INDEX idx_timestamp ON water_entries(timestamp);
+         CREATE INDEX idx_date_amount ON water_entries(date, amount_ml);",
+    )?;
+    // Ids of entries deleted before the rebuild aren't handed out again
+    conn.execute("DELETE FROM sqlite_sequence WHERE name = 'water_entries'", [])?;
+    if last_id > 0 {
+        conn.execute("INSERT INTO sqlite_sequence (name, seq) VALUES ('water_entries', ?1)", [last_id])?;
+    }
+    Ok(())
+}
+
+// Add a column unless the table already has it, as tables from before
+// user_version was tracked may
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> SqliteResult<()> {
+    let exists = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|r| r.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// Lifetime volume milestones. Each threshold is announced once: reaching it
+// records a row, and a recorded milestone is never announced again even if
+// entries are later deleted and the total drops back below it.
+
+use crate::db;
+use chrono::Local;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+pub const THRESHOLDS_ML: &[i64] = &[100_000, 500_000, 1_000_000];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Milestone {
+    pub threshold_ml: i64,
+    pub lifetime_ml: i64,
+    pub reached_at: String,
+}
+
+pub fn init_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS milestones_reached (
+            threshold_ml INTEGER PRIMARY KEY,
+            reached_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Record and return every threshold the lifetime total has passed that hasn't
+// been announced yet, lowest first
+pub fn check_milestones(conn: &Connection) -> SqliteResult<Vec<Milestone>> {
+    let lifetime_ml: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(amount_ml), 0) FROM water_entries",
+        [],
+        |row| row.get(0),
+    )?;
+    let reached_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let mut reached = Vec::new();
+    // All or none, so a milestone is never stored without being announced
+    db::with_tx(conn, |tx| {
+        for &threshold_ml in THRESHOLDS_ML.iter().filter(|&&t| lifetime_ml >= t) {
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO milestones_reached (threshold_ml, reached_at) VALUES (?1, ?2)",
+                params![threshold_ml, reached_at],
+            )?;
+            if inserted > 0 {
+                reached.push(Milestone {
+                    threshold_ml,
+                    lifetime_ml,
+                    reached_at: reached_at.clone(),
+                });
+            }
+        }
+        Ok(reached)
+    })
+}
@@ -0,0 +1,155 @@
+// The records the database holds and the shapes the commands send back
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, MutexGuard};
+
+// The app's one connection. Everything takes it through `lock`.
+pub struct Database(Mutex<Connection>);
+
+impl Database {
+    pub fn new(conn: Connection) -> Self {
+        Self(Mutex::new(conn))
+    }
+
+    // A command that panics while holding the connection poisons the Mutex,
+    // but the connection itself is still good: SQLite has either committed a
+    // statement or not, and a transaction still open is rolled back here.
+    // So the lock is taken back instead of failing every later command.
+    // Release builds abort on panic, so this only ever runs in debug builds
+    // and tests.
+    pub fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.0.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!("a command panicked while holding the database, recovering the connection");
+            self.0.clear_poison();
+            let conn = poisoned.into_inner();
+            if !conn.is_autocommit() {
+                if let Err(e) = conn.execute_batch("ROLLBACK") {
+                    tracing::error!("couldn't roll back the interrupted transaction: {}", e);
+                }
+            }
+            conn
+        })
+    }
+
+    // `db::with_tx` on the app's connection, holding the lock throughout
+    pub fn with_tx<T, E: From<rusqlite::Error>>(&self, work: impl FnOnce(&Connection) -> Result<T, E>) -> Result<T, E> {
+        crate::db::with_tx(&self.lock(), work)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WaterEntry {
+    pub id: i64,
+    pub amount_ml: i32,
+    pub timestamp: String,
+    pub date: String,
+    // Where it was logged from: "app", "notification", ...
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DailyStats {
+    pub date: String,
+    pub total_ml: i32,
+    pub goal_ml: i32,
+    pub entries_count: i32,
+    pub percentage: f32,
+    // Timestamp of the entry that pushed the running total to the goal
+    pub goal_met_at: Option<String>,
+    // A streak freeze was spent on this day
+    pub frozen: bool,
+    // Intake beyond the goal; zero on days that didn't reach it
+    pub surplus_ml: i32,
+}
+
+impl DailyStats {
+    pub fn new(date: String, total_ml: i32, goal_ml: i32, entries_count: i32, goal_met_at: Option<String>) -> Self {
+        let percentage = if goal_ml > 0 {
+            (total_ml as f32 / goal_ml as f32) * 100.0
+        } else {
+            0.0
+        };
+        Self {
+            date,
+            total_ml,
+            goal_ml,
+            entries_count,
+            percentage,
+            goal_met_at,
+            frozen: false,
+            surplus_ml: (total_ml - goal_ml).max(0),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MonthlyStats {
+    pub month: String,
+    pub year: i32,
+    pub days: Vec<DailyStats>,
+    pub total_ml: i32,
+    pub average_ml: f32,
+    pub days_goal_met: i32,
+    pub current_streak: i32,
+    pub best_streak: i32,
+    // Mean hour of day the goal was reached, over days that reached it
+    pub average_goal_met_hour: Option<f32>,
+    pub total_surplus_ml: i32,
+    pub days_over_110_percent: i32,
+    pub days_over_125_percent: i32,
+    // Share of elapsed days in the month that met the goal
+    pub adherence_percentage: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AllTimeStats {
+    pub total_ml: i64,
+    pub total_entries: i64,
+    pub first_date: Option<String>,
+    pub days_logged: i64,
+    pub average_ml_per_day: f32,
+    pub days_goal_met: i64,
+    pub goal_met_rate: f32,
+    pub longest_streak: i32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AddWaterOutcome {
+    Added { entry: WaterEntry },
+    // Over max_entry_ml; nothing was stored. Send again with `force` to keep it.
+    NeedsConfirmation { amount_ml: i32, max_entry_ml: i32 },
+}
+
+// What happened to the entries an `entries-changed` event is about
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    // Moved to another date by a new rollover hour
+    Redated,
+    Imported,
+    Restored,
+    Purged,
+}
+
+// The `entries-changed` payload. `dates` are the logical days whose entries
+// changed, oldest first; empty when it could be any of them.
+#[derive(Debug, Serialize, Clone)]
+pub struct EntriesChanged {
+    pub kind: ChangeKind,
+    pub dates: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct YearOverYear {
+    pub current: MonthlyStats,
+    pub previous: MonthlyStats,
+    pub has_prior_data: bool,
+    // Current minus previous; null when the previous year has no entries
+    pub total_delta_ml: Option<i32>,
+    pub average_delta_ml: Option<f32>,
+    pub adherence_delta: Option<f32>,
+}
@@ -0,0 +1,151 @@
+// Reminder notifications. On Linux they carry action buttons (quick-add
+// amounts and a snooze) that are handled here without opening the window.
+// The notification plugin has no desktop actions, so Linux talks to the
+// notification daemon directly; Windows and macOS get a plain notification.
+//
+// Manual checks:
+// - Linux (GNOME, KDE): buttons appear on the reminder; "250 ml" adds an
+//   entry with source "notification" and the open window refreshes; "Snooze"
+//   updates the snooze state; clicking the body opens the window on Today
+//   with the custom amount focused, including when the window was closed.
+// - Windows, macOS: a plain reminder is shown; no buttons.
+
+// Only Linux shows the buttons that reach the action handling
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use crate::models::{ChangeKind, EntriesChanged};
+use crate::{reminders, scheduler, tray, units, AppError, Database};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const ENTRY_SOURCE: &str = "notification";
+pub const ENTRIES_CHANGED_EVENT: &str = "entries-changed";
+pub const OPEN_QUICK_ADD_EVENT: &str = "open-quick-add";
+const ADD_ACTION_PREFIX: &str = "add_";
+const SNOOZE_ACTION: &str = "snooze";
+// Sent by the daemon when the body rather than a button is clicked
+const DEFAULT_ACTION: &str = "default";
+// Buttons beyond a few get cut off by most notification daemons
+const MAX_ADD_ACTIONS: usize = 2;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QuickAddContext {
+    pub total_ml: i32,
+    pub goal_ml: i32,
+    pub remaining_ml: i32,
+}
+
+// The last quick-add request, held until the frontend takes it. A window that
+// was just created can't have its listener up yet, so it picks this up on
+// mount instead of missing the event.
+#[derive(Default)]
+pub struct PendingQuickAdd(pub Mutex<Option<QuickAddContext>>);
+
+// Tells every window and the tray that stored entries changed. Sent once per
+// change, however many rows it touched, and only after it's committed.
+pub fn entries_changed(app: &AppHandle, kind: ChangeKind, dates: impl IntoIterator<Item = String>) {
+    let mut dates: Vec<String> = dates.into_iter().collect();
+    dates.sort();
+    dates.dedup();
+    let _ = app.emit(ENTRIES_CHANGED_EVENT, EntriesChanged { kind, dates });
+}
+
+pub fn open_quick_add(app: &AppHandle) -> Result<(), AppError> {
+    let db = app.state::<Database>();
+    let stats = crate::today_stats(&db.lock())?;
+    let context = QuickAddContext {
+        total_ml: stats.total_ml,
+        goal_ml: stats.goal_ml,
+        remaining_ml: (stats.goal_ml - stats.total_ml).max(0),
+    };
+
+    if let Some(pending) = app.try_state::<PendingQuickAdd>() {
+        *pending.0.lock()? = Some(context.clone());
+    }
+    tray::show_main_window(app);
+    let _ = app.emit(OPEN_QUICK_ADD_EVENT, context);
+    Ok(())
+}
+
+// (action id, label) for the buttons, taken from the quick-add amounts
+fn reminder_actions(app: &AppHandle) -> Vec<(String, String)> {
+    let db = app.state::<Database>();
+    let settings = crate::settings::load(&db.lock()).unwrap_or_default();
+
+    let mut actions: Vec<(String, String)> = tray::quick_add_amounts(&settings)
+        .into_iter()
+        .take(MAX_ADD_ACTIONS)
+        .map(|ml| {
+            (
+                format!("{}{}", ADD_ACTION_PREFIX, ml),
+                units::format_amount(ml, settings.unit),
+            )
+        })
+        .collect();
+    actions.push((
+        SNOOZE_ACTION.to_string(),
+        format!("Snooze {}m", reminders::QUICK_SNOOZE_MINUTES),
+    ));
+    actions
+}
+
+pub fn handle_action(app: &AppHandle, action: &str) -> Result<(), AppError> {
+    if let Some(amount) = action
+        .strip_prefix(ADD_ACTION_PREFIX)
+        .and_then(|ml| ml.parse::<i32>().ok())
+    {
+        let db = app.state::<Database>();
+        let conn = db.lock();
+        crate::record_entry(app, &conn, amount, ENTRY_SOURCE)?;
+        return Ok(());
+    }
+
+    match action {
+        SNOOZE_ACTION => scheduler::snooze(app, reminders::QUICK_SNOOZE_MINUTES).map(|_| ()),
+        DEFAULT_ACTION => open_quick_add(app),
+        // Dismissed or expired
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn show_reminder(app: &AppHandle, title: &str, body: &str) {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname(&app.package_info().name)
+        .summary(title)
+        .body(body)
+        .action(DEFAULT_ACTION, "Open");
+    for (id, label) in reminder_actions(app) {
+        notification.action(&id, &label);
+    }
+
+    match notification.show() {
+        Ok(handle) => {
+            // Blocks until a button is pressed or the notification closes
+            let app = app.clone();
+            std::thread::spawn(move || {
+                handle.wait_for_action(|action| {
+                    let _ = handle_action(&app, action);
+                });
+            });
+        }
+        Err(_) => show_plain(app, title, body),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn show_reminder(app: &AppHandle, title: &str, body: &str) {
+    show_plain(app, title, body);
+}
+
+pub fn show_plain(app: &AppHandle, title: &str, body: &str) {
+    use tauri_plugin_notification::NotificationExt;
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+// Confirms an entry logged from outside the app, e.g. a link or the command line
+pub fn show_logged(app: &AppHandle, body: &str) {
+    show_plain(app, "💧 Water logged", body);
+}
@@ -0,0 +1,125 @@
+// A brief on-screen confirmation for water added without the window open,
+// from the tray or a global shortcut: "+500 ml — 2,750 / 4,000 ml" near the
+// tray for a moment. The window is created the first time it's needed and
+// afterwards only shown and hidden, so it doesn't flicker. It never takes
+// focus and clicks go through it, so it can't get in the way.
+//
+// The page has no access to commands; its text is set by evaluating a call
+// in it.
+
+use crate::{settings, units, DailyStats};
+use rusqlite::Connection;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::{thread, time::Duration};
+use tauri::{AppHandle, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+pub const LABEL: &str = "overlay";
+const PAGE: &str = "overlay.html";
+const WIDTH: f64 = 300.0;
+const HEIGHT: f64 = 52.0;
+// From the edges of the screen's work area, in logical pixels
+const MARGIN: f64 = 16.0;
+const SHOWN_FOR: Duration = Duration::from_millis(1500);
+
+// Bumped by every showing, so only the latest one hides the overlay
+#[derive(Default)]
+pub struct OverlayShowings(AtomicU64);
+
+// "+500 ml — 2,750 / 4,000 ml"
+pub fn text(amount_ml: i32, stats: &DailyStats, unit: units::Unit) -> String {
+    format!(
+        "+{} — {} / {}",
+        units::format_amount(amount_ml, unit),
+        units::format_number(stats.total_ml, unit),
+        units::format_amount(stats.goal_ml, unit)
+    )
+}
+
+// Where the tray usually is: the top right corner of the screen under the
+// cursor on macOS, the bottom right elsewhere
+fn position(app: &AppHandle, window: &WebviewWindow) -> Option<PhysicalPosition<i32>> {
+    let monitor = app
+        .cursor_position()
+        .ok()
+        .and_then(|cursor| app.monitor_from_point(cursor.x, cursor.y).ok().flatten())
+        .or_else(|| app.primary_monitor().ok().flatten())?;
+    let area = monitor.work_area();
+    let size = window.outer_size().ok()?;
+    let margin = (MARGIN * monitor.scale_factor()).round() as i32;
+    let x = area.position.x + area.size.width as i32 - size.width as i32 - margin;
+    let y = if cfg!(target_os = "macos") {
+        area.position.y + margin
+    } else {
+        area.position.y + area.size.height as i32 - size.height as i32 - margin
+    };
+    Some(PhysicalPosition::new(x, y))
+}
+
+fn window_with_text(app: &AppHandle, text: &str) -> tauri::Result<WebviewWindow> {
+    let text = serde_json::to_string(text)?;
+    if let Some(window) = app.get_webview_window(LABEL) {
+        window.eval(format!("showText({})", text))?;
+        return Ok(window);
+    }
+    // The first text is set before the page loads, since it can't be
+    // evaluated until then
+    let window = WebviewWindowBuilder::new(app, LABEL, WebviewUrl::App(PAGE.into()))
+        .title("Hydra Tracker")
+        .inner_size(WIDTH, HEIGHT)
+        .decorations(false)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .focusable(false)
+        .visible(false)
+        .initialization_script(format!("window.overlayText = {};", text))
+        .build()?;
+    window.set_ignore_cursor_events(true)?;
+    Ok(window)
+}
+
+fn display(app: &AppHandle, text: &str) -> tauri::Result<()> {
+    let window = window_with_text(app, text)?;
+    if let Some(position) = position(app, &window) {
+        window.set_position(position)?;
+    }
+    window.show()?;
+
+    let Some(showings) = app.try_state::<OverlayShowings>() else {
+        return Ok(());
+    };
+    let showing = showings.0.fetch_add(1, Ordering::SeqCst) + 1;
+    thread::sleep(SHOWN_FOR);
+    if showings.0.load(Ordering::SeqCst) == showing {
+        window.hide()?;
+    }
+    Ok(())
+}
+
+pub fn show(app: &AppHandle, text: String) {
+    let app = app.clone();
+    // Building a window from an event handler can deadlock on Windows, and
+    // this waits to hide it again
+    thread::spawn(move || {
+        if let Err(e) = display(&app, &text) {
+            tracing::warn!("couldn't show the confirmation: {}", e);
+        }
+    });
+}
+
+// Confirms `amount_ml`, just recorded on `conn`, unless the overlay is turned
+// off. Returns whether it was shown.
+pub fn confirm(app: &AppHandle, conn: &Connection, amount_ml: i32) -> bool {
+    let Ok(settings) = settings::load(conn) else {
+        return false;
+    };
+    if !settings.quick_add_overlay {
+        return false;
+    }
+    let Ok(stats) = crate::today_stats(conn) else {
+        return false;
+    };
+    show(app, text(amount_ml, &stats, settings.unit));
+    true
+}
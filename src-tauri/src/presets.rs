@@ -0,0 +1,120 @@
+// Named goals ("Summer", "Travel") to switch between. Activating a preset
+// copies its amount into daily_goal_ml, so everything that reads the plain
+// goal keeps working; the preset name is only remembered for display.
+
+use crate::{db, goals, settings, AppError};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+const MAX_NAME_LEN: usize = 50;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoalPreset {
+    pub name: String,
+    pub goal_ml: i32,
+}
+
+pub fn init_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS goal_presets (
+            name TEXT PRIMARY KEY,
+            goal_ml INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn list(conn: &Connection) -> SqliteResult<Vec<GoalPreset>> {
+    let mut stmt = conn.prepare("SELECT name, goal_ml FROM goal_presets ORDER BY name")?;
+    let presets = stmt
+        .query_map([], |row| {
+            Ok(GoalPreset {
+                name: row.get(0)?,
+                goal_ml: row.get(1)?,
+            })
+        })?
+        .collect();
+    presets
+}
+
+fn find(conn: &Connection, name: &str) -> SqliteResult<Option<GoalPreset>> {
+    conn.query_row(
+        "SELECT name, goal_ml FROM goal_presets WHERE name = ?1",
+        [name],
+        |row| {
+            Ok(GoalPreset {
+                name: row.get(0)?,
+                goal_ml: row.get(1)?,
+            })
+        },
+    )
+    .optional()
+}
+
+// The preset currently in effect. Editing daily_goal_ml by hand afterwards
+// means no preset is active any more, so the amounts have to still match.
+pub fn active(conn: &Connection) -> SqliteResult<Option<GoalPreset>> {
+    let Some(name) = settings::get::<String>(conn, "active_goal_preset")? else {
+        return Ok(None);
+    };
+    let goal_ml: Option<i32> = settings::get(conn, "daily_goal_ml")?;
+    Ok(find(conn, &name)?.filter(|preset| Some(preset.goal_ml) == goal_ml))
+}
+
+// Creates or updates. Updating the active preset also moves the goal.
+pub fn save(conn: &Connection, name: &str, goal_ml: i32) -> Result<GoalPreset, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::invalid("name", format!("must be 1 to {} characters", MAX_NAME_LEN)));
+    }
+    if !settings::DAILY_GOAL_RANGE_ML.contains(&goal_ml) {
+        return Err(AppError::invalid(
+            "goal_ml",
+            format!(
+                "must be between {} and {} ml",
+                settings::DAILY_GOAL_RANGE_ML.start(),
+                settings::DAILY_GOAL_RANGE_ML.end()
+            ),
+        ));
+    }
+
+    let was_active = active(conn)?.is_some_and(|p| p.name == name);
+    db::with_tx(conn, |tx| {
+        tx.execute(
+            "INSERT INTO goal_presets (name, goal_ml) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET goal_ml = excluded.goal_ml",
+            params![name, goal_ml],
+        )?;
+        if was_active {
+            settings::set(tx, "daily_goal_ml", &goal_ml)?;
+        }
+        Ok::<_, AppError>(())
+    })?;
+
+    Ok(GoalPreset {
+        name: name.to_string(),
+        goal_ml,
+    })
+}
+
+// Deleting the active preset leaves its amount as the plain daily goal
+pub fn delete(conn: &Connection, name: &str) -> SqliteResult<()> {
+    db::with_tx(conn, |tx| {
+        tx.execute("DELETE FROM goal_presets WHERE name = ?1", [name])?;
+        if settings::get::<String>(tx, "active_goal_preset")?.as_deref() == Some(name) {
+            settings::remove(tx, "active_goal_preset")?;
+        }
+        Ok(())
+    })
+}
+
+pub fn activate(conn: &Connection, name: &str) -> Result<GoalPreset, AppError> {
+    let preset = find(conn, name)?.ok_or_else(|| AppError::NotFound(format!("goal preset \"{}\"", name)))?;
+    goals::ensure_no_program(conn, "name")?;
+    db::with_tx(conn, |tx| {
+        settings::set(tx, "daily_goal_ml", &preset.goal_ml)?;
+        settings::set(tx, "active_goal_preset", &preset.name)
+    })?;
+    Ok(preset)
+}
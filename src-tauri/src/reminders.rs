@@ -0,0 +1,216 @@
+// Whether a reminder may fire right now, and how long to wait between them.
+// The scheduler consults these on every pass.
+
+use crate::settings::ValidationError;
+use crate::{dates, db, settings, AppError, DailyStats, Settings};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use rusqlite::{Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+pub const MINUTES_PER_DAY: i32 = 24 * 60;
+pub const SNOOZE_CHANGED_EVENT: &str = "snooze-changed";
+pub const MAX_SNOOZE_MINUTES: i64 = 24 * 60;
+// What the tray item and the notification button snooze for
+pub const QUICK_SNOOZE_MINUTES: i64 = 30;
+// Smart mode assumes one reminder prompts about this much water
+pub const TYPICAL_SIP_ML: i32 = 250;
+pub const SMART_MIN_INTERVAL_MINUTES: i32 = 15;
+pub const SMART_MAX_INTERVAL_MINUTES: i32 = 120;
+// Monday = 0 through Sunday = 6
+pub const ALL_DAYS: [u8; 7] = [0, 1, 2, 3, 4, 5, 6];
+
+pub fn minute_of_day(at: NaiveDateTime) -> i32 {
+    (at.hour() * 60 + at.minute()) as i32
+}
+
+// The window is [start, end); when start is after end it wraps midnight,
+// so 22:00-07:00 covers both 23:30 and 06:59
+pub fn in_quiet_hours(settings: &Settings, minute: i32) -> bool {
+    if !settings.quiet_hours_enabled {
+        return false;
+    }
+    let (start, end) = (settings.quiet_hours_start, settings.quiet_hours_end);
+    if start < end {
+        (start..end).contains(&minute)
+    } else {
+        minute >= start || minute < end
+    }
+}
+
+pub fn validate_quiet_hours(settings: &Settings) -> Result<(), ValidationError> {
+    let valid = 0..MINUTES_PER_DAY;
+    if !valid.contains(&settings.quiet_hours_start) || !valid.contains(&settings.quiet_hours_end) {
+        return Err(ValidationError::new("quiet_hours", "must be between 0 and 1439 minutes"));
+    }
+    if settings.quiet_hours_start == settings.quiet_hours_end {
+        return Err(ValidationError::new("quiet_hours", "start and end must differ"));
+    }
+    Ok(())
+}
+
+// The weekday of the logical day, the same date entries are grouped under,
+// so 01:00 Saturday with a 3:00 rollover still counts as Friday
+pub fn is_reminder_day(settings: &Settings, now: NaiveDateTime) -> bool {
+    let weekday = dates::logical_date(now, settings.day_starts_at_hour).weekday();
+    settings
+        .reminder_days
+        .contains(&(weekday.num_days_from_monday() as u8))
+}
+
+// An empty day list with reminders on is rejected rather than read as
+// "reminders off", so there is exactly one switch for turning them off
+pub fn validate_reminder_days(settings: &Settings) -> Result<(), ValidationError> {
+    if settings.reminder_days.iter().any(|day| *day > 6) {
+        return Err(ValidationError::new("reminder_days", "must be between 0 (Monday) and 6 (Sunday)"));
+    }
+    if settings.reminder_enabled && settings.reminder_days.is_empty() {
+        return Err(ValidationError::new(
+            "reminder_days",
+            "pick at least one day or turn reminders off",
+        ));
+    }
+    Ok(())
+}
+
+// Minutes from `from` forward to `to` on the clock, wrapping past midnight
+fn minutes_until(from: i32, to: i32) -> i32 {
+    if to > from {
+        to - from
+    } else {
+        to + MINUTES_PER_DAY - from
+    }
+}
+
+// The active day runs from the end of quiet hours to their start when
+// they're on, otherwise from rollover to rollover
+fn active_day(settings: &Settings) -> (i32, i32) {
+    if settings.quiet_hours_enabled {
+        (settings.quiet_hours_end, settings.quiet_hours_start)
+    } else {
+        (settings.day_starts_at_hour * 60, settings.day_starts_at_hour * 60)
+    }
+}
+
+// Minutes left before the active day ends
+fn active_minutes_left(settings: &Settings, now: NaiveDateTime) -> i32 {
+    minutes_until(minute_of_day(now), active_day(settings).1)
+}
+
+// Share of the goal a steady pace would have reached by now, assuming
+// drinking spreads evenly over the active day
+pub fn projected_percentage(settings: &Settings, now: NaiveDateTime) -> f32 {
+    let (start, end) = active_day(settings);
+    let length = minutes_until(start, end);
+    let elapsed = length - active_minutes_left(settings, now);
+    (elapsed as f32 / length as f32 * 100.0).clamp(0.0, 100.0)
+}
+
+// Far enough behind the projected pace that reminders should get insistent.
+// Drops back as soon as the deficit shrinks below the threshold.
+pub fn is_escalated(settings: &Settings, stats: &DailyStats, now: NaiveDateTime) -> bool {
+    settings.escalation_enabled
+        && stats.total_ml < stats.goal_ml
+        && projected_percentage(settings, now) - stats.percentage >= settings.escalation_threshold_percentage
+}
+
+// Spread the sips still needed evenly over the rest of the active day. None
+// once the goal is met: no more reminders today.
+pub fn smart_interval_minutes(settings: &Settings, stats: &DailyStats, now: NaiveDateTime) -> Option<i32> {
+    let remaining_ml = stats.goal_ml - stats.total_ml;
+    if remaining_ml <= 0 {
+        return None;
+    }
+    let sips_left = (remaining_ml + TYPICAL_SIP_ML - 1) / TYPICAL_SIP_ML;
+    let interval = active_minutes_left(settings, now) / sips_left;
+    Some(interval.clamp(SMART_MIN_INTERVAL_MINUTES, SMART_MAX_INTERVAL_MINUTES))
+}
+
+// Whether today's reminders are over because the goal is met. `stats` is for
+// the logical day, so a late-night entry after the rollover can't bring them
+// back. Smart mode always stops at the goal; resuming overrides either case
+// until the next day.
+pub fn paused_for_goal(settings: &Settings, stats: &DailyStats, resumed_today: bool) -> bool {
+    stats.total_ml >= stats.goal_ml
+        && (settings.pause_reminders_when_goal_met || settings.smart_reminders)
+        && !resumed_today
+}
+
+// How long the timer should wait before its next reminder; None means no
+// more reminders today
+pub fn reminder_interval_minutes(
+    settings: &Settings,
+    stats: &DailyStats,
+    now: NaiveDateTime,
+    resumed_today: bool,
+) -> Option<i32> {
+    if paused_for_goal(settings, stats, resumed_today) {
+        return None;
+    }
+    let smart = settings
+        .smart_reminders
+        .then(|| smart_interval_minutes(settings, stats, now))
+        .flatten();
+    let interval = smart.unwrap_or(settings.reminder_interval_minutes);
+    if is_escalated(settings, stats, now) {
+        return Some(interval.min(settings.escalation_interval_minutes));
+    }
+    Some(interval)
+}
+
+pub fn should_remind(settings: &Settings, now: NaiveDateTime) -> bool {
+    settings.reminder_enabled
+        && is_reminder_day(settings, now)
+        && !in_quiet_hours(settings, minute_of_day(now))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnoozeState {
+    pub snoozed: bool,
+    pub snoozed_until: Option<String>,
+}
+
+// Kept with the settings so a restart doesn't clear it, but outside
+// `Settings` since it's state rather than a preference
+fn snoozed_until(conn: &Connection) -> SqliteResult<Option<NaiveDateTime>> {
+    let until: Option<String> = settings::get(conn, "snoozed_until")?;
+    Ok(until.and_then(|u| dates::parse_timestamp(&u)))
+}
+
+pub fn snooze_state(conn: &Connection, now: NaiveDateTime) -> SqliteResult<SnoozeState> {
+    let until = snoozed_until(conn)?.filter(|until| *until > now);
+    Ok(SnoozeState {
+        snoozed: until.is_some(),
+        snoozed_until: until.map(|u| u.format(dates::TIMESTAMP_FORMAT).to_string()),
+    })
+}
+
+pub fn is_snoozed(conn: &Connection, now: NaiveDateTime) -> SqliteResult<bool> {
+    Ok(snooze_state(conn, now)?.snoozed)
+}
+
+// Always counts from now, so snoozing again replaces rather than extends
+// whatever was left of the previous snooze
+pub fn snooze(conn: &Connection, minutes: i64, now: NaiveDateTime) -> Result<SnoozeState, AppError> {
+    if !(1..=MAX_SNOOZE_MINUTES).contains(&minutes) {
+        return Err(AppError::invalid(
+            "minutes",
+            format!("must be between 1 and {}", MAX_SNOOZE_MINUTES),
+        ));
+    }
+    let until = (now + Duration::minutes(minutes)).format(dates::TIMESTAMP_FORMAT).to_string();
+    settings::set(conn, "snoozed_until", &until)?;
+    Ok(snooze_state(conn, now)?)
+}
+
+pub fn resumed_today(conn: &Connection, today: NaiveDate) -> SqliteResult<bool> {
+    let resumed_on: Option<String> = settings::get(conn, "reminders_resumed_on")?;
+    Ok(resumed_on.and_then(|d| dates::parse_date(&d)) == Some(today))
+}
+
+// Lifts both the goal pause (for the rest of `today`) and any snooze
+pub fn resume(conn: &Connection, today: NaiveDate) -> SqliteResult<()> {
+    db::with_tx(conn, |tx| {
+        settings::set(tx, "reminders_resumed_on", &today.format(dates::DATE_FORMAT).to_string())?;
+        settings::remove(tx, "snoozed_until")
+    })
+}
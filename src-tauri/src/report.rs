@@ -0,0 +1,326 @@
+// Reports rendered in the backend from the same numbers the app shows, so
+// they match what the user sees and need nothing from the webview.
+
+use crate::units::{self, Unit};
+use crate::{dates, AppError, MonthlyStats};
+use chrono::{Datelike, Duration, NaiveDate};
+use printpdf::{IndirectFontRef, Mm, PdfDocument, PdfLayerReference};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufWriter, Cursor};
+
+const CHART_WIDTH: f32 = 720.0;
+const CHART_HEIGHT: f32 = 200.0;
+const BAR_GAP: f32 = 2.0;
+const MET_COLOR: &str = "#4ade80";
+const MISSED_COLOR: &str = "#93c5fd";
+
+// One bar per day against a dashed goal line, scaled to the larger of the
+// goal and the best day
+fn bar_chart(stats: &MonthlyStats) -> String {
+    let goal_ml = stats.days.first().map_or(0, |d| d.goal_ml);
+    let max_ml = stats.days.iter().map(|d| d.total_ml).max().unwrap_or(0).max(goal_ml).max(1) as f32;
+    let slot = CHART_WIDTH / stats.days.len().max(1) as f32;
+    let scale = |ml: i32| ml as f32 / max_ml * CHART_HEIGHT;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT
+    );
+    for (i, day) in stats.days.iter().enumerate() {
+        let height = scale(day.total_ml);
+        let color = if day.total_ml >= day.goal_ml { MET_COLOR } else { MISSED_COLOR };
+        svg.push_str(&format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{}</title></rect>",
+            i as f32 * slot + BAR_GAP / 2.0,
+            CHART_HEIGHT - height,
+            (slot - BAR_GAP).max(1.0),
+            height,
+            color,
+            day.date
+        ));
+    }
+    let goal_y = CHART_HEIGHT - scale(goal_ml);
+    svg.push_str(&format!(
+        "<line x1=\"0\" y1=\"{y:.1}\" x2=\"{w}\" y2=\"{y:.1}\" stroke=\"#64748b\" stroke-dasharray=\"6 4\"/></svg>",
+        y = goal_y,
+        w = CHART_WIDTH
+    ));
+    svg
+}
+
+// Self-contained: styles and the chart are inline, so the file can be
+// emailed and opened anywhere
+pub fn monthly_html(stats: &MonthlyStats, unit: Unit) -> String {
+    let amount = |ml: i32| units::format_amount(ml, unit);
+    let title = format!("Hydration report: {} {}", stats.month, stats.year);
+
+    let mut rows = String::new();
+    for day in &stats.days {
+        let marker = if day.frozen {
+            "❄ frozen"
+        } else if day.total_ml >= day.goal_ml {
+            "✓ met"
+        } else {
+            ""
+        };
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{:.0}%</td><td>{}</td></tr>\n",
+            day.date,
+            amount(day.total_ml),
+            day.entries_count,
+            day.percentage,
+            marker
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: -apple-system, "Segoe UI", Roboto, sans-serif; color: #1e293b; max-width: 760px; margin: 2em auto; }}
+h1 {{ font-size: 1.5em; }}
+.summary {{ display: flex; flex-wrap: wrap; gap: 1em; margin-bottom: 1.5em; }}
+.summary div {{ background: #f1f5f9; border-radius: 8px; padding: 0.6em 1em; }}
+.summary strong {{ display: block; font-size: 1.2em; }}
+table {{ border-collapse: collapse; width: 100%; margin-top: 1.5em; }}
+th, td {{ border-bottom: 1px solid #e2e8f0; padding: 4px 8px; text-align: left; }}
+.num {{ text-align: right; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<div class="summary">
+<div>Total<strong>{total}</strong></div>
+<div>Daily average<strong>{average}</strong></div>
+<div>Days goal met<strong>{days_met}</strong></div>
+<div>Adherence<strong>{adherence:.0}%</strong></div>
+<div>Current streak<strong>{current_streak} days</strong></div>
+<div>Best streak<strong>{best_streak} days</strong></div>
+</div>
+{chart}
+<table>
+<tr><th>Date</th><th class="num">Total</th><th class="num">Entries</th><th class="num">Goal</th><th></th></tr>
+{rows}</table>
+</body>
+</html>
+"#,
+        title = title,
+        total = amount(stats.total_ml),
+        average = amount(stats.average_ml.round() as i32),
+        days_met = stats.days_goal_met,
+        adherence = stats.adherence_percentage,
+        current_streak = stats.current_streak,
+        best_streak = stats.best_streak,
+        chart = bar_chart(stats),
+        rows = rows
+    )
+}
+
+// Embedded in every PDF and share card so text renders the same everywhere
+pub const REPORT_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+const PAGE_WIDTH: Mm = Mm(210.0);
+const PAGE_HEIGHT: Mm = Mm(297.0);
+const MARGIN_MM: f32 = 20.0;
+const ROW_HEIGHT_MM: f32 = 7.0;
+const TABLE_COLUMNS_MM: [f32; 5] = [20.0, 70.0, 105.0, 140.0, 170.0];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PdfReport {
+    pub path: String,
+    pub page_count: usize,
+}
+
+struct WeekRow {
+    start: NaiveDate,
+    end: NaiveDate,
+    total_ml: i64,
+    days_met: usize,
+    days: usize,
+}
+
+pub fn daily_totals(conn: &Connection, start: NaiveDate, end: NaiveDate) -> SqliteResult<HashMap<NaiveDate, i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT date, SUM(amount_ml) FROM water_entries
+         WHERE date BETWEEN ?1 AND ?2 GROUP BY date",
+    )?;
+    let rows = stmt
+        .query_map(
+            params![
+                start.format(dates::DATE_FORMAT).to_string(),
+                end.format(dates::DATE_FORMAT).to_string()
+            ],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(date, total)| Some((dates::parse_date(&date)?, total)))
+        .collect())
+}
+
+// Monday-to-Sunday weeks, with the first and last cut to the range
+fn weeks(
+    totals: &HashMap<NaiveDate, i64>,
+    start: NaiveDate,
+    end: NaiveDate,
+    goal_on: &impl Fn(NaiveDate) -> i32,
+) -> Vec<WeekRow> {
+    let mut rows: Vec<WeekRow> = Vec::new();
+    let mut day = start;
+    while day <= end {
+        let total = totals.get(&day).copied().unwrap_or(0);
+        let met = total >= goal_on(day) as i64;
+        match rows.last_mut() {
+            Some(week) if day.weekday().num_days_from_monday() != 0 => {
+                week.end = day;
+                week.total_ml += total;
+                week.days_met += met as usize;
+                week.days += 1;
+            }
+            _ => rows.push(WeekRow {
+                start: day,
+                end: day,
+                total_ml: total,
+                days_met: met as usize,
+                days: 1,
+            }),
+        }
+        day += Duration::days(1);
+    }
+    rows
+}
+
+struct PdfWriter<'a> {
+    doc: &'a printpdf::PdfDocumentReference,
+    font: IndirectFontRef,
+    layer: PdfLayerReference,
+    y: f32,
+    pages: usize,
+}
+
+impl PdfWriter<'_> {
+    fn text(&self, text: &str, size: f32, x: f32) {
+        self.layer.use_text(text, size, Mm(x), Mm(self.y), &self.font);
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(PAGE_WIDTH, PAGE_HEIGHT, "Report");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT.0 - MARGIN_MM;
+        self.pages += 1;
+    }
+
+    // Moves down a row, starting a new page when the bottom margin is reached
+    fn advance(&mut self, height: f32) -> bool {
+        self.y -= height;
+        if self.y < MARGIN_MM {
+            self.new_page();
+            return true;
+        }
+        false
+    }
+
+    fn row(&self, cells: [&str; 5], size: f32) {
+        for (cell, x) in cells.iter().zip(TABLE_COLUMNS_MM) {
+            self.text(cell, size, x);
+        }
+    }
+}
+
+pub fn range_pdf(
+    conn: &Connection,
+    start: NaiveDate,
+    end: NaiveDate,
+    today: NaiveDate,
+    goal_on: impl Fn(NaiveDate) -> i32,
+    unit: Unit,
+    user_name: Option<&str>,
+) -> Result<(Vec<u8>, usize), AppError> {
+    let totals = daily_totals(conn, start, end)?;
+    let weeks = weeks(&totals, start, end, &goal_on);
+    let amount = |ml: i64| units::format_amount(ml.clamp(0, i32::MAX as i64) as i32, unit);
+
+    let elapsed_days = ((end.min(today) - start).num_days() + 1).max(0);
+    let total_ml: i64 = totals.values().sum();
+    let days_met = totals
+        .iter()
+        .filter(|(date, total)| **total >= goal_on(**date) as i64)
+        .count();
+    // A goal program can raise the goal partway through the range
+    let (first_goal, last_goal) = (goal_on(start), goal_on(end.min(today).max(start)));
+    let goal = if first_goal == last_goal {
+        amount(first_goal as i64)
+    } else {
+        format!("{} to {}", amount(first_goal as i64), amount(last_goal as i64))
+    };
+    // Both over the days so far, like the month view
+    let (average_ml, adherence) = if elapsed_days > 0 {
+        (total_ml / elapsed_days, days_met as f32 / elapsed_days as f32 * 100.0)
+    } else {
+        (0, 0.0)
+    };
+
+    let (doc, page, layer) = PdfDocument::new("Hydration report", PAGE_WIDTH, PAGE_HEIGHT, "Report");
+    let font = doc
+        .add_external_font(Cursor::new(REPORT_FONT))
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    let mut pdf = PdfWriter {
+        layer: doc.get_page(page).get_layer(layer),
+        doc: &doc,
+        font,
+        y: PAGE_HEIGHT.0 - MARGIN_MM,
+        pages: 1,
+    };
+
+    pdf.text("Hydration report", 20.0, MARGIN_MM);
+    if let Some(name) = user_name {
+        pdf.advance(9.0);
+        pdf.text(name, 13.0, MARGIN_MM);
+    }
+    pdf.advance(8.0);
+    pdf.text(&format!("{} to {}", start, end), 11.0, MARGIN_MM);
+
+    pdf.advance(14.0);
+    for line in [
+        format!("Total: {}", amount(total_ml)),
+        format!("Daily average: {}", amount(average_ml)),
+        format!("Daily goal: {}", goal),
+        format!("Days goal met: {} of {}", days_met, elapsed_days),
+        format!("Adherence: {:.0}%", adherence),
+    ] {
+        pdf.text(&line, 11.0, MARGIN_MM);
+        pdf.advance(ROW_HEIGHT_MM);
+    }
+
+    pdf.advance(ROW_HEIGHT_MM);
+    let header = ["Week from", "to", "Total", "Per day", "Goal met"];
+    pdf.row(header, 10.0);
+    for week in &weeks {
+        if pdf.advance(ROW_HEIGHT_MM) {
+            pdf.row(header, 10.0);
+            pdf.advance(ROW_HEIGHT_MM);
+        }
+        pdf.row(
+            [
+                &week.start.to_string(),
+                &week.end.to_string(),
+                &amount(week.total_ml),
+                &amount(week.total_ml / week.days as i64),
+                &format!("{}/{}", week.days_met, week.days),
+            ],
+            10.0,
+        );
+    }
+
+    let pages = pdf.pages;
+    let mut bytes = BufWriter::new(Vec::new());
+    doc.save(&mut bytes).map_err(|e| AppError::Other(e.to_string()))?;
+    let bytes = bytes.into_inner().map_err(|e| e.into_error())?;
+    Ok((bytes, pages))
+}
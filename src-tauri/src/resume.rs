@@ -0,0 +1,110 @@
+// Waking from sleep. On Linux logind says so directly (PrepareForSleep going
+// false); everywhere, and as a fallback, the scheduler notices the wall
+// clock having run ahead of its passes (see `scheduler::clock_jump`). Both
+// end up on the scheduler thread, which handles each resume once on its
+// next pass: the day is re-evaluated, open windows get `state-refresh` to
+// refetch, the tray is redrawn, and the reminder pass works out what was
+// missed from the wake time.
+//
+// Windows and macOS report sleep through window messages and workspace
+// notifications the app doesn't receive, so they rely on the clock alone,
+// which notices within a tick. Windows' monotonic clock keeps counting
+// through sleep, so there a resume is only noticed as a new day.
+
+use crate::{dates, tray};
+use chrono::{Duration as ChronoDuration, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+pub const EVENT: &str = "state-refresh";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detected {
+    PowerEvent,
+    ClockJump,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Resume {
+    pub woke_at: NaiveDateTime,
+    // How far the wall clock moved while asleep, when a pass saw it
+    pub slept: Option<ChronoDuration>,
+    pub detected: Detected,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StateRefresh {
+    pub woke_at: String,
+    pub today: String,
+}
+
+// Logs the resume and has everything showing today's state catch up
+pub fn announce(app: &AppHandle, resume: &Resume, today: NaiveDate) {
+    let how = match resume.detected {
+        Detected::PowerEvent => "reported by the OS",
+        Detected::ClockJump => "noticed from the clock",
+    };
+    match resume.slept {
+        Some(slept) => tracing::info!(
+            "resumed from sleep at {} after {} minutes ({}), today is {}",
+            resume.woke_at.format(dates::TIMESTAMP_FORMAT),
+            slept.num_minutes(),
+            how,
+            today
+        ),
+        None => tracing::info!(
+            "resumed from sleep at {} ({}), today is {}",
+            resume.woke_at.format(dates::TIMESTAMP_FORMAT),
+            how,
+            today
+        ),
+    }
+    tray::refresh(app);
+    let _ = app.emit(
+        EVENT,
+        StateRefresh {
+            woke_at: resume.woke_at.format(dates::TIMESTAMP_FORMAT).to_string(),
+            today: today.format(dates::DATE_FORMAT).to_string(),
+        },
+    );
+}
+
+#[cfg(target_os = "linux")]
+mod logind {
+    use zbus::blocking::{Connection, Proxy};
+
+    // Blocks for as long as the system bus is up. Without one, or without
+    // logind, there's nothing to wait for and the clock has to do.
+    pub fn watch(on_resume: impl Fn()) {
+        let Ok(system) = Connection::system() else {
+            return;
+        };
+        let Ok(manager) = Proxy::new(
+            &system,
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+        ) else {
+            return;
+        };
+        let Ok(signals) = manager.receive_signal("PrepareForSleep") else {
+            tracing::debug!("logind isn't reporting sleep, relying on the clock");
+            return;
+        };
+        for message in signals {
+            // True on the way down, false once awake again
+            if matches!(message.body().deserialize::<bool>(), Ok(false)) {
+                on_resume();
+            }
+        }
+    }
+}
+
+// Starts listening for the OS's resume notifications where there are any
+pub fn start(_app: &AppHandle) {
+    #[cfg(target_os = "linux")]
+    {
+        let app = _app.clone();
+        std::thread::spawn(move || logind::watch(|| crate::scheduler::resumed(&app, chrono::Local::now().naive_local())));
+    }
+}
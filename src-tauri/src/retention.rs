@@ -0,0 +1,88 @@
+// Deleting old entries, to keep the database small or old history private.
+// A purge is two steps: `preview` says how many entries would go and hands
+// out a one-time token, and only `purge` with that token for the same date
+// deletes anything, so a stray call from the frontend can't wipe history.
+// With `retention_years` set, entries older than that are purged at startup.
+
+use crate::{dates, db, AppError};
+use chrono::{Months, NaiveDate};
+use rusqlite::{Connection, Result as SqliteResult};
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PurgePreview {
+    pub before: String,
+    pub entries: i64,
+    // Pass back to purge_entries_before to confirm
+    pub token: String,
+}
+
+// The preview most recently handed out; a token is good for one purge
+#[derive(Default)]
+pub struct PendingPurge(pub Mutex<Option<PurgePreview>>);
+
+// Unpredictable enough that it has to come from a preview, which is all it's for
+fn new_token() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    hasher.write_u128(now.map_or(0, |d| d.as_nanos()));
+    format!("{:016x}", hasher.finish())
+}
+
+fn count_before(conn: &Connection, before: NaiveDate) -> SqliteResult<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM water_entries WHERE date < ?1",
+        [before.format(dates::DATE_FORMAT).to_string()],
+        |row| row.get(0),
+    )
+}
+
+pub fn preview(conn: &Connection, pending: &PendingPurge, before: NaiveDate) -> Result<PurgePreview, AppError> {
+    let preview = PurgePreview {
+        before: before.format(dates::DATE_FORMAT).to_string(),
+        entries: count_before(conn, before)?,
+        token: new_token(),
+    };
+    *pending.0.lock()? = Some(preview.clone());
+    Ok(preview)
+}
+
+// Entries whose logical day is before `before`; returns how many were deleted
+fn delete_before(conn: &Connection, before: NaiveDate) -> Result<usize, AppError> {
+    db::with_tx(conn, |tx| {
+        tx.execute(
+            "DELETE FROM water_entries WHERE date < ?1",
+            [before.format(dates::DATE_FORMAT).to_string()],
+        )
+    })
+    .map_err(AppError::from)
+}
+
+pub fn purge(conn: &Connection, pending: &PendingPurge, before: NaiveDate, token: &str) -> Result<usize, AppError> {
+    let mut pending = pending.0.lock()?;
+    let before_str = before.format(dates::DATE_FORMAT).to_string();
+    match pending.as_ref() {
+        Some(preview) if preview.token == token && preview.before == before_str => {}
+        _ => return Err(AppError::invalid("token", "doesn't match; preview the purge again")),
+    }
+    // Spent whether or not the delete works
+    *pending = None;
+    delete_before(conn, before)
+}
+
+// First day kept when only the last `years` years are retained
+fn retention_cutoff(today: NaiveDate, years: i32) -> Option<NaiveDate> {
+    today.checked_sub_months(Months::new(12 * years.max(0) as u32))
+}
+
+// The startup purge for `retention_years`; 0 keeps everything
+pub fn apply_retention(conn: &Connection, years: i32, today: NaiveDate) -> Result<usize, AppError> {
+    if years <= 0 {
+        return Ok(0);
+    }
+    let cutoff = retention_cutoff(today, years).ok_or_else(|| AppError::invalid("retention_years", "is out of range"))?;
+    delete_before(conn, cutoff)
+}
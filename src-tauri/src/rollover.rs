@@ -0,0 +1,57 @@
+// Notices the logical day changing, at midnight or at the day_starts_at_hour
+// rollover, so an open window, the widget and the tray stop showing
+// yesterday without anyone touching them. Checked on every scheduler pass;
+// a machine that slept across the rollover is caught on the first pass
+// after it wakes, however many days it missed.
+//
+// The per-day flags (the goal celebration, reminders resumed for the day)
+// are stored against the date they apply to, so a new day already starts
+// with them clear and nothing here has to reset them.
+
+use crate::{dates, db, tray, Database};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const EVENT: &str = "day-rolled-over";
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DayRolledOver {
+    pub previous: String,
+    pub today: String,
+}
+
+#[derive(Debug, Default)]
+pub struct DayWatch {
+    today: Option<NaiveDate>,
+}
+
+impl DayWatch {
+    // The day seen before when `today` is a different one. The first call
+    // only records it. A clock set back counts as a change too.
+    pub fn advance(&mut self, today: NaiveDate) -> Option<NaiveDate> {
+        let previous = self.today.replace(today)?;
+        (previous != today).then_some(previous)
+    }
+
+    // Called from the scheduler thread on every pass, before the reminder
+    // pass so that one already counts the new day. Returns the logical today.
+    pub fn check(&mut self, app: &AppHandle, now: NaiveDateTime) -> NaiveDate {
+        let day_starts_at_hour = db::get_day_starts_at_hour(&app.state::<Database>().lock());
+        let today = dates::logical_date(now, day_starts_at_hour);
+        let Some(previous) = self.advance(today) else {
+            return today;
+        };
+
+        tracing::info!("day rolled over from {} to {}", previous, today);
+        tray::refresh(app);
+        let _ = app.emit(
+            EVENT,
+            DayRolledOver {
+                previous: previous.format(dates::DATE_FORMAT).to_string(),
+                today: today.format(dates::DATE_FORMAT).to_string(),
+            },
+        );
+        today
+    }
+}
@@ -0,0 +1,404 @@
+// Interval reminders, run from a background thread so they keep firing while
+// the window is hidden. Each pass re-reads settings from the database, and
+// the thread is woken early whenever settings, snoozes or entries change.
+//
+// The next reminder is due one interval after whichever came last, the
+// latest entry or the latest reminder. A pass that finds it due but
+// suppressed (quiet hours, snoozed, wrong weekday) just does nothing; the
+// reminder stays due, so exactly one fires once it's allowed again, and a
+// thread that wakes late fires once rather than catching up.
+//
+// A reminder that would land inside the freshness window after an entry is
+// skipped, and the next one moves out in whole intervals from that entry,
+// so it doesn't fire the moment the window closes.
+//
+// In adaptive mode the next reminder is instead the first typical drinking
+// time after the anchor, so a half hour you already drank in is skipped.
+//
+// Because the due time comes from the stored wall-clock anchors rather than
+// a countdown, a machine that slept through several intervals still fires
+// just one reminder on waking. Each pass compares wall-clock and monotonic
+// time to notice the sleep, unless the OS already said (see `resume`), and
+// logs the schedule worked out from the wake time.
+//
+// While the user is away (idle past the threshold, or the session locked)
+// nothing fires, and coming back restarts the clock from that moment
+// instead of delivering whatever fell due in the meantime.
+//
+// The same thread runs the daily scheduled CSV export, the desk-session
+// nudge (see `desk_session`) and the check for a new day (see `rollover`).
+//
+// Each decision that changes something (a reminder sent, the clock
+// restarted, the user going away or coming back) is logged at info. Passes
+// that only find nothing due, or a due reminder still held back, are logged
+// at debug, since they repeat every tick.
+
+use crate::desk_session::DeskSession;
+use crate::resume::{self, Detected, Resume};
+use crate::rollover::DayWatch;
+use crate::{analytics, csv_export, dates, idle, notifications, reminders, settings, sound, AppError, Database};
+use chrono::{Duration as ChronoDuration, Local, NaiveDateTime};
+use tauri_plugin_notification::NotificationExt;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use serde::Serialize;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+const TICK: Duration = Duration::from_secs(30);
+const LAST_REMINDER_KEY: &str = "last_reminder_at";
+// Wall-clock time unaccounted for by the pass loop beyond this means the
+// machine slept or the clock was changed
+const CLOCK_JUMP_THRESHOLD_MINUTES: i64 = 2;
+// A reminder missed by more than this is logged as a catch-up
+const CATCH_UP_AFTER_MINUTES: i64 = 5;
+pub const AWAY_CHANGED_EVENT: &str = "away-changed";
+
+pub struct Scheduler {
+    wake: Mutex<Sender<()>>,
+    away: AtomicBool,
+    // When the OS last reported waking, until a pass handles it
+    resumed: Mutex<Option<NaiveDateTime>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AwayState {
+    // False where the OS can't report idle time; reminders then never pause
+    pub supported: bool,
+    pub away: bool,
+}
+
+pub fn away_state(app: &AppHandle) -> AwayState {
+    AwayState {
+        supported: idle::idle_time().is_some(),
+        away: app
+            .try_state::<Scheduler>()
+            .is_some_and(|scheduler| scheduler.away.load(Ordering::Relaxed)),
+    }
+}
+
+// Records whether the user is away and reports whether they just came back
+fn update_away(app: &AppHandle, threshold_minutes: i32) -> (bool, bool) {
+    let Some(scheduler) = app.try_state::<Scheduler>() else {
+        return (false, false);
+    };
+    let idle = idle::idle_time();
+    let away = idle::is_away(idle, threshold_minutes);
+    let was_away = scheduler.away.swap(away, Ordering::Relaxed);
+    if away != was_away {
+        if away {
+            tracing::info!("user is away, holding reminders");
+        } else {
+            tracing::info!("user is back");
+        }
+        let _ = app.emit(
+            AWAY_CHANGED_EVENT,
+            AwayState {
+                supported: idle.is_some(),
+                away,
+            },
+        );
+    }
+    (away, was_away && !away)
+}
+
+pub fn next_fire_at(
+    last_entry: Option<NaiveDateTime>,
+    last_reminder: Option<NaiveDateTime>,
+    interval_minutes: i32,
+    freshness_window_minutes: i32,
+) -> Option<NaiveDateTime> {
+    let interval = interval_minutes.max(1) as i64;
+    let anchor = last_entry.max(last_reminder)?;
+    let due = anchor + ChronoDuration::minutes(interval);
+
+    let Some(entry) = last_entry else {
+        return Some(due);
+    };
+    let fresh_until = entry + ChronoDuration::minutes(freshness_window_minutes as i64);
+    if due >= fresh_until {
+        return Some(due);
+    }
+    // Smallest whole number of intervals after the entry that clears the window
+    let steps = (freshness_window_minutes as i64 + interval - 1) / interval;
+    Some(entry + ChronoDuration::minutes(steps.max(1) * interval))
+}
+
+pub fn adaptive_next_fire_at(
+    pattern: &analytics::TypicalPattern,
+    last_entry: Option<NaiveDateTime>,
+    last_reminder: Option<NaiveDateTime>,
+    freshness_window_minutes: i32,
+) -> Option<NaiveDateTime> {
+    let anchor = last_entry.max(last_reminder)?;
+    let earliest = match last_entry {
+        Some(entry) => anchor.max(entry + ChronoDuration::minutes(freshness_window_minutes as i64)),
+        None => anchor,
+    };
+    // Typical times on the anchor's day and the next, which always has one
+    // after the anchor if any time is typical at all
+    [anchor.date(), anchor.date() + ChronoDuration::days(1)]
+        .into_iter()
+        .flat_map(|day| {
+            pattern
+                .typical_minutes()
+                .filter_map(move |minute| day.and_hms_opt((minute / 60) as u32, (minute % 60) as u32, 0))
+        })
+        .find(|at| *at > earliest)
+}
+
+// How far the wall clock moved beyond the monotonic clock between two
+// passes. The monotonic clock stops while the machine sleeps on Linux and
+// macOS, so the difference is the sleep; a pass that was only slow, say
+// waiting on the database behind a VACUUM, moves both alike. Windows counts
+// sleep on both, so there it only shows clock changes. Negative when the
+// clock was set back.
+pub fn clock_jump(wall_elapsed: ChronoDuration, monotonic_elapsed: Duration) -> Option<ChronoDuration> {
+    let accounted = ChronoDuration::from_std(monotonic_elapsed).ok()?;
+    let jump = wall_elapsed - accounted;
+    (jump.num_minutes().abs() >= CLOCK_JUMP_THRESHOLD_MINUTES).then_some(jump)
+}
+
+// What the pass makes of a reminder that may have come due while nothing
+// was watching, as of `since`
+fn recalculated_plan(next: NaiveDateTime, since: NaiveDateTime) -> String {
+    let missed_by = since - next;
+    if missed_by.num_minutes() >= CATCH_UP_AFTER_MINUTES {
+        format!("reminder due at {} was missed, sending one catch-up", next.format("%H:%M"))
+    } else {
+        format!("next reminder at {}", next.format(dates::TIMESTAMP_FORMAT))
+    }
+}
+
+// Only after a resume or a clock change; other passes log at debug below
+fn log_recalculation(jump: Option<ChronoDuration>, resume: Option<&Resume>, next: NaiveDateTime, now: NaiveDateTime) {
+    match (resume, jump) {
+        (Some(resume), _) => tracing::info!(
+            "after resuming at {}: {}",
+            resume.woke_at.format("%H:%M"),
+            recalculated_plan(next, resume.woke_at)
+        ),
+        (None, Some(jump)) => tracing::info!(
+            "wall clock jumped {:+} minutes (clock change); {}",
+            jump.num_minutes(),
+            recalculated_plan(next, now)
+        ),
+        (None, None) => {}
+    }
+}
+
+// A resume the OS reported since the last pass, or else a jump forward big
+// enough to mean the machine slept. logind and the clock usually both see
+// the same wake, in one pass or two in a row, so it only counts once.
+fn detect_resume(
+    app: &AppHandle,
+    now: NaiveDateTime,
+    jump: Option<ChronoDuration>,
+    last_resume: &mut Option<Instant>,
+) -> Option<Resume> {
+    let reported = app
+        .try_state::<Scheduler>()
+        .and_then(|scheduler| scheduler.resumed.lock().ok()?.take());
+    let slept = jump.filter(|jump| *jump > ChronoDuration::zero());
+    let resume = match (reported, slept) {
+        (Some(woke_at), slept) => Resume {
+            woke_at,
+            slept,
+            detected: Detected::PowerEvent,
+        },
+        (None, Some(slept)) => Resume {
+            woke_at: now,
+            slept: Some(slept),
+            detected: Detected::ClockJump,
+        },
+        (None, None) => return None,
+    };
+    if last_resume.is_some_and(|at| at.elapsed() < TICK) {
+        return None;
+    }
+    *last_resume = Some(Instant::now());
+    Some(resume)
+}
+
+fn tick(
+    app: &AppHandle,
+    now: NaiveDateTime,
+    jump: Option<ChronoDuration>,
+    resume: Option<&Resume>,
+) -> Result<(), AppError> {
+    let db = app.state::<Database>();
+    let settings = settings::load(&db.lock())?;
+    if !settings.reminder_enabled {
+        return Ok(());
+    }
+
+    // Asks the OS, possibly via a subprocess, so not while holding the database
+    let (away, returned) = update_away(app, settings.idle_threshold_minutes);
+    let conn = db.lock();
+    if away {
+        return Ok(());
+    }
+    if returned {
+        tracing::info!("restarting the reminder clock after the user came back");
+        return Ok(settings::set(&conn, LAST_REMINDER_KEY, &now.format(dates::TIMESTAMP_FORMAT).to_string())?);
+    }
+    let Some(interval) = crate::reminder_interval(&conn)? else {
+        tracing::debug!("no more reminders today");
+        return Ok(());
+    };
+
+    let last_entry: Option<String> =
+        conn.query_row("SELECT MAX(timestamp) FROM water_entries", [], |row| row.get(0))?;
+    let last_reminder: Option<String> = settings::get(&conn, LAST_REMINDER_KEY)?;
+    let last_entry = last_entry.and_then(|t| dates::parse_timestamp(&t));
+    let last_reminder = last_reminder.and_then(|t| dates::parse_timestamp(&t));
+    let pattern = if settings.adaptive_reminders {
+        let today = dates::logical_date(now, settings.day_starts_at_hour);
+        Some(analytics::typical_pattern(&conn, today)?)
+    } else {
+        None
+    };
+    let next = match pattern.filter(|p| p.enough_history) {
+        Some(pattern) => adaptive_next_fire_at(&pattern, last_entry, last_reminder, settings.freshness_window_minutes)
+            .or_else(|| next_fire_at(last_entry, last_reminder, interval, settings.freshness_window_minutes)),
+        None => next_fire_at(last_entry, last_reminder, interval, settings.freshness_window_minutes),
+    };
+
+    let Some(next) = next else {
+        // Nothing to count from yet; start the clock now
+        tracing::info!("no entry or reminder to count from, starting the reminder clock");
+        return Ok(settings::set(&conn, LAST_REMINDER_KEY, &now.format(dates::TIMESTAMP_FORMAT).to_string())?);
+    };
+    log_recalculation(jump, resume, next, now);
+    if now < next {
+        tracing::debug!("next reminder at {}", next.format(dates::TIMESTAMP_FORMAT));
+        return Ok(());
+    }
+    if !reminders::should_remind(&settings, now) {
+        tracing::debug!("reminder due since {} held back by quiet hours or the reminder days", next.format("%H:%M"));
+        return Ok(());
+    }
+    if reminders::is_snoozed(&conn, now)? {
+        tracing::debug!("reminder due since {} held back by a snooze", next.format("%H:%M"));
+        return Ok(());
+    }
+
+    let stats = crate::today_stats(&conn)?;
+    let escalated = reminders::is_escalated(&settings, &stats, now);
+    settings::set(&conn, LAST_REMINDER_KEY, &now.format(dates::TIMESTAMP_FORMAT).to_string())?;
+    drop(conn);
+    tracing::info!(
+        "sending a{} reminder, due since {} with {:.0}% of the goal",
+        if escalated { "n escalated" } else { "" },
+        next.format("%H:%M"),
+        stats.percentage
+    );
+
+    if escalated {
+        notifications::show_reminder(
+            app,
+            "⚠️ You're falling behind",
+            &format!(
+                "Only {:.0}% of today's goal so far. Drink a glass of water now.",
+                stats.percentage
+            ),
+        );
+    } else {
+        notifications::show_reminder(app, "💧 Hydration Reminder", "Time to drink some water! Stay hydrated.");
+    }
+    sound::play_reminder(&settings, now, escalated);
+    Ok(())
+}
+
+// Independent of reminders being on, so it runs before `tick` can bail out
+fn export_if_due(app: &AppHandle, now: NaiveDateTime) {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let Ok(settings) = settings::load(&conn) else {
+        return;
+    };
+    if let Some(error) = csv_export::run_scheduled(&conn, &settings, now) {
+        drop(conn);
+        let _ = app
+            .notification()
+            .builder()
+            .title("Scheduled export failed")
+            .body(error)
+            .show();
+    }
+}
+
+// Shared by the command, the tray item and the notification action
+pub fn snooze(app: &AppHandle, minutes: i64) -> Result<reminders::SnoozeState, AppError> {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let state = reminders::snooze(&conn, minutes, Local::now().naive_local())?;
+    drop(conn);
+    let _ = app.emit(reminders::SNOOZE_CHANGED_EVENT, &state);
+    Ok(state)
+}
+
+// For the OS's resume notification; the pass this starts handles it
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn resumed(app: &AppHandle, at: NaiveDateTime) {
+    if let Some(scheduler) = app.try_state::<Scheduler>() {
+        if let Ok(mut resumed) = scheduler.resumed.lock() {
+            *resumed = Some(at);
+        }
+    }
+    wake(app);
+}
+
+// Re-run the scheduler now instead of at its next tick
+pub fn wake(app: &AppHandle) {
+    if let Some(scheduler) = app.try_state::<Scheduler>() {
+        if let Ok(sender) = scheduler.wake.lock() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+pub fn start(app: AppHandle) {
+    let (sender, receiver) = mpsc::channel();
+    app.manage(Scheduler {
+        wake: Mutex::new(sender),
+        away: AtomicBool::new(false),
+        resumed: Mutex::new(None),
+    });
+
+    for event in [settings::CHANGED_EVENT, reminders::SNOOZE_CHANGED_EVENT] {
+        let handle = app.clone();
+        app.listen(event, move |_| wake(&handle));
+    }
+
+    thread::spawn(move || {
+        let mut last_pass: Option<(Instant, NaiveDateTime)> = None;
+        let mut desk = DeskSession::default();
+        let mut day = DayWatch::default();
+        let mut last_resume = None;
+        loop {
+            let now = Local::now().naive_local();
+            let jump = last_pass.and_then(|(at, wall)| clock_jump(now - wall, at.elapsed()));
+            last_pass = Some((Instant::now(), now));
+            let resume = detect_resume(&app, now, jump, &mut last_resume);
+            export_if_due(&app, now);
+            let today = day.check(&app, now);
+            if let Some(resume) = &resume {
+                resume::announce(&app, resume, today);
+            }
+            if let Err(e) = tick(&app, now, jump, resume.as_ref()) {
+                tracing::error!("reminder pass failed: {}", e);
+            }
+            if let Err(e) = desk.check(&app, now) {
+                tracing::error!("desk-session check failed: {}", e);
+            }
+            if let Err(RecvTimeoutError::Disconnected) = receiver.recv_timeout(TICK) {
+                break;
+            }
+        }
+    });
+}
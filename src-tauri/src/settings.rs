@@ -0,0 +1,554 @@
+// User settings, stored one key per row with JSON-encoded values so adding an
+// option never needs a schema change. `Settings` is the shape the frontend
+// sees; keys it doesn't know about are left untouched on save, so an older
+// and a newer build can share one database.
+
+use crate::{checkpoints, db, logging, reminders, sound, theme, tray, units, AppError};
+use rusqlite::{params, types::Value, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::fmt;
+
+// Emitted with the stored `Settings` whenever they change
+pub const CHANGED_EVENT: &str = "settings-changed";
+
+pub const THEMES: [&str; 3] = ["dark", "light", theme::AUTO];
+// Filled in when read, never stored
+const DERIVED_KEYS: [&str; 1] = ["resolved_theme"];
+pub const DAILY_GOAL_RANGE_ML: std::ops::RangeInclusive<i32> = 1..=20_000;
+pub const REMINDER_INTERVAL_RANGE_MINUTES: std::ops::RangeInclusive<i32> = 5..=480;
+pub const FRESHNESS_WINDOW_RANGE_MINUTES: std::ops::RangeInclusive<i32> = 0..=240;
+pub const ESCALATION_THRESHOLD_RANGE_PERCENTAGE: std::ops::RangeInclusive<f32> = 1.0..=100.0;
+pub const AUTO_BACKUP_KEEP_RANGE: std::ops::RangeInclusive<i32> = 1..=365;
+pub const IDLE_THRESHOLD_RANGE_MINUTES: std::ops::RangeInclusive<i32> = 0..=240;
+pub const DESK_REMINDER_RANGE_MINUTES: std::ops::RangeInclusive<i32> = 30..=480;
+// Each is a tray menu item
+pub const MAX_QUICK_ADD_AMOUNTS: usize = 8;
+pub const RETENTION_RANGE_YEARS: std::ops::RangeInclusive<i32> = 0..=100;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub daily_goal_ml: i32,
+    pub reminder_interval_minutes: i32,
+    pub reminder_enabled: bool,
+    pub sound_enabled: bool,
+    pub start_with_system: bool,
+    // Stay in the tray on launch, as autostart's --hidden does
+    pub start_minimized: bool,
+    pub theme: String,
+    pub day_starts_at_hour: i32,
+    // Display/input unit only; stored amounts are always millilitres
+    pub unit: units::Unit,
+    // Amounts offered in the tray menu; empty falls back to the defaults
+    pub quick_add_amounts: Vec<i32>,
+    // macOS only: today's percentage or what's left, next to the tray icon
+    pub menu_bar_title: tray::MenuBarTitle,
+    // Show the window, log the first quick-add amount, or show/hide it
+    pub tray_left_click: tray::LeftClickAction,
+    // A one-color tray icon matching the panel; a template image on macOS
+    pub tray_icon_monochrome: bool,
+    // Ctrl+Alt+W / Ctrl+Alt+Shift+W add the smallest / largest of those
+    // from anywhere, and Ctrl+Alt+H shows the window
+    pub global_shortcuts_enabled: bool,
+    // A brief on-screen confirmation for tray and shortcut adds
+    pub quick_add_overlay: bool,
+    // Minutes since midnight; the window may wrap past midnight
+    pub quiet_hours_enabled: bool,
+    pub quiet_hours_start: i32,
+    pub quiet_hours_end: i32,
+    pub checkpoints: Vec<checkpoints::Checkpoint>,
+    // Weekdays reminders may fire on, Monday = 0
+    pub reminder_days: Vec<u8>,
+    // Derive the interval from how far behind the goal today is
+    pub smart_reminders: bool,
+    // Remind at the times of day the history says you usually drink;
+    // falls back to the interval until there's enough history
+    pub adaptive_reminders: bool,
+    // No more reminders once today's goal is met, until resumed
+    pub pause_reminders_when_goal_met: bool,
+    // A reminder due this soon after an entry is skipped; 0 turns it off
+    pub freshness_window_minutes: i32,
+    // Reminders pause after this long without input, or while locked; 0 turns it off
+    pub idle_threshold_minutes: i32,
+    // A one-off nudge after this long at the computer without logging,
+    // independent of the interval reminders
+    pub desk_reminder_enabled: bool,
+    pub desk_reminder_minutes: i32,
+    // Larger single entries need confirming, to catch an extra zero
+    pub max_entry_ml: i32,
+    // Custom reminder sound; None plays the built-in chime
+    pub reminder_sound_path: Option<String>,
+    // Remind more insistently while this many percentage points behind the
+    // projected pace for the time of day
+    pub escalation_enabled: bool,
+    pub escalation_threshold_percentage: f32,
+    pub escalation_interval_minutes: i32,
+    // None uses the regular reminder sound
+    pub escalation_sound_path: Option<String>,
+    // Shown in the header of PDF reports
+    pub user_name: Option<String>,
+    // Copy the database into backups/ once a day, keeping the newest few
+    pub auto_backup_enabled: bool,
+    pub auto_backup_keep: i32,
+    // Entries older than this many years are deleted at startup; 0 keeps everything
+    pub retention_years: i32,
+    // Write the full history as CSV into this folder every day at
+    // export_time, minutes since midnight
+    pub export_enabled: bool,
+    pub export_path: Option<String>,
+    pub export_time: i32,
+    // Also show a notification, not just the in-app celebration
+    pub notify_on_goal_reached: bool,
+    // Language tag for month names and other text the backend formats;
+    // unknown tags fall back to English
+    pub locale: String,
+    // Hex color such as "#4ade80"
+    pub accent_color: String,
+    // How much goes into the log file: error, warn, info, debug or trace
+    pub log_level: String,
+    // `theme` with "auto" replaced by the OS theme
+    pub resolved_theme: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            daily_goal_ml: 4000,
+            reminder_interval_minutes: 60,
+            reminder_enabled: true,
+            sound_enabled: true,
+            start_with_system: false,
+            start_minimized: false,
+            theme: "dark".to_string(),
+            day_starts_at_hour: 0,
+            unit: units::Unit::Ml,
+            quick_add_amounts: tray::DEFAULT_QUICK_ADD_AMOUNTS.to_vec(),
+            menu_bar_title: tray::MenuBarTitle::Off,
+            tray_left_click: tray::LeftClickAction::ShowWindow,
+            // The menu bar expects template images
+            tray_icon_monochrome: cfg!(target_os = "macos"),
+            global_shortcuts_enabled: true,
+            quick_add_overlay: true,
+            quiet_hours_enabled: false,
+            quiet_hours_start: 22 * 60,
+            quiet_hours_end: 7 * 60,
+            checkpoints: Vec::new(),
+            reminder_days: reminders::ALL_DAYS.to_vec(),
+            smart_reminders: false,
+            adaptive_reminders: false,
+            pause_reminders_when_goal_met: true,
+            max_entry_ml: 2000,
+            reminder_sound_path: None,
+            escalation_enabled: false,
+            escalation_threshold_percentage: 30.0,
+            escalation_interval_minutes: 20,
+            escalation_sound_path: None,
+            user_name: None,
+            auto_backup_enabled: false,
+            auto_backup_keep: 7,
+            retention_years: 0,
+            export_enabled: false,
+            export_path: None,
+            export_time: 23 * 60 + 30,
+            notify_on_goal_reached: true,
+            freshness_window_minutes: 20,
+            idle_threshold_minutes: 10,
+            desk_reminder_enabled: false,
+            desk_reminder_minutes: 120,
+            locale: "en".to_string(),
+            accent_color: "#4ade80".to_string(),
+            log_level: logging::DEFAULT_LEVEL.to_string(),
+            resolved_theme: "dark".to_string(),
+        }
+    }
+}
+
+// A rejected save, naming the field at fault
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+impl ValidationError {
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+fn check(ok: bool, field: &'static str, message: impl Into<String>) -> Result<(), ValidationError> {
+    if ok {
+        Ok(())
+    } else {
+        Err(ValidationError::new(field, message))
+    }
+}
+
+// Runs before anything is written, so an invalid save changes nothing
+pub fn validate(settings: &Settings) -> Result<(), ValidationError> {
+    check(
+        DAILY_GOAL_RANGE_ML.contains(&settings.daily_goal_ml),
+        "daily_goal_ml",
+        format!(
+            "must be between {} and {} ml",
+            DAILY_GOAL_RANGE_ML.start(),
+            DAILY_GOAL_RANGE_ML.end()
+        ),
+    )?;
+    check(
+        REMINDER_INTERVAL_RANGE_MINUTES.contains(&settings.reminder_interval_minutes),
+        "reminder_interval_minutes",
+        format!(
+            "must be between {} and {} minutes",
+            REMINDER_INTERVAL_RANGE_MINUTES.start(),
+            REMINDER_INTERVAL_RANGE_MINUTES.end()
+        ),
+    )?;
+    check(
+        FRESHNESS_WINDOW_RANGE_MINUTES.contains(&settings.freshness_window_minutes),
+        "freshness_window_minutes",
+        format!(
+            "must be between {} and {} minutes",
+            FRESHNESS_WINDOW_RANGE_MINUTES.start(),
+            FRESHNESS_WINDOW_RANGE_MINUTES.end()
+        ),
+    )?;
+    check(
+        ESCALATION_THRESHOLD_RANGE_PERCENTAGE.contains(&settings.escalation_threshold_percentage),
+        "escalation_threshold_percentage",
+        format!(
+            "must be between {} and {}",
+            ESCALATION_THRESHOLD_RANGE_PERCENTAGE.start(),
+            ESCALATION_THRESHOLD_RANGE_PERCENTAGE.end()
+        ),
+    )?;
+    check(
+        REMINDER_INTERVAL_RANGE_MINUTES.contains(&settings.escalation_interval_minutes),
+        "escalation_interval_minutes",
+        format!(
+            "must be between {} and {} minutes",
+            REMINDER_INTERVAL_RANGE_MINUTES.start(),
+            REMINDER_INTERVAL_RANGE_MINUTES.end()
+        ),
+    )?;
+    check(
+        AUTO_BACKUP_KEEP_RANGE.contains(&settings.auto_backup_keep),
+        "auto_backup_keep",
+        format!(
+            "must be between {} and {}",
+            AUTO_BACKUP_KEEP_RANGE.start(),
+            AUTO_BACKUP_KEEP_RANGE.end()
+        ),
+    )?;
+    check(
+        IDLE_THRESHOLD_RANGE_MINUTES.contains(&settings.idle_threshold_minutes),
+        "idle_threshold_minutes",
+        format!(
+            "must be between {} and {} minutes",
+            IDLE_THRESHOLD_RANGE_MINUTES.start(),
+            IDLE_THRESHOLD_RANGE_MINUTES.end()
+        ),
+    )?;
+    check(
+        DESK_REMINDER_RANGE_MINUTES.contains(&settings.desk_reminder_minutes),
+        "desk_reminder_minutes",
+        format!(
+            "must be between {} and {} minutes",
+            DESK_REMINDER_RANGE_MINUTES.start(),
+            DESK_REMINDER_RANGE_MINUTES.end()
+        ),
+    )?;
+    check(
+        RETENTION_RANGE_YEARS.contains(&settings.retention_years),
+        "retention_years",
+        format!(
+            "must be between {} and {} years",
+            RETENTION_RANGE_YEARS.start(),
+            RETENTION_RANGE_YEARS.end()
+        ),
+    )?;
+    check(
+        (0..reminders::MINUTES_PER_DAY).contains(&settings.export_time),
+        "export_time",
+        format!("must be between 0 and {} minutes", reminders::MINUTES_PER_DAY - 1),
+    )?;
+    check(
+        !settings.export_enabled || settings.export_path.as_deref().is_some_and(|p| !p.trim().is_empty()),
+        "export_path",
+        "must be set to turn on scheduled exports",
+    )?;
+    check(
+        THEMES.contains(&settings.theme.as_str()),
+        "theme",
+        format!("must be one of {}", THEMES.join(", ")),
+    )?;
+    check(
+        DAILY_GOAL_RANGE_ML.contains(&settings.max_entry_ml),
+        "max_entry_ml",
+        format!(
+            "must be between {} and {} ml",
+            DAILY_GOAL_RANGE_ML.start(),
+            DAILY_GOAL_RANGE_ML.end()
+        ),
+    )?;
+    check(
+        theme::is_hex_color(&settings.accent_color),
+        "accent_color",
+        "must be a hex color like #4ade80",
+    )?;
+    check(
+        logging::LEVELS.contains(&settings.log_level.as_str()),
+        "log_level",
+        format!("must be one of {}", logging::LEVELS.join(", ")),
+    )?;
+    check(
+        (0..=23).contains(&settings.day_starts_at_hour),
+        "day_starts_at_hour",
+        "must be between 0 and 23",
+    )?;
+    check(
+        settings.quick_add_amounts.len() <= MAX_QUICK_ADD_AMOUNTS,
+        "quick_add_amounts",
+        format!("must be at most {} amounts", MAX_QUICK_ADD_AMOUNTS),
+    )?;
+    check(
+        settings.quick_add_amounts.iter().all(|ml| (1..=settings.max_entry_ml).contains(ml)),
+        "quick_add_amounts",
+        format!("must all be between 1 ml and the largest single entry, {} ml", settings.max_entry_ml),
+    )?;
+    // The tray tells its items apart by amount
+    check(
+        settings.quick_add_amounts.iter().collect::<HashSet<_>>().len() == settings.quick_add_amounts.len(),
+        "quick_add_amounts",
+        "must not repeat an amount",
+    )?;
+    check(
+        settings.user_name.as_ref().is_none_or(|name| name.chars().count() <= 100),
+        "user_name",
+        "must be at most 100 characters",
+    )?;
+    if let Some(path) = &settings.reminder_sound_path {
+        sound::validate_sound_file("reminder_sound_path", path)?;
+    }
+    if let Some(path) = &settings.escalation_sound_path {
+        sound::validate_sound_file("escalation_sound_path", path)?;
+    }
+    reminders::validate_quiet_hours(settings)?;
+    reminders::validate_reminder_days(settings)?;
+    checkpoints::validate(&settings.checkpoints)?;
+    Ok(())
+}
+
+pub fn init_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS app_settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+    migrate_legacy_row(conn)
+}
+
+// Columns of the old single-row table that held 0/1 or JSON text
+const LEGACY_BOOL_COLUMNS: [&str; 5] = [
+    "reminder_enabled",
+    "sound_enabled",
+    "start_with_system",
+    "quiet_hours_enabled",
+    "smart_reminders",
+];
+const LEGACY_JSON_COLUMNS: [&str; 3] = ["quick_add_amounts", "checkpoints", "reminder_days"];
+
+fn legacy_to_json(column: &str, value: Value) -> Option<JsonValue> {
+    match value {
+        Value::Null | Value::Blob(_) => None,
+        Value::Integer(i) if LEGACY_BOOL_COLUMNS.contains(&column) => Some(JsonValue::Bool(i != 0)),
+        Value::Integer(i) => Some(JsonValue::from(i)),
+        Value::Real(f) => Some(JsonValue::from(f)),
+        Value::Text(t) if LEGACY_JSON_COLUMNS.contains(&column) => serde_json::from_str(&t).ok(),
+        Value::Text(t) => Some(JsonValue::String(t)),
+    }
+}
+
+// Copy the old `settings` row into the key/value table, then drop it. Keys
+// that already exist win, and it runs inside the migrations' transaction, so
+// an interrupted run leaves the old row where it was.
+fn migrate_legacy_row(conn: &Connection) -> SqliteResult<()> {
+    let legacy_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'settings')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !legacy_exists {
+        return Ok(());
+    }
+
+    {
+        let mut stmt = conn.prepare("SELECT * FROM settings WHERE id = 1")?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let row: Option<Vec<Value>> = stmt
+            .query_row([], |row| (0..columns.len()).map(|i| row.get(i)).collect())
+            .optional()?;
+
+        for (column, value) in columns.iter().zip(row.unwrap_or_default()) {
+            if column == "id" {
+                continue;
+            }
+            if let Some(json) = legacy_to_json(column, value) {
+                conn.execute(
+                    "INSERT OR IGNORE INTO app_settings (key, value) VALUES (?1, ?2)",
+                    params![column, json.to_string()],
+                )?;
+            }
+        }
+    }
+    conn.execute("DROP TABLE settings", [])?;
+    Ok(())
+}
+
+pub const GET_QUERY: &str = "SELECT value FROM app_settings WHERE key = ?1";
+
+// Typed read of a single key; a missing key or a value that no longer
+// parses as `T` reads as None. Cached, since most reads of anything else
+// start with the goal or the rollover hour.
+pub fn get<T: DeserializeOwned>(conn: &Connection, key: &str) -> SqliteResult<Option<T>> {
+    let value: Option<String> = conn
+        .prepare_cached(GET_QUERY)?
+        .query_row([key], |row| row.get(0))
+        .optional()?;
+    Ok(value.and_then(|v| serde_json::from_str(&v).ok()))
+}
+
+pub fn set<T: Serialize>(conn: &Connection, key: &str, value: &T) -> SqliteResult<()> {
+    let json = serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    conn.execute(
+        "INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, json],
+    )?;
+    Ok(())
+}
+
+pub fn remove(conn: &Connection, key: &str) -> SqliteResult<()> {
+    conn.execute("DELETE FROM app_settings WHERE key = ?1", [key])?;
+    Ok(())
+}
+
+fn to_object(settings: &Settings) -> serde_json::Map<String, JsonValue> {
+    match serde_json::to_value(settings) {
+        Ok(JsonValue::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    }
+}
+
+// Defaults overlaid with every stored key that still fits its field; a value
+// of the wrong type is skipped instead of failing the whole load
+pub fn load(conn: &Connection) -> SqliteResult<Settings> {
+    let mut stmt = conn.prepare("SELECT key, value FROM app_settings")?;
+    let stored = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let mut fields = to_object(&Settings::default());
+    for (key, value) in stored {
+        if !fields.contains_key(&key) || DERIVED_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let Ok(value) = serde_json::from_str::<JsonValue>(&value) else {
+            continue;
+        };
+        let mut candidate = fields.clone();
+        candidate.insert(key, value);
+        if serde_json::from_value::<Settings>(JsonValue::Object(candidate.clone())).is_ok() {
+            fields = candidate;
+        }
+    }
+
+    Ok(serde_json::from_value(JsonValue::Object(fields)).unwrap_or_default())
+}
+
+// When the preferences were last saved; lets a backup merge tell which side
+// has the newer settings
+pub const UPDATED_AT_KEY: &str = "settings_updated_at";
+
+// Writes every field in one transaction; other keys are left alone
+pub fn save(conn: &Connection, settings: &Settings) -> SqliteResult<()> {
+    db::with_tx(conn, |tx| {
+        set(
+            tx,
+            UPDATED_AT_KEY,
+            &chrono::Local::now().format(crate::dates::TIMESTAMP_FORMAT).to_string(),
+        )?;
+        for (key, value) in to_object(settings) {
+            if DERIVED_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+            tx.execute(
+                "INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                params![key, value.to_string()],
+            )?;
+        }
+        Ok(())
+    })
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SettingsImport {
+    // Names of the fields whose value differs from before the import
+    pub changed: Vec<String>,
+    pub warnings: Vec<String>,
+    pub settings: Settings,
+}
+
+pub fn export_json(settings: &Settings) -> Result<String, AppError> {
+    let mut fields = to_object(settings);
+    for key in DERIVED_KEYS {
+        fields.remove(key);
+    }
+    Ok(serde_json::to_string_pretty(&fields)?)
+}
+
+// Overlay an exported file on `current`. Fields this version doesn't know
+// are skipped with a warning; a known field with a bad value is an error.
+// Errors name `path`, the file import_settings was given.
+pub fn merge_import(current: &Settings, json: &str) -> Result<(Settings, Vec<String>), AppError> {
+    let not_settings = |reason: &dyn fmt::Display| AppError::invalid("path", format!("not a settings file: {}", reason));
+    let JsonValue::Object(imported) = serde_json::from_str::<JsonValue>(json).map_err(|e| not_settings(&e))? else {
+        return Err(not_settings(&"expected a JSON object"));
+    };
+
+    let mut fields = to_object(current);
+    let mut warnings = Vec::new();
+    for (key, value) in imported {
+        if !fields.contains_key(&key) || DERIVED_KEYS.contains(&key.as_str()) {
+            warnings.push(format!("Ignored unknown setting \"{}\"", key));
+            continue;
+        }
+        fields.insert(key, value);
+    }
+
+    let settings = serde_json::from_value(JsonValue::Object(fields)).map_err(|e| not_settings(&e))?;
+    Ok((settings, warnings))
+}
+
+pub fn changed_fields(before: &Settings, after: &Settings) -> Vec<String> {
+    let before = to_object(before);
+    to_object(after)
+        .into_iter()
+        .filter(|(key, value)| !DERIVED_KEYS.contains(&key.as_str()) && before.get(key) != Some(value))
+        .map(|(key, _)| key)
+        .collect()
+}
@@ -0,0 +1,157 @@
+// Fixed-size PNG cards for sharing progress, drawn directly rather than by
+// screenshotting the webview. Colours follow the resolved theme and accent.
+
+use crate::{dates, report, theme, AppError, Settings};
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+pub const WIDTH: u32 = 1200;
+pub const HEIGHT: u32 = 630;
+const PADDING: f32 = 80.0;
+const PROGRESS_BAR_HEIGHT: f32 = 24.0;
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum CardKind {
+    Today,
+    Streak,
+    Month,
+}
+
+// What goes on a card, worked out by the caller from the usual stats
+pub struct CardContent {
+    pub title: String,
+    pub value: String,
+    pub detail: String,
+    // 0.0..=1.0 fills the bar at the bottom; None leaves it out
+    pub progress: Option<f32>,
+}
+
+type Rgb = (u8, u8, u8);
+
+struct Palette {
+    background: Rgb,
+    text: Rgb,
+    muted: Rgb,
+    track: Rgb,
+    accent: Rgb,
+}
+
+// "#4ade80" or the short "#4d8"
+fn parse_hex(color: &str) -> Option<Rgb> {
+    if !theme::is_hex_color(color) {
+        return None;
+    }
+    let hex = &color[1..];
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    if hex.len() == 3 {
+        let digit = |i: usize| channel(&hex[i..=i].repeat(2));
+        Some((digit(0)?, digit(1)?, digit(2)?))
+    } else {
+        Some((channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?))
+    }
+}
+
+fn palette(settings: &Settings) -> Palette {
+    let accent = parse_hex(&settings.accent_color).unwrap_or((0x4a, 0xde, 0x80));
+    if settings.resolved_theme == "light" {
+        Palette {
+            background: (0xf8, 0xfa, 0xfc),
+            text: (0x0f, 0x17, 0x2a),
+            muted: (0x64, 0x74, 0x8b),
+            track: (0xe2, 0xe8, 0xf0),
+            accent,
+        }
+    } else {
+        Palette {
+            background: (0x0f, 0x17, 0x2a),
+            text: (0xf8, 0xfa, 0xfc),
+            muted: (0x94, 0xa3, 0xb8),
+            track: (0x1e, 0x29, 0x3b),
+            accent,
+        }
+    }
+}
+
+fn fill_rect(pixmap: &mut Pixmap, x: f32, y: f32, width: f32, height: f32, (r, g, b): Rgb) {
+    let Some(rect) = Rect::from_xywh(x, y, width, height) else {
+        return;
+    };
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(r, g, b, 255);
+    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+}
+
+fn text_width(font: &FontRef, text: &str, size: f32) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    text.chars().map(|c| scaled.h_advance(scaled.glyph_id(c))).sum()
+}
+
+// Glyph coverage blended straight into the pixels; the background is
+// opaque, so the result stays opaque
+fn draw_text(pixmap: &mut Pixmap, font: &FontRef, text: &str, size: f32, x: f32, baseline: f32, color: Rgb) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let data = pixmap.data_mut();
+    let mut caret = x;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        previous = Some(id);
+        let glyph = id.with_scale_and_position(size, ab_glyph::point(caret, baseline));
+        caret += scaled.h_advance(id);
+
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= width || py >= height {
+                return;
+            }
+            let i = (py * width + px) as usize * 4;
+            let coverage = coverage.clamp(0.0, 1.0);
+            for (channel, value) in [color.0, color.1, color.2].into_iter().enumerate() {
+                let under = data[i + channel] as f32;
+                data[i + channel] = (value as f32 * coverage + under * (1.0 - coverage)).round() as u8;
+            }
+        });
+    }
+}
+
+pub fn render(content: &CardContent, settings: &Settings, date: NaiveDate) -> Result<Vec<u8>, AppError> {
+    let font = FontRef::try_from_slice(report::REPORT_FONT).map_err(|e| AppError::Other(e.to_string()))?;
+    let colors = palette(settings);
+    let mut pixmap = Pixmap::new(WIDTH, HEIGHT).ok_or_else(|| AppError::Other("Can't allocate the card".to_string()))?;
+    let (r, g, b) = colors.background;
+    pixmap.fill(Color::from_rgba8(r, g, b, 255));
+
+    // Accent stripe and branding along the top
+    fill_rect(&mut pixmap, 0.0, 0.0, WIDTH as f32, 12.0, colors.accent);
+    draw_text(&mut pixmap, &font, "Hydra Tracker", 34.0, PADDING, 100.0, colors.accent);
+    let date = date.format(dates::DATE_FORMAT).to_string();
+    let date_x = WIDTH as f32 - PADDING - text_width(&font, &date, 30.0);
+    draw_text(&mut pixmap, &font, &date, 30.0, date_x, 100.0, colors.muted);
+
+    draw_text(&mut pixmap, &font, &content.title, 48.0, PADDING, 220.0, colors.muted);
+    draw_text(&mut pixmap, &font, &content.value, 140.0, PADDING, 380.0, colors.text);
+    draw_text(&mut pixmap, &font, &content.detail, 40.0, PADDING, 450.0, colors.muted);
+
+    if let Some(progress) = content.progress {
+        let track_width = WIDTH as f32 - 2.0 * PADDING;
+        let y = HEIGHT as f32 - PADDING - PROGRESS_BAR_HEIGHT;
+        fill_rect(&mut pixmap, PADDING, y, track_width, PROGRESS_BAR_HEIGHT, colors.track);
+        let filled = track_width * progress.clamp(0.0, 1.0);
+        fill_rect(&mut pixmap, PADDING, y, filled, PROGRESS_BAR_HEIGHT, colors.accent);
+    }
+
+    pixmap.encode_png().map_err(|e| AppError::Other(e.to_string()))
+}
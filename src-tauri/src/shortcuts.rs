@@ -0,0 +1,158 @@
+// System-wide keyboard shortcuts: a small and a large quick add, taken
+// from the ends of the quick-add list, and one that brings up the window.
+// Adds go straight to the database, so they work with the window closed.
+//
+// Another app may already own a combination. That shortcut is then left
+// out and the failure kept for the frontend to show, since it can't have
+// been listening for an event this early.
+
+use crate::{overlay, settings, tray, AppError, Database, Settings};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Listener, Manager, Runtime};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutEvent, ShortcutState};
+
+pub const ENTRY_SOURCE: &str = "shortcut";
+pub const FAILED_EVENT: &str = "shortcut-registration-failed";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    SmallQuickAdd,
+    LargeQuickAdd,
+    ShowWindow,
+}
+
+fn bindings() -> [(Shortcut, ShortcutAction); 3] {
+    let ctrl_alt = Modifiers::CONTROL | Modifiers::ALT;
+    [
+        (Shortcut::new(Some(ctrl_alt), Code::KeyW), ShortcutAction::SmallQuickAdd),
+        (
+            Shortcut::new(Some(ctrl_alt | Modifiers::SHIFT), Code::KeyW),
+            ShortcutAction::LargeQuickAdd,
+        ),
+        (Shortcut::new(Some(ctrl_alt), Code::KeyH), ShortcutAction::ShowWindow),
+    ]
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ShortcutStatus {
+    pub action: ShortcutAction,
+    // e.g. "control+alt+KeyW"
+    pub shortcut: String,
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+// How the last registration went, one entry per binding
+#[derive(Default)]
+pub struct ShortcutRegistrations(pub Mutex<Vec<ShortcutStatus>>);
+
+pub fn plugin<R: Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri_plugin_global_shortcut::Builder::new().build()
+}
+
+fn amount_for(settings: &Settings, action: ShortcutAction) -> Option<i32> {
+    let amounts = tray::quick_add_amounts(settings);
+    match action {
+        ShortcutAction::SmallQuickAdd => amounts.iter().min().copied(),
+        ShortcutAction::LargeQuickAdd => amounts.iter().max().copied(),
+        ShortcutAction::ShowWindow => None,
+    }
+}
+
+fn run(app: &AppHandle, action: ShortcutAction) -> Result<(), AppError> {
+    if action == ShortcutAction::ShowWindow {
+        tray::show_main_window(app);
+        return Ok(());
+    }
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let settings = settings::load(&conn)?;
+    let Some(amount) = amount_for(&settings, action) else {
+        return Ok(());
+    };
+    crate::record_entry(app, &conn, amount, ENTRY_SOURCE)?;
+    overlay::confirm(app, &conn, amount);
+    Ok(())
+}
+
+fn on_shortcut(app: &AppHandle, action: ShortcutAction, event: ShortcutEvent) {
+    if event.state != ShortcutState::Pressed {
+        return;
+    }
+    crate::in_background(app, move |app| {
+        if let Err(e) = run(app, action) {
+            tracing::error!("{:?} failed: {}", action, e);
+        }
+    });
+}
+
+// Registers every binding that isn't taken, or releases them all when
+// turned off. Safe to call again; it starts from a clean slate each time.
+pub fn apply(app: &AppHandle, enabled: bool) {
+    let manager = app.global_shortcut();
+    let _ = manager.unregister_all();
+
+    let statuses: Vec<ShortcutStatus> = if enabled {
+        bindings()
+            .into_iter()
+            .map(|(shortcut, action)| {
+                let result = manager.on_shortcut(shortcut, move |app, _, event| on_shortcut(app, action, event));
+                ShortcutStatus {
+                    action,
+                    shortcut: shortcut.to_string(),
+                    registered: result.is_ok(),
+                    error: result.err().map(|e| e.to_string()),
+                }
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let failed: Vec<&ShortcutStatus> = statuses.iter().filter(|s| !s.registered).collect();
+    if !failed.is_empty() {
+        for status in &failed {
+            tracing::warn!(
+                "couldn't register {}: {}",
+                status.shortcut,
+                status.error.as_deref().unwrap_or_default()
+            );
+        }
+        let _ = app.emit(FAILED_EVENT, &failed);
+    }
+    if let Some(registrations) = app.try_state::<ShortcutRegistrations>() {
+        if let Ok(mut current) = registrations.0.lock() {
+            *current = statuses;
+        }
+    }
+}
+
+pub fn setup(app: &AppHandle) {
+    let enabled = settings::load(&app.state::<Database>().lock())
+        .ok()
+        .is_none_or(|settings| settings.global_shortcuts_enabled);
+    apply(app, enabled);
+
+    // Only re-register when the toggle flips, not on every settings save
+    let handle = app.clone();
+    let was_enabled = Mutex::new(enabled);
+    app.listen(settings::CHANGED_EVENT, move |event| {
+        let Ok(settings) = serde_json::from_str::<Settings>(event.payload()) else {
+            return;
+        };
+        let Ok(mut was_enabled) = was_enabled.lock() else {
+            return;
+        };
+        if *was_enabled != settings.global_shortcuts_enabled {
+            *was_enabled = settings.global_shortcuts_enabled;
+            apply(&handle, settings.global_shortcuts_enabled);
+        }
+    });
+}
+
+// Called on exit so the OS releases the combinations right away
+pub fn unregister_all<R: Runtime>(app: &AppHandle<R>) {
+    let _ = app.global_shortcut().unregister_all();
+}
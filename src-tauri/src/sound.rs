@@ -0,0 +1,63 @@
+// Reminder sound, played by the backend so it works while the window is
+// hidden. A custom file can be chosen; the built-in chime is synthesized,
+// so there's no bundled asset that could go missing.
+
+use crate::settings::ValidationError;
+use crate::{reminders, Settings};
+use chrono::NaiveDateTime;
+use rodio::{source::SineWave, Decoder, OutputStream, Sink, Source};
+use std::{fs::File, io::BufReader, path::Path, thread, time::Duration};
+
+// Two rising notes
+const CHIME_NOTES_HZ: [f32; 2] = [660.0, 880.0];
+const CHIME_NOTE_LENGTH: Duration = Duration::from_millis(180);
+const CHIME_VOLUME: f32 = 0.2;
+
+fn decode(path: &Path) -> Result<Decoder<BufReader<File>>, String> {
+    let file = File::open(path).map_err(|e| format!("Can't open {}: {}", path.display(), e))?;
+    Decoder::new(BufReader::new(file)).map_err(|e| format!("Can't play {}: {}", path.display(), e))
+}
+
+// The file must exist and be in a format we can decode; `field` is the
+// setting it came from
+pub fn validate_sound_file(field: &'static str, path: &str) -> Result<(), ValidationError> {
+    decode(Path::new(path)).map(|_| ()).map_err(|message| ValidationError::new(field, message))
+}
+
+fn append_chime(sink: &Sink) {
+    for hz in CHIME_NOTES_HZ {
+        sink.append(
+            SineWave::new(hz)
+                .take_duration(CHIME_NOTE_LENGTH)
+                .amplify(CHIME_VOLUME),
+        );
+    }
+}
+
+// Plays on its own thread. A custom file that has gone missing or no longer
+// decodes falls back to the chime rather than staying silent.
+pub fn play(custom_path: Option<String>) {
+    thread::spawn(move || {
+        let Ok((_stream, handle)) = OutputStream::try_default() else {
+            return;
+        };
+        let Ok(sink) = Sink::try_new(&handle) else {
+            return;
+        };
+        match custom_path.as_deref().map(|p| decode(Path::new(p))) {
+            Some(Ok(source)) => sink.append(source),
+            _ => append_chime(&sink),
+        }
+        sink.sleep_until_end();
+    });
+}
+
+pub fn play_reminder(settings: &Settings, now: NaiveDateTime, escalated: bool) {
+    if settings.sound_enabled && !reminders::in_quiet_hours(settings, reminders::minute_of_day(now)) {
+        let path = escalated
+            .then(|| settings.escalation_sound_path.clone())
+            .flatten()
+            .or_else(|| settings.reminder_sound_path.clone());
+        play(path);
+    }
+}
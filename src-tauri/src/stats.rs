@@ -0,0 +1,398 @@
+// Daily, monthly and all-time aggregates over water_entries. Everything
+// takes a Connection; "today" is the logical day under the rollover hour.
+//
+// The tray, the widget and the taskbar progress all poll today's stats, so
+// the queries here go through the connection's statement cache rather than
+// being compiled on every call. Their SQL lives in the constants below.
+
+use crate::db::{get_base_goal_ml, goal_schedule, logical_today};
+use crate::{dates, freezes, goals, locale, AllTimeStats, DailyStats, MonthlyStats, YearOverYear};
+use chrono::{Datelike, NaiveDate};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::collections::{HashMap, HashSet};
+
+// Total and entry count for the one date ?1
+pub const DAY_TOTAL_QUERY: &str = "SELECT COALESCE(SUM(amount_ml), 0), COUNT(*) FROM water_entries WHERE date = ?1";
+
+pub fn today_stats(conn: &Connection) -> SqliteResult<DailyStats> {
+    day_stats(conn, logical_today(conn))
+}
+
+// As today_stats, for any logical day
+pub fn day_stats(conn: &Connection, day: NaiveDate) -> SqliteResult<DailyStats> {
+    let goal_on = goal_schedule(conn);
+    let date = day.format("%Y-%m-%d").to_string();
+
+    let (total_ml, entries_count): (i32, i32) = conn
+        .prepare_cached(DAY_TOTAL_QUERY)?
+        .query_row([&date], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    let goal_ml = goal_on(day);
+
+    let goal_met_at = query_goal_met_times(conn, &date, &goal_on)?.remove(&date);
+
+    Ok(DailyStats::new(date, total_ml, goal_ml, entries_count, goal_met_at))
+}
+
+pub fn monthly_stats_with_streaks(conn: &Connection, year: i32, month: u32) -> SqliteResult<MonthlyStats> {
+    let goal_on = goal_schedule(conn);
+    let today = logical_today(conn);
+
+    let mut stats = build_monthly_stats(conn, year, month, &goal_on, today)?;
+
+    // Calculate streaks
+    let (current_streak, best_streak) = calculate_streaks(conn, &goal_on, today);
+    stats.current_streak = current_streak;
+    stats.best_streak = best_streak;
+
+    Ok(stats)
+}
+
+// Shared monthly aggregation behind get_monthly_stats and the comparisons.
+// Streaks are history-wide and left for the caller to fill in.
+fn build_monthly_stats(
+    conn: &Connection,
+    year: i32,
+    month: u32,
+    goal_on: &impl Fn(NaiveDate) -> i32,
+    today: NaiveDate,
+) -> SqliteResult<MonthlyStats> {
+    let month_str = format!("{:04}-{:02}", year, month);
+
+    let logged_days = query_daily_stats(conn, &month_str, goal_on)?;
+    let mut days = fill_month_days(year, month, logged_days, goal_on, today);
+    mark_frozen(&mut days, &freezes::frozen_dates(conn)?);
+
+    let month_name = locale::current(conn).month_name(month);
+
+    Ok(summarize_month(month_name, year, days, today))
+}
+
+// Per-day totals for every date starting with ?1 ("2024", "2024-06", ...).
+// The prefix is matched as a range, which unlike LIKE is a search of
+// idx_date_amount: '~' sorts after the digits and '-' that can follow it.
+pub const DAILY_TOTALS_QUERY: &str = "SELECT date, SUM(amount_ml), COUNT(*) FROM water_entries
+     WHERE date >= ?1 AND date < ?1 || '~' GROUP BY date ORDER BY date";
+
+// Each day is judged against its own goal, so a program's ramp doesn't
+// re-judge the days before it
+fn query_daily_stats(
+    conn: &Connection,
+    date_prefix: &str,
+    goal_on: &impl Fn(NaiveDate) -> i32,
+) -> SqliteResult<Vec<DailyStats>> {
+    let mut goal_met_times = query_goal_met_times(conn, date_prefix, goal_on)?;
+
+    let mut stmt = conn.prepare_cached(DAILY_TOTALS_QUERY)?;
+
+    let rows = stmt
+        .query_map([date_prefix], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?, row.get::<_, i32>(2)?))
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(date, total_ml, entries_count)| {
+            let goal_ml = goal_on(dates::parse_date(&date)?);
+            let goal_met_at = goal_met_times.remove(&date);
+            Some(DailyStats::new(date, total_ml, goal_ml, entries_count, goal_met_at))
+        })
+        .collect())
+}
+
+// Every entry of the days matching ?1 with its day's running total, in the
+// order they were drunk
+pub const GOAL_MET_TIMES_QUERY: &str = "SELECT date, timestamp,
+            SUM(amount_ml) OVER (PARTITION BY date ORDER BY timestamp, id) AS running
+     FROM water_entries WHERE date >= ?1 AND date < ?1 || '~'
+     ORDER BY date, timestamp, id";
+
+// For each day matching `date_prefix`, the timestamp at which its running
+// total first reached that day's goal. Days that never got there are absent.
+// An empty prefix covers every day.
+pub fn query_goal_met_times(
+    conn: &Connection,
+    date_prefix: &str,
+    goal_on: &impl Fn(NaiveDate) -> i32,
+) -> SqliteResult<HashMap<String, String>> {
+    let mut stmt = conn.prepare_cached(GOAL_MET_TIMES_QUERY)?;
+
+    let mut times = HashMap::new();
+    let mut rows = stmt.query([date_prefix])?;
+    while let Some(row) = rows.next()? {
+        let date: String = row.get(0)?;
+        if times.contains_key(&date) {
+            continue;
+        }
+        let running: i64 = row.get(2)?;
+        if dates::parse_date(&date).is_some_and(|day| running >= goal_on(day) as i64) {
+            times.insert(date, row.get(1)?);
+        }
+    }
+
+    Ok(times)
+}
+
+// Hours since the start of the entry's own date, so a goal finished at 01:00
+// on a day that rolls over at 03:00 counts as 25.0 rather than 1.0
+fn hours_into_day(date: &str, timestamp: &str) -> Option<f32> {
+    let day = dates::parse_date(date)?;
+    let at = dates::parse_timestamp(timestamp)?;
+    let minutes = (at - day.and_hms_opt(0, 0, 0)?).num_minutes();
+    Some(minutes as f32 / 60.0)
+}
+
+// One entry per calendar day of the month, with zero-intake days filled in.
+// Days after `today` are omitted (unless something was logged on them) so
+// averages only cover days that have actually happened.
+fn fill_month_days(
+    year: i32,
+    month: u32,
+    logged_days: Vec<DailyStats>,
+    goal_on: &impl Fn(NaiveDate) -> i32,
+    today: NaiveDate,
+) -> Vec<DailyStats> {
+    let mut logged: HashMap<String, DailyStats> = logged_days
+        .into_iter()
+        .map(|d| (d.date.clone(), d))
+        .collect();
+
+    let mut days = Vec::new();
+    let mut day = NaiveDate::from_ymd_opt(year, month, 1);
+    while let Some(date) = day.filter(|d| d.month() == month) {
+        let date_str = date.format("%Y-%m-%d").to_string();
+        match logged.remove(&date_str) {
+            Some(stats) => days.push(stats),
+            None if date <= today => days.push(DailyStats::new(date_str, 0, goal_on(date), 0, None)),
+            None => {}
+        }
+        day = dates::next_day(date);
+    }
+
+    days
+}
+
+fn mark_frozen(days: &mut [DailyStats], frozen: &HashSet<NaiveDate>) {
+    for day in days {
+        day.frozen = dates::parse_date(&day.date).is_some_and(|date| frozen.contains(&date));
+    }
+}
+
+// Month aggregates over already-queried days. Streaks are left at zero since
+// they span the whole history rather than a single month.
+fn summarize_month(month_name: &str, year: i32, days: Vec<DailyStats>, today: NaiveDate) -> MonthlyStats {
+    let total_ml: i32 = days.iter().map(|d| d.total_ml).sum();
+    // `days` also holds later days that already have entries (see
+    // fill_month_days); only the elapsed ones are compared against, so
+    // February counts 28/29 days and the current month the days so far
+    let today = today.format(dates::DATE_FORMAT).to_string();
+    let elapsed: Vec<&DailyStats> = days.iter().filter(|d| d.date <= today).collect();
+    // Averaged over every elapsed day, zero-intake days included
+    let average_ml = if elapsed.is_empty() {
+        0.0
+    } else {
+        elapsed.iter().map(|d| d.total_ml).sum::<i32>() as f32 / elapsed.len() as f32
+    };
+    let days_goal_met = days.iter().filter(|d| d.total_ml >= d.goal_ml).count() as i32;
+    let adherence_percentage = if elapsed.is_empty() {
+        0.0
+    } else {
+        elapsed.iter().filter(|d| d.total_ml >= d.goal_ml).count() as f32 / elapsed.len() as f32 * 100.0
+    };
+
+    let goal_met_hours: Vec<f32> = days
+        .iter()
+        .filter_map(|d| hours_into_day(&d.date, d.goal_met_at.as_deref()?))
+        .collect();
+    let average_goal_met_hour = if goal_met_hours.is_empty() {
+        None
+    } else {
+        Some(goal_met_hours.iter().sum::<f32>() / goal_met_hours.len() as f32)
+    };
+
+    let total_surplus_ml = days.iter().map(|d| d.surplus_ml).sum();
+    let days_over_110_percent = days.iter().filter(|d| d.percentage > 110.0).count() as i32;
+    let days_over_125_percent = days.iter().filter(|d| d.percentage > 125.0).count() as i32;
+
+    MonthlyStats {
+        month: month_name.to_string(),
+        year,
+        days,
+        total_ml,
+        average_ml,
+        days_goal_met,
+        current_streak: 0,
+        best_streak: 0,
+        average_goal_met_hour,
+        total_surplus_ml,
+        days_over_110_percent,
+        days_over_125_percent,
+        adherence_percentage,
+    }
+}
+
+pub const STREAK_TOTALS_QUERY: &str = "SELECT date, SUM(amount_ml) as total FROM water_entries
+     GROUP BY date ORDER BY date";
+
+fn streak_totals(conn: &Connection) -> SqliteResult<Vec<(String, i32)>> {
+    let mut stmt = conn.prepare_cached(STREAK_TOTALS_QUERY)?;
+    let totals = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect();
+    totals
+}
+
+pub fn calculate_streaks(conn: &Connection, goal_on: impl Fn(NaiveDate) -> i32, today: NaiveDate) -> (i32, i32) {
+    let Ok(totals) = streak_totals(conn) else {
+        return (0, 0);
+    };
+    let frozen = freezes::frozen_dates(conn).unwrap_or_default();
+
+    streaks_from_totals(&totals, &frozen, goal_on, today)
+}
+
+// Current and best run of consecutive goal-met days, walking the stored
+// dates one calendar day at a time up to `today`. Frozen days are neutral:
+// they keep a run alive without adding to it.
+pub fn streaks_from_totals(
+    totals: &[(String, i32)],
+    frozen: &HashSet<NaiveDate>,
+    goal_on: impl Fn(NaiveDate) -> i32,
+    today: NaiveDate,
+) -> (i32, i32) {
+    let totals: HashMap<NaiveDate, i32> = totals
+        .iter()
+        .filter_map(|(date, total)| Some((dates::parse_date(date)?, *total)))
+        .collect();
+
+    let Some(&first_day) = totals.keys().min() else {
+        return (0, 0);
+    };
+
+    let mut current_streak = 0;
+    let mut best_streak = 0;
+    let mut day = Some(first_day);
+    while let Some(date) = day.filter(|d| *d <= today) {
+        if totals.get(&date).is_some_and(|total| *total >= goal_on(date)) {
+            current_streak += 1;
+            best_streak = best_streak.max(current_streak);
+        } else if !frozen.contains(&date) {
+            current_streak = 0;
+        }
+        day = dates::next_day(date);
+    }
+
+    (current_streak, best_streak)
+}
+
+pub fn year_over_year(conn: &Connection, year: i32, month: u32) -> SqliteResult<YearOverYear> {
+    let goal_on = goal_schedule(conn);
+    let today = logical_today(conn);
+
+    let current = build_monthly_stats(conn, year, month, &goal_on, today)?;
+    let previous = build_monthly_stats(conn, year - 1, month, &goal_on, today)?;
+    let has_prior_data = previous.days.iter().any(|d| d.entries_count > 0);
+
+    Ok(YearOverYear {
+        total_delta_ml: has_prior_data.then_some(current.total_ml - previous.total_ml),
+        average_delta_ml: has_prior_data.then_some(current.average_ml - previous.average_ml),
+        adherence_delta: has_prior_data
+            .then_some(current.adherence_percentage - previous.adherence_percentage),
+        has_prior_data,
+        current,
+        previous,
+    })
+}
+
+// One summary per month of `year`, from a single query over the year
+pub fn yearly_overview(conn: &Connection, year: i32) -> SqliteResult<Vec<MonthlyStats>> {
+    let goal_on = goal_schedule(conn);
+
+    let year_days = query_daily_stats(conn, &format!("{:04}-", year), &goal_on)?;
+
+    // Bucket the year's days by the month part of "YYYY-MM-DD"
+    let mut by_month: Vec<Vec<DailyStats>> = vec![Vec::new(); 12];
+    for day in year_days {
+        if let Some(month) = day.date.get(5..7).and_then(|m| m.parse::<usize>().ok()) {
+            if (1..=12).contains(&month) {
+                by_month[month - 1].push(day);
+            }
+        }
+    }
+
+    let today = logical_today(conn);
+    let locale = locale::current(conn);
+    let frozen = freezes::frozen_dates(conn)?;
+
+    Ok(by_month
+        .into_iter()
+        .zip(1u32..)
+        .map(|(logged_days, month)| {
+            let mut days = fill_month_days(year, month, logged_days, &goal_on, today);
+            mark_frozen(&mut days, &frozen);
+            summarize_month(locale.month_abbreviation(month), year, days, today)
+        })
+        .collect())
+}
+
+// Days that met the goal they had, and the longest run of them. Each day's
+// goal is the program's step from its start date ?2 on (start ?3, increment
+// ?4 every ?5 days, capped at ?6), the base goal ?1 before it or without one,
+// the same as db::goal_schedule. Goal-met and frozen days share a group key
+// when consecutive (date minus row number stays constant), so the longest
+// streak is the group with the most goal-met days. Frozen days bridge gaps
+// without counting.
+pub const ALLTIME_GOAL_QUERY: &str = "WITH met AS (
+        SELECT date FROM water_entries GROUP BY date
+        HAVING SUM(amount_ml) >= CASE
+            WHEN ?2 IS NOT NULL AND date >= ?2
+            THEN MIN(?6, ?3 + CAST(julianday(date) - julianday(?2) AS INTEGER) / ?5 * ?4)
+            ELSE ?1 END
+     ),
+     days AS (
+        SELECT date, 1 AS met FROM met
+        UNION ALL
+        SELECT date, 0 FROM streak_freezes WHERE date NOT IN (SELECT date FROM met)
+     ),
+     islands AS (
+        SELECT met, julianday(date) - ROW_NUMBER() OVER (ORDER BY date) AS grp FROM days
+     )
+     SELECT (SELECT COUNT(*) FROM met),
+            COALESCE((SELECT MAX(n) FROM (SELECT SUM(met) AS n FROM islands GROUP BY grp)), 0)";
+
+pub fn alltime_stats(conn: &Connection) -> SqliteResult<AllTimeStats> {
+    let (total_ml, total_entries, first_date, days_logged): (i64, i64, Option<String>, i64) = conn.query_row(
+        "SELECT COALESCE(SUM(amount_ml), 0), COUNT(*), MIN(date), COUNT(DISTINCT date)
+         FROM water_entries",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+
+    let program = goals::load_program(conn)?;
+    let (days_goal_met, longest_streak): (i64, i32) = conn.query_row(
+        ALLTIME_GOAL_QUERY,
+        params![
+            get_base_goal_ml(conn),
+            program.as_ref().map(|p| p.start_date.format(dates::DATE_FORMAT).to_string()),
+            program.as_ref().map(|p| p.start_ml),
+            program.as_ref().map(|p| p.increment_ml),
+            program.as_ref().map(|p| p.interval_days),
+            program.as_ref().map(|p| p.target_ml),
+        ],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    if days_logged == 0 {
+        return Ok(AllTimeStats::default());
+    }
+
+    Ok(AllTimeStats {
+        total_ml,
+        total_entries,
+        first_date,
+        days_logged,
+        average_ml_per_day: total_ml as f32 / days_logged as f32,
+        days_goal_met,
+        goal_met_rate: days_goal_met as f32 / days_logged as f32 * 100.0,
+        longest_streak,
+    })
+}
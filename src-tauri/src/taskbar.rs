@@ -0,0 +1,74 @@
+// Today's progress on the Windows taskbar button. The bar fills with the
+// percentage, switches to the paused (yellow) state once the goal is met,
+// and is hidden when nothing has been logged yet, which includes just after
+// the rollover. Other platforms have no per-window bar, so this does nothing
+// there.
+//
+// Windows drops the bar when a window is hidden, so the last value is kept
+// and shown again along with the window.
+
+#![cfg_attr(not(windows), allow(dead_code))]
+
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskbarProgress {
+    percent: u64,
+    goal_met: bool,
+}
+
+impl TaskbarProgress {
+    pub fn new(percentage: f32) -> Self {
+        Self {
+            percent: percentage.clamp(0.0, 100.0).floor() as u64,
+            goal_met: percentage >= 100.0,
+        }
+    }
+}
+
+// The value most recently set, for `reapply`
+#[derive(Default)]
+pub struct LastProgress(Mutex<Option<TaskbarProgress>>);
+
+#[cfg(windows)]
+fn apply(app: &AppHandle, progress: TaskbarProgress) {
+    use tauri::window::{ProgressBarState, ProgressBarStatus};
+
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let status = if progress.goal_met {
+        ProgressBarStatus::Paused
+    } else if progress.percent == 0 {
+        ProgressBarStatus::None
+    } else {
+        ProgressBarStatus::Normal
+    };
+    let _ = window.set_progress_bar(ProgressBarState {
+        status: Some(status),
+        progress: Some(progress.percent),
+    });
+}
+
+#[cfg(not(windows))]
+fn apply(_app: &AppHandle, _progress: TaskbarProgress) {}
+
+pub fn show(app: &AppHandle, progress: TaskbarProgress) {
+    if let Some(last) = app.try_state::<LastProgress>() {
+        if let Ok(mut last) = last.0.lock() {
+            *last = Some(progress);
+        }
+    }
+    apply(app, progress);
+}
+
+// After the window was shown or recreated
+pub fn reapply(app: &AppHandle) {
+    let last = app
+        .try_state::<LastProgress>()
+        .and_then(|last| last.0.lock().ok().and_then(|last| *last));
+    if let Some(progress) = last {
+        apply(app, progress);
+    }
+}
@@ -0,0 +1,237 @@
+// Importing free-form notes pasted from elsewhere, e.g.
+// "9:30 300ml, 11:00 coffee 250, 14:15 500". Entries are separated by new
+// lines, semicolons, or a comma followed by a space; each needs exactly one
+// time and one amount, and any other words (drink names and the like) are
+// ignored. Parsing only previews; the entries the user keeps are committed
+// separately through `csv_import::insert_rows`.
+
+use crate::csv_import::{self, ParsedRow, RowError};
+use crate::dates;
+use crate::units::{self, Unit};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+pub const ENTRY_SOURCE: &str = "paste";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TextEntry {
+    // 1-based line of the pasted text
+    pub line: usize,
+    pub text: String,
+    pub timestamp: String,
+    pub amount_ml: i32,
+    // More than max_entry_ml; most likely a typo or a misread unit
+    #[serde(default)]
+    pub over_max_entry: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RejectedText {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct TextImportPreview {
+    pub entries: Vec<TextEntry>,
+    pub rejected: Vec<RejectedText>,
+}
+
+// Splits on ", " but not on "1,500"
+fn segments(line: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let comma_break = c == ',' && chars.peek().is_some_and(|(_, next)| next.is_whitespace());
+        if c == ';' || comma_break {
+            segments.push(&line[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    segments.push(&line[start..]);
+    segments.into_iter().map(str::trim).filter(|s| !s.is_empty()).collect()
+}
+
+fn meridiem(word: &str) -> Option<bool> {
+    match word.replace('.', "").as_str() {
+        "am" => Some(false),
+        "pm" => Some(true),
+        _ => None,
+    }
+}
+
+// "9:30", "14h15", "9:30pm", "9 am". None when `word` doesn't look like a
+// time at all; an error when it does but isn't a valid one.
+fn parse_time(word: &str, next: Option<&str>) -> Option<Result<(NaiveTime, usize), String>> {
+    let (core, pm, used) = match ["am", "pm", "a.m.", "p.m."]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix).map(|core| (core, meridiem(suffix))))
+    {
+        Some((core, pm)) => (core, pm, 1),
+        None => match next.and_then(meridiem) {
+            Some(pm) => (word, Some(pm), 2),
+            None => (word, None, 1),
+        },
+    };
+    if core.is_empty() || !core.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (hour, minute) = match core.split_once([':', 'h']) {
+        Some((hour, "")) => (hour, "0"),
+        Some((hour, minute)) => (hour, minute),
+        // A bare number is only a time with am/pm after it
+        None if pm.is_some() => (core, "0"),
+        None => return None,
+    };
+    let invalid = || format!("'{}' is not a valid time", word);
+    let (Ok(mut hour), Ok(minute)) = (hour.parse::<u32>(), minute.parse::<u32>()) else {
+        return Some(Err(invalid()));
+    };
+    if let Some(pm) = pm {
+        if !(1..=12).contains(&hour) {
+            return Some(Err(invalid()));
+        }
+        hour = hour % 12 + if pm { 12 } else { 0 };
+    }
+    Some(NaiveTime::from_hms_opt(hour, minute, 0).map(|time| (time, used)).ok_or_else(invalid))
+}
+
+// "1,500" is a thousands separator; "0,5" a decimal comma
+fn parse_number(digits: &str) -> Option<f64> {
+    let groups: Vec<&str> = digits.split(',').collect();
+    let thousands = groups.len() > 1
+        && (1..=3).contains(&groups[0].len())
+        && groups[1..].iter().all(|g| g.len() == 3 && !g.contains('.'));
+    let normalized = if thousands { digits.replace(',', "") } else { digits.replace(',', ".") };
+    normalized.parse().ok()
+}
+
+// "300", "300ml", "0.5 l", "12 fl oz", "2 cups". A bare number is millilitres.
+fn parse_amount(word: &str, rest: &[&str]) -> Option<Result<(i32, usize), String>> {
+    let split = word
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(word.len());
+    let (digits, suffix) = word.split_at(split);
+    if digits.is_empty() || !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let Some(value) = parse_number(digits) else {
+        return Some(Err(format!("'{}' is not a number", digits)));
+    };
+
+    let label = |n: usize| (n <= rest.len()).then(|| rest[..n].join(" ")).and_then(|l| Unit::from_label(&l));
+    let (unit, used) = if suffix.is_empty() {
+        if let Some(unit) = label(2) {
+            (unit, 3)
+        } else if let Some(unit) = label(1) {
+            (unit, 2)
+        } else if value.fract() != 0.0 {
+            // "1.5" on its own is far more likely litres than 1.5 ml
+            return Some(Err(format!("'{}' needs a unit", word)));
+        } else {
+            (Unit::Ml, 1)
+        }
+    } else if let Some(unit) = Unit::from_label(suffix) {
+        (unit, 1)
+    } else if let Some(unit) = rest.first().and_then(|next| Unit::from_label(&format!("{} {}", suffix, next))) {
+        (unit, 2)
+    } else {
+        return Some(Err(format!("'{}' is not a unit", suffix)));
+    };
+
+    let ml = units::convert(value, unit, Unit::Ml);
+    if !(1.0..=i32::MAX as f64).contains(&ml) {
+        return Some(Err(format!("'{}' is not a positive amount", word)));
+    }
+    Some(Ok((ml as i32, used)))
+}
+
+fn parse_segment(segment: &str) -> Result<(NaiveTime, i32), String> {
+    let lowered = segment.to_lowercase();
+    let words: Vec<&str> = lowered
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c| matches!(c, ',' | '!' | '?' | '(' | ')' | '"')))
+        .filter(|word| !word.is_empty())
+        .collect();
+    let (mut times, mut amounts) = (Vec::new(), Vec::new());
+
+    let mut i = 0;
+    while i < words.len() {
+        let (word, next) = (words[i], words.get(i + 1).copied());
+        if let Some(time) = parse_time(word, next) {
+            let (time, used) = time?;
+            times.push(time);
+            i += used;
+        } else if let Some(amount) = parse_amount(word, &words[i + 1..]) {
+            let (ml, used) = amount?;
+            amounts.push(ml);
+            i += used;
+        } else {
+            i += 1;
+        }
+    }
+
+    match (times.as_slice(), amounts.as_slice()) {
+        ([], _) => Err("no time found".to_string()),
+        (_, []) => Err("no amount found".to_string()),
+        ([_, _, ..], _) => Err("more than one time; put each entry on its own line".to_string()),
+        (_, [_, _, ..]) => Err("more than one amount; put each entry on its own line".to_string()),
+        ([time], [ml]) => Ok((*time, *ml)),
+    }
+}
+
+// Times belong to the logical day `date`, so with a 03:00 rollover "1:30"
+// is the early hours of the following calendar day
+fn timestamp_on(date: NaiveDate, time: NaiveTime, day_starts_at_hour: i32) -> NaiveDateTime {
+    let day = if (time.hour() as i32) < day_starts_at_hour {
+        date + Duration::days(1)
+    } else {
+        date
+    };
+    day.and_time(time)
+}
+
+pub fn parse(text: &str, date: NaiveDate, day_starts_at_hour: i32, max_entry_ml: i32) -> TextImportPreview {
+    let mut preview = TextImportPreview::default();
+    for (index, line) in text.lines().enumerate() {
+        for segment in segments(line) {
+            match parse_segment(segment) {
+                Ok((time, amount_ml)) => preview.entries.push(TextEntry {
+                    line: index + 1,
+                    text: segment.to_string(),
+                    timestamp: timestamp_on(date, time, day_starts_at_hour)
+                        .format(dates::TIMESTAMP_FORMAT)
+                        .to_string(),
+                    amount_ml,
+                    over_max_entry: amount_ml > max_entry_ml,
+                }),
+                Err(reason) => preview.rejected.push(RejectedText {
+                    line: index + 1,
+                    text: segment.to_string(),
+                    reason,
+                }),
+            }
+        }
+    }
+    preview
+}
+
+// The previewed entries the user kept, checked again since they come back
+// from the webview
+pub fn rows(entries: &[TextEntry]) -> Vec<ParsedRow> {
+    entries
+        .iter()
+        .map(|entry| {
+            let row = dates::parse_timestamp(&entry.timestamp)
+                .ok_or_else(|| RowError::bad_timestamp(&entry.timestamp))
+                .and_then(|at| {
+                    let value = entry.amount_ml.to_string();
+                    Ok((at, csv_import::amount_in_range(entry.amount_ml as f64, &value)?))
+                });
+            (entry.line, row)
+        })
+        .collect()
+}
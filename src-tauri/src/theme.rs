@@ -0,0 +1,82 @@
+// Resolves the "auto" theme against the OS appearance. The OS theme is read
+// from the main window, and a change (e.g. a scheduled switch at sunset) is
+// re-broadcast as `theme-resolved` without needing a restart.
+//
+// The tray icon follows the OS rather than the app's own theme, since it
+// sits on the OS's panel.
+
+use crate::{settings, tray, Database, Settings};
+use tauri::{AppHandle, Emitter, Manager, Theme};
+
+pub const RESOLVED_EVENT: &str = "theme-resolved";
+pub const AUTO: &str = "auto";
+
+fn os_theme(app: &AppHandle) -> Option<Theme> {
+    app.get_webview_window("main")?.theme().ok()
+}
+
+// The concrete theme to render: the configured one, or the OS theme for
+// "auto" (dark when the OS doesn't say)
+pub fn resolve(configured: &str, os: Option<Theme>) -> String {
+    if configured != AUTO {
+        return configured.to_string();
+    }
+    match os {
+        Some(Theme::Light) => "light".to_string(),
+        _ => "dark".to_string(),
+    }
+}
+
+pub fn fill_resolved(app: &AppHandle, settings: &mut Settings) {
+    settings.resolved_theme = resolve(&settings.theme, os_theme(app));
+}
+
+// Windows themes the taskbar separately from apps, in the registry; a
+// change there alone isn't reported, so the tray catches up on its next
+// periodic refresh
+#[cfg(windows)]
+fn taskbar_is_light() -> Option<bool> {
+    use windows::core::w;
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let mut value = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("SystemUsesLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut u32 as *mut std::ffi::c_void),
+            Some(&mut size),
+        )
+    };
+    (status == ERROR_SUCCESS).then_some(value != 0)
+}
+
+// Whether the panel the tray icon sits on is dark (assumed when unknown)
+pub fn tray_is_dark(app: &AppHandle) -> bool {
+    #[cfg(windows)]
+    if let Some(light) = taskbar_is_light() {
+        return !light;
+    }
+    !matches!(os_theme(app), Some(Theme::Light))
+}
+
+pub fn on_os_theme_changed(app: &AppHandle, os: Theme) {
+    tray::refresh(app);
+    let db = app.state::<Database>();
+    let configured = settings::load(&db.lock()).map(|s| s.theme).unwrap_or_default();
+    if configured == AUTO {
+        let _ = app.emit(RESOLVED_EVENT, resolve(&configured, Some(os)));
+    }
+}
+
+// "#4ade80" or the short "#4a8" form
+pub fn is_hex_color(value: &str) -> bool {
+    value
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
@@ -0,0 +1,443 @@
+// System tray icon and menu. The quick-add items come from settings, so the
+// menu is rebuilt whenever those change rather than only at startup.
+//
+// The tooltip, the icon's fill, a disabled first menu item, and the taskbar
+// button on Windows, the dock badge and optional menu bar text on macOS, or
+// the launcher entry on Linux docks show today's progress. A background thread recomputes them when asked to
+// after a change, including the day rolling over (see `rollover`).
+// Requests go through a channel rather than locking the database from an
+// event listener, since some events are emitted while the lock is held.
+
+use crate::launcher::{self, LauncherProgress};
+use crate::taskbar::{self, TaskbarProgress};
+use crate::tray_icon::{self, IconState, IconStyle};
+use crate::units::{self, Unit};
+use crate::{
+    dock, locale, notifications, overlay, reminders, scheduler, settings, theme, widget, window_state, AppError,
+    DailyStats, Database, Settings,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::{thread, time::Duration};
+use tauri::{
+    menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem},
+    tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Listener, Manager, WebviewWindow, WebviewWindowBuilder, Wry,
+};
+
+pub const TRAY_ID: &str = "main";
+pub const ENTRY_SOURCE: &str = "tray";
+const QUICK_ADD_PREFIX: &str = "quick_add_";
+pub const DEFAULT_QUICK_ADD_AMOUNTS: [i32; 2] = [250, 500];
+// Widest the menu bar title gets, so it can't crowd out other status items
+#[cfg(target_os = "macos")]
+const MAX_TITLE_CHARS: usize = 8;
+// How long a left click that adds water waits in case it's the first half of
+// a double-click. Only Windows reports double-clicks.
+const DOUBLE_CLICK_WAIT: Duration = Duration::from_millis(400);
+
+// Text next to the tray icon in the macOS menu bar
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuBarTitle {
+    #[default]
+    Off,
+    // "62%"
+    Percentage,
+    // "1.2L" left, or a check mark once the goal is met
+    Remaining,
+}
+
+// What left-clicking the tray icon does. Double-clicking, where the platform
+// reports it, always shows the window.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LeftClickAction {
+    #[default]
+    ShowWindow,
+    // Logs the first quick-add amount, with a notification to confirm it
+    QuickAddDefault,
+    // Hides the window if it's showing
+    ToggleWindow,
+}
+
+// Left clicks on their way to becoming either a click or a double-click
+#[derive(Default)]
+struct PendingClicks {
+    // Bumped by every click, so a deferred one can tell it was superseded
+    latest: AtomicU64,
+    // The button release that ends a double-click isn't a click of its own
+    swallow_next: AtomicBool,
+}
+
+// Kept in managed state so what the tray shows can change after setup
+pub struct TrayHandle {
+    pub tray: TrayIcon<Wry>,
+    refresh: Mutex<Sender<()>>,
+    // Replaced along with the menu whenever that's rebuilt
+    items: Mutex<MenuItems>,
+}
+
+// The items that change after the menu is built
+struct MenuItems {
+    today: MenuItem<Wry>,
+    widget: CheckMenuItem<Wry>,
+}
+
+// Configured quick-add amounts, or the defaults when none are configured
+pub fn quick_add_amounts(settings: &Settings) -> Vec<i32> {
+    if settings.quick_add_amounts.is_empty() {
+        DEFAULT_QUICK_ADD_AMOUNTS.to_vec()
+    } else {
+        settings.quick_add_amounts.clone()
+    }
+}
+
+fn current_settings(app: &AppHandle) -> Settings {
+    let db = app.state::<Database>();
+    let settings = settings::load(&db.lock()).unwrap_or_default();
+    settings
+}
+
+// The menu, with its "Today: ..." item starting out as `today_text`
+fn build_menu(app: &AppHandle, settings: &Settings, today_text: &str) -> tauri::Result<(Menu<Wry>, MenuItems)> {
+    let today = MenuItem::with_id(app, "today", today_text, false, None::<&str>)?;
+    let show = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let widget = CheckMenuItem::with_id(app, "widget", "Widget", true, widget::is_visible(app), None::<&str>)?;
+    let quick_adds = quick_add_amounts(settings)
+        .into_iter()
+        .map(|ml| {
+            let label = format!("Quick Add {}", units::format_amount(ml, settings.unit));
+            MenuItem::with_id(app, format!("{}{}", QUICK_ADD_PREFIX, ml), label, true, None::<&str>)
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let snooze = MenuItem::with_id(
+        app,
+        "snooze",
+        format!("Snooze {}m", reminders::QUICK_SNOOZE_MINUTES),
+        true,
+        None::<&str>,
+    )?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let mut items: Vec<&dyn IsMenuItem<Wry>> = vec![&today, &show, &widget];
+    items.extend(quick_adds.iter().map(|item| item as &dyn IsMenuItem<Wry>));
+    items.push(&snooze);
+    items.push(&quit);
+
+    let menu = Menu::with_items(app, &items)?;
+    Ok((menu, MenuItems { today, widget }))
+}
+
+// Replace the tray menu after the quick-add amounts or unit changed. The
+// today item keeps its text until the refresh that follows.
+pub fn rebuild_menu(app: &AppHandle, settings: &Settings) -> tauri::Result<()> {
+    let Some(handle) = app.try_state::<TrayHandle>() else {
+        return Ok(());
+    };
+    let Ok(mut items) = handle.items.lock() else {
+        return Ok(());
+    };
+    let (menu, new_items) = build_menu(app, settings, &items.today.text()?)?;
+    handle.tray.set_menu(Some(menu))?;
+    *items = new_items;
+    Ok(())
+}
+
+// Keeps the Widget item in step when the widget is shown or hidden elsewhere
+pub fn set_widget_checked(app: &AppHandle, checked: bool) {
+    if let Some(handle) = app.try_state::<TrayHandle>() {
+        if let Ok(items) = handle.items.lock() {
+            let _ = items.widget.set_checked(checked);
+        }
+    }
+}
+
+// Builds the main window again from its config entry if it was destroyed
+fn recreate_main_window(app: &AppHandle) -> Option<WebviewWindow> {
+    let config = app.config().app.windows.first()?.clone();
+    let window = WebviewWindowBuilder::from_config(app, &config).ok()?.build().ok()?;
+    window_state::restore(app, &window);
+    Some(window)
+}
+
+fn toggle_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false) {
+            window_state::save(app, &window);
+            let _ = window.hide();
+            return;
+        }
+    }
+    show_main_window(app);
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    let window = app
+        .get_webview_window("main")
+        .or_else(|| recreate_main_window(app));
+    if let Some(window) = window {
+        let _ = window.show();
+        let _ = window.set_focus();
+        taskbar::reapply(app);
+    }
+}
+
+// "2,250 / 4,000 ml (56%)"; rounded down so it only reads 100% once the goal is met
+pub fn progress_text(stats: &DailyStats, unit: Unit) -> String {
+    format!(
+        "{} / {} ({:.0}%)",
+        units::format_number(stats.total_ml, unit),
+        units::format_amount(stats.goal_ml, unit),
+        stats.percentage.floor()
+    )
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TodayDisplay {
+    tooltip: String,
+    // "Today: 1,750 ml (44%)"
+    menu_text: String,
+    icon: IconState,
+    taskbar: TaskbarProgress,
+    launcher: LauncherProgress,
+    badge: Option<String>,
+    #[cfg(target_os = "macos")]
+    title: Option<String>,
+}
+
+#[cfg(target_os = "macos")]
+fn menu_bar_title(stats: &DailyStats, settings: &Settings) -> Option<String> {
+    let title = match settings.menu_bar_title {
+        MenuBarTitle::Off => return None,
+        MenuBarTitle::Percentage => format!("{:.0}%", stats.percentage.floor()),
+        MenuBarTitle::Remaining if stats.total_ml >= stats.goal_ml => "✓".to_string(),
+        MenuBarTitle::Remaining => units::format_compact(stats.goal_ml - stats.total_ml, settings.unit),
+    };
+    Some(title.chars().take(MAX_TITLE_CHARS).collect())
+}
+
+// From the same numbers as get_today_stats
+fn today_display(app: &AppHandle) -> Option<TodayDisplay> {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let stats = crate::today_stats(&conn).ok()?;
+    let settings = settings::load(&conn).ok()?;
+    let locale = locale::current(&conn);
+    drop(conn);
+    Some(TodayDisplay {
+        tooltip: progress_text(&stats, settings.unit),
+        menu_text: format!(
+            "{}: {} ({:.0}%)",
+            locale.today(),
+            units::format_amount(stats.total_ml, settings.unit),
+            stats.percentage.floor()
+        ),
+        // Asks the window, so not while holding the database
+        icon: IconState::new(
+            stats.percentage,
+            theme::tray_is_dark(app),
+            IconStyle::new(settings.tray_icon_monochrome),
+        ),
+        taskbar: TaskbarProgress::new(stats.percentage),
+        launcher: LauncherProgress::new(&stats),
+        badge: dock::badge_text(&stats, settings.unit),
+        #[cfg(target_os = "macos")]
+        title: menu_bar_title(&stats, &settings),
+    })
+}
+
+// Only touches the tray for what actually changed
+fn show_today(app: &AppHandle, shown: &mut Option<TodayDisplay>) {
+    let Some(today) = today_display(app) else {
+        return;
+    };
+    let Some(handle) = app.try_state::<TrayHandle>() else {
+        return;
+    };
+    if shown.as_ref().map(|s| &s.tooltip) != Some(&today.tooltip) {
+        let _ = handle.tray.set_tooltip(Some(&today.tooltip));
+    }
+    if shown.as_ref().map(|s| &s.menu_text) != Some(&today.menu_text) {
+        if let Ok(items) = handle.items.lock() {
+            let _ = items.today.set_text(&today.menu_text);
+        }
+    }
+    if shown.as_ref().map(|s| s.icon) != Some(today.icon) {
+        if let Some(icon) = tray_icon::render(today.icon) {
+            let _ = handle.tray.set_icon_as_template(today.icon.is_template());
+            let _ = handle.tray.set_icon(Some(icon));
+        }
+    }
+    if shown.as_ref().map(|s| s.taskbar) != Some(today.taskbar) {
+        taskbar::show(app, today.taskbar);
+    }
+    if shown.as_ref().map(|s| s.launcher) != Some(today.launcher) {
+        launcher::show(app, today.launcher);
+    }
+    if shown.as_ref().map(|s| &s.badge) != Some(&today.badge) {
+        dock::set_badge(app, today.badge.clone());
+    }
+    #[cfg(target_os = "macos")]
+    if shown.as_ref().map(|s| &s.title) != Some(&today.title) {
+        let _ = handle.tray.set_title(today.title.as_deref());
+    }
+    *shown = Some(today);
+}
+
+// Recompute what the tray shows; cheap, and safe to call with the database locked
+pub fn refresh(app: &AppHandle) {
+    if let Some(handle) = app.try_state::<TrayHandle>() {
+        if let Ok(sender) = handle.refresh.lock() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+fn start_refresh(app: AppHandle, receiver: Receiver<()>) {
+    thread::spawn(move || {
+        let mut shown = None;
+        loop {
+            show_today(&app, &mut shown);
+            if receiver.recv().is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// Stored here rather than handed to the window, which may not be loaded or
+// may not exist; an open window refreshes from entries-changed
+// Returns whether the overlay confirmed it
+fn quick_add(app: &AppHandle, amount_ml: i32) -> Result<bool, AppError> {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    crate::record_entry(app, &conn, amount_ml, ENTRY_SOURCE)?;
+    Ok(overlay::confirm(app, &conn, amount_ml))
+}
+
+fn quick_add_default(app: &AppHandle) {
+    let settings = current_settings(app);
+    let Some(&amount) = quick_add_amounts(&settings).first() else {
+        return;
+    };
+    match quick_add(app, amount) {
+        Ok(true) => {}
+        Ok(false) => notifications::show_logged(app, &format!("Logged {}.", units::format_amount(amount, settings.unit))),
+        Err(e) => tracing::error!("quick add failed: {}", e),
+    }
+}
+
+// Settings are read on every click, so a change applies straight away
+fn left_click(app: &AppHandle, clicks: &Arc<PendingClicks>) {
+    if clicks.swallow_next.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    // Counted here rather than once the settings are read, so a double-click
+    // arriving meanwhile still supersedes it
+    let click = clicks.latest.fetch_add(1, Ordering::SeqCst) + 1;
+    let clicks = clicks.clone();
+    crate::in_background(app, move |app| match current_settings(app).tray_left_click {
+        LeftClickAction::ShowWindow => show_main_window(app),
+        LeftClickAction::ToggleWindow => toggle_main_window(app),
+        LeftClickAction::QuickAddDefault => {
+            if cfg!(windows) {
+                thread::sleep(DOUBLE_CLICK_WAIT);
+            }
+            if clicks.latest.load(Ordering::SeqCst) == click {
+                quick_add_default(app);
+            }
+        }
+    });
+}
+
+fn double_click(app: &AppHandle, clicks: &PendingClicks) {
+    // Cancels the water the first click would have added
+    clicks.latest.fetch_add(1, Ordering::SeqCst);
+    clicks.swallow_next.store(true, Ordering::SeqCst);
+    show_main_window(app);
+}
+
+pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let today = today_display(app);
+    let (menu, items) = build_menu(
+        app,
+        &current_settings(app),
+        today.as_ref().map_or("", |today| today.menu_text.as_str()),
+    )?;
+
+    let clicks = Arc::new(PendingClicks::default());
+    let tray = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(app.default_window_icon().unwrap().clone())
+        .tooltip(today.map(|today| today.tooltip).unwrap_or_default())
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "show" => show_main_window(app),
+            "widget" => {
+                // Not from the event handler, which it may need to build a window from
+                let app = app.clone();
+                thread::spawn(move || {
+                    if let Err(e) = widget::toggle(&app) {
+                        tracing::warn!("couldn't toggle the widget: {}", e);
+                    }
+                });
+            }
+            "snooze" => crate::in_background(app, |app| {
+                let _ = scheduler::snooze(app, reminders::QUICK_SNOOZE_MINUTES);
+            }),
+            "quit" => {
+                app.exit(0);
+            }
+            id => {
+                // Quick-add ids carry their amount, e.g. "quick_add_330"
+                let amount = id
+                    .strip_prefix(QUICK_ADD_PREFIX)
+                    .and_then(|ml| ml.parse::<i32>().ok());
+                if let Some(amount) = amount {
+                    crate::in_background(app, move |app| {
+                        if let Err(e) = quick_add(app, amount) {
+                            tracing::error!("quick add failed: {}", e);
+                        }
+                    });
+                }
+            }
+        })
+        .on_tray_icon_event(move |tray, event| match event {
+            TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } => left_click(tray.app_handle(), &clicks),
+            TrayIconEvent::DoubleClick {
+                button: MouseButton::Left,
+                ..
+            } => double_click(tray.app_handle(), &clicks),
+            _ => {}
+        })
+        .build(app)?;
+
+    let (sender, receiver) = mpsc::channel();
+    app.manage(TrayHandle {
+        tray,
+        refresh: Mutex::new(sender),
+        items: Mutex::new(items),
+    });
+    start_refresh(app.clone(), receiver);
+
+    // Quick-add amounts and the unit shown in labels can change at any time,
+    // and the goal with them
+    let handle = app.clone();
+    app.listen(settings::CHANGED_EVENT, move |event| {
+        if let Ok(settings) = serde_json::from_str::<Settings>(event.payload()) {
+            let _ = rebuild_menu(&handle, &settings);
+        }
+        refresh(&handle);
+    });
+    let handle = app.clone();
+    app.listen(notifications::ENTRIES_CHANGED_EVENT, move |_| refresh(&handle));
+
+    Ok(())
+}
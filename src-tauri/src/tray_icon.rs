@@ -0,0 +1,209 @@
+// The tray icon, drawn at runtime as a droplet filled to today's
+// percentage. The fill moves in 5% steps and the tray only redraws when the
+// step, completion, style or theme changes, so adding a sip doesn't cost a
+// render. Once the goal is met the colored droplet turns green and gets a
+// check mark; the monochrome ones fill up with the check mark cut out.
+//
+// Everything that decides the icon goes through `IconState` and `render`,
+// so the fill level and the tray's theme never set the icon separately.
+
+use tauri::image::Image;
+use tiny_skia::{BlendMode, FillRule, LineCap, Mask, Paint, Path, PathBuilder, Pixmap, Stroke, Transform};
+
+const SIZE: u32 = 64;
+const FILL_STEPS: f32 = 20.0;
+// The droplet: a circle with a point above it, and the inner body inset
+// from that outline by scaling about its middle
+const APEX_Y: f32 = 2.0;
+const CENTER_X: f32 = 32.0;
+const CENTER_Y: f32 = 40.0;
+const RADIUS: f32 = 22.0;
+const BODY_SCALE: f32 = 0.8;
+const BODY_PIVOT_Y: f32 = 38.0;
+
+type Rgb = (u8, u8, u8);
+
+const WATER: Rgb = (0x60, 0xa5, 0xfa);
+const COMPLETE: Rgb = (0x4a, 0xde, 0x80);
+const CHECK: Rgb = (0xff, 0xff, 0xff);
+// macOS only looks at the alpha of a template image
+const TEMPLATE: Rgb = (0x00, 0x00, 0x00);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconStyle {
+    // Blue water in a body that stands out on the tray
+    Color,
+    // A single color that contrasts with the tray, with a see-through body
+    Monochrome,
+    // Monochrome for the macOS menu bar, which recolors it to match
+    Template,
+}
+
+impl IconStyle {
+    pub fn new(monochrome: bool) -> Self {
+        match monochrome {
+            false => IconStyle::Color,
+            true if cfg!(target_os = "macos") => IconStyle::Template,
+            true => IconStyle::Monochrome,
+        }
+    }
+}
+
+// What the icon depends on; equal states draw identical icons
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IconState {
+    step: u8,
+    complete: bool,
+    // Whether the tray it sits on is dark
+    dark: bool,
+    style: IconStyle,
+}
+
+impl IconState {
+    pub fn new(percentage: f32, dark: bool, style: IconStyle) -> Self {
+        Self {
+            step: (percentage.clamp(0.0, 100.0) / 100.0 * FILL_STEPS).floor() as u8,
+            complete: percentage >= 100.0,
+            // A template looks the same on any tray, so needn't be redrawn
+            dark: dark && style != IconStyle::Template,
+            style,
+        }
+    }
+
+    pub fn is_template(&self) -> bool {
+        self.style == IconStyle::Template
+    }
+}
+
+// None where the icon is left see-through
+struct Colors {
+    outline: Rgb,
+    empty: Option<Rgb>,
+    water: Rgb,
+    check: Option<Rgb>,
+}
+
+// Outline and empty body, picked to stand out on a dark or a light panel
+fn palette(dark: bool) -> (Rgb, Rgb) {
+    if dark {
+        ((0xe2, 0xe8, 0xf0), (0x1e, 0x29, 0x3b))
+    } else {
+        ((0x1e, 0x29, 0x3b), (0xf1, 0xf5, 0xf9))
+    }
+}
+
+fn colors(state: IconState) -> Colors {
+    let (outline, empty) = palette(state.dark);
+    let ink = match state.style {
+        IconStyle::Color => {
+            return Colors {
+                outline,
+                empty: Some(empty),
+                water: if state.complete { COMPLETE } else { WATER },
+                check: Some(CHECK),
+            }
+        }
+        IconStyle::Monochrome => outline,
+        IconStyle::Template => TEMPLATE,
+    };
+    Colors {
+        outline: ink,
+        empty: None,
+        water: ink,
+        check: None,
+    }
+}
+
+fn paint((r, g, b): Rgb) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(r, g, b, 255);
+    paint.anti_alias = true;
+    paint
+}
+
+// Paints `color`, or erases what's there for None
+fn paint_or_clear(color: Option<Rgb>) -> Paint<'static> {
+    match color {
+        Some(color) => paint(color),
+        None => {
+            let mut paint = paint(TEMPLATE);
+            paint.blend_mode = BlendMode::Clear;
+            paint
+        }
+    }
+}
+
+// The point, as a triangle reaching the circle's tangents, and the circle
+fn droplet() -> Vec<Path> {
+    let sin = RADIUS / (CENTER_Y - APEX_Y);
+    let cos = (1.0 - sin * sin).sqrt();
+    let mut point = PathBuilder::new();
+    point.move_to(CENTER_X, APEX_Y);
+    point.line_to(CENTER_X + RADIUS * cos, CENTER_Y - RADIUS * sin);
+    point.line_to(CENTER_X - RADIUS * cos, CENTER_Y - RADIUS * sin);
+    point.close();
+    [point.finish(), PathBuilder::from_circle(CENTER_X, CENTER_Y, RADIUS)]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn body_transform() -> Transform {
+    Transform::from_translate(-CENTER_X, -BODY_PIVOT_Y)
+        .post_scale(BODY_SCALE, BODY_SCALE)
+        .post_translate(CENTER_X, BODY_PIVOT_Y)
+}
+
+// Straight (not premultiplied) RGBA, as the tray expects
+fn to_image(pixmap: &Pixmap) -> Image<'static> {
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let c = pixel.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    Image::new_owned(rgba, SIZE, SIZE)
+}
+
+pub fn render(state: IconState) -> Option<Image<'static>> {
+    let mut pixmap = Pixmap::new(SIZE, SIZE)?;
+    let colors = colors(state);
+    let shape = droplet();
+    let body = body_transform();
+
+    for path in &shape {
+        pixmap.fill_path(path, &paint(colors.outline), FillRule::Winding, Transform::identity(), None);
+    }
+    let mut mask = Mask::new(SIZE, SIZE)?;
+    for path in &shape {
+        pixmap.fill_path(path, &paint_or_clear(colors.empty), FillRule::Winding, body, None);
+        mask.fill_path(path, FillRule::Winding, true, body);
+    }
+
+    // Water rises from the bottom of the body to its apex
+    let top = BODY_PIVOT_Y + (APEX_Y - BODY_PIVOT_Y) * BODY_SCALE;
+    let bottom = BODY_PIVOT_Y + (CENTER_Y + RADIUS - BODY_PIVOT_Y) * BODY_SCALE;
+    let level = bottom - (bottom - top) * state.step as f32 / FILL_STEPS;
+    if let Some(rect) = tiny_skia::Rect::from_ltrb(0.0, level, SIZE as f32, SIZE as f32) {
+        pixmap.fill_rect(rect, &paint(colors.water), Transform::identity(), Some(&mask));
+    }
+
+    if state.complete {
+        let mut check = PathBuilder::new();
+        check.move_to(23.0, 41.0);
+        check.line_to(30.0, 48.0);
+        check.line_to(42.0, 34.0);
+        if let Some(check) = check.finish() {
+            let stroke = Stroke {
+                width: 5.0,
+                line_cap: LineCap::Round,
+                ..Stroke::default()
+            };
+            pixmap.stroke_path(&check, &paint_or_clear(colors.check), &stroke, Transform::identity(), None);
+        }
+    }
+
+    Some(to_image(&pixmap))
+}
@@ -0,0 +1,111 @@
+// Volume units for display and input. Everything is stored in whole
+// millilitres; conversions happen only at the edges.
+
+use serde::{Deserialize, Serialize};
+
+// US customary fluid ounce and cup
+const ML_PER_FL_OZ: f64 = 29.573_529_562_5;
+const ML_PER_CUP: f64 = 236.588_236_5;
+const ML_PER_LITER: f64 = 1000.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Unit {
+    #[default]
+    Ml,
+    FlOz,
+    Cups,
+    Liters,
+}
+
+impl Unit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Ml => "ml",
+            Unit::FlOz => "fl oz",
+            Unit::Cups => "cups",
+            Unit::Liters => "L",
+        }
+    }
+
+    // Labels other apps write next to amounts, e.g. "oz", "fl oz", "cups", "L"
+    pub fn from_label(label: &str) -> Option<Unit> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => Some(Unit::Ml),
+            "oz" | "fl oz" | "fl. oz" | "fl. oz." | "floz" | "fluid ounce" | "fluid ounces" => Some(Unit::FlOz),
+            "cup" | "cups" => Some(Unit::Cups),
+            "l" | "liter" | "liters" | "litre" | "litres" => Some(Unit::Liters),
+            _ => None,
+        }
+    }
+
+    fn ml_per_unit(self) -> f64 {
+        match self {
+            Unit::Ml => 1.0,
+            Unit::FlOz => ML_PER_FL_OZ,
+            Unit::Cups => ML_PER_CUP,
+            Unit::Liters => ML_PER_LITER,
+        }
+    }
+
+    // Decimal places worth showing; finer than this is conversion noise
+    fn precision(self) -> i32 {
+        match self {
+            Unit::Ml => 0,
+            Unit::FlOz => 1,
+            Unit::Cups | Unit::Liters => 2,
+        }
+    }
+}
+
+fn round_to(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+// Rounded to the target unit's display precision, so 8 fl oz comes out as
+// 237 ml rather than 236.588 being truncated to 236 somewhere downstream
+pub fn convert(value: f64, from: Unit, to: Unit) -> f64 {
+    let ml = value * from.ml_per_unit();
+    round_to(ml / to.ml_per_unit(), to.precision())
+}
+
+// "1,750 ml", "59.2 fl oz", "1.75 L"
+pub fn format_amount(ml: i32, unit: Unit) -> String {
+    format!("{} {}", format_number(ml, unit), unit.symbol())
+}
+
+// The number alone, for "1,750 / 4,000 ml"
+pub fn format_number(ml: i32, unit: Unit) -> String {
+    match unit {
+        Unit::Ml => group_thousands(ml as i64),
+        _ => convert(ml as f64, Unit::Ml, unit).to_string(),
+    }
+}
+
+// Short enough for a badge: "750ml", "1.2L", "40oz"
+pub fn format_compact(ml: i32, unit: Unit) -> String {
+    let value = convert(ml as f64, Unit::Ml, unit);
+    match unit {
+        Unit::Ml if ml < 1000 => format!("{}ml", ml),
+        Unit::Ml => format!("{:.1}L", ml as f64 / ML_PER_LITER),
+        Unit::Liters => format!("{:.1}L", value),
+        Unit::FlOz => format!("{:.0}oz", value),
+        Unit::Cups => format!("{:.1} cups", value),
+    }
+}
+
+pub fn group_thousands(value: i64) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    if value < 0 {
+        grouped.insert(0, '-');
+    }
+    grouped
+}
@@ -0,0 +1,88 @@
+// WaterMinder's CSV export: separate date and time columns, and amounts
+// with their unit either in the cell ("8 oz") or in a unit column. Its drink
+// types are dropped, since every entry here is water.
+
+use crate::csv_import::{self, CsvImportSummary, ParsedRow, RejectReason, RowError};
+use crate::units::{self, Unit};
+use crate::AppError;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::Connection;
+use std::path::Path;
+
+pub const ENTRY_SOURCE: &str = "waterminder";
+
+const DATE_HEADERS: [&str; 2] = ["date", "day"];
+const TIME_HEADERS: [&str; 1] = ["time"];
+const AMOUNT_HEADERS: [&str; 3] = ["amount", "volume", "quantity"];
+const UNIT_HEADERS: [&str; 1] = ["unit"];
+const DATE_FORMATS: [&str; 4] = ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y", "%b %d, %Y"];
+const TIME_FORMATS: [&str; 4] = ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"];
+
+fn parse_with<T>(value: &str, formats: &[&str], parse: impl Fn(&str, &str) -> Option<T>) -> Option<T> {
+    formats.iter().find_map(|format| parse(value.trim(), format))
+}
+
+// "8 oz", "250ml", "1.5 L"; a bare number takes the unit column's unit,
+// or ml without one
+fn parse_amount(value: &str, unit_column: Option<&str>) -> Result<i32, RowError> {
+    let trimmed = value.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(trimmed.len());
+    let (number, label) = trimmed.split_at(split);
+    let label = if label.trim().is_empty() { unit_column.unwrap_or("ml") } else { label };
+    let unit = Unit::from_label(label).ok_or_else(|| {
+        RowError::new(
+            RejectReason::UnknownUnit,
+            value,
+            format!("unknown unit '{}'", label.trim()),
+        )
+    })?;
+    // Exports use "." for decimals, so a comma can only group thousands
+    let number: f64 = number.replace(',', "").parse().map_err(|_| {
+        RowError::new(
+            RejectReason::NonNumericAmount,
+            value,
+            format!("amount '{}' is not a number", trimmed),
+        )
+    })?;
+    csv_import::amount_in_range(units::convert(number, unit, Unit::Ml), value)
+}
+
+pub fn import(
+    conn: &Connection,
+    path: &Path,
+    day_starts_at_hour: i32,
+    strict: bool,
+) -> Result<CsvImportSummary, AppError> {
+    let mut reader = csv_import::open_reader(path)?;
+    let headers = reader.headers()?.clone();
+    let date_column = csv_import::require_column(&headers, &DATE_HEADERS, "date")?;
+    let time_column = csv_import::require_column(&headers, &TIME_HEADERS, "time")?;
+    let amount_column = csv_import::require_column(&headers, &AMOUNT_HEADERS, "amount")?;
+    let unit_column = csv_import::find_column(&headers, &UNIT_HEADERS);
+
+    let rows: Vec<ParsedRow> = reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            let row = record.map_err(RowError::from).and_then(|record| {
+                let date = record.get(date_column).unwrap_or_default();
+                let unrecognised = |what: &str, value: &str| {
+                    let message = format!("{} '{}' is not recognised", what, value);
+                    RowError::new(RejectReason::BadTimestamp, value, message)
+                };
+                let date = parse_with(date, &DATE_FORMATS, |v, f| NaiveDate::parse_from_str(v, f).ok())
+                    .ok_or_else(|| unrecognised("date", date))?;
+                let time = record.get(time_column).unwrap_or_default();
+                let time = parse_with(time, &TIME_FORMATS, |v, f| NaiveTime::parse_from_str(v, f).ok())
+                    .ok_or_else(|| unrecognised("time", time))?;
+                let unit = unit_column.and_then(|column| record.get(column));
+                let amount = parse_amount(record.get(amount_column).unwrap_or_default(), unit)?;
+                Ok((NaiveDateTime::new(date, time), amount))
+            });
+            (index + 2, row)
+        })
+        .collect();
+    csv_import::insert_rows(conn, rows, ENTRY_SOURCE, day_starts_at_hour, strict)
+}
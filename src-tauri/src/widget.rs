@@ -0,0 +1,186 @@
+// A small always-on-top window with today's progress and a quick-add
+// button, for keeping in a corner while the main window is closed. Its
+// position and whether it's showing are kept across restarts; a position
+// that's no longer on any monitor is dropped for the default corner.
+//
+// Closing it only hides it, like the main window, but without touching the
+// main window's saved geometry. Windows can deadlock when a window is built
+// from a synchronous command or an event handler, so `show` is only called
+// from setup, async commands and spawned threads.
+
+use crate::{settings, tray, units, AppError, Database};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, LogicalPosition, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+pub const LABEL: &str = "widget";
+pub const ENTRY_SOURCE: &str = "widget";
+const PAGE: &str = "widget.html";
+const STATE_KEY: &str = "widget_state";
+const WIDTH: f64 = 150.0;
+const HEIGHT: f64 = 190.0;
+// From the top right corner of the primary monitor when nowhere is saved
+const DEFAULT_MARGIN: f64 = 24.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+struct WidgetState {
+    // Physical pixels; None until it's been shown and put away once
+    position: Option<(i32, i32)>,
+    visible: bool,
+}
+
+// What the widget shows, formatted here so it needn't know about units
+#[derive(Debug, Serialize, Clone)]
+pub struct WidgetStats {
+    pub percentage: f32,
+    // "1,750 / 4,000 ml"
+    pub progress: String,
+    pub quick_add_ml: i32,
+    // "+250 ml"
+    pub quick_add_label: String,
+}
+
+fn load(conn: &Connection) -> WidgetState {
+    settings::get(conn, STATE_KEY).ok().flatten().unwrap_or_default()
+}
+
+fn update(app: &AppHandle, change: impl FnOnce(&mut WidgetState)) {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let mut state = load(&conn);
+    change(&mut state);
+    if let Err(e) = settings::set(&conn, STATE_KEY, &state) {
+        tracing::warn!("couldn't save the widget state: {}", e);
+    }
+}
+
+fn saved(app: &AppHandle) -> WidgetState {
+    load(&app.state::<Database>().lock())
+}
+
+fn place(app: &AppHandle, window: &WebviewWindow, position: Option<(i32, i32)>) -> tauri::Result<()> {
+    // Kept only while its corner is on one of the monitors
+    let on_screen = position.filter(|&(x, y)| {
+        app.monitor_from_point(x as f64, y as f64)
+            .ok()
+            .flatten()
+            .is_some()
+    });
+    if let Some((x, y)) = on_screen {
+        return window.set_position(PhysicalPosition::new(x, y));
+    }
+    if let Some(monitor) = app.primary_monitor()? {
+        let area = monitor.work_area();
+        let scale = monitor.scale_factor();
+        let right = area.position.to_logical::<f64>(scale).x + area.size.to_logical::<f64>(scale).width;
+        let top = area.position.to_logical::<f64>(scale).y;
+        window.set_position(LogicalPosition::new(right - WIDTH - DEFAULT_MARGIN, top + DEFAULT_MARGIN))?;
+    }
+    Ok(())
+}
+
+fn create(app: &AppHandle) -> tauri::Result<WebviewWindow> {
+    let window = WebviewWindowBuilder::new(app, LABEL, WebviewUrl::App(PAGE.into()))
+        .title("Hydra Tracker")
+        .inner_size(WIDTH, HEIGHT)
+        .decorations(false)
+        .resizable(false)
+        .maximizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()?;
+    place(app, &window, saved(app).position)?;
+    Ok(window)
+}
+
+pub fn is_visible(app: &AppHandle) -> bool {
+    app.get_webview_window(LABEL)
+        .is_some_and(|window| window.is_visible().unwrap_or(false))
+}
+
+pub fn show(app: &AppHandle) -> Result<(), AppError> {
+    let window = match app.get_webview_window(LABEL) {
+        Some(window) => window,
+        None => create(app)?,
+    };
+    window.show()?;
+    update(app, |state| state.visible = true);
+    tray::set_widget_checked(app, true);
+    Ok(())
+}
+
+// Where it is now, if it's showing
+fn current_position(app: &AppHandle) -> Option<(i32, i32)> {
+    let window = app.get_webview_window(LABEL)?;
+    if !window.is_visible().unwrap_or(false) {
+        return None;
+    }
+    let position = window.outer_position().ok()?;
+    Some((position.x, position.y))
+}
+
+pub fn hide(app: &AppHandle) -> Result<(), AppError> {
+    let position = current_position(app);
+    if let Some(window) = app.get_webview_window(LABEL) {
+        window.hide()?;
+    }
+    update(app, |state| {
+        state.position = position.or(state.position);
+        state.visible = false;
+    });
+    tray::set_widget_checked(app, false);
+    Ok(())
+}
+
+pub fn toggle(app: &AppHandle) -> Result<(), AppError> {
+    if is_visible(app) {
+        hide(app)
+    } else {
+        show(app)
+    }
+}
+
+// On quit: remembers where it is, and that it was showing
+pub fn save(app: &AppHandle) {
+    if let Some(position) = current_position(app) {
+        update(app, |state| state.position = Some(position));
+    }
+}
+
+// Brings it back if it was showing when the app last quit
+pub fn setup(app: &AppHandle) {
+    if saved(app).visible {
+        if let Err(e) = show(app) {
+            tracing::warn!("couldn't show the widget: {}", e);
+        }
+    }
+}
+
+pub fn stats(conn: &Connection) -> Result<WidgetStats, AppError> {
+    let settings = settings::load(conn)?;
+    let stats = crate::today_stats(conn)?;
+    let quick_add_ml = tray::quick_add_amounts(&settings)
+        .first()
+        .copied()
+        .unwrap_or(tray::DEFAULT_QUICK_ADD_AMOUNTS[0]);
+    Ok(WidgetStats {
+        percentage: stats.percentage,
+        progress: format!(
+            "{} / {}",
+            units::format_number(stats.total_ml, settings.unit),
+            units::format_amount(stats.goal_ml, settings.unit)
+        ),
+        quick_add_ml,
+        quick_add_label: format!("+{}", units::format_amount(quick_add_ml, settings.unit)),
+    })
+}
+
+// Adds the first quick-add amount, the one on the widget's button
+pub fn quick_add(app: &AppHandle) -> Result<WidgetStats, AppError> {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    let amount = stats(&conn)?.quick_add_ml;
+    crate::record_entry(app, &conn, amount, ENTRY_SOURCE)?;
+    stats(&conn)
+}
@@ -0,0 +1,150 @@
+// The main window's size, position and maximized state, kept across
+// restarts. Saved when the window is closed to the tray and when the app
+// quits, and restored before the window is first shown. A position that's
+// no longer on any monitor (one was unplugged, or the layout changed) is
+// dropped in favour of centering on the primary monitor.
+
+use crate::{settings, AppError, Database};
+use rusqlite::{Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, LogicalSize, Manager, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+const GEOMETRY_KEY: &str = "window_geometry";
+// How much of the window has to be on a monitor to count as reachable:
+// enough of the top edge to grab the title bar
+const MIN_VISIBLE_WIDTH: i32 = 100;
+const MIN_VISIBLE_HEIGHT: i32 = 40;
+
+// Physical pixels. While maximized, the size and position are the ones to
+// go back to when it's restored.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Area {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Area {
+    fn new(position: PhysicalPosition<i32>, size: PhysicalSize<u32>) -> Self {
+        Self {
+            x: position.x,
+            y: position.y,
+            width: size.width as i32,
+            height: size.height as i32,
+        }
+    }
+
+    // Whether the title bar of `geometry` is on this area
+    fn holds_title_bar(&self, geometry: &WindowGeometry) -> bool {
+        let left = geometry.x.max(self.x);
+        let right = (geometry.x + geometry.width as i32).min(self.x + self.width);
+        right - left >= MIN_VISIBLE_WIDTH
+            && geometry.y >= self.y
+            && geometry.y + MIN_VISIBLE_HEIGHT <= self.y + self.height
+    }
+}
+
+pub fn load(conn: &Connection) -> SqliteResult<Option<WindowGeometry>> {
+    settings::get(conn, GEOMETRY_KEY)
+}
+
+fn capture(window: &WebviewWindow, previous: Option<WindowGeometry>) -> Option<WindowGeometry> {
+    // A minimized window reports a parking spot rather than where it was
+    if window.is_minimized().unwrap_or(false) || !window.is_visible().unwrap_or(false) {
+        return None;
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    if maximized {
+        return previous.map(|previous| WindowGeometry { maximized, ..previous });
+    }
+    let position = window.outer_position().ok()?;
+    let size = window.inner_size().ok()?;
+    Some(WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized,
+    })
+}
+
+// Remembers where the window is; does nothing while it's hidden or minimized
+pub fn save(app: &AppHandle, window: &WebviewWindow) {
+    let db = app.state::<Database>();
+    let conn = db.lock();
+    if let Some(geometry) = capture(window, load(&conn).ok().flatten()) {
+        if let Err(e) = settings::set(&conn, GEOMETRY_KEY, &geometry) {
+            tracing::warn!("couldn't save the window geometry: {}", e);
+        }
+    }
+}
+
+// The saved geometry if its title bar is on one of the monitors, shrunk to
+// fit that monitor
+fn fit_to_monitors(geometry: WindowGeometry, monitors: &[Area]) -> Option<WindowGeometry> {
+    let monitor = monitors.iter().find(|monitor| monitor.holds_title_bar(&geometry))?;
+    Some(WindowGeometry {
+        width: geometry.width.min(monitor.width as u32),
+        height: geometry.height.min(monitor.height as u32),
+        ..geometry
+    })
+}
+
+// Puts the window back where it was saved. Call before showing it.
+pub fn restore(app: &AppHandle, window: &WebviewWindow) {
+    let saved = load(&app.state::<Database>().lock()).ok().flatten();
+    let Some(saved) = saved else {
+        return;
+    };
+
+    let monitors: Vec<Area> = window
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|monitor| Area::new(*monitor.position(), *monitor.size()))
+        .collect();
+    match fit_to_monitors(saved, &monitors) {
+        Some(geometry) => {
+            let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+            let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+        }
+        // Off-screen: keep the size if it fits the primary monitor, and center
+        None => {
+            if let Ok(Some(primary)) = window.primary_monitor() {
+                let size = primary.size();
+                let _ = window.set_size(PhysicalSize::new(
+                    saved.width.min(size.width),
+                    saved.height.min(size.height),
+                ));
+            }
+            let _ = window.center();
+        }
+    }
+    if saved.maximized {
+        let _ = window.maximize();
+    }
+}
+
+// Forgets the saved geometry and puts the window back to its configured
+// size, centered
+pub fn reset(app: &AppHandle, conn: &Connection) -> Result<(), AppError> {
+    settings::remove(conn, GEOMETRY_KEY)?;
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    let _ = window.unmaximize();
+    if let Some(config) = app.config().app.windows.first() {
+        window.set_size(LogicalSize::new(config.width, config.height))?;
+    }
+    Ok(window.center()?)
+}
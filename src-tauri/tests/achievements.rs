@@ -0,0 +1,101 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::open;
+use hydra_tracker_lib::achievements::{self, AchievementFacts, Rule};
+use hydra_tracker_lib::csv_import;
+use rusqlite::Connection;
+use std::path::Path;
+
+// Eight days in a row at a 2000 ml goal, met before noon on the fifth, then
+// a day that falls short
+const HISTORY: &str = "tests/fixtures/achievements_history.csv";
+
+fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 4, 10).unwrap()
+}
+
+fn with_history() -> Connection {
+    let conn = open();
+    csv_import::import(&conn, Path::new(HISTORY), 0, true).unwrap();
+    conn
+}
+
+fn unlocked(conn: &Connection) -> Vec<String> {
+    achievements::get_achievements(conn)
+        .unwrap()
+        .into_iter()
+        .filter(|a| a.unlocked)
+        .map(|a| a.key)
+        .collect()
+}
+
+#[test]
+fn facts_are_gathered_from_the_history() {
+    let facts = achievements::gather_facts(&with_history(), |_| 2000, today()).unwrap();
+    assert_eq!(facts.entries, 17);
+    assert_eq!(facts.lifetime_ml, 7 * 2200 + 2000 + 500);
+    assert_eq!(facts.best_streak, 8);
+    assert_eq!(facts.days_goal_met, 8);
+    assert_eq!(facts.earliest_goal_hour, Some(11));
+}
+
+#[test]
+fn the_history_unlocks_exactly_the_rules_it_satisfies() {
+    let conn = with_history();
+    let newly: Vec<String> = achievements::check_achievements(&conn, |_| 2000, today())
+        .unwrap()
+        .into_iter()
+        .map(|a| a.key)
+        .collect();
+    assert_eq!(newly, ["first_entry", "first_goal", "streak_7", "early_bird"]);
+    assert_eq!(unlocked(&conn), newly);
+
+    // Checking again unlocks nothing new
+    assert!(achievements::check_achievements(&conn, |_| 2000, today()).unwrap().is_empty());
+}
+
+#[test]
+fn a_higher_goal_earns_less() {
+    let conn = with_history();
+    let newly: Vec<String> = achievements::check_achievements(&conn, |_| 2100, today())
+        .unwrap()
+        .into_iter()
+        .map(|a| a.key)
+        .collect();
+    // The 2000 ml day before noon now falls short and splits the streak
+    assert_eq!(newly, ["first_entry", "first_goal"]);
+}
+
+#[test]
+fn unlocks_survive_deleting_the_entries_that_earned_them() {
+    let conn = with_history();
+    achievements::check_achievements(&conn, |_| 2000, today()).unwrap();
+    conn.execute("DELETE FROM water_entries", []).unwrap();
+
+    assert!(achievements::check_achievements(&conn, |_| 2000, today()).unwrap().is_empty());
+    assert_eq!(unlocked(&conn), ["first_entry", "first_goal", "streak_7", "early_bird"]);
+}
+
+#[test]
+fn each_rule_holds_from_its_threshold() {
+    let facts = AchievementFacts {
+        entries: 100,
+        lifetime_ml: 100_000,
+        best_streak: 7,
+        days_goal_met: 1,
+        earliest_goal_hour: Some(11),
+    };
+    assert!(achievements::is_satisfied(Rule::EntriesLogged(100), &facts));
+    assert!(!achievements::is_satisfied(Rule::EntriesLogged(101), &facts));
+    assert!(achievements::is_satisfied(Rule::LifetimeMl(100_000), &facts));
+    assert!(!achievements::is_satisfied(Rule::LifetimeMl(100_001), &facts));
+    assert!(achievements::is_satisfied(Rule::StreakDays(7), &facts));
+    assert!(!achievements::is_satisfied(Rule::StreakDays(8), &facts));
+    assert!(achievements::is_satisfied(Rule::DaysGoalMet(1), &facts));
+    assert!(!achievements::is_satisfied(Rule::DaysGoalMet(2), &facts));
+    // Before noon means an hour under 12
+    assert!(achievements::is_satisfied(Rule::GoalMetBeforeHour(12), &facts));
+    assert!(!achievements::is_satisfied(Rule::GoalMetBeforeHour(11), &facts));
+    assert!(!achievements::is_satisfied(Rule::GoalMetBeforeHour(12), &AchievementFacts::default()));
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use chrono::Duration;
+use common::{add, add_on, at, date, open, set_setting};
+use hydra_tracker_lib::analytics::{self, Trend, TrendDirection, WeeklyAverage, PATTERN_MIN_DAYS};
+
+fn weeks(averages: &[f32]) -> Vec<WeeklyAverage> {
+    averages
+        .iter()
+        .map(|average_ml| WeeklyAverage {
+            week_start: String::new(),
+            week_end: String::new(),
+            average_ml: *average_ml,
+        })
+        .collect()
+}
+
+fn direction(averages: &[f32]) -> (TrendDirection, f32) {
+    match analytics::compute_trend(weeks(averages)) {
+        Trend::Trending {
+            direction,
+            change_percentage,
+            ..
+        } => (direction, change_percentage),
+        other => panic!("expected a trend, got {:?}", other),
+    }
+}
+
+#[test]
+fn weekly_averages_end_on_today_and_count_empty_days_as_zero() {
+    let conn = open();
+    let today = date(2024, 5, 28);
+    add_on(&conn, date(2024, 5, 14), 7000);
+    add_on(&conn, date(2024, 5, 15), 700);
+    add_on(&conn, date(2024, 5, 21), 700);
+    add_on(&conn, date(2024, 5, 22), 1400);
+    add_on(&conn, date(2024, 5, 29), 7000);
+
+    let averages = analytics::weekly_averages(&conn, 2, today).unwrap();
+    let summary: Vec<(&str, &str, f32)> = averages
+        .iter()
+        .map(|w| (w.week_start.as_str(), w.week_end.as_str(), w.average_ml))
+        .collect();
+    assert_eq!(
+        summary,
+        [("2024-05-15", "2024-05-21", 200.0), ("2024-05-22", "2024-05-28", 200.0)]
+    );
+
+    let empty = analytics::weekly_averages(&open(), 4, today).unwrap();
+    assert_eq!(empty.len(), 4);
+    assert!(empty.iter().all(|w| w.average_ml == 0.0));
+}
+
+#[test]
+fn a_trend_needs_two_weeks_with_data() {
+    for averages in [&[][..], &[2000.0], &[0.0, 0.0, 2000.0]] {
+        match analytics::compute_trend(weeks(averages)) {
+            Trend::InsufficientData { weeks_with_data, .. } => {
+                assert_eq!(weeks_with_data, averages.iter().filter(|a| **a > 0.0).count());
+            }
+            other => panic!("expected too little data for {:?}, got {:?}", averages, other),
+        }
+    }
+}
+
+#[test]
+fn the_trend_is_the_fitted_change_relative_to_the_mean() {
+    // A slope of 500 a week over two weeks, against a mean of 1500
+    let (up, change) = direction(&[1000.0, 1500.0, 2000.0]);
+    assert_eq!(up, TrendDirection::Up);
+    assert!((change - 66.67).abs() < 0.01, "{}", change);
+
+    let (down, change) = direction(&[2000.0, 1500.0, 1000.0]);
+    assert_eq!(down, TrendDirection::Down);
+    assert!((change + 66.67).abs() < 0.01, "{}", change);
+
+    // Half a percent either way is noise
+    assert_eq!(direction(&[2000.0, 2010.0]).0, TrendDirection::Flat);
+    assert_eq!(direction(&[2010.0, 2000.0]).0, TrendDirection::Flat);
+}
+
+#[test]
+fn drink_times_are_hours_into_the_logical_day() {
+    let conn = open();
+    set_setting(&conn, "day_starts_at_hour", "3");
+    add(&conn, 250, at(date(2024, 5, 1), 7, 30));
+    add(&conn, 250, at(date(2024, 5, 1), 22, 0));
+    // Filed under May 1st, so 25.5 hours into it
+    add(&conn, 250, at(date(2024, 5, 2), 1, 30));
+    add(&conn, 250, at(date(2024, 5, 2), 9, 30));
+
+    let trends = analytics::drink_time_trends(&conn, date(2024, 5, 1), date(2024, 5, 2)).unwrap();
+    let days: Vec<(&str, &str, &str, f32, f32)> = trends
+        .days
+        .iter()
+        .map(|d| {
+            (
+                d.date.as_str(),
+                d.first_drink_at.as_str(),
+                d.last_drink_at.as_str(),
+                d.first_drink_hour,
+                d.last_drink_hour,
+            )
+        })
+        .collect();
+    assert_eq!(
+        days,
+        [
+            ("2024-05-01", "07:30", "01:30", 7.5, 25.5),
+            ("2024-05-02", "09:30", "09:30", 9.5, 9.5)
+        ]
+    );
+    assert_eq!(trends.days[1].rolling_first_drink_hour, 8.5);
+    assert_eq!(trends.summary.days_counted, 2);
+    assert_eq!(trends.summary.average_first_drink_hour, Some(8.5));
+    assert_eq!(trends.malformed_timestamps, 0);
+}
+
+#[test]
+fn the_typical_pattern_marks_half_hours_drunk_in_on_most_days() {
+    let conn = open();
+    let today = date(2024, 6, 1);
+    for days_ago in 1..=PATTERN_MIN_DAYS as i64 {
+        let day = today - Duration::days(days_ago);
+        add(&conn, 250, at(day, 9, 10));
+        // Only on every third day
+        if days_ago % 3 == 0 {
+            add(&conn, 250, at(day, 15, 0));
+        }
+    }
+    // Today doesn't count yet
+    add(&conn, 250, at(today, 20, 0));
+
+    let pattern = analytics::typical_pattern(&conn, today).unwrap();
+    assert_eq!(pattern.days_counted, PATTERN_MIN_DAYS);
+    assert!(pattern.enough_history);
+    assert_eq!(pattern.typical_minutes().collect::<Vec<_>>(), [9 * 60]);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{logged_across_a_rollover, open};
+use hydra_tracker_lib::anonymize::{self, AnonymizedFormat};
+
+fn date(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, d).unwrap()
+}
+
+// The goal went up on the 3rd
+fn goal_on(day: NaiveDate) -> i32 {
+    if day < date(3) {
+        2000
+    } else {
+        2500
+    }
+}
+
+#[test]
+fn the_csv_keeps_day_gaps_hours_amounts_and_goals_only() {
+    let (csv, count) = anonymize::export(&logged_across_a_rollover(), AnonymizedFormat::Csv, goal_on).unwrap();
+    assert_eq!(count, 4);
+    assert_eq!(
+        csv,
+        "day,hour,amount_ml,goal_ml\n\
+         0,9,250,2000\n\
+         0,21,300,2000\n\
+         0,1,200,2000\n\
+         3,7,400,2500\n"
+    );
+}
+
+#[test]
+fn the_json_groups_entries_by_day() {
+    let (json, count) = anonymize::export(&logged_across_a_rollover(), AnonymizedFormat::Json, goal_on).unwrap();
+    assert_eq!(count, 4);
+    let exported: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        exported,
+        serde_json::json!({
+            "days": [
+                {
+                    "day": 0,
+                    "goal_ml": 2000,
+                    "entries": [
+                        { "hour": 9, "amount_ml": 250 },
+                        { "hour": 21, "amount_ml": 300 },
+                        { "hour": 1, "amount_ml": 200 }
+                    ]
+                },
+                { "day": 3, "goal_ml": 2500, "entries": [{ "hour": 7, "amount_ml": 400 }] }
+            ]
+        })
+    );
+}
+
+#[test]
+fn no_dates_times_or_ids_leave_the_machine() {
+    for format in [AnonymizedFormat::Csv, AnonymizedFormat::Json] {
+        let (output, _) = anonymize::export(&logged_across_a_rollover(), format, goal_on).unwrap();
+        assert!(!output.contains("2024"), "{}", output);
+        assert!(!output.contains(":15") && !output.contains(":50") && !output.contains(":05"), "{}", output);
+        assert!(!output.contains("\"id\"") && !output.contains("source"), "{}", output);
+    }
+}
+
+#[test]
+fn an_empty_history_exports_no_days() {
+    let (csv, count) = anonymize::export(&open(), AnonymizedFormat::Csv, goal_on).unwrap();
+    assert_eq!((csv.as_str(), count), ("day,hour,amount_ml,goal_ml\n", 0));
+    let (json, _) = anonymize::export(&open(), AnonymizedFormat::Json, goal_on).unwrap();
+    let exported: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(exported, serde_json::json!({ "days": [] }));
+}
@@ -0,0 +1,198 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{add, at, open, set_setting};
+use hydra_tracker_lib::backup::{self, ConflictStrategy, RestoreMode};
+use hydra_tracker_lib::{db, migrations, AppError};
+
+fn day() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+}
+
+#[test]
+fn a_merge_skips_the_same_drink_logged_within_a_minute() {
+    let other = open();
+    add(&other, 250, at(day(), 9, 0));
+    add(&other, 300, at(day(), 12, 0));
+    let backup = backup::create(&other, "test").unwrap();
+
+    let conn = open();
+    // 30 seconds off the first, two minutes off the second
+    add(&conn, 250, at(day(), 9, 0) + chrono::Duration::seconds(30));
+    add(&conn, 300, at(day(), 12, 2));
+
+    let summary = backup::restore(&conn, &backup, RestoreMode::Merge, ConflictStrategy::KeepLocal, false).unwrap();
+
+    assert_eq!(summary.skipped["water_entries"], 1);
+    assert_eq!(summary.inserted["water_entries"], 1);
+    assert_eq!(db::entries_on(&conn, day()).unwrap().len(), 3);
+}
+
+#[test]
+fn a_dry_run_reports_the_merge_and_changes_nothing() {
+    let other = open();
+    add(&other, 250, at(day(), 9, 0));
+    let backup = backup::create(&other, "test").unwrap();
+
+    let conn = open();
+    let summary = backup::restore(&conn, &backup, RestoreMode::Merge, ConflictStrategy::KeepLocal, true).unwrap();
+
+    assert!(summary.dry_run);
+    assert_eq!(summary.inserted["water_entries"], 1);
+    assert!(db::entries_on(&conn, day()).unwrap().is_empty());
+    assert!(conn.is_autocommit());
+}
+
+// (timestamp, date) of every entry
+fn dated(conn: &rusqlite::Connection) -> Vec<(String, String)> {
+    let mut stmt = conn
+        .prepare("SELECT timestamp, date FROM water_entries ORDER BY timestamp")
+        .unwrap();
+    stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn entry(timestamp: &str, date: &str) -> (String, String) {
+    (timestamp.to_string(), date.to_string())
+}
+
+#[test]
+fn a_merge_dates_only_the_new_entries_and_by_the_local_rollover() {
+    let other = open();
+    add(&other, 300, at(day(), 1, 30));
+    let backup = backup::create(&other, "test").unwrap();
+
+    let conn = open();
+    // Logged under a midnight rollover, before it was moved to 3:00
+    add(&conn, 250, at(day(), 2, 0));
+    set_setting(&conn, "day_starts_at_hour", "3");
+
+    for strategy in [ConflictStrategy::KeepLocal, ConflictStrategy::KeepBackup] {
+        backup::restore(&conn, &backup, RestoreMode::Merge, strategy, true).unwrap();
+        assert_eq!(dated(&conn), [entry("2024-05-01 02:00:00", "2024-05-01")]);
+    }
+
+    backup::restore(&conn, &backup, RestoreMode::Merge, ConflictStrategy::KeepLocal, false).unwrap();
+    assert_eq!(
+        dated(&conn),
+        [
+            entry("2024-05-01 01:30:00", "2024-04-30"),
+            entry("2024-05-01 02:00:00", "2024-05-01"),
+        ]
+    );
+}
+
+#[test]
+fn a_replace_dates_the_entries_by_the_backups_rollover() {
+    // The snapshot taken before replacing goes into the data directory
+    let data = temp_dir("replace-data");
+    std::env::set_var("XDG_DATA_HOME", &data);
+
+    let other = open();
+    set_setting(&other, "day_starts_at_hour", "3");
+    add(&other, 300, at(day(), 1, 30));
+    let backup = backup::create(&other, "test").unwrap();
+
+    let conn = open();
+    add(&conn, 250, at(day(), 2, 0));
+    backup::restore(&conn, &backup, RestoreMode::Replace, ConflictStrategy::KeepLocal, false).unwrap();
+
+    assert_eq!(dated(&conn), [entry("2024-05-01 01:30:00", "2024-04-30")]);
+    assert_eq!(db::get_day_starts_at_hour(&conn), 3);
+    let _ = std::fs::remove_dir_all(&data);
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("hydra-backup-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn count(conn: &rusqlite::Connection, table: &str) -> i64 {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn a_snapshot_opens_with_the_same_rows() {
+    let dir = temp_dir("snapshot");
+    let conn = open();
+    for hour in 8..20 {
+        add(&conn, 250, at(day(), hour, 0));
+    }
+    common::set_setting(&conn, "daily_goal_ml", "2500");
+    let path = dir.join("snapshot.db");
+
+    let size = backup::snapshot_to(&conn, &path, false).unwrap();
+
+    assert_eq!(size, std::fs::metadata(&path).unwrap().len());
+    let copy = rusqlite::Connection::open(&path).unwrap();
+    for table in ["water_entries", "app_settings"] {
+        assert_eq!(count(&copy, table), count(&conn, table), "{}", table);
+    }
+    assert!(!path.with_extension("tmp").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_snapshot_only_replaces_an_existing_file_when_asked() {
+    let dir = temp_dir("overwrite");
+    let conn = open();
+    add(&conn, 250, at(day(), 9, 0));
+    let path = dir.join("snapshot.db");
+    std::fs::write(&path, "not a database").unwrap();
+
+    let error = backup::snapshot_to(&conn, &path, false).unwrap_err();
+    assert_eq!(error.code(), "invalid_input");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a database");
+
+    backup::snapshot_to(&conn, &path, true).unwrap();
+    let copy = rusqlite::Connection::open(&path).unwrap();
+    assert_eq!(count(&copy, "water_entries"), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn an_older_database_file_is_read_through_an_upgraded_copy() {
+    let path = temp_dir("older").join("hydra.db");
+    {
+        // Before entries had a source
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE water_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                amount_ml INTEGER NOT NULL,
+                timestamp TEXT NOT NULL,
+                date TEXT NOT NULL
+             );
+             INSERT INTO water_entries (amount_ml, timestamp, date) VALUES (250, '2024-05-01 09:00:00', '2024-05-01');",
+        )
+        .unwrap();
+    }
+
+    let backup = backup::read(&path).unwrap();
+    let entries = &backup.tables["water_entries"];
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["amount_ml"], 250);
+    assert_eq!(entries[0]["source"], "app");
+
+    // The file itself is left as it was
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    assert_eq!(migrations::schema_version(&conn).unwrap(), 0);
+    assert_eq!(count(&conn, "sqlite_master WHERE name = 'app_settings'"), 0);
+}
+
+#[test]
+fn a_database_file_from_a_newer_version_is_refused() {
+    let path = temp_dir("newer").join("hydra.db");
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    db::init_db(&conn).unwrap();
+    conn.pragma_update(None, "user_version", migrations::latest_version() + 1).unwrap();
+    drop(conn);
+
+    let error = backup::read(&path).unwrap_err();
+    assert!(matches!(error, AppError::InvalidInput { ref field, .. } if field == "path"), "{}", error);
+}
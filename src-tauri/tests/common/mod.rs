@@ -0,0 +1,54 @@
+// Shared setup for the integration tests: a fresh in-memory database with
+// the app's schema, and helpers for filling it
+
+#![allow(dead_code)]
+
+use chrono::{NaiveDate, NaiveDateTime};
+use hydra_tracker_lib::db;
+use rusqlite::Connection;
+
+pub fn open() -> Connection {
+    let conn = Connection::open_in_memory().expect("in-memory database");
+    db::init_db(&conn).expect("schema");
+    conn
+}
+
+// Settings are stored as JSON text, so `value` is e.g. "2000" or "true"
+pub fn set_setting(conn: &Connection, key: &str, value: &str) {
+    conn.execute(
+        "INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    )
+    .expect("setting");
+}
+
+pub fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).expect("valid date")
+}
+
+pub fn at(date: NaiveDate, hour: u32, minute: u32) -> NaiveDateTime {
+    date.and_hms_opt(hour, minute, 0).expect("valid time")
+}
+
+pub fn add(conn: &Connection, amount_ml: i32, at: NaiveDateTime) {
+    db::insert_entry(conn, amount_ml, "app", at).expect("insert");
+}
+
+// `amount_ml` logged at noon on `date`
+pub fn add_on(conn: &Connection, date: NaiveDate, amount_ml: i32) {
+    add(conn, amount_ml, at(date, 12, 0));
+}
+
+// A few days in May 2024 under a 3:00 rollover: 250 ml at 9:15 and 300 ml
+// at 21:50 on the 1st, 200 ml at 1:30 on the 2nd that is filed under the
+// 1st, and 400 ml at 7:05 on the 4th
+pub fn logged_across_a_rollover() -> Connection {
+    let conn = open();
+    set_setting(&conn, "day_starts_at_hour", "3");
+    add(&conn, 250, at(date(2024, 5, 1), 9, 15));
+    add(&conn, 300, at(date(2024, 5, 1), 21, 50));
+    add(&conn, 200, at(date(2024, 5, 2), 1, 30));
+    add(&conn, 400, at(date(2024, 5, 4), 7, 5));
+    conn
+}
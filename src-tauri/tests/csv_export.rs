@@ -0,0 +1,144 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{add, at, logged_across_a_rollover, open};
+use hydra_tracker_lib::{csv_export, csv_import, Settings};
+use rusqlite::Connection;
+use std::path::PathBuf;
+
+fn day() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hydra-csv-export-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn rows(conn: &Connection) -> Vec<(String, i32, String)> {
+    let mut stmt = conn
+        .prepare("SELECT timestamp, amount_ml, date FROM water_entries ORDER BY timestamp")
+        .unwrap();
+    stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn scheduled(dir: &std::path::Path) -> Settings {
+    Settings {
+        export_enabled: true,
+        export_path: Some(dir.display().to_string()),
+        export_time: 23 * 60,
+        ..Settings::default()
+    }
+}
+
+#[test]
+fn the_export_lists_every_entry_oldest_first() {
+    let dir = temp_dir("layout");
+    let path = dir.join("entries.csv");
+    assert_eq!(csv_export::export(&logged_across_a_rollover(), &path).unwrap(), 4);
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "timestamp,amount_ml,date,source\n\
+         2024-05-01 09:15:00,250,2024-05-01,app\n\
+         2024-05-01 21:50:00,300,2024-05-01,app\n\
+         2024-05-02 01:30:00,200,2024-05-01,app\n\
+         2024-05-04 07:05:00,400,2024-05-04,app\n"
+    );
+    assert!(!dir.join("entries.tmp").exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn an_export_imports_back_as_the_same_entries() {
+    let dir = temp_dir("round-trip");
+    let path = dir.join("entries.csv");
+    let conn = logged_across_a_rollover();
+    csv_export::export(&conn, &path).unwrap();
+
+    let copy = open();
+    let summary = csv_import::import(&copy, &path, 3, true).unwrap();
+    assert_eq!(summary.inserted, 4);
+    assert_eq!(rows(&copy), rows(&conn));
+
+    // And into the same database, every row is already there
+    let again = csv_import::import(&conn, &path, 3, true).unwrap();
+    assert_eq!(again.skipped_duplicates, 4);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_failed_export_leaves_the_previous_file_alone() {
+    let dir = temp_dir("failed");
+    let path = dir.join("entries.csv");
+    std::fs::write(&path, "previous").unwrap();
+    // A directory where the temporary file would go
+    std::fs::create_dir(dir.join("entries.tmp")).unwrap();
+
+    let error = csv_export::export(&logged_across_a_rollover(), &path).unwrap_err();
+    assert!(error.to_string().contains("Can't write"), "{}", error);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn the_scheduled_export_runs_once_a_day_from_its_time() {
+    let dir = temp_dir("scheduled");
+    let conn = logged_across_a_rollover();
+    let settings = scheduled(&dir);
+    let file = dir.join(csv_export::SCHEDULED_FILE_NAME);
+
+    assert_eq!(csv_export::run_scheduled(&conn, &settings, at(day(), 22, 59)), None);
+    assert!(!file.exists());
+    assert!(csv_export::status(&conn).unwrap().is_none());
+
+    assert_eq!(csv_export::run_scheduled(&conn, &settings, at(day(), 23, 0)), None);
+    let status = csv_export::status(&conn).unwrap().unwrap();
+    assert!(status.succeeded);
+    assert_eq!(status.entries, 4);
+    assert_eq!(status.attempted_at, "2024-05-01 23:00:00");
+
+    // Not again that day, even with more to write
+    add(&conn, 100, at(day(), 23, 30));
+    csv_export::run_scheduled(&conn, &settings, at(day(), 23, 50));
+    assert_eq!(csv_export::status(&conn).unwrap().unwrap().entries, 4);
+
+    let next_day = day() + chrono::Duration::days(1);
+    csv_export::run_scheduled(&conn, &settings, at(next_day, 23, 5));
+    assert_eq!(csv_export::status(&conn).unwrap().unwrap().entries, 5);
+
+    let off = Settings {
+        export_enabled: false,
+        ..settings
+    };
+    assert_eq!(csv_export::run_scheduled(&conn, &off, at(next_day + chrono::Duration::days(1), 23, 5)), None);
+    assert_eq!(
+        csv_export::status(&conn).unwrap().unwrap().attempted_at,
+        "2024-05-02 23:05:00"
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_missing_folder_is_reported_once_until_an_export_succeeds() {
+    let dir = temp_dir("missing");
+    let conn = logged_across_a_rollover();
+    let settings = scheduled(&dir.join("gone"));
+    let next_day = day() + chrono::Duration::days(1);
+
+    assert!(csv_export::run_scheduled(&conn, &settings, at(day(), 23, 0)).is_some());
+    assert!(!csv_export::status(&conn).unwrap().unwrap().succeeded);
+    // Still missing the next day, but the user already knows
+    assert_eq!(csv_export::run_scheduled(&conn, &settings, at(next_day, 23, 0)), None);
+
+    // A new folder is tried straight away, the same day
+    let fixed = scheduled(&dir);
+    assert_eq!(csv_export::run_scheduled(&conn, &fixed, at(next_day, 23, 10)), None);
+    assert!(csv_export::status(&conn).unwrap().unwrap().succeeded);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{at, open};
+use hydra_tracker_lib::csv_import::{self, ParsedRow, RejectReason, RowError};
+use hydra_tracker_lib::db;
+use std::path::PathBuf;
+
+fn day() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+}
+
+// `contents` written to a file of its own
+fn csv_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hydra-csv-import-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("entries.csv");
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn rows() -> Vec<ParsedRow> {
+    vec![
+        (2, Ok((at(day(), 9, 0), 250))),
+        (3, Err(RowError::bad_timestamp("yesterday"))),
+        (4, Ok((at(day(), 12, 0), 330))),
+    ]
+}
+
+#[test]
+fn reimporting_counts_duplicates_apart_from_rejections() {
+    let conn = open();
+    let first = csv_import::insert_rows(&conn, rows(), "import", 0, false).unwrap();
+    assert_eq!(first.inserted, 2);
+    assert_eq!(first.skipped_duplicates, 0);
+    assert_eq!(first.rejected_total, 1);
+
+    let again = csv_import::insert_rows(&conn, rows(), "import", 0, false).unwrap();
+    assert_eq!(again.inserted, 0);
+    assert_eq!(again.skipped_duplicates, 2);
+    let duplicates: Vec<(usize, RejectReason, &str)> =
+        again.duplicates.iter().map(|d| (d.line, d.reason, d.value.as_str())).collect();
+    assert_eq!(
+        duplicates,
+        [(2, RejectReason::Duplicate, "2024-05-01 09:00:00"), (4, RejectReason::Duplicate, "2024-05-01 12:00:00")]
+    );
+    // Only the row that really couldn't be read
+    assert_eq!(again.rejected_total, 1);
+    assert_eq!(again.rejected.len(), 1);
+    assert_eq!(again.rejected[0].line, 3);
+    assert_eq!(again.rejected[0].reason, RejectReason::BadTimestamp);
+}
+
+#[test]
+fn duplicates_never_abort_a_strict_import() {
+    let conn = open();
+    let first = [(2, Ok((at(day(), 9, 0), 250)))];
+    csv_import::insert_rows(&conn, first, "import", 0, true).unwrap();
+
+    let rows = [(2, Ok((at(day(), 9, 0), 250))), (3, Ok((at(day(), 10, 0), 300)))];
+    let summary = csv_import::insert_rows(&conn, rows, "import", 0, true).unwrap();
+    assert_eq!(summary.inserted, 1);
+    assert_eq!(summary.skipped_duplicates, 1);
+    assert_eq!(summary.duplicates[0].line, 2);
+    assert_eq!(summary.rejected_total, 0);
+}
+
+#[test]
+fn columns_are_found_by_name_in_any_order() {
+    let path = csv_file(
+        "columns",
+        "note,Amount,Timestamp\n\
+         tea,250,2024-05-01 09:00:00\n\
+         ,300,2024-05-01T10:30\n\
+         ,330, 2024-05-01 23:15 \n",
+    );
+    let conn = open();
+    let summary = csv_import::import(&conn, &path, 0, false).unwrap();
+
+    assert_eq!(summary.inserted, 3);
+    assert_eq!(summary.rejected_total, 0);
+    let entries = db::entries_on(&conn, day()).unwrap();
+    let imported: Vec<(&str, i32, &str)> = entries
+        .iter()
+        .map(|e| (e.timestamp.as_str(), e.amount_ml, e.source.as_str()))
+        .collect();
+    assert_eq!(
+        imported,
+        [
+            ("2024-05-01 23:15:00", 330, "import"),
+            ("2024-05-01 10:30:00", 300, "import"),
+            ("2024-05-01 09:00:00", 250, "import"),
+        ]
+    );
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn each_bad_row_is_reported_with_its_line_and_reason() {
+    let path = csv_file(
+        "rejects",
+        "timestamp,amount_ml\n\
+         2024-05-01 09:00:00,250\n\
+         05/01/2024 10:00,250\n\
+         2024-05-01 11:00:00,a lot\n\
+         2024-05-01 12:00:00,-5\n\
+         2024-05-01 13:00:00\n",
+    );
+    let summary = csv_import::import(&open(), &path, 0, false).unwrap();
+
+    assert_eq!(summary.inserted, 1);
+    let rejected: Vec<(usize, RejectReason, &str)> = summary
+        .rejected
+        .iter()
+        .map(|r| (r.line, r.reason, r.value.as_str()))
+        .collect();
+    assert_eq!(
+        rejected,
+        [
+            (3, RejectReason::BadTimestamp, "05/01/2024 10:00"),
+            (4, RejectReason::NonNumericAmount, "a lot"),
+            (5, RejectReason::OutOfRange, "-5"),
+            // The missing cell reads as empty
+            (6, RejectReason::NonNumericAmount, ""),
+        ]
+    );
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn a_file_without_a_timestamp_column_names_the_ones_it_looked_for() {
+    let path = csv_file("no-timestamp", "when,amount_ml\n2024-05-01 09:00:00,250\n");
+    let error = csv_import::import(&open(), &path, 0, false).unwrap_err();
+    assert!(error.to_string().contains("no timestamp column; expected one of timestamp"), "{}", error);
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{add, at, open};
+use hydra_tracker_lib::{datadir, AppError};
+use std::path::PathBuf;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hydra-datadir-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn a_directory_that_already_has_a_database_is_refused() {
+    let conn = open();
+    let current = temp_dir("current-taken");
+    let taken = temp_dir("taken");
+    std::fs::write(taken.join(datadir::DB_FILE), "").unwrap();
+
+    let error = datadir::move_to(&conn, &current, &taken).unwrap_err();
+    assert!(matches!(error, AppError::InvalidInput { ref field, .. } if field == "new_path"), "{}", error);
+}
+
+// A backup that can't be copied stops the move partway through
+#[cfg(unix)]
+fn unreadable_backups(name: &str) -> PathBuf {
+    let current = temp_dir(name);
+    std::fs::create_dir_all(current.join("backups")).unwrap();
+    std::os::unix::fs::symlink(current.join("gone.db"), current.join("backups").join("hydra-auto.db")).unwrap();
+    current
+}
+
+#[cfg(unix)]
+#[test]
+fn a_failed_move_removes_the_directory_it_created() {
+    let conn = open();
+    add(&conn, 250, at(chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(), 9, 0));
+    let current = unreadable_backups("current-created");
+    let new_dir = temp_dir("created").join("data");
+
+    assert!(datadir::move_to(&conn, &current, &new_dir).is_err());
+    assert!(!new_dir.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn a_failed_move_into_an_existing_directory_removes_only_what_it_copied() {
+    let conn = open();
+    let current = unreadable_backups("current-existing");
+    let new_dir = temp_dir("existing");
+    std::fs::write(new_dir.join("notes.txt"), "mine").unwrap();
+
+    assert!(datadir::move_to(&conn, &current, &new_dir).is_err());
+    let left: Vec<_> = std::fs::read_dir(&new_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(left, ["notes.txt"]);
+}
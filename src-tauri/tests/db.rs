@@ -0,0 +1,295 @@
+mod common;
+
+use common::{add, at, date, open, set_setting};
+use hydra_tracker_lib::{check_entry_limit, csv_import, dates, db, stats, AddWaterOutcome, Database};
+use rusqlite::Connection;
+
+#[test]
+fn init_db_runs_again_on_an_existing_database() {
+    let conn = open();
+    db::init_db(&conn).unwrap();
+}
+
+#[test]
+fn init_db_adds_the_source_column_to_old_databases() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute(
+        "CREATE TABLE water_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            amount_ml INTEGER NOT NULL,
+            timestamp TEXT NOT NULL,
+            date TEXT NOT NULL
+        )",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO water_entries (amount_ml, timestamp, date) VALUES (250, '2024-05-01 09:00:00', '2024-05-01')",
+        [],
+    )
+    .unwrap();
+
+    db::init_db(&conn).unwrap();
+
+    let entries = db::entries_on(&conn, date(2024, 5, 1)).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].source, "app");
+}
+
+#[test]
+fn insert_entry_stores_the_row_it_returns() {
+    let conn = open();
+    let entry = db::insert_entry(&conn, 330, "tray", at(date(2024, 5, 1), 9, 15)).unwrap();
+
+    assert_eq!(entry.amount_ml, 330);
+    assert_eq!(entry.timestamp, "2024-05-01 09:15:00");
+    assert_eq!(entry.date, "2024-05-01");
+    assert_eq!(entry.source, "tray");
+
+    let stored = db::entries_on(&conn, date(2024, 5, 1)).unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].id, entry.id);
+}
+
+#[test]
+fn insert_entry_stores_amount_ml_as_an_integer() {
+    let conn = open();
+    let entry = db::insert_entry(&conn, 250, "app", at(date(2024, 5, 1), 9, 0)).unwrap();
+
+    let kind: String = conn
+        .query_row("SELECT typeof(amount_ml) FROM water_entries WHERE id = ?1", [entry.id], |row| row.get(0))
+        .unwrap();
+    assert_eq!(kind, "integer");
+}
+
+#[test]
+fn init_db_rewrites_non_integer_amounts() {
+    let conn = open();
+    // What older versions could leave behind: a real, and text that doesn't
+    // coerce under the column's affinity. Those versions didn't set
+    // user_version, so every migration runs again.
+    conn.pragma_update(None, "user_version", 0).unwrap();
+    conn.execute(
+        "INSERT INTO water_entries (amount_ml, timestamp, date) VALUES
+            (250.0 + 0.5, '2024-05-01 09:00:00', '2024-05-01'),
+            ('330 ml', '2024-05-01 10:00:00', '2024-05-01')",
+        [],
+    )
+    .unwrap();
+
+    db::init_db(&conn).unwrap();
+
+    let kinds: Vec<(String, i64)> = conn
+        .prepare("SELECT typeof(amount_ml), amount_ml FROM water_entries ORDER BY timestamp")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(kinds, [("integer".to_string(), 250), ("integer".to_string(), 330)]);
+}
+
+#[test]
+fn insert_entry_dates_by_the_rollover_hour() {
+    let conn = open();
+    set_setting(&conn, "day_starts_at_hour", "4");
+
+    let late = db::insert_entry(&conn, 250, "app", at(date(2024, 3, 10), 2, 30)).unwrap();
+    let morning = db::insert_entry(&conn, 250, "app", at(date(2024, 3, 10), 5, 0)).unwrap();
+
+    assert_eq!(late.date, "2024-03-09");
+    assert_eq!(morning.date, "2024-03-10");
+}
+
+#[test]
+fn a_drink_at_half_past_midnight_counts_for_the_evening_before() {
+    let conn = open();
+    set_setting(&conn, "day_starts_at_hour", "3");
+
+    let entry = db::insert_entry(&conn, 250, "app", at(date(2024, 5, 2), 0, 30)).unwrap();
+    assert_eq!(entry.date, "2024-05-01");
+    assert_eq!(db::entries_on(&conn, date(2024, 5, 1)).unwrap().len(), 1);
+    assert!(db::entries_on(&conn, date(2024, 5, 2)).unwrap().is_empty());
+
+    // Across a year end as well, and the rollover hour itself starts the day
+    assert_eq!(dates::logical_date(at(date(2024, 1, 1), 0, 30), 3), date(2023, 12, 31));
+    assert_eq!(dates::logical_date(at(date(2024, 1, 1), 3, 0), 3), date(2024, 1, 1));
+    assert_eq!(dates::logical_date(at(date(2024, 1, 1), 0, 30), 0), date(2024, 1, 1));
+}
+
+#[test]
+fn entries_on_lists_one_day_newest_first() {
+    let conn = open();
+    add(&conn, 200, at(date(2024, 5, 1), 8, 0));
+    add(&conn, 300, at(date(2024, 5, 1), 14, 0));
+    add(&conn, 400, at(date(2024, 5, 2), 8, 0));
+
+    let entries = db::entries_on(&conn, date(2024, 5, 1)).unwrap();
+    let amounts: Vec<i32> = entries.iter().map(|e| e.amount_ml).collect();
+    assert_eq!(amounts, [300, 200]);
+}
+
+#[test]
+fn delete_entry_removes_only_that_entry() {
+    let conn = open();
+    let first = db::insert_entry(&conn, 200, "app", at(date(2024, 5, 1), 8, 0)).unwrap();
+    add(&conn, 300, at(date(2024, 5, 1), 9, 0));
+
+    assert_eq!(db::delete_entry(&conn, first.id).unwrap().as_deref(), Some("2024-05-01"));
+    assert_eq!(db::delete_entry(&conn, first.id).unwrap(), None);
+
+    let entries = db::entries_on(&conn, date(2024, 5, 1)).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].amount_ml, 300);
+}
+
+#[test]
+fn an_amount_over_the_limit_asks_first_and_nothing_is_stored() {
+    let conn = open();
+    set_setting(&conn, "max_entry_ml", "2000");
+
+    match check_entry_limit(&conn, 2500, false).unwrap() {
+        Some(AddWaterOutcome::NeedsConfirmation { amount_ml, max_entry_ml }) => {
+            assert_eq!((amount_ml, max_entry_ml), (2500, 2000));
+        }
+        other => panic!("expected a confirmation, got {:?}", other),
+    }
+    assert!(check_entry_limit(&conn, 2000, false).unwrap().is_none());
+    assert!(check_entry_limit(&conn, 2500, true).unwrap().is_none());
+
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM water_entries", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(total, 0);
+}
+
+#[test]
+fn a_forced_entry_is_stored_like_one_under_the_limit() {
+    let when = at(date(2024, 5, 1), 9, 0);
+    let record = |max_entry_ml: &str, force: bool| {
+        let conn = open();
+        set_setting(&conn, "daily_goal_ml", "2000");
+        set_setting(&conn, "max_entry_ml", max_entry_ml);
+        assert!(check_entry_limit(&conn, 2500, force).unwrap().is_none());
+        let entry = db::insert_entry(&conn, 2500, "app", when).unwrap();
+        (conn, entry)
+    };
+    let (forced_conn, forced) = record("2000", true);
+    let (normal_conn, normal) = record("3000", false);
+
+    assert_eq!(
+        (forced.id, forced.amount_ml, &forced.timestamp, &forced.date, &forced.source),
+        (normal.id, normal.amount_ml, &normal.timestamp, &normal.date, &normal.source)
+    );
+    let month = |conn: &Connection| {
+        let stats = stats::monthly_stats_with_streaks(conn, 2024, 5).unwrap();
+        (stats.total_ml, stats.days_goal_met, stats.days[0].goal_met_at.clone())
+    };
+    assert_eq!(month(&forced_conn), month(&normal_conn));
+    assert_eq!(month(&forced_conn).1, 1);
+}
+
+#[test]
+fn recompute_entry_dates_applies_a_new_rollover_hour() {
+    let conn = open();
+    add(&conn, 250, at(date(2024, 3, 10), 2, 0));
+    add(&conn, 250, at(date(2024, 3, 10), 10, 0));
+
+    set_setting(&conn, "day_starts_at_hour", "3");
+    assert_eq!(db::recompute_entry_dates(&conn).unwrap(), 1);
+    assert_eq!(db::entries_on(&conn, date(2024, 3, 9)).unwrap().len(), 1);
+    assert_eq!(db::entries_on(&conn, date(2024, 3, 10)).unwrap().len(), 1);
+
+    // Nothing left to move
+    assert_eq!(db::recompute_entry_dates(&conn).unwrap(), 0);
+}
+
+#[test]
+fn open_uses_wal_and_a_busy_timeout() {
+    let dir = std::env::temp_dir().join(format!("hydra-db-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("hydra.db");
+
+    let conn = db::open(&path).unwrap();
+    let pragma = |name: &str| -> i64 { conn.pragma_query_value(None, name, |row| row.get(0)).unwrap() };
+    let journal_mode: String = conn.pragma_query_value(None, "journal_mode", |row| row.get(0)).unwrap();
+    assert_eq!(journal_mode, "wal");
+    // 1 is NORMAL
+    assert_eq!(pragma("synchronous"), 1);
+    assert_eq!(pragma("busy_timeout"), 5000);
+    assert_eq!(pragma("foreign_keys"), 1);
+
+    // Copies are single files on the rollback journal, including what's
+    // still only in the WAL
+    db::init_db(&conn).unwrap();
+    add(&conn, 250, at(date(2024, 5, 1), 9, 0));
+    let copy_path = dir.join("copy.db");
+    db::vacuum_into(&conn, &copy_path).unwrap();
+    let copy = Connection::open(&copy_path).unwrap();
+    let journal_mode: String = copy.pragma_query_value(None, "journal_mode", |row| row.get(0)).unwrap();
+    assert_eq!(journal_mode, "delete");
+    assert_eq!(db::entries_on(&copy, date(2024, 5, 1)).unwrap().len(), 1);
+
+    drop((conn, copy));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn configure_keeps_working_where_wal_is_unavailable() {
+    // In-memory databases can't use WAL, like some network filesystems
+    let conn = Connection::open_in_memory().unwrap();
+    db::configure(&conn).unwrap();
+    let journal_mode: String = conn.pragma_query_value(None, "journal_mode", |row| row.get(0)).unwrap();
+    assert_eq!(journal_mode, "memory");
+}
+
+#[test]
+fn a_panic_while_holding_the_database_does_not_lock_everyone_out() {
+    let db = std::sync::Arc::new(Database::new(open()));
+    let holder = std::sync::Arc::clone(&db);
+    let panicked = std::thread::spawn(move || {
+        let conn = holder.lock();
+        conn.execute_batch("BEGIN").unwrap();
+        add(&conn, 250, at(date(2024, 5, 1), 9, 0));
+        panic!("poison the lock mid-transaction");
+    })
+    .join();
+    assert!(panicked.is_err());
+
+    // The unfinished transaction is rolled back and the connection works
+    let conn = db.lock();
+    assert!(conn.is_autocommit());
+    assert!(db::entries_on(&conn, date(2024, 5, 1)).unwrap().is_empty());
+    add(&conn, 330, at(date(2024, 5, 1), 10, 0));
+    drop(conn);
+    assert_eq!(db::entries_on(&db.lock(), date(2024, 5, 1)).unwrap().len(), 1);
+}
+
+#[test]
+fn write_entry_dates_by_the_rollover_hour_it_is_given() {
+    let conn = open();
+    let entry = db::write_entry(&conn, 250, "import", at(date(2024, 3, 10), 2, 30), 3).unwrap();
+
+    assert_eq!(entry.date, "2024-03-09");
+    assert_eq!(db::entries_on(&conn, date(2024, 3, 9)).unwrap().len(), 1);
+}
+
+#[test]
+fn insert_rows_lists_each_day_it_added_to_once() {
+    let conn = open();
+    add(&conn, 250, at(date(2024, 5, 4), 9, 0));
+    let rows = [
+        (2, Ok((at(date(2024, 5, 2), 9, 0), 250))),
+        // Before the rollover, so it counts toward May 1
+        (3, Ok((at(date(2024, 5, 2), 1, 0), 300))),
+        (4, Ok((at(date(2024, 5, 2), 18, 0), 400))),
+        // Already stored, so its day isn't listed
+        (5, Ok((at(date(2024, 5, 4), 9, 0), 250))),
+    ];
+
+    let summary = csv_import::insert_rows(&conn, rows, "import", 3, false).unwrap();
+
+    assert_eq!(summary.inserted, 3);
+    let dates: Vec<&str> = summary.dates.iter().map(String::as_str).collect();
+    assert_eq!(dates, ["2024-05-01", "2024-05-02"]);
+}
@@ -0,0 +1,60 @@
+use hydra_tracker_lib::AppError;
+use rusqlite::Connection;
+use serde_json::json;
+use std::sync::Mutex;
+
+#[test]
+fn invalid_input_names_the_field() {
+    let error = AppError::invalid("amount_ml", "must be positive");
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({ "code": "invalid_input", "message": "amount_ml: must be positive", "field": "amount_ml" })
+    );
+}
+
+#[test]
+fn other_errors_have_no_field() {
+    let error = AppError::NotFound("entry 7".to_string());
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({ "code": "not_found", "message": "entry 7 not found", "field": null })
+    );
+}
+
+#[test]
+fn a_job_already_running_has_its_own_code() {
+    let error = AppError::AlreadyRunning("Database maintenance");
+    assert_eq!(error.code(), "already_running");
+    assert_eq!(error.to_string(), "Database maintenance is already running");
+}
+
+#[test]
+fn sqlite_errors_map_to_database_or_not_found() {
+    let conn = Connection::open_in_memory().unwrap();
+
+    let missing_table = conn.execute("DELETE FROM nowhere", []).unwrap_err();
+    assert_eq!(AppError::from(missing_table).code(), "database");
+
+    let no_rows = conn
+        .query_row("SELECT 1 WHERE 0", [], |row| row.get::<_, i32>(0))
+        .unwrap_err();
+    assert_eq!(AppError::from(no_rows).code(), "not_found");
+}
+
+#[test]
+fn a_poisoned_lock_is_its_own_error() {
+    let lock = Mutex::new(());
+    let _ = std::panic::catch_unwind(|| {
+        let _guard = lock.lock().unwrap();
+        panic!("poison the lock");
+    });
+    let error = AppError::from(lock.lock().unwrap_err());
+    assert_eq!(error.code(), "state_poisoned");
+}
+
+#[test]
+fn plain_string_errors_pass_through_unchanged() {
+    let error = AppError::from("Backup file is not valid JSON".to_string());
+    assert_eq!(error.code(), "other");
+    assert_eq!(String::from(error), "Backup file is not valid JSON");
+}
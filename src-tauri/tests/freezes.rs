@@ -0,0 +1,52 @@
+mod common;
+
+use common::{add_on, date, open};
+use hydra_tracker_lib::{freezes, AppError};
+
+fn goal(_: chrono::NaiveDate) -> i32 {
+    2000
+}
+
+fn refused(error: AppError) -> String {
+    match error {
+        AppError::InvalidInput { field, reason } if field == "date" => reason,
+        other => panic!("{}", other),
+    }
+}
+
+#[test]
+fn only_a_past_day_that_missed_the_goal_can_be_frozen() {
+    let conn = open();
+    let today = date(2024, 5, 10);
+    add_on(&conn, date(2024, 5, 8), 2000);
+    add_on(&conn, date(2024, 5, 9), 500);
+
+    assert!(refused(freezes::apply_streak_freeze(&conn, today, goal, today).unwrap_err()).contains("past"));
+    let met = freezes::apply_streak_freeze(&conn, date(2024, 5, 8), goal, today).unwrap_err();
+    assert!(refused(met).contains("goal was met"));
+
+    let freeze = freezes::apply_streak_freeze(&conn, date(2024, 5, 9), goal, today).unwrap();
+    assert_eq!(freeze.date, "2024-05-09");
+    let again = freezes::apply_streak_freeze(&conn, date(2024, 5, 9), goal, today).unwrap_err();
+    assert!(refused(again).contains("already frozen"));
+    assert_eq!(freezes::frozen_dates(&conn).unwrap().into_iter().collect::<Vec<_>>(), [date(2024, 5, 9)]);
+}
+
+#[test]
+fn each_month_has_its_own_allowance() {
+    let conn = open();
+    let today = date(2024, 6, 10);
+    for day in 1..=freezes::FREEZES_PER_MONTH as u32 {
+        freezes::apply_streak_freeze(&conn, date(2024, 6, day), goal, today).unwrap();
+    }
+    let over = freezes::apply_streak_freeze(&conn, date(2024, 6, 5), goal, today).unwrap_err();
+    assert!(refused(over).contains("2024-06"));
+    assert_eq!(freezes::get_streak_freezes(&conn, today).unwrap().remaining_this_month, 0);
+
+    // May still has its own
+    freezes::apply_streak_freeze(&conn, date(2024, 5, 31), goal, today).unwrap();
+    let listed = freezes::get_streak_freezes(&conn, date(2024, 5, 31)).unwrap();
+    assert_eq!(listed.remaining_this_month, freezes::FREEZES_PER_MONTH - 1);
+    let dates: Vec<String> = listed.freezes.into_iter().map(|f| f.date).collect();
+    assert_eq!(dates, ["2024-06-02", "2024-06-01", "2024-05-31"]);
+}
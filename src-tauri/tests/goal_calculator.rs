@@ -0,0 +1,49 @@
+use hydra_tracker_lib::goal_calculator::{self, ActivityLevel, Climate, MAX_WEIGHT_KG, MIN_WEIGHT_KG};
+use hydra_tracker_lib::AppError;
+
+fn suggested(weight_kg: f64, activity: ActivityLevel, climate: Climate) -> i32 {
+    goal_calculator::suggested_goal_ml(weight_kg, activity, climate).unwrap()
+}
+
+#[test]
+fn the_goal_is_33_ml_per_kg_rounded_to_50() {
+    // 2310 ml
+    assert_eq!(suggested(70.0, ActivityLevel::Sedentary, Climate::Temperate), 2300);
+    // 2327.5 ml
+    assert_eq!(suggested(70.5, ActivityLevel::Sedentary, Climate::Temperate), 2350);
+}
+
+#[test]
+fn activity_and_climate_add_flat_amounts() {
+    let activities = [
+        (ActivityLevel::Sedentary, 2300),
+        (ActivityLevel::Light, 2650),
+        (ActivityLevel::Moderate, 3000),
+        (ActivityLevel::Active, 3300),
+    ];
+    for (activity, expected) in activities {
+        assert_eq!(suggested(70.0, activity, Climate::Temperate), expected, "{:?}", activity);
+    }
+    assert_eq!(suggested(70.0, ActivityLevel::Sedentary, Climate::Warm), 2800);
+    assert_eq!(suggested(70.0, ActivityLevel::Sedentary, Climate::Hot), 3300);
+    assert_eq!(suggested(70.0, ActivityLevel::Active, Climate::Hot), 4300);
+}
+
+#[test]
+fn the_weight_limits_themselves_are_accepted() {
+    assert_eq!(suggested(MIN_WEIGHT_KG, ActivityLevel::Sedentary, Climate::Temperate), 1000);
+    assert_eq!(suggested(MAX_WEIGHT_KG, ActivityLevel::Sedentary, Climate::Temperate), 8250);
+}
+
+#[test]
+fn weights_outside_the_limits_are_rejected() {
+    for weight_kg in [MIN_WEIGHT_KG - 0.1, MAX_WEIGHT_KG + 0.1, 0.0, -70.0, f64::NAN, f64::INFINITY] {
+        let error = goal_calculator::suggested_goal_ml(weight_kg, ActivityLevel::Light, Climate::Warm).unwrap_err();
+        assert!(
+            matches!(error, AppError::InvalidInput { ref field, .. } if field == "weight_kg"),
+            "{}: {}",
+            weight_kg,
+            error
+        );
+    }
+}
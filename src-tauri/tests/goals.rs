@@ -0,0 +1,118 @@
+mod common;
+
+use chrono::{Duration, NaiveDate};
+use common::{date, open, set_setting};
+use hydra_tracker_lib::{db, goals, settings, AppError};
+
+fn ramp() -> goals::GoalProgram {
+    goals::GoalProgram {
+        start_ml: 2000,
+        increment_ml: 300,
+        interval_days: 7,
+        start_date: date(2024, 4, 1),
+        target_ml: 2800,
+    }
+}
+
+#[test]
+fn a_goal_program_overrides_the_daily_goal_until_it_stops() {
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "2000");
+    let today = db::logical_today(&conn);
+    let program = goals::GoalProgram {
+        start_ml: 1500,
+        increment_ml: 250,
+        interval_days: 7,
+        start_date: today - Duration::days(14),
+        target_ml: 3000,
+    };
+
+    goals::save_program(&conn, &program).unwrap();
+    // Two full weeks in: two increments
+    assert_eq!(db::get_goal_ml(&conn), 2000);
+    assert_eq!(program.goal_on(today + Duration::days(7)), 2250);
+    assert_eq!(program.goal_on(NaiveDate::MAX), 3000);
+
+    goals::clear_program(&conn).unwrap();
+    assert!(goals::load_program(&conn).unwrap().is_none());
+    set_setting(&conn, "daily_goal_ml", "2600");
+    assert_eq!(db::get_goal_ml(&conn), 2600);
+}
+
+#[test]
+fn a_goal_program_ramps_within_the_daily_goal_range() {
+    let program = goals::GoalProgram {
+        start_ml: 2000,
+        increment_ml: 500,
+        interval_days: 7,
+        start_date: date(2024, 1, 1),
+        target_ml: 4000,
+    };
+    assert!(program.validate().is_ok());
+
+    let too_high = goals::GoalProgram {
+        target_ml: settings::DAILY_GOAL_RANGE_ML.end() + 1,
+        ..program.clone()
+    };
+    let error = too_high.validate().unwrap_err();
+    assert!(matches!(error, AppError::InvalidInput { ref field, .. } if field == "target_ml"), "{}", error);
+    let too_low = goals::GoalProgram { start_ml: 0, ..program };
+    let error = too_low.validate().unwrap_err();
+    assert!(matches!(error, AppError::InvalidInput { ref field, .. } if field == "start_ml"), "{}", error);
+}
+
+#[test]
+fn the_last_step_is_capped_at_the_target() {
+    let program = ramp();
+    assert_eq!(program.goal_on(date(2024, 3, 1)), 2000);
+    assert_eq!(program.goal_on(date(2024, 4, 7)), 2000);
+    assert_eq!(program.goal_on(date(2024, 4, 8)), 2300);
+    assert_eq!(program.goal_on(date(2024, 4, 15)), 2600);
+    // 2900 would overshoot
+    assert_eq!(program.goal_on(date(2024, 4, 22)), 2800);
+}
+
+#[test]
+fn the_status_counts_steps_up_to_the_target() {
+    let program = ramp();
+    let started = program.status_on(date(2024, 4, 3));
+    assert_eq!((started.phase, started.total_phases), (1, 4));
+    assert_eq!(started.current_goal_ml, 2000);
+    assert_eq!(started.next_increase_date.as_deref(), Some("2024-04-08"));
+    assert!(!started.target_reached);
+
+    let before = program.status_on(date(2024, 3, 20));
+    assert_eq!(before.next_increase_date.as_deref(), Some("2024-04-01"));
+
+    let done = program.status_on(date(2024, 6, 1));
+    assert_eq!((done.phase, done.current_goal_ml), (4, 2800));
+    assert_eq!(done.next_increase_date, None);
+    assert!(done.target_reached);
+}
+
+#[test]
+fn a_saved_program_loads_back_and_a_partial_one_is_no_program() {
+    let conn = open();
+    goals::save_program(&conn, &ramp()).unwrap();
+    let loaded = goals::load_program(&conn).unwrap().unwrap();
+    assert_eq!(
+        (loaded.start_ml, loaded.increment_ml, loaded.interval_days, loaded.start_date, loaded.target_ml),
+        (2000, 300, 7, date(2024, 4, 1), 2800)
+    );
+
+    settings::remove(&conn, "goal_program_target_ml").unwrap();
+    assert!(goals::load_program(&conn).unwrap().is_none());
+}
+
+#[test]
+fn the_plain_goal_is_refused_only_while_a_program_runs() {
+    let conn = open();
+    assert!(goals::ensure_no_program(&conn, "daily_goal_ml").is_ok());
+
+    goals::save_program(&conn, &ramp()).unwrap();
+    let error = goals::ensure_no_program(&conn, "daily_goal_ml").unwrap_err();
+    assert!(matches!(error, AppError::InvalidInput { ref field, .. } if field == "daily_goal_ml"), "{}", error);
+
+    goals::clear_program(&conn).unwrap();
+    assert!(goals::ensure_no_program(&conn, "daily_goal_ml").is_ok());
+}
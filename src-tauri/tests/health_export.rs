@@ -0,0 +1,80 @@
+mod common;
+
+use chrono::{DateTime, NaiveDate};
+use common::{add, at, open};
+use hydra_tracker_lib::health_export::{self, GoogleFitFormat, Volume};
+use rusqlite::Connection;
+
+// The offsets in the fixtures are Berlin's, on either side of the switch to
+// summer time on 2024-03-31
+fn berlin() {
+    std::env::set_var("TZ", "Europe/Berlin");
+}
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+}
+
+fn logged() -> Connection {
+    let conn = open();
+    add(&conn, 333, at(day(30), 9, 0));
+    add(&conn, 1250, at(day(31), 12, 30));
+    add(&conn, 5, at(day(31), 23, 59));
+    // Outside the exported range
+    add(&conn, 400, at(day(29), 9, 0));
+    conn
+}
+
+#[test]
+fn ml_convert_to_exact_liters() {
+    assert_eq!(health_export::ml_to_liters(333), "0.333");
+    assert_eq!(health_export::ml_to_liters(1250), "1.25");
+    assert_eq!(health_export::ml_to_liters(2000), "2");
+    assert_eq!(health_export::ml_to_liters(5), "0.005");
+    assert_eq!(health_export::ml_to_liters(0), "0");
+}
+
+#[test]
+fn apple_health_matches_the_fixture() {
+    berlin();
+    let exported_at = DateTime::parse_from_rfc3339("2024-04-01T08:00:00+02:00").unwrap();
+    let (xml, records) =
+        health_export::apple_health_xml(&logged(), day(30), day(31), Volume::Effective, exported_at).unwrap();
+    assert_eq!(records, 3);
+    assert_eq!(xml, include_str!("fixtures/apple_health.xml"));
+}
+
+#[test]
+fn google_fit_json_matches_the_fixture() {
+    berlin();
+    let (json, records) =
+        health_export::google_fit(&logged(), day(30), day(31), GoogleFitFormat::Json, Volume::Effective).unwrap();
+    assert_eq!(records, 3);
+    let exported: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let expected: serde_json::Value = serde_json::from_str(include_str!("fixtures/google_fit.json")).unwrap();
+    assert_eq!(exported, expected);
+}
+
+#[test]
+fn google_fit_csv_matches_the_fixture() {
+    berlin();
+    let (csv, records) =
+        health_export::google_fit(&logged(), day(30), day(31), GoogleFitFormat::Csv, Volume::Effective).unwrap();
+    assert_eq!(records, 3);
+    assert_eq!(csv, include_str!("fixtures/google_fit.csv"));
+}
+
+#[test]
+fn plain_water_exports_the_same_raw_or_effective() {
+    berlin();
+    let conn = logged();
+    let exported_at = DateTime::parse_from_rfc3339("2024-04-01T08:00:00+02:00").unwrap();
+    let effective = health_export::apple_health_xml(&conn, day(30), day(31), Volume::Effective, exported_at).unwrap();
+    let raw = health_export::apple_health_xml(&conn, day(30), day(31), Volume::Raw, exported_at).unwrap();
+    assert_eq!(effective, raw);
+    for format in [GoogleFitFormat::Json, GoogleFitFormat::Csv] {
+        let effective = health_export::google_fit(&conn, day(30), day(31), format, Volume::Effective).unwrap();
+        let raw = health_export::google_fit(&conn, day(30), day(31), format, Volume::Raw).unwrap();
+        assert_eq!(effective, raw);
+    }
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::open;
+use hydra_tracker_lib::csv_import::{CsvImportSummary, RejectReason};
+use hydra_tracker_lib::{hydrocoach, AppError};
+use rusqlite::Connection;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Both date layouts the app writes, a late drink and three rows it can't take
+const SAMPLE: &str = "tests/fixtures/hydrocoach_sample.csv";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hydra-hydrocoach-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// A ZIP holding `files`, like the one the app shares
+fn zip(path: &Path, files: &[(&str, &[u8])]) {
+    let mut archive = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for (name, contents) in files {
+        archive.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+        archive.write_all(contents).unwrap();
+    }
+    archive.finish().unwrap();
+}
+
+fn entries(conn: &Connection) -> Vec<(String, String, i32, String)> {
+    let mut stmt = conn
+        .prepare("SELECT timestamp, date, amount_ml, source FROM water_entries ORDER BY timestamp")
+        .unwrap();
+    stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn assert_sample_imported(conn: &Connection, summary: &CsvImportSummary) {
+    assert_eq!(summary.inserted, 3);
+    assert_eq!(summary.rejected_total, 3);
+    let rejected: Vec<(usize, RejectReason, &str)> = summary
+        .rejected
+        .iter()
+        .map(|r| (r.line, r.reason, r.value.as_str()))
+        .collect();
+    assert_eq!(
+        rejected,
+        vec![
+            (5, RejectReason::BadTimestamp, "2024-05-02 soon"),
+            (6, RejectReason::NonNumericAmount, "lots"),
+            (7, RejectReason::OutOfRange, "0"),
+        ]
+    );
+
+    let entry = |timestamp: &str, date: &str, amount_ml| {
+        (timestamp.to_string(), date.to_string(), amount_ml, "hydrocoach".to_string())
+    };
+    assert_eq!(
+        entries(conn),
+        vec![
+            entry("2024-05-01 09:00:00", "2024-05-01", 250),
+            entry("2024-05-01 12:30:15", "2024-05-01", 330),
+            // Before the 3:00 rollover
+            entry("2024-05-02 01:15:00", "2024-05-01", 200),
+        ]
+    );
+}
+
+#[test]
+fn the_sample_csv_imports_with_rejects_by_line() {
+    let conn = open();
+    let summary = hydrocoach::import(&conn, Path::new(SAMPLE), 3, false).unwrap();
+    assert_sample_imported(&conn, &summary);
+
+    let again = hydrocoach::import(&conn, Path::new(SAMPLE), 3, false).unwrap();
+    assert_eq!(again.inserted, 0);
+    assert_eq!(again.skipped_duplicates, 3);
+}
+
+#[test]
+fn the_csv_is_found_inside_the_zip() {
+    let dir = temp_dir("zip");
+    let archive = dir.join("HydroCoach.zip");
+    let sample = std::fs::read(SAMPLE).unwrap();
+    zip(&archive, &[("readme.txt", b"not this one"), ("export/drinks.CSV", &sample)]);
+
+    let conn = open();
+    let summary = hydrocoach::import(&conn, &archive, 3, false).unwrap();
+    assert_sample_imported(&conn, &summary);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn an_archive_without_a_csv_is_rejected() {
+    let dir = temp_dir("no-csv");
+    let archive = dir.join("HydroCoach.zip");
+    zip(&archive, &[("readme.txt", b"nothing to import")]);
+
+    let error = hydrocoach::import(&open(), &archive, 0, false).unwrap_err();
+    assert!(matches!(error, AppError::InvalidInput { ref field, .. } if field == "path"), "{}", error);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_file_without_an_amount_column_is_rejected() {
+    let dir = temp_dir("no-amount");
+    let path = dir.join("drinks.csv");
+    std::fs::write(&path, "Date Time,Drink\n2024-05-01 09:00,Water\n").unwrap();
+
+    let error = hydrocoach::import(&open(), &path, 0, false).unwrap_err();
+    assert!(error.to_string().contains("no amount column"), "{}", error);
+    let _ = std::fs::remove_dir_all(&dir);
+}
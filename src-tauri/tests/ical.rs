@@ -0,0 +1,67 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{add, add_on, at, open};
+use hydra_tracker_lib::units::Unit;
+use ical::property::Property;
+use std::io::BufReader;
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, d).unwrap()
+}
+
+// The properties of every event, read back by a calendar parser
+fn read_back(ics: &str) -> Vec<Vec<Property>> {
+    let mut calendars = ical::IcalParser::new(BufReader::new(ics.as_bytes()));
+    let calendar = calendars.next().expect("a calendar").expect("a valid calendar");
+    assert!(calendars.next().is_none());
+    calendar.events.into_iter().map(|event| event.properties).collect()
+}
+
+fn property<'a>(event: &'a [Property], name: &str) -> &'a Property {
+    event.iter().find(|p| p.name == name).unwrap_or_else(|| panic!("no {}", name))
+}
+
+fn value<'a>(event: &'a [Property], name: &str) -> &'a str {
+    property(event, name).value.as_deref().unwrap_or_default()
+}
+
+#[test]
+fn a_calendar_parser_reads_one_all_day_event_per_goal_met_day() {
+    let conn = open();
+    add(&conn, 2100, at(day(1), 9, 0));
+    add(&conn, 2000, at(day(1), 15, 0));
+    add_on(&conn, day(2), 1500);
+    add_on(&conn, day(3), 2000);
+
+    let (ics, count) = hydra_tracker_lib::ical::goal_met_calendar(&conn, day(1), day(3), |_| 2000, Unit::Ml).unwrap();
+    assert_eq!(count, 2);
+    assert!(ics.lines().all(|line| line.len() <= 75 + 1), "{}", ics);
+
+    let events = read_back(&ics);
+    assert_eq!(events.len(), 2);
+    let first = &events[0];
+    assert_eq!(value(first, "UID"), "goal-met-20240501@hydra-tracker");
+    assert_eq!(value(first, "DTSTART"), "20240501");
+    assert_eq!(value(first, "DTEND"), "20240502");
+    assert_eq!(
+        property(first, "DTSTART").params,
+        Some(vec![("VALUE".to_string(), vec!["DATE".to_string()])])
+    );
+    // The thousands comma is escaped on the way out
+    assert_eq!(value(first, "SUMMARY"), "Hydration goal met (4\\,100 ml)");
+    assert_eq!(value(&events[1], "UID"), "goal-met-20240503@hydra-tracker");
+}
+
+#[test]
+fn re_exporting_keeps_each_days_uid() {
+    let conn = open();
+    add_on(&conn, day(1), 2500);
+    let export = || {
+        let (ics, _) = hydra_tracker_lib::ical::goal_met_calendar(&conn, day(1), day(1), |_| 2000, Unit::Ml).unwrap();
+        value(&read_back(&ics)[0], "UID").to_string()
+    };
+    let before = export();
+    add(&conn, 250, at(day(1), 20, 0));
+    assert_eq!(export(), before);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::open;
+use hydra_tracker_lib::{backup, csv_export, db, maintenance, stats};
+use rusqlite::{Connection, ToSql};
+
+// The detail column of EXPLAIN QUERY PLAN, one line per step
+fn plan(conn: &Connection, sql: &str, params: &[&dyn ToSql]) -> Vec<String> {
+    conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql))
+        .unwrap()
+        .query_map(params, |row| row.get(3))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+}
+
+fn indexes(conn: &Connection) -> Vec<String> {
+    conn.prepare("SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'water_entries' ORDER BY name")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+}
+
+#[test]
+fn entries_have_the_timestamp_and_daily_total_indexes() {
+    assert_eq!(indexes(&open()), ["idx_date_amount", "idx_timestamp"]);
+}
+
+#[test]
+fn a_days_entries_are_looked_up_by_date() {
+    let steps = plan(&open(), db::ENTRIES_ON_QUERY, &[&"2024-05-01"]);
+    assert!(steps[0].starts_with("SEARCH water_entries USING INDEX idx_date_amount (date=?)"), "{:?}", steps);
+}
+
+#[test]
+fn daily_totals_are_read_from_the_index_alone() {
+    let steps = plan(&open(), stats::DAILY_TOTALS_QUERY, &[&"2024-05"]);
+    assert_eq!(
+        steps,
+        ["SEARCH water_entries USING COVERING INDEX idx_date_amount (date>? AND date<?)"]
+    );
+}
+
+#[test]
+fn the_export_walks_the_timestamp_index_without_sorting() {
+    let steps = plan(&open(), csv_export::EXPORT_QUERY, &[]);
+    assert_eq!(steps, ["SCAN water_entries USING INDEX idx_timestamp"]);
+}
+
+#[test]
+fn maintenance_gathers_statistics_for_both_indexes() {
+    let conn = open();
+    for day in 1..=20 {
+        common::add_on(&conn, chrono::NaiveDate::from_ymd_opt(2024, 5, day).unwrap(), 250);
+    }
+
+    maintenance::run(&conn).unwrap();
+
+    let analyzed: Vec<String> = conn
+        .prepare("SELECT idx FROM sqlite_stat1 WHERE tbl = 'water_entries' ORDER BY idx")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(analyzed, ["idx_date_amount", "idx_timestamp"]);
+}
+
+#[test]
+fn a_backup_merge_looks_each_entry_up_by_timestamp() {
+    let steps = plan(&open(), backup::ENTRY_EXISTS_QUERY, &[&"2024-05-01 09:00:00", &250, &60]);
+    assert!(
+        steps.iter().any(|step| step.starts_with("SEARCH water_entries USING INDEX idx_timestamp (timestamp>? AND timestamp<?)")),
+        "{:?}",
+        steps
+    );
+}
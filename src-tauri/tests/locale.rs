@@ -0,0 +1,51 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{add_on, open, set_setting};
+use hydra_tracker_lib::locale::{self, Locale};
+use hydra_tracker_lib::stats;
+
+#[test]
+fn german_and_french_month_names_differ_from_english() {
+    for month in 1..=12 {
+        let english = Locale::En.month_name(month);
+        assert_ne!(Locale::De.month_name(month), Locale::Fr.month_name(month), "{}", month);
+        assert!(!english.is_empty());
+        if ![4, 8, 9, 11].contains(&month) {
+            assert_ne!(Locale::De.month_name(month), english, "{}", month);
+        }
+        assert_ne!(Locale::Fr.month_name(month), english, "{}", month);
+    }
+    assert_eq!(Locale::En.month_name(3), "March");
+    assert_eq!(Locale::De.month_name(3), "März");
+    assert_eq!(Locale::Fr.month_name(3), "mars");
+}
+
+#[test]
+fn unknown_tags_fall_back_to_english_and_regions_are_ignored() {
+    assert_eq!(Locale::parse("de-AT"), Locale::De);
+    assert_eq!(Locale::parse("FR_ca"), Locale::Fr);
+    assert_eq!(Locale::parse("pt-BR"), Locale::En);
+    assert_eq!(Locale::parse(""), Locale::En);
+    assert_eq!(Locale::En.month_name(0), "");
+    assert_eq!(Locale::En.month_name(13), "");
+}
+
+#[test]
+fn monthly_stats_name_the_month_in_the_chosen_locale_but_keep_iso_dates() {
+    let conn = open();
+    add_on(&conn, NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(), 500);
+    assert_eq!(locale::current(&conn), Locale::En);
+    assert_eq!(stats::monthly_stats_with_streaks(&conn, 2024, 3).unwrap().month, "March");
+
+    set_setting(&conn, "locale", "\"de\"");
+    let german = stats::monthly_stats_with_streaks(&conn, 2024, 3).unwrap();
+    assert_eq!(german.month, "März");
+    assert_eq!(german.days[4].date, "2024-03-05");
+
+    set_setting(&conn, "locale", "\"fr\"");
+    assert_eq!(stats::monthly_stats_with_streaks(&conn, 2024, 3).unwrap().month, "mars");
+
+    set_setting(&conn, "locale", "\"xx\"");
+    assert_eq!(stats::monthly_stats_with_streaks(&conn, 2024, 3).unwrap().month, "March");
+}
@@ -0,0 +1,76 @@
+use hydra_tracker_lib::logging::{self, RollingFile};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// A fresh directory per test, since they run in parallel
+fn log_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hydra-logging-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn the_log_rotates_by_size_and_keeps_only_the_newest_files() {
+    let dir = log_dir("rotate");
+    let path = dir.join(logging::LOG_FILE);
+    let mut log = RollingFile::new(path.clone(), 100, 3);
+
+    // 29 bytes a line, so three fit in a file
+    for n in 0..20 {
+        log.write_all(format!("line {:02} of the rotation test\n", n).as_bytes()).unwrap();
+    }
+
+    assert_eq!(files(&dir), ["hydra.log", "hydra.log.1", "hydra.log.2"]);
+    for name in files(&dir) {
+        assert!(fs::metadata(dir.join(name)).unwrap().len() <= 100);
+    }
+    let current = fs::read_to_string(&path).unwrap();
+    assert!(current.ends_with("line 19 of the rotation test\n"));
+    assert!(current.starts_with("line 18"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn an_existing_log_is_appended_to_until_it_is_full() {
+    let dir = log_dir("append");
+    let path = dir.join(logging::LOG_FILE);
+    fs::write(&path, "from the last run\n").unwrap();
+
+    let mut log = RollingFile::new(path.clone(), 1024, 2);
+    log.write_all(b"from this run\n").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "from the last run\nfrom this run\n");
+    assert_eq!(files(&dir), ["hydra.log"]);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn recent_lines_reads_back_into_the_previous_file() {
+    let dir = log_dir("recent");
+    let path = dir.join(logging::LOG_FILE);
+    fs::write(dir.join("hydra.log.1"), "one\ntwo\nthree\n").unwrap();
+    fs::write(&path, "four\nfive\n").unwrap();
+
+    assert_eq!(logging::recent_lines(&path, 3).unwrap(), ["three", "four", "five"]);
+    assert_eq!(logging::recent_lines(&path, 100).unwrap().len(), 5);
+    assert!(logging::recent_lines(&path, 0).unwrap().is_empty());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn recent_lines_is_empty_before_anything_was_logged() {
+    let dir = log_dir("empty");
+    assert!(logging::recent_lines(&dir.join(logging::LOG_FILE), 10).unwrap().is_empty());
+    let _ = fs::remove_dir_all(&dir);
+}
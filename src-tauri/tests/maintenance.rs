@@ -0,0 +1,72 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{add, at};
+use hydra_tracker_lib::{db, maintenance};
+use std::path::PathBuf;
+
+fn temp_db(name: &str) -> (PathBuf, rusqlite::Connection) {
+    let dir = std::env::temp_dir().join(format!("hydra-maintenance-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let conn = db::open(dir.join("hydra.db")).unwrap();
+    db::init_db(&conn).unwrap();
+    (dir, conn)
+}
+
+#[test]
+fn vacuum_gives_back_the_space_of_deleted_entries() {
+    let (dir, conn) = temp_db("vacuum");
+    let day = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    for i in 0..2000 {
+        add(&conn, 250, at(day, 9, i % 60));
+    }
+    conn.execute("DELETE FROM water_entries WHERE amount_ml = 250", []).unwrap();
+
+    let report = maintenance::run(&conn).unwrap();
+
+    assert!(report.integrity_ok);
+    assert!(report.problems.is_empty());
+    assert!(report.vacuumed);
+    assert!(report.size_after_bytes < report.size_before_bytes);
+    let file_size = std::fs::metadata(dir.join("hydra.db")).unwrap().len();
+    assert_eq!(file_size, report.size_after_bytes);
+
+    drop(conn);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_failed_integrity_check_is_reported_and_nothing_is_changed() {
+    let (dir, conn) = temp_db("corrupt");
+    let day = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    add(&conn, 250, at(day, 9, 0));
+    // Points idx_date_amount at columns it wasn't built from, so the entry
+    // is missing from it as far as the check can tell
+    conn.execute_batch(
+        "PRAGMA writable_schema = ON;
+         UPDATE sqlite_master SET sql = 'CREATE INDEX idx_date_amount ON water_entries(amount_ml, date)'
+           WHERE name = 'idx_date_amount';
+         PRAGMA writable_schema = OFF;",
+    )
+    .unwrap();
+    drop(conn);
+    let conn = db::open(dir.join("hydra.db")).unwrap();
+
+    let report = maintenance::run(&conn).unwrap();
+
+    assert!(!report.integrity_ok);
+    assert!(report.problems.iter().any(|p| p.contains("idx_date_amount")), "{:?}", report.problems);
+    assert!(!report.vacuumed);
+    assert_eq!(report.size_after_bytes, report.size_before_bytes);
+
+    drop(conn);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn only_one_run_at_a_time() {
+    let guard = maintenance::try_begin().expect("nothing else is running");
+    assert!(maintenance::try_begin().is_none());
+    drop(guard);
+    assert!(maintenance::try_begin().is_some());
+}
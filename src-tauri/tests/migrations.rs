@@ -0,0 +1,187 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{at, open};
+use hydra_tracker_lib::db;
+use hydra_tracker_lib::migrations::{self, MigrationError};
+use rusqlite::Connection;
+
+fn tables(conn: &Connection) -> Vec<String> {
+    conn.prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name != 'sqlite_sequence' ORDER BY name")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+}
+
+#[test]
+fn a_new_database_ends_at_the_latest_version() {
+    let conn = Connection::open_in_memory().unwrap();
+    assert_eq!(migrations::schema_version(&conn).unwrap(), 0);
+
+    let applied = migrations::run(&conn).unwrap();
+
+    assert_eq!(applied as u32, migrations::latest_version());
+    assert_eq!(migrations::schema_version(&conn).unwrap(), migrations::latest_version());
+}
+
+#[test]
+fn running_again_applies_nothing() {
+    let conn = open();
+    assert_eq!(migrations::run(&conn).unwrap(), 0);
+    assert_eq!(migrations::schema_version(&conn).unwrap(), migrations::latest_version());
+}
+
+#[test]
+fn a_database_from_before_user_version_is_brought_up_to_date() {
+    let conn = open();
+    let before = tables(&conn);
+    conn.pragma_update(None, "user_version", 0).unwrap();
+
+    migrations::run(&conn).unwrap();
+
+    assert_eq!(tables(&conn), before);
+    assert_eq!(migrations::schema_version(&conn).unwrap(), migrations::latest_version());
+}
+
+#[test]
+fn the_legacy_settings_row_moves_into_app_settings() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE settings (id INTEGER PRIMARY KEY, daily_goal_ml INTEGER, sound_enabled INTEGER);
+         INSERT INTO settings VALUES (1, 2500, 0);",
+    )
+    .unwrap();
+
+    db::init_db(&conn).unwrap();
+
+    assert!(!tables(&conn).contains(&"settings".to_string()));
+    let goal: String = conn
+        .query_row("SELECT value FROM app_settings WHERE key = 'daily_goal_ml'", [], |row| row.get(0))
+        .unwrap();
+    let sound: String = conn
+        .query_row("SELECT value FROM app_settings WHERE key = 'sound_enabled'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!((goal.as_str(), sound.as_str()), ("2500", "false"));
+}
+
+#[test]
+fn a_failed_step_rolls_back_and_keeps_the_version() {
+    let conn = open();
+    conn.pragma_update(None, "user_version", 2).unwrap();
+    // Two rows for the integer amounts step to rewrite, and a trigger that
+    // fails it on the second
+    conn.execute_batch(
+        "INSERT INTO water_entries (amount_ml, timestamp, date) VALUES
+            (250.5, '2024-05-01 09:00:00', '2024-05-01'),
+            (330.5, '2024-05-01 10:00:00', '2024-05-01');
+         CREATE TEMP TRIGGER refuse BEFORE UPDATE ON water_entries WHEN old.amount_ml > 300
+         BEGIN SELECT RAISE(ABORT, 'refused'); END;",
+    )
+    .unwrap();
+
+    let error = migrations::run(&conn).unwrap_err();
+
+    assert!(matches!(error, MigrationError::Failed { version: 3, .. }), "{}", error);
+    assert_eq!(migrations::schema_version(&conn).unwrap(), 2);
+    let kinds: Vec<String> = conn
+        .prepare("SELECT typeof(amount_ml) FROM water_entries ORDER BY timestamp")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(kinds, ["real", "real"]);
+}
+
+#[test]
+fn a_database_from_a_newer_version_is_refused_untouched() {
+    let conn = open();
+    let newer = migrations::latest_version() + 1;
+    conn.pragma_update(None, "user_version", newer).unwrap();
+
+    let error = db::init_db(&conn).unwrap_err();
+
+    assert!(matches!(error, MigrationError::TooNew { found, .. } if found == newer));
+    assert!(error.to_string().contains("newer version"));
+    assert_eq!(migrations::schema_version(&conn).unwrap(), newer);
+}
+
+// water_entries as it was before dates were checked against timestamps
+fn unchecked_entries() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE water_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            amount_ml INTEGER NOT NULL,
+            timestamp TEXT NOT NULL,
+            date TEXT NOT NULL,
+            source TEXT NOT NULL DEFAULT 'app'
+         );",
+    )
+    .unwrap();
+    conn
+}
+
+fn dates(conn: &Connection) -> Vec<String> {
+    conn.prepare("SELECT date FROM water_entries ORDER BY id")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+}
+
+#[test]
+fn entry_dates_that_disagree_with_their_timestamp_are_corrected() {
+    let conn = unchecked_entries();
+    conn.execute_batch(
+        "INSERT INTO water_entries (amount_ml, timestamp, date) VALUES
+            (250, '2024-05-01 09:00:00', '2024-05-01'),
+            (330, '2024-05-02 09:00:00', '2024-04-20'),
+            (200, '2024-05-03T09:00:00', '2024-01-01');",
+    )
+    .unwrap();
+
+    db::init_db(&conn).unwrap();
+
+    assert_eq!(dates(&conn), ["2024-05-01", "2024-05-02", "2024-05-03"]);
+}
+
+#[test]
+fn the_schema_refuses_a_date_its_timestamp_cannot_have() {
+    let conn = open();
+    let insert = |date: &str| {
+        conn.execute(
+            "INSERT INTO water_entries (amount_ml, timestamp, date) VALUES (250, '2024-05-01 02:00:00', ?1)",
+            [date],
+        )
+    };
+
+    // The calendar day, or the one before under a rollover hour
+    insert("2024-05-01").unwrap();
+    insert("2024-04-30").unwrap();
+    assert!(insert("2024-05-02").is_err());
+    assert!(insert("2024-04-29").is_err());
+    assert!(conn
+        .execute("UPDATE water_entries SET timestamp = '2024-06-01 09:00:00'", [])
+        .is_err());
+}
+
+#[test]
+fn ids_of_deleted_entries_are_not_handed_out_again_after_the_rebuild() {
+    let conn = unchecked_entries();
+    conn.execute_batch(
+        "INSERT INTO water_entries (amount_ml, timestamp, date) VALUES
+            (250, '2024-05-01 09:00:00', '2024-05-01'),
+            (330, '2024-05-01 10:00:00', '2024-05-01');
+         DELETE FROM water_entries WHERE id = 2;",
+    )
+    .unwrap();
+
+    db::init_db(&conn).unwrap();
+
+    let entry = db::insert_entry(&conn, 200, "app", at(NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(), 9, 0)).unwrap();
+    assert_eq!(entry.id, 3);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{add_on, date, open};
+use hydra_tracker_lib::milestones;
+
+fn thresholds(reached: Vec<milestones::Milestone>) -> Vec<i64> {
+    reached.into_iter().map(|m| m.threshold_ml).collect()
+}
+
+#[test]
+fn every_threshold_passed_is_announced_once_lowest_first() {
+    let conn = open();
+    assert!(milestones::check_milestones(&conn).unwrap().is_empty());
+
+    add_on(&conn, date(2024, 5, 1), 99_999);
+    assert!(milestones::check_milestones(&conn).unwrap().is_empty());
+
+    add_on(&conn, date(2024, 5, 2), 500_001);
+    let reached = milestones::check_milestones(&conn).unwrap();
+    assert_eq!(reached[0].lifetime_ml, 600_000);
+    assert_eq!(thresholds(reached), [100_000, 500_000]);
+    assert!(milestones::check_milestones(&conn).unwrap().is_empty());
+}
+
+#[test]
+fn a_milestone_is_not_announced_again_after_dropping_below_it() {
+    let conn = open();
+    add_on(&conn, date(2024, 5, 1), 100_000);
+    assert_eq!(thresholds(milestones::check_milestones(&conn).unwrap()), [100_000]);
+
+    conn.execute("DELETE FROM water_entries", []).unwrap();
+    add_on(&conn, date(2024, 5, 2), 100_000);
+    assert!(milestones::check_milestones(&conn).unwrap().is_empty());
+}
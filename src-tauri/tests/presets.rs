@@ -0,0 +1,77 @@
+mod common;
+
+use common::{date, open, set_setting};
+use hydra_tracker_lib::{db, goals, presets, AppError};
+
+#[test]
+fn activating_a_preset_changes_the_goal_and_deleting_it_keeps_the_amount() {
+    let conn = open();
+    presets::save(&conn, "Summer", 3500).unwrap();
+    presets::activate(&conn, "Summer").unwrap();
+    assert_eq!(db::get_goal_ml(&conn), 3500);
+
+    // Editing the active preset moves the goal with it
+    presets::save(&conn, "Summer", 3800).unwrap();
+    assert_eq!(db::get_goal_ml(&conn), 3800);
+
+    presets::delete(&conn, "Summer").unwrap();
+    assert!(presets::active(&conn).unwrap().is_none());
+    assert_eq!(db::get_goal_ml(&conn), 3800);
+}
+
+#[test]
+fn a_preset_cant_be_activated_while_a_goal_program_runs() {
+    let conn = open();
+    presets::save(&conn, "Summer", 3500).unwrap();
+    let program = goals::GoalProgram {
+        start_ml: 1500,
+        increment_ml: 250,
+        interval_days: 7,
+        start_date: date(2024, 1, 1),
+        target_ml: 3000,
+    };
+    goals::save_program(&conn, &program).unwrap();
+
+    let error = presets::activate(&conn, "Summer").unwrap_err();
+    assert!(matches!(error, AppError::InvalidInput { ref field, .. } if field == "name"), "{}", error);
+    assert!(presets::active(&conn).unwrap().is_none());
+
+    goals::clear_program(&conn).unwrap();
+    presets::activate(&conn, "Summer").unwrap();
+    assert_eq!(db::get_goal_ml(&conn), 3500);
+}
+
+#[test]
+fn a_preset_needs_a_name_and_a_goal_in_range() {
+    let conn = open();
+    let field = |name: &str, goal_ml: i32| match presets::save(&conn, name, goal_ml) {
+        Err(AppError::InvalidInput { field, .. }) => Some(field),
+        _ => None,
+    };
+    assert_eq!(field("  ", 2000).as_deref(), Some("name"));
+    assert_eq!(field(&"x".repeat(200), 2000).as_deref(), Some("name"));
+    assert_eq!(field("Summer", 0).as_deref(), Some("goal_ml"));
+    assert_eq!(field(" Summer ", 2000), None);
+
+    // Names are trimmed, and saving again updates in place
+    presets::save(&conn, "Summer", 2500).unwrap();
+    let listed: Vec<(String, i32)> = presets::list(&conn).unwrap().into_iter().map(|p| (p.name, p.goal_ml)).collect();
+    assert_eq!(listed, [("Summer".to_string(), 2500)]);
+}
+
+#[test]
+fn editing_the_goal_by_hand_leaves_no_preset_active() {
+    let conn = open();
+    presets::save(&conn, "Summer", 3500).unwrap();
+    presets::activate(&conn, "Summer").unwrap();
+    assert_eq!(presets::active(&conn).unwrap().map(|p| p.name).as_deref(), Some("Summer"));
+
+    set_setting(&conn, "daily_goal_ml", "2000");
+    assert!(presets::active(&conn).unwrap().is_none());
+}
+
+#[test]
+fn activating_an_unknown_preset_is_not_found() {
+    let conn = open();
+    assert!(matches!(presets::activate(&conn, "Winter"), Err(AppError::NotFound(_))));
+}
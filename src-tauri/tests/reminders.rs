@@ -0,0 +1,172 @@
+mod common;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use common::{at, date};
+use hydra_tracker_lib::{db, reminders, DailyStats, Settings};
+
+fn reminding_on(days: &[u8], day_starts_at_hour: i32) -> Settings {
+    Settings {
+        reminder_days: days.to_vec(),
+        day_starts_at_hour,
+        ..Settings::default()
+    }
+}
+
+#[test]
+fn the_reminder_weekday_is_the_day_entries_are_filed_under() {
+    // Every hour of a week, under a midnight and a 3:00 rollover
+    let start = at(date(2024, 5, 6), 0, 30);
+    for day_starts_at_hour in [0, 3] {
+        for hour in 0..7 * 24 {
+            let now = start + Duration::hours(hour);
+            let filed_under = NaiveDate::parse_from_str(&db::entry_date(now, day_starts_at_hour), "%Y-%m-%d").unwrap();
+            let weekday = filed_under.weekday().num_days_from_monday() as u8;
+            let others: Vec<u8> = reminders::ALL_DAYS.into_iter().filter(|d| *d != weekday).collect();
+
+            assert!(reminders::is_reminder_day(&reminding_on(&[weekday], day_starts_at_hour), now), "{}", now);
+            assert!(!reminders::is_reminder_day(&reminding_on(&others, day_starts_at_hour), now), "{}", now);
+        }
+    }
+}
+
+#[test]
+fn one_in_the_morning_on_saturday_is_still_friday_with_a_3am_rollover() {
+    let weekdays = [0, 1, 2, 3, 4];
+    let saturday = date(2024, 5, 11);
+    assert!(reminders::is_reminder_day(&reminding_on(&weekdays, 3), at(saturday, 1, 0)));
+    assert!(!reminders::is_reminder_day(&reminding_on(&weekdays, 3), at(saturday, 3, 0)));
+    assert!(!reminders::is_reminder_day(&reminding_on(&weekdays, 0), at(saturday, 1, 0)));
+}
+
+#[test]
+fn should_remind_skips_days_off() {
+    let weekdays = reminding_on(&[0, 1, 2, 3, 4], 0);
+    assert!(reminders::should_remind(&weekdays, at(date(2024, 5, 10), 10, 0)));
+    assert!(!reminders::should_remind(&weekdays, at(date(2024, 5, 11), 10, 0)));
+}
+
+#[test]
+fn an_empty_day_list_is_only_allowed_with_reminders_off() {
+    let mut settings = reminding_on(&[], 0);
+    let error = reminders::validate_reminder_days(&settings).unwrap_err();
+    assert_eq!(error.field, "reminder_days");
+
+    settings.reminder_enabled = false;
+    assert!(reminders::validate_reminder_days(&settings).is_ok());
+}
+
+#[test]
+fn days_past_sunday_are_rejected() {
+    let error = reminders::validate_reminder_days(&reminding_on(&[0, 7], 0)).unwrap_err();
+    assert_eq!(error.field, "reminder_days");
+    assert!(reminders::validate_reminder_days(&reminding_on(&reminders::ALL_DAYS, 0)).is_ok());
+}
+
+fn drunk(total_ml: i32, goal_ml: i32) -> DailyStats {
+    DailyStats::new("2024-05-10".to_string(), total_ml, goal_ml, 1, None)
+}
+
+fn smart() -> Settings {
+    Settings {
+        smart_reminders: true,
+        escalation_enabled: false,
+        ..Settings::default()
+    }
+}
+
+#[test]
+fn the_smart_interval_spreads_the_sips_left_over_the_rest_of_the_day() {
+    let day = date(2024, 5, 10);
+    // 1500 ml is six sips over the four hours to midnight
+    assert_eq!(reminders::smart_interval_minutes(&smart(), &drunk(500, 2000), at(day, 20, 0)), Some(40));
+    // A part sip still counts as one
+    assert_eq!(reminders::smart_interval_minutes(&smart(), &drunk(1100, 2000), at(day, 20, 0)), Some(60));
+
+    // Quiet hours end the active day at 22:00 instead
+    let quiet = Settings {
+        quiet_hours_enabled: true,
+        ..smart()
+    };
+    assert_eq!(reminders::smart_interval_minutes(&quiet, &drunk(1000, 2000), at(day, 18, 0)), Some(60));
+}
+
+#[test]
+fn the_smart_interval_stays_between_the_limits() {
+    let day = date(2024, 5, 10);
+    assert_eq!(
+        reminders::smart_interval_minutes(&smart(), &drunk(1750, 2000), at(day, 8, 0)),
+        Some(reminders::SMART_MAX_INTERVAL_MINUTES)
+    );
+    assert_eq!(
+        reminders::smart_interval_minutes(&smart(), &drunk(0, 2000), at(day, 23, 0)),
+        Some(reminders::SMART_MIN_INTERVAL_MINUTES)
+    );
+}
+
+#[test]
+fn no_more_smart_reminders_once_the_goal_is_met() {
+    let day = date(2024, 5, 10);
+    assert_eq!(reminders::smart_interval_minutes(&smart(), &drunk(2000, 2000), at(day, 12, 0)), None);
+    assert_eq!(reminders::smart_interval_minutes(&smart(), &drunk(2600, 2000), at(day, 12, 0)), None);
+    assert_eq!(reminders::reminder_interval_minutes(&smart(), &drunk(2000, 2000), at(day, 12, 0), false), None);
+}
+
+#[test]
+fn resuming_after_the_goal_falls_back_to_the_fixed_interval() {
+    let settings = Settings {
+        reminder_interval_minutes: 45,
+        ..smart()
+    };
+    let noon = at(date(2024, 5, 10), 12, 0);
+    assert_eq!(reminders::reminder_interval_minutes(&settings, &drunk(2000, 2000), noon, true), Some(45));
+}
+
+fn quiet(start: i32, end: i32) -> Settings {
+    Settings {
+        quiet_hours_enabled: true,
+        quiet_hours_start: start,
+        quiet_hours_end: end,
+        ..Settings::default()
+    }
+}
+
+#[test]
+fn quiet_hours_include_their_start_but_not_their_end() {
+    let lunch = quiet(12 * 60, 13 * 60);
+    assert!(!reminders::in_quiet_hours(&lunch, 12 * 60 - 1));
+    assert!(reminders::in_quiet_hours(&lunch, 12 * 60));
+    assert!(reminders::in_quiet_hours(&lunch, 13 * 60 - 1));
+    assert!(!reminders::in_quiet_hours(&lunch, 13 * 60));
+}
+
+#[test]
+fn quiet_hours_can_wrap_midnight() {
+    let night = quiet(22 * 60, 7 * 60);
+    for minute in [22 * 60, 23 * 60 + 30, 0, 6 * 60 + 59] {
+        assert!(reminders::in_quiet_hours(&night, minute), "{}", minute);
+    }
+    for minute in [7 * 60, 12 * 60, 22 * 60 - 1] {
+        assert!(!reminders::in_quiet_hours(&night, minute), "{}", minute);
+    }
+    assert!(!reminders::should_remind(&night, at(date(2024, 5, 10), 23, 30)));
+    assert!(reminders::should_remind(&night, at(date(2024, 5, 10), 7, 0)));
+}
+
+#[test]
+fn quiet_hours_turned_off_never_silence_anything() {
+    let off = Settings {
+        quiet_hours_enabled: false,
+        ..quiet(22 * 60, 7 * 60)
+    };
+    assert!(!reminders::in_quiet_hours(&off, 23 * 60));
+}
+
+#[test]
+fn quiet_hours_need_two_different_minutes_of_the_day() {
+    let field = |settings: Settings| reminders::validate_quiet_hours(&settings).err().map(|e| e.field);
+    assert_eq!(field(quiet(0, 1439)), None);
+    assert_eq!(field(quiet(22 * 60, 7 * 60)), None);
+    assert_eq!(field(quiet(-1, 7 * 60)), Some("quiet_hours"));
+    assert_eq!(field(quiet(22 * 60, 1440)), Some("quiet_hours"));
+    assert_eq!(field(quiet(8 * 60, 8 * 60)), Some("quiet_hours"));
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::{add_on, date, open, set_setting};
+use hydra_tracker_lib::report;
+use hydra_tracker_lib::stats;
+use hydra_tracker_lib::units::Unit;
+use rusqlite::Connection;
+
+fn logged() -> Connection {
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "2000");
+    add_on(&conn, date(2024, 2, 5), 2000);
+    add_on(&conn, date(2024, 2, 6), 500);
+    add_on(&conn, date(2024, 3, 1), 750);
+    conn
+}
+
+#[test]
+fn the_monthly_html_has_a_row_and_a_bar_for_every_day() {
+    let stats = stats::monthly_stats_with_streaks(&logged(), 2024, 2).unwrap();
+    let html = report::monthly_html(&stats, Unit::Ml);
+
+    assert!(html.contains("<title>Hydration report: February 2024</title>"), "{}", html);
+    assert_eq!(html.matches("<rect ").count(), 29);
+    assert_eq!(html.matches("<tr><td>2024-02-").count(), 29);
+    assert!(html.contains(
+        "<tr><td>2024-02-05</td><td class=\"num\">2,000 ml</td><td class=\"num\">1</td><td class=\"num\">100%</td><td>✓ met</td></tr>"
+    ));
+    assert!(html.contains(
+        "<tr><td>2024-02-06</td><td class=\"num\">500 ml</td><td class=\"num\">1</td><td class=\"num\">25%</td><td></td></tr>"
+    ));
+    assert!(html.contains("<div>Total<strong>2,500 ml</strong></div>"));
+    // Only the day that met the goal is drawn in the goal color
+    assert_eq!(html.matches("fill=\"#4ade80\"").count(), 1);
+}
+
+#[test]
+fn the_monthly_html_shows_amounts_in_the_chosen_unit() {
+    let stats = stats::monthly_stats_with_streaks(&logged(), 2024, 2).unwrap();
+    let html = report::monthly_html(&stats, Unit::FlOz);
+    assert!(html.contains("<td class=\"num\">67.6 fl oz</td>"));
+    assert!(!html.contains(" ml<"));
+}
+
+#[test]
+fn daily_totals_cover_the_range_inclusively() {
+    let totals = report::daily_totals(&logged(), date(2024, 2, 6), date(2024, 3, 1)).unwrap();
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals[&date(2024, 2, 6)], 500);
+    assert_eq!(totals[&date(2024, 3, 1)], 750);
+}
+
+#[test]
+fn the_pdf_runs_onto_more_pages_for_a_long_range() {
+    let conn = logged();
+    let goal_on = |_| 2000;
+    let today = date(2024, 3, 1);
+    let (month, pages) = report::range_pdf(
+        &conn,
+        date(2024, 2, 1),
+        date(2024, 2, 29),
+        today,
+        goal_on,
+        Unit::Ml,
+        Some("Sam"),
+    )
+    .unwrap();
+    assert!(month.starts_with(b"%PDF-"));
+    assert_eq!(pages, 1);
+
+    let (years, pages) =
+        report::range_pdf(&conn, date(2020, 1, 1), date(2024, 12, 31), today, goal_on, Unit::Ml, None).unwrap();
+    assert!(years.starts_with(b"%PDF-"));
+    assert!(pages > 1, "{}", pages);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{add_on, date, open};
+use hydra_tracker_lib::{retention, AppError};
+
+fn entry_count(conn: &rusqlite::Connection) -> i64 {
+    conn.query_row("SELECT COUNT(*) FROM water_entries", [], |row| row.get(0)).unwrap()
+}
+
+fn logged() -> rusqlite::Connection {
+    let conn = open();
+    add_on(&conn, date(2023, 12, 31), 250);
+    add_on(&conn, date(2024, 1, 1), 300);
+    add_on(&conn, date(2024, 1, 2), 400);
+    conn
+}
+
+fn is_token_error(error: &AppError) -> bool {
+    matches!(error, AppError::InvalidInput { field, .. } if field == "token")
+}
+
+#[test]
+fn a_purge_deletes_what_its_preview_counted() {
+    let conn = logged();
+    let pending = retention::PendingPurge::default();
+    let preview = retention::preview(&conn, &pending, date(2024, 1, 2)).unwrap();
+    assert_eq!((preview.before.as_str(), preview.entries), ("2024-01-02", 2));
+
+    assert_eq!(retention::purge(&conn, &pending, date(2024, 1, 2), &preview.token).unwrap(), 2);
+    assert_eq!(entry_count(&conn), 1);
+}
+
+#[test]
+fn a_purge_needs_the_token_for_the_same_date() {
+    let conn = logged();
+    let pending = retention::PendingPurge::default();
+    let error = retention::purge(&conn, &pending, date(2024, 1, 2), "guess").unwrap_err();
+    assert!(is_token_error(&error), "{}", error);
+
+    let preview = retention::preview(&conn, &pending, date(2024, 1, 1)).unwrap();
+    let error = retention::purge(&conn, &pending, date(2024, 1, 2), &preview.token).unwrap_err();
+    assert!(is_token_error(&error), "{}", error);
+    let error = retention::purge(&conn, &pending, date(2024, 1, 1), "guess").unwrap_err();
+    assert!(is_token_error(&error), "{}", error);
+    assert_eq!(entry_count(&conn), 3);
+}
+
+#[test]
+fn a_token_is_good_for_one_purge() {
+    let conn = logged();
+    let pending = retention::PendingPurge::default();
+    let preview = retention::preview(&conn, &pending, date(2024, 1, 1)).unwrap();
+    assert_eq!(retention::purge(&conn, &pending, date(2024, 1, 1), &preview.token).unwrap(), 1);
+
+    let error = retention::purge(&conn, &pending, date(2024, 1, 1), &preview.token).unwrap_err();
+    assert!(is_token_error(&error), "{}", error);
+}
+
+#[test]
+fn a_new_preview_replaces_the_last_token() {
+    let conn = logged();
+    let pending = retention::PendingPurge::default();
+    let first = retention::preview(&conn, &pending, date(2024, 1, 2)).unwrap();
+    let second = retention::preview(&conn, &pending, date(2024, 1, 2)).unwrap();
+    assert_ne!(first.token, second.token);
+
+    assert!(retention::purge(&conn, &pending, date(2024, 1, 2), &first.token).is_err());
+}
+
+#[test]
+fn retention_keeps_whole_years_back_from_today() {
+    let conn = logged();
+    assert_eq!(retention::apply_retention(&conn, 0, date(2025, 1, 2)).unwrap(), 0);
+    // Keeps 2024-01-02 onwards
+    assert_eq!(retention::apply_retention(&conn, 1, date(2025, 1, 2)).unwrap(), 2);
+    assert_eq!(entry_count(&conn), 1);
+}
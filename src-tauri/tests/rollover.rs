@@ -0,0 +1,29 @@
+mod common;
+
+use common::date;
+use hydra_tracker_lib::rollover::DayWatch;
+
+#[test]
+fn the_first_day_seen_is_not_a_rollover() {
+    let mut watch = DayWatch::default();
+    assert_eq!(watch.advance(date(2024, 5, 1)), None);
+    assert_eq!(watch.advance(date(2024, 5, 1)), None);
+}
+
+#[test]
+fn a_new_day_reports_the_one_before_once() {
+    let mut watch = DayWatch::default();
+    watch.advance(date(2024, 5, 1));
+
+    assert_eq!(watch.advance(date(2024, 5, 2)), Some(date(2024, 5, 1)));
+    assert_eq!(watch.advance(date(2024, 5, 2)), None);
+}
+
+#[test]
+fn sleeping_through_several_days_rolls_over_once() {
+    let mut watch = DayWatch::default();
+    watch.advance(date(2024, 5, 1));
+
+    assert_eq!(watch.advance(date(2024, 5, 4)), Some(date(2024, 5, 1)));
+    assert_eq!(watch.advance(date(2024, 5, 4)), None);
+}
@@ -0,0 +1,120 @@
+use chrono::{Duration as ChronoDuration, NaiveDate, NaiveDateTime};
+use hydra_tracker_lib::analytics::{PatternBucket, TypicalPattern};
+use hydra_tracker_lib::scheduler::{adaptive_next_fire_at, clock_jump, next_fire_at};
+use std::time::Duration;
+
+fn at(hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+}
+
+#[test]
+fn the_next_reminder_is_an_interval_after_the_latest_anchor() {
+    assert_eq!(next_fire_at(Some(at(9, 0)), Some(at(10, 0)), 60, 0), Some(at(11, 0)));
+    assert_eq!(next_fire_at(Some(at(10, 30)), Some(at(10, 0)), 60, 0), Some(at(11, 30)));
+    assert_eq!(next_fire_at(None, Some(at(10, 0)), 45, 0), Some(at(10, 45)));
+    assert_eq!(next_fire_at(None, None, 60, 0), None);
+}
+
+#[test]
+fn a_reminder_inside_the_freshness_window_moves_out_in_whole_intervals() {
+    // Reminder at 10:40, entry at 10:50: 11:10 is inside the 30 minute
+    // window, so the next is two 20 minute intervals after the entry
+    assert_eq!(next_fire_at(Some(at(10, 50)), Some(at(10, 40)), 20, 30), Some(at(11, 30)));
+    // Clear of the window already
+    assert_eq!(next_fire_at(Some(at(10, 0)), None, 60, 30), Some(at(11, 0)));
+}
+
+#[test]
+fn a_reminder_due_just_as_the_window_closes_is_not_moved() {
+    // Entry at 10:00, 30 minute window: the window is over at 10:30
+    assert_eq!(next_fire_at(Some(at(10, 0)), Some(at(9, 30)), 30, 30), Some(at(10, 30)));
+    assert_eq!(next_fire_at(Some(at(10, 0)), Some(at(10, 10)), 20, 30), Some(at(10, 30)));
+    // Moved out, it can land exactly on the close too
+    assert_eq!(next_fire_at(Some(at(10, 0)), None, 20, 40), Some(at(10, 40)));
+    // A window of several intervals rounds up to the next whole one
+    assert_eq!(next_fire_at(Some(at(10, 0)), None, 20, 50), Some(at(11, 0)));
+}
+
+#[test]
+fn a_zero_window_never_moves_a_reminder() {
+    assert_eq!(next_fire_at(Some(at(10, 50)), Some(at(10, 40)), 20, 0), Some(at(11, 10)));
+    assert_eq!(next_fire_at(Some(at(10, 50)), None, 5, 0), Some(at(10, 55)));
+    assert_eq!(next_fire_at(Some(at(10, 0)), Some(at(10, 50)), 20, 0), Some(at(11, 10)));
+}
+
+// Typical at 9:00, 12:30 and 18:00
+fn pattern() -> TypicalPattern {
+    TypicalPattern {
+        computed_on: "2024-05-01".to_string(),
+        days_counted: 30,
+        enough_history: true,
+        buckets: (0..48)
+            .map(|i| PatternBucket {
+                minute_of_day: i * 30,
+                share: 0.0,
+                typical: [18, 25, 36].contains(&i),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn adaptive_reminders_go_to_the_next_typical_time() {
+    assert_eq!(adaptive_next_fire_at(&pattern(), None, Some(at(9, 0)), 0), Some(at(12, 30)));
+    assert_eq!(adaptive_next_fire_at(&pattern(), Some(at(10, 0)), Some(at(9, 0)), 0), Some(at(12, 30)));
+    // Past the last one it's tomorrow's first
+    let tomorrow_morning = at(9, 0) + ChronoDuration::days(1);
+    assert_eq!(adaptive_next_fire_at(&pattern(), Some(at(18, 5)), None, 0), Some(tomorrow_morning));
+    assert_eq!(adaptive_next_fire_at(&pattern(), None, None, 0), None);
+}
+
+#[test]
+fn adaptive_reminders_skip_typical_times_inside_the_freshness_window() {
+    // Drank at 12:15, so 12:30 is too soon after it
+    assert_eq!(adaptive_next_fire_at(&pattern(), Some(at(12, 15)), None, 30), Some(at(18, 0)));
+    // Only times after the window count, and this one closes at 12:30
+    assert_eq!(adaptive_next_fire_at(&pattern(), Some(at(12, 0)), None, 30), Some(at(18, 0)));
+    assert_eq!(adaptive_next_fire_at(&pattern(), Some(at(12, 0)), None, 29), Some(at(12, 30)));
+}
+
+#[test]
+fn adaptive_reminders_need_a_typical_time() {
+    let flat = TypicalPattern {
+        buckets: Vec::new(),
+        ..pattern()
+    };
+    assert_eq!(adaptive_next_fire_at(&flat, Some(at(10, 0)), None, 0), None);
+}
+
+#[test]
+fn a_sleep_shows_as_wall_time_the_monotonic_clock_missed() {
+    let jump = clock_jump(ChronoDuration::hours(8), Duration::from_secs(30)).unwrap();
+    assert_eq!(jump.num_minutes(), 479);
+}
+
+#[test]
+fn a_slow_pass_is_not_a_jump() {
+    // Waiting five minutes on the database moves both clocks alike
+    assert_eq!(clock_jump(ChronoDuration::minutes(5), Duration::from_secs(5 * 60)), None);
+    assert_eq!(clock_jump(ChronoDuration::seconds(31), Duration::from_secs(30)), None);
+}
+
+#[test]
+fn a_clock_set_back_is_a_negative_jump() {
+    let jump = clock_jump(ChronoDuration::minutes(-60), Duration::from_secs(30)).unwrap();
+    assert!(jump < ChronoDuration::zero());
+}
+
+#[test]
+fn after_sleeping_through_several_intervals_one_reminder_is_due() {
+    // Last reminder at 22:00, hourly; the machine wakes at 07:00. Recomputed
+    // from the stored anchors the next is 23:00, already past, so one pass
+    // fires once rather than once per missed interval.
+    let woke = at(23, 0) + ChronoDuration::hours(8);
+    let next = next_fire_at(None, Some(at(22, 0)), 60, 0).unwrap();
+    assert_eq!(next, at(23, 0));
+    assert!(next <= woke);
+
+    // That reminder becomes the anchor, and the one after counts from waking
+    assert_eq!(next_fire_at(None, Some(woke), 60, 0), Some(woke + ChronoDuration::hours(1)));
+}
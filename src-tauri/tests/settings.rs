@@ -0,0 +1,218 @@
+mod common;
+
+use common::{open, set_setting};
+use hydra_tracker_lib::{db, settings, Settings};
+
+#[test]
+fn an_empty_database_loads_the_defaults() {
+    let conn = open();
+    let loaded = settings::load(&conn).unwrap();
+    let defaults = Settings::default();
+
+    assert!(settings::changed_fields(&defaults, &loaded).is_empty());
+    assert_eq!(db::get_goal_ml(&conn), defaults.daily_goal_ml);
+    assert_eq!(db::get_day_starts_at_hour(&conn), 0);
+}
+
+#[test]
+fn saved_settings_load_back_unchanged() {
+    let conn = open();
+    let changed = Settings {
+        daily_goal_ml: 2750,
+        reminder_interval_minutes: 45,
+        quick_add_amounts: vec![150, 500],
+        day_starts_at_hour: 4,
+        ..Settings::default()
+    };
+
+    settings::save(&conn, &changed).unwrap();
+    let loaded = settings::load(&conn).unwrap();
+
+    assert!(settings::changed_fields(&changed, &loaded).is_empty());
+    assert_eq!(db::get_day_starts_at_hour(&conn), 4);
+    // Saving records when, for backup merges
+    assert!(settings::get::<String>(&conn, settings::UPDATED_AT_KEY).unwrap().is_some());
+}
+
+#[test]
+fn a_stored_value_of_the_wrong_type_falls_back_to_its_default() {
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "\"plenty\"");
+    set_setting(&conn, "reminder_interval_minutes", "90");
+    set_setting(&conn, "no_longer_a_setting", "true");
+
+    let loaded = settings::load(&conn).unwrap();
+
+    assert_eq!(loaded.daily_goal_ml, Settings::default().daily_goal_ml);
+    assert_eq!(loaded.reminder_interval_minutes, 90);
+}
+
+#[test]
+fn single_keys_read_back_typed_and_can_be_removed() {
+    let conn = open();
+    settings::set(&conn, "snoozed_until", &"2024-05-01 10:00:00").unwrap();
+    assert_eq!(
+        settings::get::<String>(&conn, "snoozed_until").unwrap().as_deref(),
+        Some("2024-05-01 10:00:00")
+    );
+    // Read as the wrong type, it's missing rather than an error
+    assert_eq!(settings::get::<i32>(&conn, "snoozed_until").unwrap(), None);
+
+    settings::remove(&conn, "snoozed_until").unwrap();
+    assert_eq!(settings::get::<String>(&conn, "snoozed_until").unwrap(), None);
+}
+
+#[test]
+fn validation_names_the_field_at_fault() {
+    let invalid = Settings {
+        daily_goal_ml: 0,
+        ..Settings::default()
+    };
+    assert_eq!(settings::validate(&invalid).unwrap_err().field, "daily_goal_ml");
+
+    let invalid = Settings {
+        reminder_interval_minutes: 1,
+        ..Settings::default()
+    };
+    assert_eq!(settings::validate(&invalid).unwrap_err().field, "reminder_interval_minutes");
+
+    let invalid = Settings {
+        log_level: "verbose".to_string(),
+        ..Settings::default()
+    };
+    assert_eq!(settings::validate(&invalid).unwrap_err().field, "log_level");
+
+    assert!(settings::validate(&Settings::default()).is_ok());
+}
+
+// The field validation turns `settings` down for, if any
+fn rejected_field(settings: Settings) -> Option<&'static str> {
+    settings::validate(&settings).err().map(|e| e.field)
+}
+
+#[test]
+fn the_daily_goal_runs_from_1_to_20000_ml() {
+    let goal = |daily_goal_ml| rejected_field(Settings { daily_goal_ml, ..Settings::default() });
+    assert_eq!(goal(0), Some("daily_goal_ml"));
+    assert_eq!(goal(-5), Some("daily_goal_ml"));
+    assert_eq!(goal(1), None);
+    assert_eq!(goal(20_000), None);
+    assert_eq!(goal(20_001), Some("daily_goal_ml"));
+}
+
+#[test]
+fn the_reminder_interval_runs_from_5_to_480_minutes() {
+    let interval = |reminder_interval_minutes| {
+        rejected_field(Settings {
+            reminder_interval_minutes,
+            ..Settings::default()
+        })
+    };
+    assert_eq!(interval(0), Some("reminder_interval_minutes"));
+    assert_eq!(interval(4), Some("reminder_interval_minutes"));
+    assert_eq!(interval(5), None);
+    assert_eq!(interval(480), None);
+    assert_eq!(interval(481), Some("reminder_interval_minutes"));
+}
+
+#[test]
+fn the_theme_is_one_of_the_known_ones() {
+    let theme = |theme: &str| {
+        rejected_field(Settings {
+            theme: theme.to_string(),
+            ..Settings::default()
+        })
+    };
+    for known in settings::THEMES {
+        assert_eq!(theme(known), None, "{}", known);
+    }
+    assert_eq!(theme("\u{1f4a7}"), Some("theme"));
+    assert_eq!(theme("Dark"), Some("theme"));
+    assert_eq!(theme(""), Some("theme"));
+}
+
+#[test]
+fn the_day_starts_between_midnight_and_11pm() {
+    let hour = |day_starts_at_hour| {
+        rejected_field(Settings {
+            day_starts_at_hour,
+            ..Settings::default()
+        })
+    };
+    assert_eq!(hour(-1), Some("day_starts_at_hour"));
+    assert_eq!(hour(0), None);
+    assert_eq!(hour(23), None);
+    assert_eq!(hour(24), Some("day_starts_at_hour"));
+}
+
+#[test]
+fn the_largest_single_entry_runs_from_1_to_20000_ml() {
+    let max = |max_entry_ml| {
+        rejected_field(Settings {
+            max_entry_ml,
+            // Quick-add amounts can't be over it
+            quick_add_amounts: vec![1],
+            ..Settings::default()
+        })
+    };
+    assert_eq!(max(0), Some("max_entry_ml"));
+    assert_eq!(max(1), None);
+    assert_eq!(max(20_000), None);
+    assert_eq!(max(20_001), Some("max_entry_ml"));
+}
+
+#[test]
+fn quick_add_amounts_are_distinct_entries_that_fit_the_tray() {
+    let amounts = |quick_add_amounts: Vec<i32>| {
+        rejected_field(Settings {
+            quick_add_amounts,
+            max_entry_ml: 1000,
+            ..Settings::default()
+        })
+    };
+    assert_eq!(amounts(vec![]), None);
+    assert_eq!(amounts(vec![250, 1000]), None);
+    assert_eq!(amounts(vec![0]), Some("quick_add_amounts"));
+    assert_eq!(amounts(vec![250, 1001]), Some("quick_add_amounts"));
+    assert_eq!(amounts(vec![250, 500, 250]), Some("quick_add_amounts"));
+    let most: Vec<i32> = (1..=settings::MAX_QUICK_ADD_AMOUNTS as i32).map(|n| n * 100).collect();
+    assert_eq!(amounts(most.clone()), None);
+    assert_eq!(amounts([most, vec![950]].concat()), Some("quick_add_amounts"));
+}
+
+#[test]
+fn the_freshness_window_runs_from_off_to_4_hours() {
+    let window = |freshness_window_minutes| {
+        rejected_field(Settings {
+            freshness_window_minutes,
+            ..Settings::default()
+        })
+    };
+    assert_eq!(window(-1), Some("freshness_window_minutes"));
+    assert_eq!(window(0), None);
+    assert_eq!(window(240), None);
+    assert_eq!(window(241), Some("freshness_window_minutes"));
+}
+
+#[test]
+fn the_export_time_is_a_minute_of_the_day() {
+    let time = |export_time| rejected_field(Settings { export_time, ..Settings::default() });
+    assert_eq!(time(-1), Some("export_time"));
+    assert_eq!(time(0), None);
+    assert_eq!(time(1439), None);
+    assert_eq!(time(1440), Some("export_time"));
+}
+
+#[test]
+fn an_import_skips_unknown_fields_and_rejects_bad_values() {
+    let current = Settings::default();
+
+    let (imported, warnings) =
+        settings::merge_import(&current, r#"{ "daily_goal_ml": 3000, "from_the_future": 1 }"#).unwrap();
+    assert_eq!(imported.daily_goal_ml, 3000);
+    assert_eq!(settings::changed_fields(&current, &imported), ["daily_goal_ml"]);
+    assert_eq!(warnings.len(), 1);
+
+    assert!(settings::merge_import(&current, r#"{ "daily_goal_ml": "lots" }"#).is_err());
+    assert!(settings::merge_import(&current, "[1, 2]").is_err());
+}
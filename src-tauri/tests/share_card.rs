@@ -0,0 +1,89 @@
+use chrono::NaiveDate;
+use hydra_tracker_lib::share_card::{self, CardContent, HEIGHT, WIDTH};
+use hydra_tracker_lib::Settings;
+use tiny_skia::Pixmap;
+
+fn content(progress: Option<f32>) -> CardContent {
+    CardContent {
+        title: "Today".to_string(),
+        value: "1,250 ml".to_string(),
+        detail: "50% of 2,500 ml".to_string(),
+        progress,
+    }
+}
+
+fn render(content: &CardContent, settings: &Settings) -> Pixmap {
+    let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    let png = share_card::render(content, settings, date).unwrap();
+    Pixmap::decode_png(&png).unwrap()
+}
+
+fn rgb(pixmap: &Pixmap, x: u32, y: u32) -> (u8, u8, u8) {
+    let pixel = pixmap.pixel(x, y).unwrap();
+    (pixel.red(), pixel.green(), pixel.blue())
+}
+
+// Just inside the progress bar, at `fraction` of its width
+fn bar_pixel(pixmap: &Pixmap, fraction: f32) -> (u8, u8, u8) {
+    let (padding, bar_height) = (80.0, 24.0);
+    let x = padding + (WIDTH as f32 - 2.0 * padding) * fraction;
+    let y = HEIGHT as f32 - padding - bar_height / 2.0;
+    rgb(pixmap, x as u32, y as u32)
+}
+
+#[test]
+fn the_card_is_a_fixed_size_png() {
+    let card = render(&content(Some(0.5)), &Settings::default());
+    assert_eq!((card.width(), card.height()), (1200, 630));
+}
+
+#[test]
+fn the_accent_colours_the_stripe_and_the_filled_part_of_the_bar() {
+    let settings = Settings {
+        accent_color: "#3b82f6".to_string(),
+        ..Settings::default()
+    };
+    let card = render(&content(Some(0.5)), &settings);
+
+    let accent = (0x3b, 0x82, 0xf6);
+    assert_eq!(rgb(&card, WIDTH / 2, 4), accent);
+    assert_eq!(bar_pixel(&card, 0.25), accent);
+    // Past the halfway mark the track shows through
+    assert_eq!(bar_pixel(&card, 0.75), (0x1e, 0x29, 0x3b));
+}
+
+#[test]
+fn the_background_follows_the_theme() {
+    let dark = render(&content(None), &Settings::default());
+    assert_eq!(rgb(&dark, 4, HEIGHT - 4), (0x0f, 0x17, 0x2a));
+
+    let light = Settings {
+        resolved_theme: "light".to_string(),
+        ..Settings::default()
+    };
+    let light = render(&content(None), &light);
+    assert_eq!(rgb(&light, 4, HEIGHT - 4), (0xf8, 0xfa, 0xfc));
+}
+
+#[test]
+fn no_progress_leaves_the_bar_out() {
+    let card = render(&content(None), &Settings::default());
+    assert_eq!(bar_pixel(&card, 0.25), (0x0f, 0x17, 0x2a));
+}
+
+#[test]
+fn the_numbers_are_drawn() {
+    let blank = render(
+        &CardContent {
+            title: String::new(),
+            value: String::new(),
+            detail: String::new(),
+            progress: None,
+        },
+        &Settings::default(),
+    );
+    let card = render(&content(None), &Settings::default());
+    // The big value sits between the title and the detail line
+    let differs = (260..400).any(|y| (0..WIDTH).any(|x| rgb(&card, x, y) != rgb(&blank, x, y)));
+    assert!(differs);
+}
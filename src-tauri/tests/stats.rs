@@ -0,0 +1,482 @@
+mod common;
+
+use chrono::Duration;
+use common::{add, add_on, at, date, open, set_setting};
+use hydra_tracker_lib::{db, goals, settings, stats, DailyStats};
+use rusqlite::StatementStatus;
+use std::collections::HashSet;
+
+#[test]
+fn day_stats_are_empty_without_entries() {
+    let conn = open();
+    let stats = stats::day_stats(&conn, date(2024, 5, 10)).unwrap();
+
+    assert_eq!(stats.date, "2024-05-10");
+    assert_eq!(stats.total_ml, 0);
+    assert_eq!(stats.entries_count, 0);
+    assert_eq!(stats.goal_ml, 4000);
+    assert_eq!(stats.percentage, 0.0);
+    assert_eq!(stats.goal_met_at, None);
+}
+
+#[test]
+fn day_stats_total_the_day_and_find_when_the_goal_was_met() {
+    let conn = open();
+    let day = date(2024, 5, 10);
+    set_setting(&conn, "daily_goal_ml", "2000");
+    add(&conn, 1000, at(day, 0, 1));
+    add(&conn, 1200, at(day, 0, 2));
+    add(&conn, 500, at(day - Duration::days(1), 12, 0));
+
+    let stats = stats::day_stats(&conn, day).unwrap();
+
+    assert_eq!(stats.total_ml, 2200);
+    assert_eq!(stats.entries_count, 2);
+    assert_eq!(stats.percentage, 110.0);
+    assert_eq!(stats.surplus_ml, 200);
+    let met_at = at(day, 0, 2).format("%Y-%m-%d %H:%M:%S").to_string();
+    assert_eq!(stats.goal_met_at, Some(met_at));
+}
+
+#[test]
+fn day_stats_judge_a_past_day_by_its_own_goal() {
+    let conn = open();
+    let start = date(2024, 4, 1);
+    goals::save_program(
+        &conn,
+        &goals::GoalProgram {
+            start_ml: 1000,
+            increment_ml: 500,
+            interval_days: 1,
+            start_date: start,
+            target_ml: 3000,
+        },
+    )
+    .unwrap();
+    add(&conn, 900, at(date(2024, 4, 2), 9, 0));
+    add(&conn, 700, at(date(2024, 4, 2), 15, 30));
+    add(&conn, 400, at(date(2024, 4, 3), 9, 0));
+
+    let stats = stats::day_stats(&conn, date(2024, 4, 2)).unwrap();
+
+    assert_eq!(stats.date, "2024-04-02");
+    assert_eq!(stats.total_ml, 1600);
+    assert_eq!(stats.entries_count, 2);
+    assert_eq!(stats.goal_ml, 1500);
+    assert_eq!(stats.goal_met_at.as_deref(), Some("2024-04-02 15:30:00"));
+}
+
+#[test]
+fn monthly_stats_cover_every_day_of_a_past_month() {
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "2000");
+    add_on(&conn, date(2024, 2, 1), 2000);
+    add_on(&conn, date(2024, 2, 2), 2500);
+    add_on(&conn, date(2024, 2, 3), 1000);
+
+    let stats = stats::monthly_stats_with_streaks(&conn, 2024, 2).unwrap();
+
+    assert_eq!(stats.days.len(), 29);
+    assert_eq!(stats.total_ml, 5500);
+    assert_eq!(stats.days_goal_met, 2);
+    assert_eq!(stats.days[3].total_ml, 0);
+    assert_eq!(stats.total_surplus_ml, 500);
+    assert!((stats.adherence_percentage - 2.0 / 29.0 * 100.0).abs() < 0.01);
+    assert_eq!(stats.best_streak, 2);
+}
+
+#[test]
+fn streaks_from_totals_count_consecutive_goal_days() {
+    let totals = [
+        ("2024-01-01".to_string(), 2000),
+        ("2024-01-02".to_string(), 2100),
+        ("2024-01-03".to_string(), 2000),
+        ("2024-01-05".to_string(), 2000),
+        ("2024-01-06".to_string(), 2000),
+    ];
+
+    let (current, best) = stats::streaks_from_totals(&totals, &HashSet::new(), |_| 2000, date(2024, 1, 6));
+    assert_eq!((current, best), (2, 3));
+
+    // A day with nothing logged yet ends the current run
+    let (current, best) = stats::streaks_from_totals(&totals, &HashSet::new(), |_| 2000, date(2024, 1, 7));
+    assert_eq!((current, best), (0, 3));
+}
+
+#[test]
+fn streaks_from_totals_bridge_frozen_days_without_counting_them() {
+    let totals = [
+        ("2024-01-01".to_string(), 2000),
+        ("2024-01-02".to_string(), 500),
+        ("2024-01-03".to_string(), 2000),
+    ];
+    let frozen = HashSet::from([date(2024, 1, 2)]);
+
+    let (current, best) = stats::streaks_from_totals(&totals, &frozen, |_| 2000, date(2024, 1, 3));
+    assert_eq!((current, best), (2, 2));
+}
+
+#[test]
+fn calculate_streaks_reads_the_whole_history() {
+    let conn = open();
+    for day in 1..=4 {
+        add_on(&conn, date(2024, 1, day), 2000);
+    }
+    add_on(&conn, date(2024, 1, 5), 100);
+
+    assert_eq!(stats::calculate_streaks(&conn, |_| 2000, date(2024, 1, 4)), (4, 4));
+    assert_eq!(stats::calculate_streaks(&conn, |_| 2000, date(2024, 1, 5)), (0, 4));
+}
+
+#[test]
+fn alltime_stats_summarize_every_entry() {
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "2000");
+    for day in 1..=3 {
+        add_on(&conn, date(2024, 1, day), 2000);
+    }
+    add_on(&conn, date(2024, 1, 4), 1000);
+    add_on(&conn, date(2024, 1, 5), 2000);
+    add_on(&conn, date(2024, 1, 5), 1000);
+
+    let stats = stats::alltime_stats(&conn).unwrap();
+
+    assert_eq!(stats.total_ml, 10000);
+    assert_eq!(stats.total_entries, 6);
+    assert_eq!(stats.first_date.as_deref(), Some("2024-01-01"));
+    assert_eq!(stats.days_logged, 5);
+    assert_eq!(stats.average_ml_per_day, 2000.0);
+    assert_eq!(stats.days_goal_met, 4);
+    assert_eq!(stats.longest_streak, 3);
+}
+
+#[test]
+fn alltime_stats_are_zero_without_entries() {
+    let stats = stats::alltime_stats(&open()).unwrap();
+    assert_eq!(stats.days_logged, 0);
+    assert_eq!(stats.first_date, None);
+    assert_eq!(stats.longest_streak, 0);
+}
+
+#[test]
+fn yearly_overview_buckets_days_by_month() {
+    let conn = open();
+    add_on(&conn, date(2023, 1, 10), 1500);
+    add_on(&conn, date(2023, 1, 11), 500);
+    add_on(&conn, date(2023, 12, 31), 3000);
+    add_on(&conn, date(2024, 1, 1), 9000);
+
+    let months = stats::yearly_overview(&conn, 2023).unwrap();
+
+    assert_eq!(months.len(), 12);
+    assert_eq!(months[0].total_ml, 2000);
+    assert_eq!(months[0].days.len(), 31);
+    assert_eq!(months[5].total_ml, 0);
+    assert_eq!(months[11].total_ml, 3000);
+}
+
+#[test]
+fn the_year_view_marks_frozen_days_like_the_month_view() {
+    let conn = open();
+    conn.execute(
+        "INSERT INTO streak_freezes (date, applied_at) VALUES ('2023-03-14', '2023-03-15 09:00:00')",
+        [],
+    )
+    .unwrap();
+
+    let year = stats::yearly_overview(&conn, 2023).unwrap();
+    let month = stats::monthly_stats_with_streaks(&conn, 2023, 3).unwrap();
+
+    let frozen = |days: &[hydra_tracker_lib::DailyStats]| -> Vec<String> {
+        days.iter().filter(|d| d.frozen).map(|d| d.date.clone()).collect()
+    };
+    assert_eq!(frozen(&year[2].days), ["2023-03-14"]);
+    assert_eq!(frozen(&year[2].days), frozen(&month.days));
+}
+
+#[test]
+fn year_over_year_compares_against_the_same_month() {
+    let conn = open();
+    add_on(&conn, date(2023, 6, 1), 3000);
+    add_on(&conn, date(2022, 6, 1), 2000);
+
+    let comparison = stats::year_over_year(&conn, 2023, 6).unwrap();
+    assert!(comparison.has_prior_data);
+    assert_eq!(comparison.total_delta_ml, Some(1000));
+
+    let first_year = stats::year_over_year(&conn, 2022, 6).unwrap();
+    assert!(!first_year.has_prior_data);
+    assert_eq!(first_year.total_delta_ml, None);
+}
+
+#[test]
+fn months_after_today_have_no_days_yet() {
+    let conn = open();
+    let stats = stats::monthly_stats_with_streaks(&conn, 9999, 1).unwrap();
+    assert!(stats.days.is_empty());
+    assert_eq!(stats.average_ml, 0.0);
+}
+
+#[test]
+fn entries_logged_ahead_dont_count_as_elapsed_days() {
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "2000");
+    add_on(&conn, date(9999, 1, 5), 2500);
+
+    let stats = stats::monthly_stats_with_streaks(&conn, 9999, 1).unwrap();
+    assert_eq!(stats.days.len(), 1);
+    assert_eq!(stats.total_ml, 2500);
+    assert_eq!(stats.adherence_percentage, 0.0);
+    assert_eq!(stats.average_ml, 0.0);
+}
+
+#[test]
+fn a_past_month_with_nothing_logged_is_all_zero_days() {
+    let conn = open();
+    let stats = stats::monthly_stats_with_streaks(&conn, 2024, 4).unwrap();
+
+    assert_eq!(stats.days.len(), 30);
+    assert!(stats.days.iter().all(|day| day.total_ml == 0 && day.entries_count == 0));
+    assert_eq!(stats.total_ml, 0);
+    assert_eq!(stats.days_goal_met, 0);
+    assert_eq!((stats.current_streak, stats.best_streak), (0, 0));
+}
+
+#[test]
+fn entries_on_either_side_of_a_month_end_stay_in_their_own_month() {
+    let conn = open();
+    add(&conn, 700, at(date(2024, 1, 31), 23, 59));
+    add(&conn, 900, at(date(2024, 2, 1), 0, 0));
+
+    let january = stats::monthly_stats_with_streaks(&conn, 2024, 1).unwrap();
+    let february = stats::monthly_stats_with_streaks(&conn, 2024, 2).unwrap();
+
+    assert_eq!(january.total_ml, 700);
+    assert_eq!(january.days[30].total_ml, 700);
+    assert_eq!(february.total_ml, 900);
+    assert_eq!(february.days[0].total_ml, 900);
+}
+
+#[test]
+fn a_late_night_drink_counts_for_the_month_it_rolled_over_from() {
+    let conn = open();
+    set_setting(&conn, "day_starts_at_hour", "3");
+    // 01:30 on the 1st is still the last day of January
+    add(&conn, 400, at(date(2024, 2, 1), 1, 30));
+    add(&conn, 600, at(date(2024, 2, 1), 3, 0));
+
+    let january = stats::monthly_stats_with_streaks(&conn, 2024, 1).unwrap();
+    let february = stats::monthly_stats_with_streaks(&conn, 2024, 2).unwrap();
+
+    assert_eq!(january.days[30].total_ml, 400);
+    assert_eq!(february.days[0].total_ml, 600);
+}
+
+#[test]
+fn streaks_run_across_month_and_year_ends() {
+    let conn = open();
+    for day in [date(2023, 12, 30), date(2023, 12, 31), date(2024, 1, 1), date(2024, 1, 2)] {
+        add_on(&conn, day, 2000);
+    }
+
+    assert_eq!(stats::calculate_streaks(&conn, |_| 2000, date(2024, 1, 2)), (4, 4));
+    // A leap day is a day like any other
+    for day in [date(2024, 2, 28), date(2024, 2, 29), date(2024, 3, 1)] {
+        add_on(&conn, day, 2000);
+    }
+    assert_eq!(stats::calculate_streaks(&conn, |_| 2000, date(2024, 3, 1)), (3, 4));
+}
+
+#[test]
+fn streaks_are_zero_without_entries() {
+    assert_eq!(stats::calculate_streaks(&open(), |_| 2000, date(2024, 1, 1)), (0, 0));
+}
+
+#[test]
+fn changing_the_goal_re_judges_past_days() {
+    // The plain daily goal isn't stored per day, so outside a goal program
+    // history is measured against the goal in effect now
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "2000");
+    add_on(&conn, date(2024, 3, 1), 2000);
+    add_on(&conn, date(2024, 3, 2), 2500);
+    assert_eq!(stats::monthly_stats_with_streaks(&conn, 2024, 3).unwrap().days_goal_met, 2);
+
+    set_setting(&conn, "daily_goal_ml", "2500");
+    let stats = stats::monthly_stats_with_streaks(&conn, 2024, 3).unwrap();
+    assert_eq!(stats.days_goal_met, 1);
+    assert_eq!(stats.days[0].goal_ml, 2500);
+    assert_eq!(stats.days[0].goal_met_at, None);
+}
+
+#[test]
+fn a_goal_program_judges_each_day_by_its_own_step() {
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "2000");
+    let program = goals::GoalProgram {
+        start_ml: 2500,
+        increment_ml: 500,
+        interval_days: 1,
+        start_date: date(2024, 3, 2),
+        target_ml: 4000,
+    };
+    goals::save_program(&conn, &program).unwrap();
+    // Before the program, on its first step, and short of its second
+    add_on(&conn, date(2024, 3, 1), 2000);
+    add_on(&conn, date(2024, 3, 2), 2500);
+    add_on(&conn, date(2024, 3, 3), 2500);
+
+    let march = stats::monthly_stats_with_streaks(&conn, 2024, 3).unwrap();
+    let goals: Vec<i32> = march.days[..3].iter().map(|d| d.goal_ml).collect();
+    assert_eq!(goals, [2000, 2500, 3000]);
+    assert_eq!(march.days_goal_met, 2);
+    assert!(march.days[1].goal_met_at.is_some());
+    assert_eq!(march.days[2].goal_met_at, None);
+
+    assert_eq!(stats::yearly_overview(&conn, 2024).unwrap()[2].days_goal_met, 2);
+    let alltime = stats::alltime_stats(&conn).unwrap();
+    assert_eq!((alltime.days_goal_met, alltime.longest_streak), (2, 2));
+    assert_eq!(stats::calculate_streaks(&conn, db::goal_schedule(&conn), date(2024, 3, 3)), (0, 2));
+}
+
+#[test]
+fn alltime_stats_step_through_a_program_in_sql_like_the_month_view() {
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "1500");
+    let program = goals::GoalProgram {
+        start_ml: 2000,
+        increment_ml: 300,
+        interval_days: 2,
+        start_date: date(2024, 4, 3),
+        target_ml: 2500,
+    };
+    goals::save_program(&conn, &program).unwrap();
+    // Goals 1500, 1500, 2000, 2000, 2300, 2300, then capped at 2500
+    for (day, amount) in [(1, 1500), (2, 1500), (3, 2000), (4, 2000), (5, 2300), (7, 2500), (8, 2400), (9, 2500)] {
+        add_on(&conn, date(2024, 4, day), amount);
+    }
+    // Bridges the missed 6th
+    conn.execute(
+        "INSERT INTO streak_freezes (date, applied_at) VALUES ('2024-04-06', '2024-04-07 09:00:00')",
+        [],
+    )
+    .unwrap();
+
+    let alltime = stats::alltime_stats(&conn).unwrap();
+    let april = stats::monthly_stats_with_streaks(&conn, 2024, 4).unwrap();
+    assert_eq!(alltime.days_goal_met, 7);
+    assert_eq!(alltime.days_goal_met, april.days_goal_met as i64);
+    assert_eq!(alltime.longest_streak, 6);
+    assert_eq!(
+        stats::calculate_streaks(&conn, db::goal_schedule(&conn), date(2024, 4, 9)).1,
+        alltime.longest_streak
+    );
+}
+
+#[test]
+fn removing_an_entry_takes_it_out_of_its_day() {
+    let conn = open();
+    let day = date(2024, 5, 10);
+    add(&conn, 300, at(day, 0, 1));
+    let second = db::insert_entry(&conn, 500, "app", at(day, 0, 2)).unwrap();
+    assert_eq!(stats::day_stats(&conn, day).unwrap().total_ml, 800);
+
+    assert!(db::delete_entry(&conn, second.id).unwrap().is_some());
+
+    let stats = stats::day_stats(&conn, day).unwrap();
+    assert_eq!((stats.total_ml, stats.entries_count), (300, 1));
+}
+
+#[test]
+fn polling_day_stats_reuses_its_cached_statements() {
+    let conn = open();
+    let day = date(2024, 5, 10);
+    add(&conn, 250, at(day, 0, 1));
+    let runs = |sql: &str| conn.prepare_cached(sql).unwrap().get_status(StatementStatus::Run);
+
+    for _ in 0..3 {
+        assert_eq!(stats::day_stats(&conn, day).unwrap().total_ml, 250);
+    }
+
+    // A statement compiled again would start from zero
+    assert_eq!(runs(stats::DAY_TOTAL_QUERY), 3);
+    assert_eq!(runs(stats::GOAL_MET_TIMES_QUERY), 3);
+    assert!(runs(settings::GET_QUERY) >= 3);
+    assert_eq!(conn.prepare(stats::DAY_TOTAL_QUERY).unwrap().get_status(StatementStatus::Run), 0);
+}
+
+#[test]
+fn a_streak_runs_through_a_spring_forward_night() {
+    // In Europe/Berlin 02:00 to 03:00 doesn't exist on 2024-03-31, so that
+    // day has 23 hours. A drink logged as 02:30 by a clock that hadn't caught
+    // up still belongs to the night before with a 3am rollover, and the
+    // days either side are consecutive dates all the same.
+    let conn = open();
+    set_setting(&conn, "day_starts_at_hour", "3");
+    add(&conn, 1500, at(date(2024, 3, 30), 20, 0));
+    let late = db::insert_entry(&conn, 500, "app", at(date(2024, 3, 31), 2, 30)).unwrap();
+    let after = db::insert_entry(&conn, 2000, "app", at(date(2024, 3, 31), 3, 0)).unwrap();
+    add(&conn, 2000, at(date(2024, 4, 1), 9, 0));
+
+    assert_eq!(late.date, "2024-03-30");
+    assert_eq!(after.date, "2024-03-31");
+    assert_eq!(stats::calculate_streaks(&conn, |_| 2000, date(2024, 4, 1)), (3, 3));
+}
+
+#[test]
+fn flying_west_mid_streak_keeps_the_days_in_a_row() {
+    // Logged late on the 2nd, then the laptop's clock moves back seven
+    // hours on landing and the next drinks carry earlier wall-clock times
+    // than the last one. Days are counted by their stored dates, so the
+    // jump back neither repeats nor skips a day.
+    let conn = open();
+    add(&conn, 2000, at(date(2024, 5, 1), 12, 0));
+    add(&conn, 1000, at(date(2024, 5, 2), 9, 0));
+    add(&conn, 1000, at(date(2024, 5, 2), 23, 0));
+    add(&conn, 500, at(date(2024, 5, 2), 17, 0));
+    add(&conn, 2000, at(date(2024, 5, 3), 8, 0));
+
+    let may = stats::monthly_stats_with_streaks(&conn, 2024, 5).unwrap();
+    assert_eq!(may.days[1].total_ml, 2500);
+    assert_eq!(stats::calculate_streaks(&conn, |_| 2000, date(2024, 5, 3)), (3, 3));
+
+    // Flying east past midnight skips a wall-clock evening but not a date
+    let conn = open();
+    add(&conn, 2000, at(date(2024, 5, 1), 18, 0));
+    add(&conn, 2000, at(date(2024, 5, 2), 7, 0));
+    assert_eq!(stats::calculate_streaks(&conn, |_| 2000, date(2024, 5, 2)), (2, 2));
+}
+
+#[test]
+fn surplus_is_only_what_went_past_the_goal() {
+    let short = DailyStats::new("2024-05-01".to_string(), 1500, 2000, 3, None);
+    assert_eq!(short.surplus_ml, 0);
+    let exact = DailyStats::new("2024-05-01".to_string(), 2000, 2000, 4, None);
+    assert_eq!(exact.surplus_ml, 0);
+    let over = DailyStats::new("2024-05-01".to_string(), 2600, 2000, 5, None);
+    assert_eq!(over.surplus_ml, 600);
+    // The percentage isn't capped at 100
+    assert_eq!(over.percentage, 130.0);
+}
+
+#[test]
+fn months_and_years_total_the_surplus_and_count_the_days_well_over() {
+    let conn = open();
+    set_setting(&conn, "daily_goal_ml", "2000");
+    // 75%, 100%, 110%, 115% and 130% of the goal
+    for (day, ml) in [(1, 1500), (2, 2000), (3, 2200), (4, 2300), (5, 2600)] {
+        add_on(&conn, date(2024, 5, day), ml);
+    }
+
+    let may = stats::monthly_stats_with_streaks(&conn, 2024, 5).unwrap();
+    assert_eq!(may.total_surplus_ml, 200 + 300 + 600);
+    // Strictly over, so exactly 110% doesn't count
+    assert_eq!(may.days_over_110_percent, 2);
+    assert_eq!(may.days_over_125_percent, 1);
+
+    let year = stats::yearly_overview(&conn, 2024).unwrap();
+    assert_eq!(year[4].total_surplus_ml, may.total_surplus_ml);
+    assert_eq!(year[4].days_over_110_percent, 2);
+    assert_eq!(year[4].days_over_125_percent, 1);
+    assert_eq!(year[3].total_surplus_ml, 0);
+}
@@ -0,0 +1,119 @@
+use chrono::NaiveDate;
+use hydra_tracker_lib::text_import::{self, TextImportPreview};
+
+const MAX_ENTRY_ML: i32 = 2000;
+
+fn day() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+}
+
+fn parse(text: &str) -> TextImportPreview {
+    text_import::parse(text, day(), 0, MAX_ENTRY_ML)
+}
+
+// (timestamp, amount) of every entry found
+fn entries(text: &str) -> Vec<(String, i32)> {
+    parse(text).entries.into_iter().map(|e| (e.timestamp, e.amount_ml)).collect()
+}
+
+// The reason the only segment of `text` was turned down
+fn rejection(text: &str) -> String {
+    let preview = parse(text);
+    assert!(preview.entries.is_empty(), "{:?}", preview.entries);
+    assert_eq!(preview.rejected.len(), 1, "{:?}", preview.rejected);
+    preview.rejected[0].reason.clone()
+}
+
+fn entry(time: &str, amount_ml: i32) -> (String, i32) {
+    (format!("2024-05-01 {}:00", time), amount_ml)
+}
+
+#[test]
+fn a_comma_and_a_space_separate_entries_but_a_thousands_comma_does_not() {
+    assert_eq!(entries("9:00 1,500"), vec![entry("09:00", 1500)]);
+    assert_eq!(
+        entries("9:00 300, 11:00 coffee 250; 14:15 500"),
+        vec![entry("09:00", 300), entry("11:00", 250), entry("14:15", 500)]
+    );
+}
+
+#[test]
+fn a_comma_is_a_thousands_separator_only_before_groups_of_three() {
+    assert_eq!(entries("9:00 1,500ml"), vec![entry("09:00", 1500)]);
+    assert_eq!(entries("9:00 0,5 l"), vec![entry("09:00", 500)]);
+    assert_eq!(entries("9:00 1,25 l"), vec![entry("09:00", 1250)]);
+}
+
+#[test]
+fn times_take_am_pm_and_the_h_separator() {
+    assert_eq!(entries("12am 300"), vec![entry("00:00", 300)]);
+    assert_eq!(entries("12pm 300"), vec![entry("12:00", 300)]);
+    assert_eq!(entries("9 pm 300"), vec![entry("21:00", 300)]);
+    assert_eq!(entries("9:30p.m. 300"), vec![entry("21:30", 300)]);
+    assert_eq!(entries("14h15 300"), vec![entry("14:15", 300)]);
+    assert_eq!(entries("14h 300"), vec![entry("14:00", 300)]);
+}
+
+#[test]
+fn an_hour_past_twelve_with_am_or_pm_is_not_a_time() {
+    assert_eq!(rejection("13pm 300"), "'13pm' is not a valid time");
+    assert_eq!(rejection("0 am 300"), "'0' is not a valid time");
+    assert_eq!(rejection("9:75 300"), "'9:75' is not a valid time");
+}
+
+#[test]
+fn a_fraction_without_a_unit_is_turned_down() {
+    assert_eq!(rejection("9:00 1.5"), "'1.5' needs a unit");
+    assert_eq!(entries("9:00 1.5 l"), vec![entry("09:00", 1500)]);
+    assert_eq!(entries("9:00 1.5l"), vec![entry("09:00", 1500)]);
+}
+
+#[test]
+fn amounts_are_converted_from_their_unit() {
+    assert_eq!(entries("9:00 12 fl oz"), vec![entry("09:00", 355)]);
+    assert_eq!(entries("9:00 12fl oz"), vec![entry("09:00", 355)]);
+    assert_eq!(entries("9:00 2 cups"), vec![entry("09:00", 473)]);
+    assert_eq!(entries("9:00 300"), vec![entry("09:00", 300)]);
+    assert_eq!(rejection("9:00 300xl"), "'xl' is not a unit");
+}
+
+#[test]
+fn each_segment_needs_exactly_one_time_and_one_amount() {
+    assert_eq!(rejection("water 300"), "no time found");
+    assert_eq!(rejection("9:00 water"), "no amount found");
+    assert_eq!(
+        rejection("9:00 10:00 300"),
+        "more than one time; put each entry on its own line"
+    );
+    assert_eq!(
+        rejection("9:00 300 ml 200 ml"),
+        "more than one amount; put each entry on its own line"
+    );
+}
+
+#[test]
+fn rejections_keep_their_line_and_the_rest_still_parses() {
+    let preview = parse("9:00 300\nlunch\n\n18:00 500");
+    assert_eq!(preview.entries.iter().map(|e| e.line).collect::<Vec<_>>(), vec![1, 4]);
+    assert_eq!(preview.rejected.len(), 1);
+    assert_eq!(preview.rejected[0].line, 2);
+    assert_eq!(preview.rejected[0].text, "lunch");
+}
+
+#[test]
+fn times_before_the_rollover_belong_to_the_next_calendar_day() {
+    let preview = text_import::parse("23:30 300\n1:30 250\n3:00 200", day(), 3, MAX_ENTRY_ML);
+    let timestamps: Vec<&str> = preview.entries.iter().map(|e| e.timestamp.as_str()).collect();
+    assert_eq!(
+        timestamps,
+        vec!["2024-05-01 23:30:00", "2024-05-02 01:30:00", "2024-05-01 03:00:00"]
+    );
+}
+
+#[test]
+fn amounts_over_the_entry_limit_are_flagged_not_dropped() {
+    let preview = parse("9:00 2500");
+    assert_eq!(preview.entries.len(), 1);
+    assert!(preview.entries[0].over_max_entry);
+    assert!(!parse("9:00 2000").entries[0].over_max_entry);
+}
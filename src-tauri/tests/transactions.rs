@@ -0,0 +1,113 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{add, at, open};
+use hydra_tracker_lib::{csv_import, db, settings, Database};
+use rusqlite::Connection;
+
+fn day() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+}
+
+fn entry_count(conn: &Connection) -> i64 {
+    conn.query_row("SELECT COUNT(*) FROM water_entries", [], |row| row.get(0)).unwrap()
+}
+
+// Makes any insert of `amount_ml` fail, as a full disk or a constraint would
+fn fail_inserts_of(conn: &Connection, amount_ml: i32) {
+    conn.execute_batch(&format!(
+        "CREATE TEMP TRIGGER fail_insert BEFORE INSERT ON water_entries WHEN new.amount_ml = {}
+         BEGIN SELECT RAISE(ABORT, 'injected failure'); END;",
+        amount_ml
+    ))
+    .unwrap();
+}
+
+#[test]
+fn a_bulk_insert_that_fails_midway_stores_nothing() {
+    let conn = open();
+    fail_inserts_of(&conn, 333);
+    let rows = [250, 300, 333, 400]
+        .into_iter()
+        .enumerate()
+        .map(|(i, amount_ml)| (i + 2, Ok((at(day(), 9 + i as u32, 0), amount_ml))));
+
+    let error = csv_import::insert_rows(&conn, rows, "csv", 0, false).unwrap_err();
+
+    assert!(error.to_string().contains("injected failure"), "{}", error);
+    assert_eq!(entry_count(&conn), 0);
+    assert!(conn.is_autocommit());
+}
+
+#[test]
+fn with_tx_keeps_everything_on_ok_and_nothing_on_err() {
+    let conn = open();
+
+    db::with_tx(&conn, |conn| {
+        add(conn, 250, at(day(), 9, 0));
+        add(conn, 300, at(day(), 10, 0));
+        Ok::<_, rusqlite::Error>(())
+    })
+    .unwrap();
+    assert_eq!(entry_count(&conn), 2);
+
+    let failed: rusqlite::Result<()> = db::with_tx(&conn, |conn| {
+        add(conn, 400, at(day(), 11, 0));
+        conn.execute("INSERT INTO nowhere VALUES (1)", [])?;
+        Ok(())
+    });
+    assert!(failed.is_err());
+    assert_eq!(entry_count(&conn), 2);
+    assert!(conn.is_autocommit());
+}
+
+#[test]
+fn a_failed_inner_with_tx_undoes_only_its_own_writes() {
+    let conn = open();
+
+    db::with_tx(&conn, |conn| {
+        add(conn, 250, at(day(), 9, 0));
+        let inner = db::with_tx(conn, |conn| {
+            add(conn, 300, at(day(), 10, 0));
+            Err::<(), _>(rusqlite::Error::QueryReturnedNoRows)
+        });
+        assert!(inner.is_err());
+        // Still inside the outer one, which goes on to commit
+        assert!(!conn.is_autocommit());
+        Ok::<_, rusqlite::Error>(())
+    })
+    .unwrap();
+
+    let amounts: Vec<i32> = db::entries_on(&conn, day()).unwrap().iter().map(|e| e.amount_ml).collect();
+    assert_eq!(amounts, [250]);
+}
+
+#[test]
+fn a_settings_save_that_fails_midway_changes_no_setting() {
+    let conn = open();
+    let before = settings::load(&conn).unwrap();
+    conn.execute_batch(
+        "CREATE TEMP TRIGGER fail_setting BEFORE INSERT ON app_settings WHEN new.key = 'unit'
+         BEGIN SELECT RAISE(ABORT, 'injected failure'); END;",
+    )
+    .unwrap();
+    let mut changed = before.clone();
+    changed.daily_goal_ml += 500;
+
+    assert!(settings::save(&conn, &changed).is_err());
+
+    assert_eq!(settings::load(&conn).unwrap().daily_goal_ml, before.daily_goal_ml);
+}
+
+#[test]
+fn database_with_tx_holds_the_lock_and_commits() {
+    let db = Database::new(open());
+
+    let id = db
+        .with_tx(|conn| db::insert_entry(conn, 250, "app", at(day(), 9, 0)).map(|entry| entry.id))
+        .unwrap();
+
+    let conn = db.lock();
+    assert!(conn.is_autocommit());
+    assert_eq!(db::entries_on(&conn, day()).unwrap()[0].id, id);
+}
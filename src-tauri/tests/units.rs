@@ -0,0 +1,38 @@
+use hydra_tracker_lib::units::{self, Unit};
+
+#[test]
+fn eight_ounces_is_237_ml_not_236() {
+    assert_eq!(units::convert(8.0, Unit::FlOz, Unit::Ml), 237.0);
+    assert_eq!(units::convert(1.0, Unit::Cups, Unit::Ml), 237.0);
+    assert_eq!(units::format_amount(237, Unit::FlOz), "8 fl oz");
+    assert_eq!(units::format_compact(237, Unit::FlOz), "8oz");
+}
+
+#[test]
+fn whole_ounces_survive_the_trip_through_millilitres() {
+    for oz in 1..=64 {
+        let ml = units::convert(oz as f64, Unit::FlOz, Unit::Ml);
+        assert_eq!(ml, ml.trunc(), "{} fl oz gave {} ml", oz, ml);
+        assert_eq!(units::convert(ml, Unit::Ml, Unit::FlOz), oz as f64, "{} fl oz via {} ml", oz, ml);
+    }
+}
+
+#[test]
+fn quarter_cups_and_liters_survive_the_trip_through_millilitres() {
+    for quarters in 1..=40 {
+        let amount = quarters as f64 / 4.0;
+        for unit in [Unit::Cups, Unit::Liters] {
+            let ml = units::convert(amount, unit, Unit::Ml);
+            assert_eq!(units::convert(ml, Unit::Ml, unit), amount, "{} {:?} via {} ml", amount, unit, ml);
+        }
+    }
+}
+
+#[test]
+fn amounts_show_at_the_unit_precision() {
+    assert_eq!(units::format_amount(1750, Unit::Ml), "1,750 ml");
+    assert_eq!(units::format_amount(1750, Unit::FlOz), "59.2 fl oz");
+    assert_eq!(units::format_amount(1750, Unit::Liters), "1.75 L");
+    assert_eq!(units::format_amount(1000, Unit::Cups), "4.23 cups");
+    assert_eq!(units::format_number(-12345, Unit::Ml), "-12,345");
+}
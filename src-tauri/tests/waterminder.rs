@@ -0,0 +1,63 @@
+mod common;
+
+use common::open;
+use hydra_tracker_lib::csv_import::RejectReason;
+use hydra_tracker_lib::waterminder;
+use std::path::Path;
+
+// A few days of a real WaterMinder export with the drinks renamed and the
+// dates moved, plus rows it can't take
+const SAMPLE: &str = "tests/fixtures/waterminder_sample.csv";
+
+#[test]
+fn the_sample_export_imports_in_ml_with_rejects_by_line() {
+    let conn = open();
+    let summary = waterminder::import(&conn, Path::new(SAMPLE), 0, false).unwrap();
+
+    assert_eq!(summary.inserted, 4);
+    assert_eq!(summary.skipped_duplicates, 1);
+    assert_eq!(summary.duplicates.len(), 1);
+    assert_eq!((summary.duplicates[0].line, summary.duplicates[0].reason), (9, RejectReason::Duplicate));
+    assert_eq!(summary.rejected_total, 3);
+    let rejected: Vec<(usize, RejectReason, &str)> = summary
+        .rejected
+        .iter()
+        .map(|r| (r.line, r.reason, r.value.as_str()))
+        .collect();
+    assert_eq!(
+        rejected,
+        vec![
+            (6, RejectReason::UnknownUnit, "2 pints"),
+            (7, RejectReason::BadTimestamp, "yesterday"),
+            (8, RejectReason::NonNumericAmount, "1..5 L"),
+        ]
+    );
+
+    let mut stmt = conn
+        .prepare("SELECT timestamp, amount_ml, source FROM water_entries ORDER BY timestamp")
+        .unwrap();
+    let entries: Vec<(String, i32, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let waterminder = || "waterminder".to_string();
+    assert_eq!(
+        entries,
+        vec![
+            ("2023-01-02 08:15:00".to_string(), 237, waterminder()),
+            ("2023-01-02 12:30:00".to_string(), 500, waterminder()),
+            ("2023-01-02 18:00:00".to_string(), 1500, waterminder()),
+            ("2023-01-03 07:45:10".to_string(), 355, waterminder()),
+        ]
+    );
+}
+
+#[test]
+fn a_strict_import_of_the_sample_stops_at_the_first_bad_line() {
+    let conn = open();
+    let error = waterminder::import(&conn, Path::new(SAMPLE), 0, true).unwrap_err();
+    assert!(error.to_string().contains("line 6"), "{}", error);
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM water_entries", [], |row| row.get(0)).unwrap();
+    assert_eq!(count, 0);
+}
@@ -4,9 +4,7 @@ import { listen } from '@tauri-apps/api/event';
 import {
   isPermissionGranted,
   requestPermission,
-  sendNotification,
 } from '@tauri-apps/plugin-notification';
-import { enable as enableAutostart, disable as disableAutostart, isEnabled as isAutostartEnabled } from '@tauri-apps/plugin-autostart';
 import {
   BarChart,
   Bar,
@@ -16,7 +14,7 @@ import {
   Tooltip,
   ReferenceLine,
 } from 'recharts';
-import type { WaterEntry, DailyStats, MonthlyStats, Settings, Tab } from './types';
+import type { WaterEntry, DailyStats, MonthlyStats, Settings, Achievement, Tab, AddWaterOutcome, QuickAddContext, ShortcutStatus, TrayLeftClick, AppError, EntriesChanged, DayRolledOver, StateRefresh } from './types';
 
 // Icons
 // Ink Ribbon Icon
@@ -73,7 +71,52 @@ const defaultSettings: Settings = {
   reminder_enabled: true,
   sound_enabled: true,
   start_with_system: false,
+  start_minimized: false,
   theme: 'dark',
+  day_starts_at_hour: 0,
+  unit: 'ml',
+  quick_add_amounts: [250, 500],
+  menu_bar_title: 'off',
+  tray_left_click: 'show_window',
+  tray_icon_monochrome: false,
+  global_shortcuts_enabled: true,
+  quick_add_overlay: true,
+  quiet_hours_enabled: false,
+  quiet_hours_start: 22 * 60,
+  quiet_hours_end: 7 * 60,
+  checkpoints: [],
+  reminder_days: [0, 1, 2, 3, 4, 5, 6],
+  smart_reminders: false,
+  adaptive_reminders: false,
+  pause_reminders_when_goal_met: true,
+  freshness_window_minutes: 20,
+  idle_threshold_minutes: 10,
+  desk_reminder_enabled: false,
+  desk_reminder_minutes: 120,
+  max_entry_ml: 2000,
+  reminder_sound_path: null,
+  escalation_enabled: false,
+  escalation_threshold_percentage: 30,
+  escalation_interval_minutes: 20,
+  escalation_sound_path: null,
+  user_name: null,
+  auto_backup_enabled: false,
+  auto_backup_keep: 7,
+  retention_years: 0,
+  export_enabled: false,
+  export_path: null,
+  export_time: 23 * 60 + 30,
+  notify_on_goal_reached: true,
+  locale: 'en',
+  accent_color: '#4ade80',
+  log_level: 'info',
+  resolved_theme: 'dark',
+};
+
+// The backend resolves 'auto' against the OS theme
+const applyAppearance = (theme: string, accentColor: string) => {
+  document.documentElement.setAttribute('data-theme', theme);
+  document.documentElement.style.setProperty('--re-green', accentColor);
 };
 
 // Sound Effects Utility - RE2/RE3 OG Menu Sounds
@@ -124,6 +167,8 @@ function App() {
   const [entries, setEntries] = useState<WaterEntry[]>([]);
   const [settings, setSettings] = useState<Settings>(defaultSettings);
   const [customAmount, setCustomAmount] = useState('');
+  const [quickAddContext, setQuickAddContext] = useState<QuickAddContext | null>(null);
+  const customInputRef = useRef<HTMLInputElement>(null);
   const [toast, setToast] = useState<{ message: string; show: boolean }>({ message: '', show: false });
   const [monthlyStats, setMonthlyStats] = useState<MonthlyStats | null>(null);
   const [selectedMonth, setSelectedMonth] = useState(() => {
@@ -136,7 +181,6 @@ function App() {
     message: '',
   });
   
-  const reminderInterval = useRef<ReturnType<typeof setInterval> | null>(null);
   const achievementTimeout = useRef<ReturnType<typeof setTimeout> | null>(null);
 
   // Load data
@@ -149,23 +193,10 @@ function App() {
       ]);
       setStats(todayStats);
       setEntries(todayEntries);
-      
-      // Sync autostart state with actual system state
-      try {
-        const autostartEnabled = await isAutostartEnabled();
-        if (savedSettings.start_with_system !== autostartEnabled) {
-          savedSettings.start_with_system = autostartEnabled;
-          // Update database to match actual state
-          await invoke('save_settings', { settings: savedSettings });
-        }
-      } catch (error) {
-        console.error('Failed to check autostart status:', error);
-      }
-      
       setSettings(savedSettings);
       
       // Apply theme
-      document.documentElement.setAttribute('data-theme', savedSettings.theme);
+      applyAppearance(savedSettings.resolved_theme, savedSettings.accent_color);
     } catch (error) {
       console.error('Failed to load data:', error);
     }
@@ -196,106 +227,131 @@ function App() {
     }
   }, [tab, loadMonthlyStats]);
 
-  // Listen for quick-add events from system tray
+  // Settings can change from other windows or commands; goal changes move today's percentage
   useEffect(() => {
-    const unlisten = listen<number>('quick-add', (event) => {
-      handleAddWater(event.payload);
+    const unlisten = listen<Settings>('settings-changed', async (event) => {
+      setSettings(event.payload);
+      applyAppearance(event.payload.resolved_theme, event.payload.accent_color);
+      setStats(await invoke<DailyStats>('get_today_stats'));
     });
-    
+    // Fires when the OS switches appearance while the theme is 'auto'
+    const unlistenTheme = listen<string>('theme-resolved', (event) => {
+      document.documentElement.setAttribute('data-theme', event.payload);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+      unlistenTheme.then(fn => fn());
+    };
+  }, []);
+
+  // Takes the request from a clicked reminder, if there is one, and focuses
+  // the custom amount on Today
+  const openPendingQuickAdd = useCallback(async () => {
+    const context = await invoke<QuickAddContext | null>('take_pending_quick_add');
+    if (!context) return;
+    setTab('today');
+    setQuickAddContext(context);
+    setTimeout(() => customInputRef.current?.focus(), 0);
+  }, []);
+
+  // Every change to stored entries, including the window's own adds and
+  // removals, a new day starting, waking from sleep, and a clicked reminder
+  // asking for the quick-add screen
+  useEffect(() => {
+    const unlistenEntries = listen<EntriesChanged>('entries-changed', () => {
+      loadData();
+    });
+    const unlistenDay = listen<DayRolledOver>('day-rolled-over', () => {
+      loadData();
+    });
+    const unlistenResume = listen<StateRefresh>('state-refresh', () => {
+      loadData();
+    });
+    const unlistenQuickAdd = listen('open-quick-add', () => {
+      openPendingQuickAdd();
+    });
+    // A window created by the click mounts after the event was sent
+    openPendingQuickAdd();
+
+    return () => {
+      unlistenEntries.then(fn => fn());
+      unlistenDay.then(fn => fn());
+      unlistenResume.then(fn => fn());
+      unlistenQuickAdd.then(fn => fn());
+    };
+  }, [loadData, openPendingQuickAdd]);
+
+  // Listen for achievements unlocked by the backend
+  useEffect(() => {
+    const unlisten = listen<Achievement>('achievement-unlocked', (event) => {
+      setAchievement({
+        show: true,
+        message: `${event.payload.title.toUpperCase()}\nACHIEVEMENT UNLOCKED`,
+      });
+      if (achievementTimeout.current) {
+        clearTimeout(achievementTimeout.current);
+      }
+      achievementTimeout.current = setTimeout(() => {
+        setAchievement({ show: false, message: '' });
+      }, 5000);
+    });
+
     return () => {
       unlisten.then(fn => fn());
     };
   }, []);
 
-  // Setup reminder system
+  // Global shortcuts another app already owns. Startup registration runs
+  // before this listener is up, so the current status is fetched as well.
   useEffect(() => {
-    const setupReminder = async () => {
+    const reportFailed = (failed: ShortcutStatus[]) => {
+      if (failed.length === 0) return;
+      const list = failed.map(s => `${s.shortcut} (${s.error ?? 'unavailable'})`).join(', ');
+      setToast({ message: `Couldn't register shortcut: ${list}`, show: true });
+      setTimeout(() => setToast({ message: '', show: false }), 5000);
+    };
+    invoke<ShortcutStatus[]>('get_shortcut_status')
+      .then(statuses => reportFailed(statuses.filter(s => !s.registered)))
+      .catch(() => {});
+    const unlisten = listen<ShortcutStatus[]>('shortcut-registration-failed', (event) => {
+      reportFailed(event.payload);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // Reminders are scheduled by the backend; the frontend only makes sure
+  // notifications are allowed
+  useEffect(() => {
+    const checkPermission = async () => {
       if (!settings.reminder_enabled) {
-        if (reminderInterval.current) {
-          clearInterval(reminderInterval.current);
-          reminderInterval.current = null;
-        }
         return;
       }
 
-      // Check notification permission
       let permissionGranted = await isPermissionGranted();
-      console.log('Current notification permission:', permissionGranted);
-      
       if (!permissionGranted) {
-        console.log('Requesting notification permission...');
         const permission = await requestPermission();
         permissionGranted = permission === 'granted';
-        console.log('Permission request result:', permission, 'Granted:', permissionGranted);
       }
 
       if (!permissionGranted) {
         console.warn('Notification permission not granted. Reminders will not work.');
         setToast({ message: 'Notification permission required for reminders! Check system settings.', show: true });
         setTimeout(() => setToast({ message: '', show: false }), 5000);
-        return;
-      }
-      
-      console.log('Notification permission granted. Setting up reminders...');
-
-      // Clear existing interval
-      if (reminderInterval.current) {
-        clearInterval(reminderInterval.current);
-        reminderInterval.current = null;
       }
-
-      // Calculate interval in milliseconds
-      const intervalMs = settings.reminder_interval_minutes * 60 * 1000;
-      console.log(`Setting reminder interval: ${settings.reminder_interval_minutes} minutes (${intervalMs}ms)`);
-
-      // Send test notification immediately to verify it works (after a short delay to ensure system is ready)
-      setTimeout(async () => {
-        try {
-          console.log('Sending test notification...');
-          const result = await sendNotification({
-            title: '💧 Hydration Reminder',
-            body: 'Reminder system active! You will be notified every ' + settings.reminder_interval_minutes + ' minutes.',
-          });
-          console.log('✅ Test notification sent successfully!', result);
-          
-          // Also test sound for notification
-          playSound('add', settings.sound_enabled);
-        } catch (error) {
-          console.error('❌ Failed to send test notification:', error);
-          console.error('Error details:', JSON.stringify(error, null, 2));
-        }
-      }, 2000);
-
-      // Set new interval
-      reminderInterval.current = setInterval(async () => {
-        try {
-          console.log('⏰ Reminder interval triggered at', new Date().toLocaleTimeString());
-          const result = await sendNotification({
-            title: '💧 Hydration Reminder',
-            body: 'Time to drink some water! Stay hydrated.',
-          });
-          console.log('✅ Reminder notification sent successfully!', result);
-          
-          // Play notification sound
-          playSound('add', settings.sound_enabled);
-        } catch (error) {
-          console.error('❌ Failed to send reminder notification:', error);
-          console.error('Error details:', JSON.stringify(error, null, 2));
-        }
-      }, intervalMs);
     };
 
-    setupReminder();
+    checkPermission();
 
     return () => {
-      if (reminderInterval.current) {
-        clearInterval(reminderInterval.current);
-      }
       if (achievementTimeout.current) {
         clearTimeout(achievementTimeout.current);
       }
     };
-  }, [settings.reminder_enabled, settings.reminder_interval_minutes]);
+  }, [settings.reminder_enabled]);
 
   // Add water
   const handleAddWater = async (amount: number) => {
@@ -306,7 +362,17 @@ function App() {
     
     try {
       playSound('add', settings.sound_enabled);
-      await invoke('add_water', { amountMl: amount });
+      let outcome = await invoke<AddWaterOutcome>('add_water', { amountMl: amount });
+      if (outcome.status === 'needs_confirmation') {
+        const confirmed = window.confirm(
+          `${outcome.amount_ml} ml is more than your ${outcome.max_entry_ml} ml limit for one entry. Log it anyway?`
+        );
+        if (!confirmed) {
+          return;
+        }
+        outcome = await invoke<AddWaterOutcome>('add_water', { amountMl: amount, force: true });
+      }
+      setQuickAddContext(null);
       const [updatedStats] = await Promise.all([
         invoke<DailyStats>('get_today_stats'),
         invoke<WaterEntry[]>('get_today_entries'),
@@ -328,7 +394,6 @@ function App() {
         }, 5000);
       }
       
-      await loadData();
       showToast(`Added ${amount}ml`);
       setCustomAmount('');
     } catch (error) {
@@ -342,8 +407,12 @@ function App() {
     try {
       playSound('delete', settings.sound_enabled);
       await invoke('remove_entry', { id });
-      await loadData();
     } catch (error) {
+      // Already gone, e.g. removed from another window
+      if ((error as AppError).code === 'not_found') {
+        await loadData();
+        return;
+      }
       console.error('Failed to remove entry:', error);
       playSound('error', settings.sound_enabled);
     }
@@ -354,31 +423,17 @@ function App() {
     const updated = { ...settings, ...newSettings };
     setSettings(updated);
     
-    if (newSettings.theme) {
+    if (newSettings.theme && newSettings.theme !== 'auto') {
       document.documentElement.setAttribute('data-theme', newSettings.theme);
     }
     
-    // Handle autostart
-    if (newSettings.start_with_system !== undefined) {
-      try {
-        if (newSettings.start_with_system) {
-          await enableAutostart();
-          console.log('Autostart enabled');
-        } else {
-          await disableAutostart();
-          console.log('Autostart disabled');
-        }
-      } catch (error) {
-        console.error('Failed to update autostart:', error);
-        showToast('Failed to update autostart setting');
-      }
-    }
-    
     try {
-      await invoke('save_settings', { settings: updated });
-      await loadData();
+      const saved = await invoke<Settings>('save_settings', { settings: updated });
+      setSettings(saved);
     } catch (error) {
       console.error('Failed to save settings:', error);
+      setSettings(settings);
+      showToast((error as AppError).message ?? String(error));
     }
   };
 
@@ -610,9 +665,14 @@ function App() {
             <div className="custom-input-section">
               <div className="custom-input-wrapper">
                 <input
+                  ref={customInputRef}
                   type="number"
                   className="custom-input"
-                  placeholder="Custom amount"
+                  placeholder={
+                    quickAddContext && quickAddContext.remaining_ml > 0
+                      ? `${quickAddContext.remaining_ml} ml to go`
+                      : 'Custom amount'
+                  }
                   value={customAmount}
                   onChange={(e) => setCustomAmount(e.target.value)}
                   onKeyDown={(e) => {
@@ -905,6 +965,39 @@ function App() {
                     <span className="setting-unit">min</span>
                   </div>
                 </div>
+                <div className="setting-item">
+                  <div className="setting-info">
+                    <div className="setting-label">Desk Reminder</div>
+                    <div className="setting-description">Nudge after a long stretch at the computer without a drink</div>
+                  </div>
+                  <label className="toggle">
+                    <input
+                      type="checkbox"
+                      checked={settings.desk_reminder_enabled}
+                      onChange={(e) => handleSaveSettings({ desk_reminder_enabled: e.target.checked })}
+                    />
+                    <span className="toggle-slider" />
+                  </label>
+                </div>
+                <div className="setting-item">
+                  <div className="setting-info">
+                    <div className="setting-label">Desk Stretch</div>
+                    <div className="setting-description">How long at the computer before the nudge</div>
+                  </div>
+                  <div className="setting-control">
+                    <input
+                      type="number"
+                      className="setting-input"
+                      value={settings.desk_reminder_minutes}
+                      onChange={(e) => handleSaveSettings({ desk_reminder_minutes: parseInt(e.target.value) || 120 })}
+                      step="15"
+                      min="30"
+                      max="480"
+                      disabled={!settings.desk_reminder_enabled}
+                    />
+                    <span className="setting-unit">min</span>
+                  </div>
+                </div>
                 <div className="setting-item">
                   <div className="setting-info">
                     <div className="setting-label">Sound</div>
@@ -938,8 +1031,23 @@ function App() {
                   >
                     <option value="dark">Dark</option>
                     <option value="light">Light</option>
+                    <option value="auto">System</option>
                   </select>
                 </div>
+                <div className="setting-item">
+                  <div className="setting-info">
+                    <div className="setting-label">Monochrome Tray Icon</div>
+                    <div className="setting-description">One color that matches the system tray</div>
+                  </div>
+                  <label className="toggle">
+                    <input
+                      type="checkbox"
+                      checked={settings.tray_icon_monochrome}
+                      onChange={(e) => handleSaveSettings({ tray_icon_monochrome: e.target.checked })}
+                    />
+                    <span className="toggle-slider" />
+                  </label>
+                </div>
               </div>
             </div>
 
@@ -961,6 +1069,49 @@ function App() {
                     <span className="toggle-slider" />
                   </label>
                 </div>
+                <div className="setting-item">
+                  <div className="setting-info">
+                    <div className="setting-label">Start Minimized</div>
+                    <div className="setting-description">Open in the tray without showing the window</div>
+                  </div>
+                  <label className="toggle">
+                    <input
+                      type="checkbox"
+                      checked={settings.start_minimized}
+                      onChange={(e) => handleSaveSettings({ start_minimized: e.target.checked })}
+                    />
+                    <span className="toggle-slider" />
+                  </label>
+                </div>
+                <div className="setting-item">
+                  <div className="setting-info">
+                    <div className="setting-label">Quick Add Overlay</div>
+                    <div className="setting-description">Briefly confirm tray and shortcut adds on screen</div>
+                  </div>
+                  <label className="toggle">
+                    <input
+                      type="checkbox"
+                      checked={settings.quick_add_overlay}
+                      onChange={(e) => handleSaveSettings({ quick_add_overlay: e.target.checked })}
+                    />
+                    <span className="toggle-slider" />
+                  </label>
+                </div>
+                <div className="setting-item">
+                  <div className="setting-info">
+                    <div className="setting-label">Tray Click</div>
+                    <div className="setting-description">What left-clicking the tray icon does</div>
+                  </div>
+                  <select
+                    className="setting-select"
+                    value={settings.tray_left_click}
+                    onChange={(e) => handleSaveSettings({ tray_left_click: e.target.value as TrayLeftClick })}
+                  >
+                    <option value="show_window">Show window</option>
+                    <option value="quick_add_default">Add first quick-add amount</option>
+                    <option value="toggle_window">Show or hide window</option>
+                  </select>
+                </div>
               </div>
             </div>
           </>
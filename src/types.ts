@@ -3,6 +3,29 @@ export interface WaterEntry {
   amount_ml: number;
   timestamp: string;
   date: string;
+  // 'app', 'notification', ...
+  source: string;
+}
+
+// Payload of the 'entries-changed' event, sent after anything changes stored
+// entries. `dates` is empty when any day may have changed.
+export type ChangeKind = 'added' | 'removed' | 'redated' | 'imported' | 'restored' | 'purged';
+
+export interface EntriesChanged {
+  kind: ChangeKind;
+  dates: string[];
+}
+
+// Payload of 'day-rolled-over', sent when the logical day changes
+export interface DayRolledOver {
+  previous: string;
+  today: string;
+}
+
+// Payload of 'state-refresh', sent after the machine wakes from sleep
+export interface StateRefresh {
+  woke_at: string;
+  today: string;
 }
 
 export interface DailyStats {
@@ -11,6 +34,9 @@ export interface DailyStats {
   goal_ml: number;
   entries_count: number;
   percentage: number;
+  goal_met_at: string | null;
+  frozen: boolean;
+  surplus_ml: number;
 }
 
 export interface MonthlyStats {
@@ -22,15 +48,376 @@ export interface MonthlyStats {
   days_goal_met: number;
   current_streak: number;
   best_streak: number;
+  average_goal_met_hour: number | null;
+  total_surplus_ml: number;
+  days_over_110_percent: number;
+  days_over_125_percent: number;
+  adherence_percentage: number;
 }
 
+export interface YearOverYear {
+  current: MonthlyStats;
+  previous: MonthlyStats;
+  has_prior_data: boolean;
+  total_delta_ml: number | null;
+  average_delta_ml: number | null;
+  adherence_delta: number | null;
+}
+
+export interface AllTimeStats {
+  total_ml: number;
+  total_entries: number;
+  first_date: string | null;
+  days_logged: number;
+  average_ml_per_day: number;
+  days_goal_met: number;
+  goal_met_rate: number;
+  longest_streak: number;
+}
+
+export interface WeeklyAverage {
+  week_start: string;
+  week_end: string;
+  average_ml: number;
+}
+
+export type Trend =
+  | { status: 'insufficient_data'; weeks_with_data: number; weekly_averages: WeeklyAverage[] }
+  | {
+      status: 'trending';
+      direction: 'up' | 'down' | 'flat';
+      change_percentage: number;
+      weekly_averages: WeeklyAverage[];
+    };
+
+export interface DayDrinkTimes {
+  date: string;
+  first_drink_at: string;
+  last_drink_at: string;
+  first_drink_hour: number;
+  last_drink_hour: number;
+  rolling_first_drink_hour: number;
+}
+
+export interface DrinkTimeSummary {
+  days_counted: number;
+  average_first_drink_hour: number | null;
+  average_last_drink_hour: number | null;
+}
+
+export interface DrinkTimeTrends {
+  days: DayDrinkTimes[];
+  summary: DrinkTimeSummary;
+  rolling_window_days: number;
+  malformed_timestamps: number;
+}
+
+export type TrayLeftClick = 'show_window' | 'quick_add_default' | 'toggle_window';
+
 export interface Settings {
   daily_goal_ml: number;
   reminder_interval_minutes: number;
   reminder_enabled: boolean;
   sound_enabled: boolean;
   start_with_system: boolean;
+  start_minimized: boolean;
   theme: string;
+  day_starts_at_hour: number;
+  unit: Unit;
+  quick_add_amounts: number[];
+  // macOS only
+  menu_bar_title: 'off' | 'percentage' | 'remaining';
+  tray_left_click: TrayLeftClick;
+  // On macOS, a template image
+  tray_icon_monochrome: boolean;
+  global_shortcuts_enabled: boolean;
+  quick_add_overlay: boolean;
+  quiet_hours_enabled: boolean;
+  // Minutes since midnight
+  quiet_hours_start: number;
+  quiet_hours_end: number;
+  checkpoints: Checkpoint[];
+  // Monday = 0 through Sunday = 6
+  reminder_days: number[];
+  smart_reminders: boolean;
+  adaptive_reminders: boolean;
+  pause_reminders_when_goal_met: boolean;
+  freshness_window_minutes: number;
+  idle_threshold_minutes: number;
+  desk_reminder_enabled: boolean;
+  desk_reminder_minutes: number;
+  max_entry_ml: number;
+  reminder_sound_path: string | null;
+  escalation_enabled: boolean;
+  escalation_threshold_percentage: number;
+  escalation_interval_minutes: number;
+  escalation_sound_path: string | null;
+  user_name: string | null;
+  auto_backup_enabled: boolean;
+  auto_backup_keep: number;
+  retention_years: number;
+  export_enabled: boolean;
+  export_path: string | null;
+  // Minutes since midnight
+  export_time: number;
+  notify_on_goal_reached: boolean;
+  locale: string;
+  accent_color: string;
+  // How much goes into the log file
+  log_level: 'error' | 'warn' | 'info' | 'debug' | 'trace';
+  // Read-only: theme with 'auto' resolved against the OS
+  resolved_theme: 'dark' | 'light';
+}
+
+export interface Checkpoint {
+  // Minutes since midnight
+  time_of_day: number;
+  expected_percentage: number;
+}
+
+export type Unit = 'ml' | 'fl_oz' | 'cups' | 'liters';
+
+export interface Achievement {
+  key: string;
+  title: string;
+  description: string;
+  unlocked: boolean;
+  unlocked_at: string | null;
+}
+
+export interface Milestone {
+  threshold_ml: number;
+  lifetime_ml: number;
+  reached_at: string;
+}
+
+export interface GoalProgram {
+  start_ml: number;
+  increment_ml: number;
+  interval_days: number;
+  start_date: string;
+  target_ml: number;
+}
+
+export interface GoalProgramStatus {
+  active: boolean;
+  program: GoalProgram | null;
+  current_goal_ml: number;
+  phase: number;
+  total_phases: number;
+  next_increase_date: string | null;
+  target_reached: boolean;
+}
+
+export interface StreakFreeze {
+  date: string;
+  applied_at: string;
+}
+
+export interface StreakFreezes {
+  freezes: StreakFreeze[];
+  remaining_this_month: number;
 }
 
 export type Tab = 'today' | 'analytics' | 'settings';
+
+export interface SnoozeState {
+  snoozed: boolean;
+  snoozed_until: string | null;
+}
+
+export interface AwayState {
+  supported: boolean;
+  away: boolean;
+}
+
+export type ActivityLevel = 'sedentary' | 'light' | 'moderate' | 'active';
+
+export type Climate = 'temperate' | 'warm' | 'hot';
+
+export interface SettingsImport {
+  changed: string[];
+  warnings: string[];
+  settings: Settings;
+}
+
+export interface GoalPreset {
+  name: string;
+  goal_ml: number;
+}
+
+export type AddWaterOutcome =
+  | { status: 'added'; entry: WaterEntry }
+  | { status: 'needs_confirmation'; amount_ml: number; max_entry_ml: number };
+
+export interface GoalReached {
+  date: string;
+  total_ml: number;
+  goal_ml: number;
+  reached_at: string;
+}
+
+export interface PatternBucket {
+  minute_of_day: number;
+  share: number;
+  typical: boolean;
+}
+
+export interface TypicalPattern {
+  computed_on: string;
+  days_counted: number;
+  enough_history: boolean;
+  buckets: PatternBucket[];
+}
+
+export interface QuickAddContext {
+  total_ml: number;
+  goal_ml: number;
+  remaining_ml: number;
+}
+
+export type RejectReason =
+  | 'malformed_row'
+  | 'bad_timestamp'
+  | 'non_numeric_amount'
+  | 'unknown_unit'
+  | 'out_of_range'
+  | 'duplicate';
+
+export interface RejectedRow {
+  line: number;
+  reason: RejectReason;
+  value: string;
+  message: string;
+}
+
+export interface CsvImportSummary {
+  inserted: number;
+  skipped_duplicates: number;
+  // The first 100 of each, in file order
+  duplicates: RejectedRow[];
+  rejected: RejectedRow[];
+  rejected_total: number;
+}
+
+export interface PurgePreview {
+  before: string;
+  entries: number;
+  token: string;
+}
+
+export interface ExportStatus {
+  attempted_at: string;
+  path: string;
+  succeeded: boolean;
+  entries: number;
+  error: string | null;
+}
+
+export type ShortcutAction = 'small_quick_add' | 'large_quick_add' | 'show_window';
+
+// Also sent with 'shortcut-registration-failed' for the ones that failed
+export interface ShortcutStatus {
+  action: ShortcutAction;
+  shortcut: string;
+  registered: boolean;
+  error: string | null;
+}
+
+export interface TextEntry {
+  line: number;
+  text: string;
+  timestamp: string;
+  amount_ml: number;
+  over_max_entry: boolean;
+}
+
+export interface RejectedText {
+  line: number;
+  text: string;
+  reason: string;
+}
+
+export interface TextImportPreview {
+  entries: TextEntry[];
+  rejected: RejectedText[];
+}
+
+export interface BackupSummary {
+  path: string;
+  size_bytes: number;
+  row_counts: Record<string, number>;
+}
+
+export type RestoreMode = 'replace' | 'merge';
+
+export type ConflictStrategy = 'keep_local' | 'keep_backup' | 'newest';
+
+export interface MergeItem {
+  table: string;
+  key: string;
+  action: 'inserted' | 'skipped' | 'overridden';
+}
+
+export interface RestoreResult {
+  dry_run: boolean;
+  inserted: Record<string, number>;
+  skipped: Record<string, number>;
+  overridden: Record<string, number>;
+  items: MergeItem[];
+  snapshot_path: string | null;
+  settings: Settings;
+}
+
+export interface BackupFile {
+  name: string;
+  path: string;
+  modified_at: string;
+  size_bytes: number;
+  automatic: boolean;
+}
+
+export interface PdfReport {
+  path: string;
+  page_count: number;
+}
+
+// From get_widget_stats, formatted in the backend
+export interface WidgetStats {
+  percentage: number;
+  // "1,750 / 4,000 ml"
+  progress: string;
+  quick_add_ml: number;
+  // "+250 ml"
+  quick_add_label: string;
+}
+
+export interface MaintenanceReport {
+  integrity_ok: boolean;
+  // What the integrity check found, empty when it passed
+  problems: string[];
+  // False when the check failed and nothing else was run
+  vacuumed: boolean;
+  size_before_bytes: number;
+  size_after_bytes: number;
+  duration_ms: number;
+}
+
+// What a failed invoke() rejects with. `code` is stable, so branch on it
+// rather than on `message`, which is only for display.
+export type AppErrorCode =
+  | 'database'
+  | 'database_busy'
+  | 'state_poisoned'
+  | 'invalid_input'
+  | 'not_found'
+  | 'already_running'
+  | 'io'
+  | 'other';
+
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+  // The argument or setting at fault, for invalid_input
+  field: string | null;
+}
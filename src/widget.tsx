@@ -0,0 +1,138 @@
+import React, { useCallback, useEffect, useState } from 'react';
+import ReactDOM from 'react-dom/client';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import type { WidgetStats } from './types';
+
+// Catches entries made in the main window and the day rolling over, which
+// don't send an event
+const POLL_MS = 30_000;
+const RADIUS = 46;
+const CIRCUMFERENCE = 2 * Math.PI * RADIUS;
+
+const styles = `
+  html, body {
+    margin: 0;
+    height: 100%;
+    overflow: hidden;
+    background: #0a0a0a;
+    color: #e5e5e5;
+    font-family: 'Outfit', system-ui, sans-serif;
+    user-select: none;
+    cursor: default;
+  }
+  .widget {
+    height: 100%;
+    box-sizing: border-box;
+    border: 1px solid rgba(115, 115, 115, 0.35);
+    display: flex;
+    flex-direction: column;
+    align-items: center;
+    justify-content: center;
+    gap: 8px;
+    position: relative;
+  }
+  .close {
+    position: absolute;
+    top: 2px;
+    right: 6px;
+    background: none;
+    border: none;
+    color: #737373;
+    font-size: 16px;
+    cursor: pointer;
+  }
+  .ring { position: relative; width: 104px; height: 104px; }
+  .ring svg { transform: rotate(-90deg); }
+  .ring-label {
+    position: absolute;
+    inset: 0;
+    display: flex;
+    align-items: center;
+    justify-content: center;
+    font-size: 22px;
+    font-weight: 600;
+  }
+  .progress { font-size: 11px; color: #a3a3a3; }
+  .add {
+    background: #4ade80;
+    color: #0a0a0a;
+    border: none;
+    border-radius: 4px;
+    padding: 4px 14px;
+    font-weight: 600;
+    cursor: pointer;
+  }
+`;
+
+function Widget() {
+  const [stats, setStats] = useState<WidgetStats | null>(null);
+
+  const load = useCallback(async () => {
+    try {
+      setStats(await invoke<WidgetStats>('get_widget_stats'));
+    } catch (error) {
+      console.error('Failed to load widget stats:', error);
+    }
+  }, []);
+
+  useEffect(() => {
+    load();
+    const timer = setInterval(load, POLL_MS);
+    const unlistenEntries = listen('entries-changed', load);
+    const unlistenDay = listen('day-rolled-over', load);
+    const unlistenResume = listen('state-refresh', load);
+    const unlistenSettings = listen('settings-changed', load);
+    return () => {
+      clearInterval(timer);
+      unlistenEntries.then(fn => fn());
+      unlistenDay.then(fn => fn());
+      unlistenResume.then(fn => fn());
+      unlistenSettings.then(fn => fn());
+    };
+  }, [load]);
+
+  const quickAdd = async () => {
+    try {
+      setStats(await invoke<WidgetStats>('widget_quick_add'));
+    } catch (error) {
+      console.error('Failed to add water:', error);
+    }
+  };
+
+  const percentage = Math.min(stats?.percentage ?? 0, 100);
+
+  return (
+    <div className="widget" data-tauri-drag-region>
+      <style>{styles}</style>
+      <button className="close" onClick={() => invoke('hide_widget')} title="Hide">×</button>
+      <div className="ring" data-tauri-drag-region>
+        <svg width="104" height="104">
+          <circle cx="52" cy="52" r={RADIUS} fill="none" stroke="#1f1f1f" strokeWidth="8" />
+          <circle
+            cx="52"
+            cy="52"
+            r={RADIUS}
+            fill="none"
+            stroke="#4ade80"
+            strokeWidth="8"
+            strokeLinecap="round"
+            strokeDasharray={CIRCUMFERENCE}
+            strokeDashoffset={CIRCUMFERENCE * (1 - percentage / 100)}
+          />
+        </svg>
+        <div className="ring-label" data-tauri-drag-region>{Math.floor(stats?.percentage ?? 0)}%</div>
+      </div>
+      <div className="progress" data-tauri-drag-region>{stats?.progress}</div>
+      <button className="add" onClick={quickAdd} disabled={!stats}>
+        {stats?.quick_add_label ?? '+'}
+      </button>
+    </div>
+  );
+}
+
+ReactDOM.createRoot(document.getElementById('root') as HTMLElement).render(
+  <React.StrictMode>
+    <Widget />
+  </React.StrictMode>
+);
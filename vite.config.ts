@@ -15,5 +15,12 @@ export default defineConfig({
     target: 'esnext',
     minify: 'esbuild',
     sourcemap: false,
+    // The always-on-top widget is a page of its own
+    rollupOptions: {
+      input: {
+        main: 'index.html',
+        widget: 'widget.html',
+      },
+    },
   },
 });